@@ -1,7 +1,7 @@
 //! Convert FastC compilation errors to LSP diagnostics
 
-use fastc::diag::CompileError;
-use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+use fastc::diag::{CompileError, Severity};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range, Url};
 
 /// Convert a byte offset to an LSP Position (line/character)
 pub fn byte_to_position(source: &str, byte_offset: usize) -> Position {
@@ -34,11 +34,16 @@ pub fn byte_span_to_range(source: &str, start: usize, end: usize) -> Range {
 }
 
 /// Convert a CompileError to LSP Diagnostics
-pub fn compile_error_to_diagnostics(error: &CompileError, source: &str) -> Vec<Diagnostic> {
+pub fn compile_error_to_diagnostics(
+    error: &CompileError,
+    source: &str,
+    uri: &Url,
+) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
 
     match error {
         CompileError::Parse {
+            code,
             message,
             span,
             hint,
@@ -48,7 +53,7 @@ pub fn compile_error_to_diagnostics(error: &CompileError, source: &str) -> Vec<D
                 range: byte_span_to_range(source, span.start, span.end),
                 severity: Some(DiagnosticSeverity::ERROR),
                 code: Some(tower_lsp::lsp_types::NumberOrString::String(
-                    "fastc::parse".to_string(),
+                    code.to_string(),
                 )),
                 source: Some("fastc".to_string()),
                 message: format!("Parse error: {}", message),
@@ -65,6 +70,7 @@ pub fn compile_error_to_diagnostics(error: &CompileError, source: &str) -> Vec<D
             });
         }
         CompileError::Resolve {
+            code,
             message,
             span,
             hint,
@@ -74,7 +80,7 @@ pub fn compile_error_to_diagnostics(error: &CompileError, source: &str) -> Vec<D
                 range: byte_span_to_range(source, span.start, span.end),
                 severity: Some(DiagnosticSeverity::ERROR),
                 code: Some(tower_lsp::lsp_types::NumberOrString::String(
-                    "fastc::resolve".to_string(),
+                    code.to_string(),
                 )),
                 source: Some("fastc".to_string()),
                 message: format!("Resolution error: {}", message),
@@ -91,6 +97,7 @@ pub fn compile_error_to_diagnostics(error: &CompileError, source: &str) -> Vec<D
             });
         }
         CompileError::Type {
+            code,
             message,
             span,
             hint,
@@ -100,7 +107,7 @@ pub fn compile_error_to_diagnostics(error: &CompileError, source: &str) -> Vec<D
                 range: byte_span_to_range(source, span.start, span.end),
                 severity: Some(DiagnosticSeverity::ERROR),
                 code: Some(tower_lsp::lsp_types::NumberOrString::String(
-                    "fastc::typecheck".to_string(),
+                    code.to_string(),
                 )),
                 source: Some("fastc".to_string()),
                 message: format!("Type error: {}", message),
@@ -117,6 +124,7 @@ pub fn compile_error_to_diagnostics(error: &CompileError, source: &str) -> Vec<D
             });
         }
         CompileError::Safety {
+            code,
             message,
             span,
             hint,
@@ -126,7 +134,7 @@ pub fn compile_error_to_diagnostics(error: &CompileError, source: &str) -> Vec<D
                 range: byte_span_to_range(source, span.start, span.end),
                 severity: Some(DiagnosticSeverity::ERROR),
                 code: Some(tower_lsp::lsp_types::NumberOrString::String(
-                    "fastc::safety".to_string(),
+                    code.to_string(),
                 )),
                 source: Some("fastc".to_string()),
                 message: format!("Safety error: {}", message),
@@ -142,6 +150,53 @@ pub fn compile_error_to_diagnostics(error: &CompileError, source: &str) -> Vec<D
                 ..Default::default()
             });
         }
+        CompileError::Borrow {
+            code,
+            message,
+            labels,
+            hint,
+            ..
+        } => {
+            // The first label is the error site; the conflicting borrow,
+            // move, or declaration is related information
+            let Some((primary, rest)) = labels.split_first() else {
+                return diagnostics;
+            };
+            let mut related: Vec<_> = rest
+                .iter()
+                .map(|l| tower_lsp::lsp_types::DiagnosticRelatedInformation {
+                    location: tower_lsp::lsp_types::Location {
+                        uri: uri.clone(),
+                        range: byte_span_to_range(source, l.offset(), l.offset() + l.len()),
+                    },
+                    message: l.label().unwrap_or_default().to_string(),
+                })
+                .collect();
+            if let Some(h) = hint {
+                related.push(tower_lsp::lsp_types::DiagnosticRelatedInformation {
+                    location: tower_lsp::lsp_types::Location {
+                        uri: tower_lsp::lsp_types::Url::parse("file:///hint").unwrap(),
+                        range: Range::default(),
+                    },
+                    message: h.clone(),
+                });
+            }
+            diagnostics.push(Diagnostic {
+                range: byte_span_to_range(
+                    source,
+                    primary.offset(),
+                    primary.offset() + primary.len(),
+                ),
+                severity: Some(DiagnosticSeverity::ERROR),
+                code: Some(tower_lsp::lsp_types::NumberOrString::String(
+                    code.to_string(),
+                )),
+                source: Some("fastc".to_string()),
+                message: format!("Borrow error: {}", message),
+                related_information: Some(related),
+                ..Default::default()
+            });
+        }
         CompileError::P10 {
             code,
             message,
@@ -153,7 +208,7 @@ pub fn compile_error_to_diagnostics(error: &CompileError, source: &str) -> Vec<D
                 range: byte_span_to_range(source, span.start, span.end),
                 severity: Some(DiagnosticSeverity::ERROR),
                 code: Some(tower_lsp::lsp_types::NumberOrString::String(
-                    format!("fastc::p10::{}", code),
+                    code.clone(),
                 )),
                 source: Some("fastc".to_string()),
                 message: format!("Power of 10 [{}]: {}", code, message),
@@ -169,9 +224,33 @@ pub fn compile_error_to_diagnostics(error: &CompileError, source: &str) -> Vec<D
                 ..Default::default()
             });
         }
+        CompileError::Lint(lint) => {
+            diagnostics.push(Diagnostic {
+                range: byte_span_to_range(source, lint.span.start, lint.span.end),
+                severity: Some(match lint.severity {
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                }),
+                code: Some(tower_lsp::lsp_types::NumberOrString::String(
+                    lint.code.clone(),
+                )),
+                source: Some("fastc".to_string()),
+                message: lint.message.clone(),
+                related_information: lint.hint.as_ref().map(|h| {
+                    vec![tower_lsp::lsp_types::DiagnosticRelatedInformation {
+                        location: tower_lsp::lsp_types::Location {
+                            uri: tower_lsp::lsp_types::Url::parse("file:///hint").unwrap(),
+                            range: Range::default(),
+                        },
+                        message: h.clone(),
+                    }]
+                }),
+                ..Default::default()
+            });
+        }
         CompileError::Multiple { errors } => {
             for error in errors {
-                diagnostics.extend(compile_error_to_diagnostics(error, source));
+                diagnostics.extend(compile_error_to_diagnostics(error, source, uri));
             }
         }
     }
@@ -191,6 +270,20 @@ mod tests {
         assert_eq!(byte_to_position(source, source.len()), Position::new(1, 5));
     }
 
+    #[test]
+    fn test_diagnostic_code_is_error_code() {
+        let source = "fn main() -> i32 { let x: bool = 1; return 0; }";
+        let error = fastc::check(source, "test.fc").unwrap_err();
+        let uri = Url::parse("file:///test.fc").unwrap();
+        let diagnostics = compile_error_to_diagnostics(&error, source, &uri);
+        assert_eq!(
+            diagnostics[0].code,
+            Some(tower_lsp::lsp_types::NumberOrString::String(
+                "E0201".to_string()
+            ))
+        );
+    }
+
     #[test]
     fn test_byte_span_to_range() {
         let source = "abc\ndef";
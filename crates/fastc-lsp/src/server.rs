@@ -44,10 +44,14 @@ impl FastcLanguageServer {
         let filename = uri.path().to_string();
         drop(doc); // Release the lock before async operation
 
-        let diagnostics = match fastc::check(&content, &filename) {
-            Ok(()) => vec![],
-            Err(e) => compile_error_to_diagnostics(&e, &content),
-        };
+        let diagnostics =
+            match fastc::check_with_p10(&content, &filename, fastc::P10Config::standard()) {
+                Ok(warnings) => warnings
+                    .iter()
+                    .flat_map(|w| compile_error_to_diagnostics(w, &content, uri))
+                    .collect(),
+                Err(e) => compile_error_to_diagnostics(&e, &content, uri),
+            };
 
         self.client
             .publish_diagnostics(uri.clone(), diagnostics, None)
@@ -179,6 +183,18 @@ impl FastcLanguageServer {
             ("cast", "Type cast", "cast(${1:Type}, ${2:value})"),
             ("cstr", "C string literal", "cstr(\"${1:string}\")"),
             ("bytes", "Byte slice literal", "bytes(\"${1:string}\")"),
+            ("as_slice", "Slice of an array", "as_slice(${1:arr})"),
+            (
+                "subslice",
+                "Bounds-checked sub-slice",
+                "subslice(${1:slice}, ${2:start}, ${3:end})",
+            ),
+            (
+                "slice_from_raw",
+                "Slice from raw pointer (unsafe)",
+                "slice_from_raw(${1:ptr}, ${2:len})",
+            ),
+            ("len", "Length of a slice or array", "len(${1:slice})"),
             ("none", "Empty optional", "none(${1:Type})"),
             ("some", "Wrap in optional", "some(${1:value})"),
             ("ok", "Success result", "ok(${1:value})"),
@@ -442,6 +458,14 @@ impl LanguageServer for FastcLanguageServer {
             "cast" => Some("**cast(T, x)**\n\nCast x to type T"),
             "cstr" => Some("**cstr(\"...\")**\n\nC string literal (null-terminated)"),
             "bytes" => Some("**bytes(\"...\")**\n\nByte slice literal"),
+            "as_slice" => Some("**as_slice(arr)**\n\nSlice viewing every element of an array"),
+            "subslice" => Some(
+                "**subslice(s, start, end)**\n\nElements `start..end` of a slice or array, bounds-checked",
+            ),
+            "slice_from_raw" => Some(
+                "**slice_from_raw(p, len)**\n\nSlice of `len` elements at a raw pointer (requires unsafe)",
+            ),
+            "len" => Some("**len(s)**\n\nNumber of elements in a slice or array"),
             "none" => Some("**none(T)**\n\nEmpty optional of type T"),
             "some" => Some("**some(x)**\n\nWrap value in optional"),
             "ok" => Some("**ok(x)**\n\nSuccess result with value x"),
@@ -479,15 +503,12 @@ impl LanguageServer for FastcLanguageServer {
         let filename = uri.path().to_string();
         drop(doc);
 
-        // Parse the file to extract symbols
+        // Parse the file to extract symbols; items that parse keep their
+        // symbols while the file has syntax errors elsewhere
         let lexer = fastc::lexer::Lexer::new(&content);
         let tokens: Vec<_> = lexer.collect();
         let mut parser = fastc::parser::Parser::new(&tokens, &content, &filename);
-
-        let ast = match parser.parse_file() {
-            Ok(ast) => ast,
-            Err(_) => return Ok(None),
-        };
+        let (ast, _) = parser.parse_file_recovering();
 
         let mut symbols = Vec::new();
 
@@ -755,6 +776,7 @@ impl LanguageServer for FastcLanguageServer {
                         children: None,
                     });
                 }
+                fastc::ast::Item::Error { .. } => {}
             }
         }
 
@@ -768,7 +790,11 @@ fn format_fn_signature(decl: &fastc::ast::FnDecl) -> String {
     if decl.is_unsafe {
         sig.push_str("unsafe ");
     }
-    sig.push_str("fn(");
+    sig.push_str("fn");
+    if !decl.type_params.is_empty() {
+        sig.push_str(&format!("[{}]", decl.type_params.join(", ")));
+    }
+    sig.push('(');
     for (i, param) in decl.params.iter().enumerate() {
         if i > 0 {
             sig.push_str(", ");
@@ -828,6 +854,10 @@ fn format_type(ty: &fastc::ast::TypeExpr) -> String {
         }
         .to_string(),
         fastc::ast::TypeExpr::Named(name) => name.clone(),
+        fastc::ast::TypeExpr::Generic(name, args) => {
+            let args: Vec<String> = args.iter().map(format_type).collect();
+            format!("{}[{}]", name, args.join(", "))
+        }
         fastc::ast::TypeExpr::Ref(inner) => format!("ref({})", format_type(inner)),
         fastc::ast::TypeExpr::Mref(inner) => format!("mref({})", format_type(inner)),
         fastc::ast::TypeExpr::Raw(inner) => format!("raw({})", format_type(inner)),
@@ -863,5 +893,6 @@ fn format_type(ty: &fastc::ast::TypeExpr) -> String {
             sig
         }
         fastc::ast::TypeExpr::Void => "void".to_string(),
+        fastc::ast::TypeExpr::Error => "{unknown}".to_string(),
     }
 }
@@ -85,9 +85,8 @@ impl Workspace {
         let tokens = fastc::lexer::strip_comments(tokens);
         let mut parser = fastc::parser::Parser::new(&tokens, content, &filename);
 
-        let Ok(ast) = parser.parse_file() else {
-            return; // Skip files with parse errors
-        };
+        // Items that parse are indexed even if others have syntax errors
+        let (ast, _) = parser.parse_file_recovering();
 
         // Extract symbols from AST
         for item in &ast.items {
@@ -197,7 +196,7 @@ impl Workspace {
                         }
                     }
                 }
-                fastc::ast::Item::Use(_) => {
+                fastc::ast::Item::Use(_) | fastc::ast::Item::Error { .. } => {
                     // Use declarations don't create symbols in the workspace index
                 }
                 fastc::ast::Item::Mod(decl) => {
@@ -61,7 +61,12 @@ fn extract_error_info(code: &str, e: &fastc::diag::CompileError) -> CompileError
         CompileError::Resolve { span, hint, .. } => (Some(span.clone()), hint.clone()),
         CompileError::Type { span, hint, .. } => (Some(span.clone()), hint.clone()),
         CompileError::Safety { span, hint, .. } => (Some(span.clone()), hint.clone()),
+        CompileError::Borrow { labels, hint, .. } => (
+            labels.first().map(|l| l.offset()..l.offset() + l.len()),
+            hint.clone(),
+        ),
         CompileError::P10 { span, hint, .. } => (Some(span.clone()), hint.clone()),
+        CompileError::Lint(lint) => (Some(lint.span.clone()), lint.hint.clone()),
         CompileError::Multiple { errors } => {
             // For multiple errors, use the first error's span
             if let Some(first) = errors.first() {
@@ -156,10 +161,15 @@ pub fn compile(code: &str, emit_header: bool) -> CompileResponse {
 
 /// Type-check FastC code without compiling
 pub fn check(code: &str) -> CheckResponse {
-    match fastc::check(code, "playground.fc") {
-        Ok(_) => CheckResponse {
+    match fastc::check_with_p10(code, "playground.fc", fastc::P10Config::standard()) {
+        Ok(warnings) => CheckResponse {
             success: true,
-            diagnostics: Some(vec![]),
+            diagnostics: Some(
+                warnings
+                    .iter()
+                    .map(|w| extract_error_info(code, w))
+                    .collect(),
+            ),
             error: None,
         },
         Err(e) => CheckResponse {
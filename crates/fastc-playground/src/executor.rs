@@ -137,7 +137,9 @@ impl Executor {
             .arg("-Wextra");
 
         if let Some(include) = &runtime_include {
-            cc_cmd.arg(format!("-I{}", include.display()));
+            cc_cmd
+                .arg(include.join("fastc_runtime.c"))
+                .arg(format!("-I{}", include.display()));
         }
 
         cc_cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
@@ -1,5 +1,7 @@
 //! Declaration AST nodes
 
+use std::sync::Arc;
+
 use super::{Block, ConstExpr, Span, TypeExpr};
 
 /// A top-level item
@@ -13,13 +15,37 @@ pub enum Item {
     Extern(ExternBlock),
     Use(UseDecl),
     Mod(ModDecl),
+    /// An item that failed to parse; its error is reported separately
+    Error {
+        span: Span,
+    },
+}
+
+impl Item {
+    pub fn span(&self) -> Span {
+        match self {
+            Item::Fn(decl) => decl.span.clone(),
+            Item::Struct(decl) => decl.span.clone(),
+            Item::Enum(decl) => decl.span.clone(),
+            Item::Const(decl) => decl.span.clone(),
+            Item::Opaque(decl) => decl.span.clone(),
+            Item::Extern(block) => block.span.clone(),
+            Item::Use(decl) => decl.span.clone(),
+            Item::Mod(decl) => decl.span.clone(),
+            Item::Error { span } => span.clone(),
+        }
+    }
 }
 
 /// Function declaration
 #[derive(Debug, Clone)]
 pub struct FnDecl {
     pub is_unsafe: bool,
+    /// `@drop`: releases values of its `own(T)` parameter's type at scope exit
+    pub is_drop: bool,
     pub name: String,
+    /// Type parameters: `fn max[T](...)`
+    pub type_params: Vec<String>,
     pub params: Vec<Param>,
     pub return_type: TypeExpr,
     pub body: Block,
@@ -39,6 +65,8 @@ pub struct Param {
 pub struct StructDecl {
     pub repr: Option<Repr>,
     pub name: String,
+    /// Type parameters: `struct Pair[A, B] { ... }`
+    pub type_params: Vec<String>,
     pub fields: Vec<Field>,
     pub span: Span,
 }
@@ -68,6 +96,28 @@ pub struct Variant {
     pub span: Span,
 }
 
+impl EnumDecl {
+    /// Whether any variant carries a payload (lowered as a tagged union)
+    pub fn has_payload(&self) -> bool {
+        self.variants.iter().any(|v| !v.payload().is_empty())
+    }
+
+    /// Look up a variant by its qualified name (e.g. `Shape_Circle`)
+    pub fn variant(&self, qualified: &str) -> Option<&Variant> {
+        let short = qualified
+            .strip_prefix(self.name.as_str())?
+            .strip_prefix('_')?;
+        self.variants.iter().find(|v| v.name == short)
+    }
+}
+
+impl Variant {
+    /// Payload field types (empty for unit variants)
+    pub fn payload(&self) -> &[TypeExpr] {
+        self.fields.as_deref().unwrap_or(&[])
+    }
+}
+
 /// Constant declaration
 #[derive(Debug, Clone)]
 pub struct ConstDecl {
@@ -158,6 +208,16 @@ pub struct ModDecl {
     pub name: String,
     /// Inline module body, or None to load from file
     pub body: Option<Vec<Item>>,
+    /// File the body was loaded from, once a `mod name;` is expanded; spans
+    /// inside the body are offsets into it
+    pub file: Option<SourceFile>,
     /// Source span
     pub span: Span,
 }
+
+/// A source file loaded for a module
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub path: String,
+    pub text: Arc<str>,
+}
@@ -37,6 +37,82 @@ pub enum UnaryOp {
     BitNot,
 }
 
+impl BinOp {
+    /// Fold an arithmetic operator over integer constants
+    ///
+    /// Returns `None` for non-arithmetic operators and on overflow.
+    pub fn fold_int(self, l: i128, r: i128) -> Option<i128> {
+        match self {
+            BinOp::Add => l.checked_add(r),
+            BinOp::Sub => l.checked_sub(r),
+            BinOp::Mul => l.checked_mul(r),
+            BinOp::Div => l.checked_div(r),
+            BinOp::Rem => l.checked_rem(r),
+            _ => None,
+        }
+    }
+}
+
+/// Builtin operations on opt/res, raw pointers, and slices, called like
+/// functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Builtin {
+    // opt(T) and res(T, E)
+    IsSome,
+    IsNone,
+    IsOk,
+    IsErr,
+    Unwrap,
+    UnwrapOr,
+    UnwrapErr,
+    // Pointer conversions
+    ToRaw,
+    ToRawm,
+    FromRaw,
+    FromRawm,
+    FromRawUnchecked,
+    FromRawmUnchecked,
+    // Slices
+    AsSlice,
+    Subslice,
+    SliceFromRaw,
+    Len,
+}
+
+impl Builtin {
+    /// Source spelling of the builtin
+    pub fn name(self) -> &'static str {
+        match self {
+            Builtin::IsSome => "is_some",
+            Builtin::IsNone => "is_none",
+            Builtin::IsOk => "is_ok",
+            Builtin::IsErr => "is_err",
+            Builtin::Unwrap => "unwrap",
+            Builtin::UnwrapOr => "unwrap_or",
+            Builtin::UnwrapErr => "unwrap_err",
+            Builtin::ToRaw => "to_raw",
+            Builtin::ToRawm => "to_rawm",
+            Builtin::FromRaw => "from_raw",
+            Builtin::FromRawm => "from_rawm",
+            Builtin::FromRawUnchecked => "from_raw_unchecked",
+            Builtin::FromRawmUnchecked => "from_rawm_unchecked",
+            Builtin::AsSlice => "as_slice",
+            Builtin::Subslice => "subslice",
+            Builtin::SliceFromRaw => "slice_from_raw",
+            Builtin::Len => "len",
+        }
+    }
+
+    /// Number of arguments the builtin takes
+    pub fn arity(self) -> usize {
+        match self {
+            Builtin::UnwrapOr | Builtin::SliceFromRaw => 2,
+            Builtin::Subslice => 3,
+            _ => 1,
+        }
+    }
+}
+
 /// An expression
 #[derive(Debug, Clone)]
 pub enum Expr {
@@ -103,9 +179,22 @@ pub enum Expr {
     Ok { value: Box<Expr>, span: Span },
     /// err(e) - error result
     Err { value: Box<Expr>, span: Span },
-    /// Struct literal: Name { field: value, ... }
+    /// Generic function instance: name[T, ...]
+    Instance {
+        name: String,
+        type_args: Vec<TypeExpr>,
+        span: Span,
+    },
+    /// Builtin opt/res operation: unwrap(o), is_ok(r), from_raw(p), ...
+    Builtin {
+        op: Builtin,
+        args: Vec<Expr>,
+        span: Span,
+    },
+    /// Struct literal: Name { field: value, ... } or Name[T, ...] { ... }
     StructLit {
         name: String,
+        type_args: Vec<TypeExpr>,
         fields: Vec<FieldInit>,
         span: Span,
     },
@@ -144,7 +233,125 @@ pub enum ConstExpr {
     Bytes(String),
 }
 
+impl ConstExpr {
+    /// Evaluate an integer constant expression
+    ///
+    /// `lookup` resolves const names to their values. Returns `None` for
+    /// non-integer values, casts (which may truncate), unsupported operators,
+    /// overflow, and cyclic definitions.
+    pub fn eval_int<'a>(&'a self, lookup: &impl Fn(&str) -> Option<&'a ConstExpr>) -> Option<i128> {
+        self.eval_int_in(lookup, 0)
+    }
+
+    fn eval_int_in<'a>(
+        &'a self,
+        lookup: &impl Fn(&str) -> Option<&'a ConstExpr>,
+        depth: usize,
+    ) -> Option<i128> {
+        // Deeper nesting than this only comes from a cyclic definition
+        if depth > 64 {
+            return None;
+        }
+        match self {
+            ConstExpr::IntLit(n) => Some(*n),
+            ConstExpr::Paren(inner) => inner.eval_int_in(lookup, depth + 1),
+            ConstExpr::Ident(name) => lookup(name)?.eval_int_in(lookup, depth + 1),
+            ConstExpr::Unary {
+                op: UnaryOp::Neg,
+                operand,
+            } => operand.eval_int_in(lookup, depth + 1)?.checked_neg(),
+            ConstExpr::Binary { op, lhs, rhs } => {
+                let l = lhs.eval_int_in(lookup, depth + 1)?;
+                let r = rhs.eval_int_in(lookup, depth + 1)?;
+                op.fold_int(l, r)
+            }
+            _ => None,
+        }
+    }
+
+    /// Evaluate an `arr` size
+    ///
+    /// Like `eval_int`, except that casts keep their operand's value. The
+    /// error says why the expression is not a non-negative `usize`.
+    pub fn eval_size<'a>(
+        &'a self,
+        lookup: &impl Fn(&str) -> Option<&'a ConstExpr>,
+    ) -> Result<usize, &'static str> {
+        let size = self.eval_size_in(lookup, 0)?;
+        usize::try_from(size).map_err(|_| {
+            if size < 0 {
+                "is negative"
+            } else {
+                "is too large"
+            }
+        })
+    }
+
+    fn eval_size_in<'a>(
+        &'a self,
+        lookup: &impl Fn(&str) -> Option<&'a ConstExpr>,
+        depth: usize,
+    ) -> Result<i128, &'static str> {
+        if depth > 64 {
+            return Err("is defined in terms of itself");
+        }
+        match self {
+            ConstExpr::IntLit(n) => Ok(*n),
+            ConstExpr::Paren(inner) | ConstExpr::Cast { expr: inner, .. } => {
+                inner.eval_size_in(lookup, depth + 1)
+            }
+            ConstExpr::Ident(name) => lookup(name)
+                .ok_or("is not a constant")?
+                .eval_size_in(lookup, depth + 1),
+            ConstExpr::Unary {
+                op: UnaryOp::Neg,
+                operand,
+            } => operand
+                .eval_size_in(lookup, depth + 1)?
+                .checked_neg()
+                .ok_or("overflows"),
+            ConstExpr::Binary { op, lhs, rhs } => {
+                let l = lhs.eval_size_in(lookup, depth + 1)?;
+                let r = rhs.eval_size_in(lookup, depth + 1)?;
+                match op {
+                    BinOp::Div | BinOp::Rem if r == 0 => Err("divides by zero"),
+                    BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem => {
+                        op.fold_int(l, r).ok_or("overflows")
+                    }
+                    _ => Err("uses an operator other than +, -, *, /, and %"),
+                }
+            }
+            ConstExpr::Unary { .. } => Err("uses an operator other than +, -, *, /, and %"),
+            ConstExpr::FloatLit(_)
+            | ConstExpr::BoolLit(_)
+            | ConstExpr::CStr(_)
+            | ConstExpr::Bytes(_) => Err("is not an integer"),
+        }
+    }
+}
+
 impl Expr {
+    /// Evaluate an expression built from integer literals and consts
+    ///
+    /// `lookup` resolves a name to a const's value, and should return `None`
+    /// for names that are not consts in scope (locals shadow consts).
+    pub fn eval_int<'a>(&self, lookup: &impl Fn(&str) -> Option<&'a ConstExpr>) -> Option<i128> {
+        match self {
+            Expr::IntLit { value, .. } => Some(*value),
+            Expr::Paren { inner, .. } => inner.eval_int(lookup),
+            Expr::Ident { name, .. } => lookup(name)?.eval_int(lookup),
+            Expr::Unary {
+                op: UnaryOp::Neg,
+                operand,
+                ..
+            } => operand.eval_int(lookup)?.checked_neg(),
+            Expr::Binary { op, lhs, rhs, .. } => {
+                op.fold_int(lhs.eval_int(lookup)?, rhs.eval_int(lookup)?)
+            }
+            _ => None,
+        }
+    }
+
     pub fn span(&self) -> Span {
         match self {
             Expr::IntLit { span, .. }
@@ -166,6 +373,8 @@ impl Expr {
             | Expr::Some { span, .. }
             | Expr::Ok { span, .. }
             | Expr::Err { span, .. }
+            | Expr::Instance { span, .. }
+            | Expr::Builtin { span, .. }
             | Expr::StructLit { span, .. } => span.clone(),
         }
     }
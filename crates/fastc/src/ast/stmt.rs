@@ -47,6 +47,13 @@ pub enum Stmt {
         default: Option<Vec<Stmt>>,
         span: Span,
     },
+    /// match (expr) { case Enum_Variant(a, b): ... }
+    Match {
+        expr: Expr,
+        arms: Vec<MatchArm>,
+        default: Option<Vec<Stmt>>,
+        span: Span,
+    },
     /// return expr;
     Return { value: Option<Expr>, span: Span },
     /// break;
@@ -63,6 +70,32 @@ pub enum Stmt {
     Unsafe { body: Block, span: Span },
     /// A block as a statement
     Block(Block),
+    /// A statement that failed to parse; its error is reported separately
+    Error { span: Span },
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Let { span, .. }
+            | Stmt::Assign { span, .. }
+            | Stmt::If { span, .. }
+            | Stmt::IfLet { span, .. }
+            | Stmt::While { span, .. }
+            | Stmt::For { span, .. }
+            | Stmt::Switch { span, .. }
+            | Stmt::Match { span, .. }
+            | Stmt::Return { span, .. }
+            | Stmt::Break { span }
+            | Stmt::Continue { span }
+            | Stmt::Defer { span, .. }
+            | Stmt::Expr { span, .. }
+            | Stmt::Discard { span, .. }
+            | Stmt::Unsafe { span, .. }
+            | Stmt::Error { span } => span.clone(),
+            Stmt::Block(block) => block.span.clone(),
+        }
+    }
 }
 
 /// A block of statements
@@ -108,3 +141,14 @@ pub struct Case {
     pub stmts: Vec<Stmt>,
     pub span: Span,
 }
+
+/// A match arm: `case Enum_Variant(a, b):` binding the variant's payload
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    /// Qualified variant name (e.g. `Shape_Circle`)
+    pub variant: String,
+    /// One name per payload field; `_` ignores the field
+    pub bindings: Vec<String>,
+    pub stmts: Vec<Stmt>,
+    pub span: Span,
+}
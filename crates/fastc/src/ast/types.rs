@@ -9,6 +9,8 @@ pub enum TypeExpr {
     Primitive(PrimitiveType),
     /// Named type (struct, enum, or alias)
     Named(String),
+    /// Name[T, ...] - instance of a generic struct
+    Generic(String, Vec<TypeExpr>),
     /// ref(T) - non-null immutable reference
     Ref(Box<TypeExpr>),
     /// mref(T) - non-null mutable reference
@@ -35,6 +37,9 @@ pub enum TypeExpr {
     },
     /// void (return type only)
     Void,
+    /// Type of an expression whose error was already reported; the type
+    /// checker accepts it anywhere so that one mistake yields one diagnostic
+    Error,
 }
 
 /// Primitive types
@@ -55,6 +60,42 @@ pub enum PrimitiveType {
     Isize,
 }
 
+impl PrimitiveType {
+    /// Source spelling of the type
+    pub fn name(self) -> &'static str {
+        match self {
+            PrimitiveType::I8 => "i8",
+            PrimitiveType::I16 => "i16",
+            PrimitiveType::I32 => "i32",
+            PrimitiveType::I64 => "i64",
+            PrimitiveType::U8 => "u8",
+            PrimitiveType::U16 => "u16",
+            PrimitiveType::U32 => "u32",
+            PrimitiveType::U64 => "u64",
+            PrimitiveType::F32 => "f32",
+            PrimitiveType::F64 => "f64",
+            PrimitiveType::Bool => "bool",
+            PrimitiveType::Usize => "usize",
+            PrimitiveType::Isize => "isize",
+        }
+    }
+
+    /// Inclusive value range of an integer type (`usize`/`isize` are 64-bit)
+    pub fn int_range(self) -> Option<(i128, i128)> {
+        Some(match self {
+            PrimitiveType::I8 => (i8::MIN.into(), i8::MAX.into()),
+            PrimitiveType::I16 => (i16::MIN.into(), i16::MAX.into()),
+            PrimitiveType::I32 => (i32::MIN.into(), i32::MAX.into()),
+            PrimitiveType::I64 | PrimitiveType::Isize => (i64::MIN.into(), i64::MAX.into()),
+            PrimitiveType::U8 => (0, u8::MAX.into()),
+            PrimitiveType::U16 => (0, u16::MAX.into()),
+            PrimitiveType::U32 => (0, u32::MAX.into()),
+            PrimitiveType::U64 | PrimitiveType::Usize => (0, u64::MAX.into()),
+            PrimitiveType::F32 | PrimitiveType::F64 | PrimitiveType::Bool => return None,
+        })
+    }
+}
+
 /// A type expression with span information
 #[derive(Debug, Clone)]
 pub struct SpannedType {
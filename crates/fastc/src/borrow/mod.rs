@@ -0,0 +1,1186 @@
+//! Ownership and borrow checking pass
+//!
+//! Runs per function after type checking and enforces the rules in
+//! `docs/language.md`:
+//! 1. `mref` borrows are exclusive: a place may not be borrowed mutably
+//!    while another borrow of an overlapping place is live
+//! 2. `own(T)` locals are move-only and may not be used after a move
+//! 3. References to locals may not be returned
+//!
+//! Borrows are lexical. A borrow held by a local lasts until that local
+//! goes out of scope or is reassigned; a borrow passed as a call argument
+//! lasts for the call; any other borrow lasts for its statement.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{
+    Block, Builtin, ElseBranch, Expr, File, FnDecl, ForInit, ForStep, Item, Stmt, TypeExpr,
+};
+use crate::diag::{CompileError, ErrorCode};
+use crate::lexer::Span;
+use crate::typecheck::TypeContext;
+
+/// A local variable or parameter
+struct Local {
+    name: String,
+    ty: Option<TypeExpr>,
+    decl_span: Span,
+    /// Scope depth the local was declared at
+    depth: usize,
+    /// Local whose storage this reference points into, and the borrow site
+    origin: Option<(usize, Span)>,
+}
+
+/// A memory location: a local plus a path of fields, `*` (deref), or `[]`
+#[derive(Clone, PartialEq)]
+struct Place {
+    root: usize,
+    path: Vec<String>,
+}
+
+impl Place {
+    /// Whether two places can refer to the same memory
+    fn overlaps(&self, other: &Place) -> bool {
+        self.root == other.root && self.path.iter().zip(&other.path).all(|(a, b)| a == b)
+    }
+}
+
+/// A live borrow
+#[derive(Clone)]
+struct Borrow {
+    place: Place,
+    mutable: bool,
+    span: Span,
+    /// The local holding the reference; `None` for a temporary
+    holder: Option<usize>,
+}
+
+/// Moved `own` locals, with the span of the move
+type MoveState = HashMap<usize, Span>;
+
+/// Bindings, statements, and span of a switch case or match arm
+type Arm<'s> = (&'s [String], &'s [Stmt], &'s Span);
+
+/// A loop or switch that `break` (and, for loops, `continue`) can leave
+struct BreakTarget {
+    is_loop: bool,
+    scope_depth: usize,
+    breaks: Vec<MoveState>,
+    continues: Vec<MoveState>,
+}
+
+/// Ownership and borrow checker
+pub struct BorrowChecker<'a> {
+    source: &'a str,
+    types: &'a TypeContext,
+    current_fn: String,
+    locals: Vec<Local>,
+    /// Local ids declared in each open scope, innermost last
+    scopes: Vec<Vec<usize>>,
+    /// Deferred blocks registered in each open scope
+    defers: Vec<Vec<Block>>,
+    borrows: Vec<Borrow>,
+    /// Temporary borrows of the statement or call being checked
+    temps: Vec<Borrow>,
+    moved: MoveState,
+    /// Set when control cannot reach the next statement
+    diverged: bool,
+    targets: Vec<BreakTarget>,
+    errors: Vec<CompileError>,
+    /// (message, span start) of reported errors; loop bodies are walked twice
+    reported: HashSet<(String, usize)>,
+}
+
+impl<'a> BorrowChecker<'a> {
+    pub fn new(source: &'a str, types: &'a TypeContext) -> Self {
+        Self {
+            source,
+            types,
+            current_fn: String::new(),
+            locals: Vec::new(),
+            scopes: Vec::new(),
+            defers: Vec::new(),
+            borrows: Vec::new(),
+            temps: Vec::new(),
+            moved: MoveState::new(),
+            diverged: false,
+            targets: Vec::new(),
+            errors: Vec::new(),
+            reported: HashSet::new(),
+        }
+    }
+
+    /// Check every function in a file
+    pub fn check(&mut self, file: &File) -> Result<(), CompileError> {
+        self.check_items(&file.items);
+
+        if !self.errors.is_empty() {
+            Err(CompileError::multiple(std::mem::take(&mut self.errors)))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_items(&mut self, items: &[Item]) {
+        for item in items {
+            match item {
+                Item::Fn(fn_decl) => self.check_fn(fn_decl),
+                Item::Mod(mod_decl) => {
+                    if let Some(body) = &mod_decl.body {
+                        self.check_items(body);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn check_fn(&mut self, fn_decl: &FnDecl) {
+        self.current_fn = fn_decl.name.clone();
+        self.locals.clear();
+        self.borrows.clear();
+        self.moved.clear();
+        self.diverged = false;
+
+        self.enter_scope();
+        for param in &fn_decl.params {
+            self.declare(&param.name, Some(param.ty.clone()), param.span.clone());
+        }
+        self.check_stmts(&fn_decl.body.stmts);
+        self.exit_scope();
+    }
+
+    // === Scopes ===
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(Vec::new());
+        self.defers.push(Vec::new());
+    }
+
+    /// Leave a scope: run its defers, then end the borrows its locals hold
+    fn exit_scope(&mut self) {
+        if !self.diverged {
+            self.run_defers(self.scopes.len() - 1);
+        }
+        let depth = self.scopes.len();
+        self.scopes.pop();
+        self.defers.pop();
+        let locals = &self.locals;
+        self.borrows
+            .retain(|b| b.holder.is_none_or(|h| locals[h].depth < depth));
+    }
+
+    /// Check the defers of every scope from the innermost down to `to_depth`
+    fn run_defers(&mut self, to_depth: usize) {
+        for depth in (to_depth..self.defers.len()).rev() {
+            for body in self.defers[depth].clone().iter().rev() {
+                self.check_block(body);
+            }
+        }
+    }
+
+    fn declare(&mut self, name: &str, ty: Option<TypeExpr>, decl_span: Span) -> usize {
+        let id = self.locals.len();
+        self.locals.push(Local {
+            name: name.to_string(),
+            ty,
+            decl_span,
+            depth: self.scopes.len(),
+            origin: None,
+        });
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(id);
+        }
+        id
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .flatten()
+            .copied()
+            .find(|&id| self.locals[id].name == name)
+    }
+
+    fn is_own(&self, id: usize) -> bool {
+        matches!(self.locals[id].ty, Some(TypeExpr::Own(_)))
+    }
+
+    // === Statements ===
+
+    fn check_block(&mut self, block: &Block) {
+        self.enter_scope();
+        self.check_stmts(&block.stmts);
+        self.exit_scope();
+    }
+
+    fn check_stmts(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            self.check_stmt(stmt);
+            self.temps.clear();
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Let {
+                name,
+                ty,
+                init,
+                span,
+            } => {
+                self.check_expr(init, true);
+                let id = self.declare(name, Some(ty.clone()), span.clone());
+                self.hold(id, init);
+            }
+            Stmt::Assign { lhs, rhs, .. } => {
+                self.check_expr(rhs, true);
+                match lhs {
+                    // Assigning a whole local re-initializes it
+                    Expr::Ident { name, .. } => {
+                        if let Some(id) = self.lookup(name) {
+                            self.moved.remove(&id);
+                            self.borrows.retain(|b| b.holder != Some(id));
+                            self.hold(id, rhs);
+                        }
+                    }
+                    _ => self.check_expr(lhs, false),
+                }
+            }
+            Stmt::If {
+                cond,
+                then_block,
+                else_block,
+                ..
+            } => {
+                self.check_expr(cond, false);
+                self.temps.clear();
+                let entry = self.moved.clone();
+                self.check_block(then_block);
+                let then_state = self.take_state(entry);
+                match else_block {
+                    Some(ElseBranch::ElseIf(stmt)) => self.check_stmt(stmt),
+                    Some(ElseBranch::Else(block)) => self.check_block(block),
+                    None => {}
+                }
+                self.join(then_state);
+            }
+            Stmt::IfLet {
+                name,
+                expr,
+                then_block,
+                else_block,
+                span,
+            } => {
+                self.check_expr(expr, false);
+                self.temps.clear();
+                let entry = self.moved.clone();
+                self.enter_scope();
+                let ty = match self.types.expr_type(&self.current_fn, &expr.span()) {
+                    Some(TypeExpr::Opt(inner) | TypeExpr::Res(inner, _)) => {
+                        Some(inner.as_ref().clone())
+                    }
+                    _ => None,
+                };
+                self.declare(name, ty, span.clone());
+                self.check_stmts(&then_block.stmts);
+                self.exit_scope();
+                let then_state = self.take_state(entry);
+                if let Some(block) = else_block {
+                    self.check_block(block);
+                }
+                self.join(then_state);
+            }
+            Stmt::While { cond, body, .. } => {
+                self.check_loop(Some(cond), None, body);
+            }
+            Stmt::For {
+                init,
+                cond,
+                step,
+                body,
+                span,
+            } => {
+                self.enter_scope();
+                match init {
+                    Some(ForInit::Let { name, ty, init }) => {
+                        self.check_expr(init, true);
+                        let id = self.declare(name, Some(ty.clone()), span.clone());
+                        self.hold(id, init);
+                    }
+                    Some(ForInit::Assign { lhs, rhs }) => {
+                        self.check_stmt(&Stmt::Assign {
+                            lhs: lhs.clone(),
+                            rhs: rhs.clone(),
+                            span: span.clone(),
+                        });
+                    }
+                    Some(ForInit::Call(expr)) => self.check_expr(expr, false),
+                    None => {}
+                }
+                self.temps.clear();
+                self.check_loop(cond.as_ref(), step.as_ref(), body);
+                self.exit_scope();
+            }
+            Stmt::Switch {
+                expr,
+                cases,
+                default,
+                ..
+            } => {
+                self.check_expr(expr, false);
+                self.temps.clear();
+                let arms: Vec<Arm> = cases
+                    .iter()
+                    .map(|c| (&[][..], &c.stmts[..], &c.span))
+                    .collect();
+                self.check_arms(&arms, default.as_deref(), false);
+            }
+            Stmt::Match {
+                expr,
+                arms,
+                default,
+                ..
+            } => {
+                self.check_expr(expr, false);
+                self.temps.clear();
+                let arms: Vec<Arm> = arms
+                    .iter()
+                    .map(|a| (&a.bindings[..], &a.stmts[..], &a.span))
+                    .collect();
+                // A match without a default covers every variant
+                self.check_arms(&arms, default.as_deref(), true);
+            }
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.check_expr(value, true);
+                    self.check_returned(value);
+                }
+                self.run_defers(0);
+                self.diverged = true;
+            }
+            Stmt::Break { .. } => self.leave_to_target(false),
+            Stmt::Continue { .. } => self.leave_to_target(true),
+            Stmt::Error { .. } => {}
+            Stmt::Defer { body, .. } => {
+                if let Some(defers) = self.defers.last_mut() {
+                    defers.push(body.clone());
+                }
+            }
+            Stmt::Expr { expr, .. } | Stmt::Discard { expr, .. } => self.check_expr(expr, true),
+            Stmt::Unsafe { body, .. } => self.check_block(body),
+            Stmt::Block(block) => self.check_block(block),
+        }
+    }
+
+    /// Walk a loop body twice, so that a move in one iteration is seen by
+    /// the uses in the next
+    fn check_loop(&mut self, cond: Option<&Expr>, step: Option<&ForStep>, body: &Block) {
+        let entry = self.moved.clone();
+        let mut head = entry.clone();
+        let mut exits = Vec::new();
+
+        for _ in 0..2 {
+            self.moved = head.clone();
+            self.diverged = false;
+            if let Some(cond) = cond {
+                self.check_expr(cond, false);
+                self.temps.clear();
+            }
+            // A loop without a condition only exits through break
+            if cond.is_some() {
+                exits.push(self.moved.clone());
+            }
+
+            self.targets.push(BreakTarget {
+                is_loop: true,
+                scope_depth: self.scopes.len(),
+                breaks: Vec::new(),
+                continues: Vec::new(),
+            });
+            self.check_block(body);
+            let target = self.targets.pop().unwrap();
+
+            let mut next = if self.diverged {
+                None
+            } else {
+                Some(self.moved.clone())
+            };
+            for state in target.continues {
+                merge_into(&mut next, state);
+            }
+            if let Some(state) = &next {
+                self.moved = state.clone();
+                self.diverged = false;
+                match step {
+                    Some(ForStep::Assign { lhs, rhs }) => {
+                        self.check_expr(rhs, true);
+                        match lhs {
+                            Expr::Ident { name, .. } => {
+                                if let Some(id) = self.lookup(name) {
+                                    self.moved.remove(&id);
+                                }
+                            }
+                            _ => self.check_expr(lhs, false),
+                        }
+                    }
+                    Some(ForStep::Call(expr)) => self.check_expr(expr, false),
+                    None => {}
+                }
+                self.temps.clear();
+                merge_map(&mut head, &self.moved);
+            }
+            exits.extend(target.breaks);
+        }
+
+        let mut exit = None;
+        for state in exits {
+            merge_into(&mut exit, state);
+        }
+        self.diverged = exit.is_none();
+        self.moved = exit.unwrap_or(entry);
+    }
+
+    /// Check switch cases or match arms, each starting from the state
+    /// before the switch
+    fn check_arms(&mut self, arms: &[Arm], default: Option<&[Stmt]>, exhaustive: bool) {
+        let entry = self.moved.clone();
+        let mut end = None;
+        self.targets.push(BreakTarget {
+            is_loop: false,
+            scope_depth: self.scopes.len(),
+            breaks: Vec::new(),
+            continues: Vec::new(),
+        });
+        let no_span = Span::default();
+        let default = default.map(|stmts| (&[][..], stmts, &no_span));
+        for &(bindings, stmts, span) in arms.iter().chain(&default) {
+            self.moved = entry.clone();
+            self.diverged = false;
+            self.enter_scope();
+            for binding in bindings {
+                self.declare(binding, None, span.clone());
+            }
+            self.check_stmts(stmts);
+            self.exit_scope();
+            if !self.diverged {
+                merge_into(&mut end, self.moved.clone());
+            }
+        }
+        let target = self.targets.pop().unwrap();
+        if default.is_none() && !exhaustive {
+            merge_into(&mut end, entry.clone());
+        }
+        for state in target.breaks {
+            merge_into(&mut end, state);
+        }
+        self.diverged = end.is_none();
+        self.moved = end.unwrap_or(entry);
+    }
+
+    /// `break` or `continue`: run the defers being left and record the state
+    fn leave_to_target(&mut self, is_continue: bool) {
+        let Some(index) = self.targets.iter().rposition(|t| t.is_loop || !is_continue) else {
+            return;
+        };
+        self.run_defers(self.targets[index].scope_depth);
+        let state = self.moved.clone();
+        let target = &mut self.targets[index];
+        if is_continue {
+            target.continues.push(state);
+        } else {
+            target.breaks.push(state);
+        }
+        self.diverged = true;
+    }
+
+    /// End a branch: returns its state (`None` if it diverged) and restores
+    /// `entry` for the next branch
+    fn take_state(&mut self, entry: MoveState) -> Option<MoveState> {
+        let state = std::mem::replace(&mut self.moved, entry);
+        let diverged = std::mem::replace(&mut self.diverged, false);
+        (!diverged).then_some(state)
+    }
+
+    /// Merge the state of an earlier branch into the current one
+    fn join(&mut self, other: Option<MoveState>) {
+        match other {
+            Some(state) if self.diverged => {
+                self.moved = state;
+                self.diverged = false;
+            }
+            Some(state) => merge_map(&mut self.moved, &state),
+            None => {}
+        }
+    }
+
+    // === Expressions ===
+
+    /// Check an expression; `moves` is set where an `own` value is moved
+    /// out of a local: initializers, assignments, returns, and arguments
+    fn check_expr(&mut self, expr: &Expr, moves: bool) {
+        match expr {
+            Expr::Ident { name, span } => {
+                let Some(id) = self.lookup(name) else {
+                    return;
+                };
+                if let Some(move_span) = self.moved.get(&id).cloned() {
+                    self.report(CompileError::borrow_with_hint(
+                        ErrorCode::UseAfterMove,
+                        format!("use of moved value '{}'", name),
+                        (span.clone(), "value used here after move"),
+                        (move_span, "value moved here"),
+                        self.source,
+                        format!("assign '{}' a new value before using it again", name),
+                    ));
+                } else if moves && self.is_own(id) {
+                    self.moved.insert(id, span.clone());
+                }
+            }
+            Expr::Paren { inner, .. } => self.check_expr(inner, moves),
+            Expr::Some { value, .. } | Expr::Ok { value, .. } | Expr::Err { value, .. } => {
+                self.check_expr(value, moves)
+            }
+            Expr::StructLit { fields, .. } => {
+                for field in fields {
+                    self.check_expr(&field.value, moves);
+                }
+            }
+            Expr::Call { callee, args, .. } => {
+                self.check_expr(callee, false);
+                // Borrows passed to the call last until it returns
+                let mark = self.temps.len();
+                for arg in args {
+                    self.check_expr(arg, true);
+                }
+                self.temps.truncate(mark);
+            }
+            Expr::Addr { operand, span } => {
+                self.check_expr(operand, false);
+                if let Some(place) = self.place(operand) {
+                    let mutable = matches!(
+                        self.types.expr_type(&self.current_fn, span),
+                        Some(TypeExpr::Mref(_))
+                    );
+                    self.check_borrow(&place, mutable, span);
+                    self.temps.push(Borrow {
+                        place,
+                        mutable,
+                        span: span.clone(),
+                        holder: None,
+                    });
+                }
+            }
+            Expr::Binary { lhs, rhs, .. } => {
+                self.check_expr(lhs, false);
+                self.check_expr(rhs, false);
+            }
+            Expr::At { base, index, .. } => {
+                self.check_expr(base, false);
+                self.check_expr(index, false);
+            }
+            Expr::Builtin { op, args, span } => {
+                for arg in args {
+                    self.check_expr(arg, false);
+                }
+                // A slice of an arr borrows its elements like `addr` does
+                if *op == Builtin::AsSlice
+                    && let Some(place) = self.place(&args[0])
+                {
+                    self.check_borrow(&place, false, span);
+                    self.temps.push(Borrow {
+                        place,
+                        mutable: false,
+                        span: span.clone(),
+                        holder: None,
+                    });
+                }
+            }
+            Expr::Unary { operand, .. } | Expr::Deref { operand, .. } => {
+                self.check_expr(operand, false)
+            }
+            Expr::Field { base, .. } => self.check_expr(base, false),
+            Expr::Cast { expr, .. } => self.check_expr(expr, false),
+            Expr::IntLit { .. }
+            | Expr::FloatLit { .. }
+            | Expr::BoolLit { .. }
+            | Expr::CStr { .. }
+            | Expr::Bytes { .. }
+            | Expr::None { .. }
+            | Expr::Instance { .. } => {}
+        }
+    }
+
+    /// The place an addressable expression names, if it is rooted in a local
+    fn place(&self, expr: &Expr) -> Option<Place> {
+        match expr {
+            Expr::Ident { name, .. } => Some(Place {
+                root: self.lookup(name)?,
+                path: Vec::new(),
+            }),
+            Expr::Paren { inner, .. } => self.place(inner),
+            Expr::Field { base, field, .. } => {
+                let mut place = self.place(base)?;
+                place.path.push(field.clone());
+                Some(place)
+            }
+            Expr::Deref { operand, .. } => {
+                let mut place = self.place(operand)?;
+                place.path.push("*".to_string());
+                Some(place)
+            }
+            Expr::At { base, .. } => {
+                let mut place = self.place(base)?;
+                place.path.push("[]".to_string());
+                Some(place)
+            }
+            _ => None,
+        }
+    }
+
+    /// Report a new borrow that conflicts with a live one
+    fn check_borrow(&mut self, place: &Place, mutable: bool, span: &Span) {
+        let conflict = self
+            .borrows
+            .iter()
+            .chain(&self.temps)
+            .find(|b| (mutable || b.mutable) && b.place.overlaps(place))
+            .cloned();
+        let Some(earlier) = conflict else {
+            return;
+        };
+
+        let name = &self.locals[place.root].name;
+        let (code, message, label, related_label) = match (earlier.mutable, mutable) {
+            (true, true) => (
+                ErrorCode::DoubleMutableBorrow,
+                format!("cannot borrow '{}' mutably more than once at a time", name),
+                "second mutable borrow here",
+                "first mutable borrow here",
+            ),
+            (false, _) => (
+                ErrorCode::ConflictingBorrow,
+                format!(
+                    "cannot borrow '{}' mutably while it is borrowed immutably",
+                    name
+                ),
+                "mutable borrow here",
+                "immutable borrow here",
+            ),
+            (true, false) => (
+                ErrorCode::ConflictingBorrow,
+                format!(
+                    "cannot borrow '{}' immutably while it is borrowed mutably",
+                    name
+                ),
+                "immutable borrow here",
+                "mutable borrow here",
+            ),
+        };
+        let hint = match earlier.holder {
+            Some(holder) => format!(
+                "the earlier borrow lasts until '{}' goes out of scope or is reassigned",
+                self.locals[holder].name
+            ),
+            None => "both borrows are live for the whole statement".to_string(),
+        };
+        self.report(CompileError::borrow_with_hint(
+            code,
+            message,
+            (span.clone(), label),
+            (earlier.span, related_label),
+            self.source,
+            hint,
+        ));
+    }
+
+    /// Make local `id` hold the borrows of the value it was assigned
+    fn hold(&mut self, id: usize, value: &Expr) {
+        let spans = self.held_borrows(value);
+        let mut origin = None;
+        let mut held = Vec::new();
+        self.temps.retain(|b| {
+            if spans.contains(&b.span) {
+                held.push(b.clone());
+                false
+            } else {
+                true
+            }
+        });
+        for mut borrow in held {
+            if self.is_local_storage(&borrow.place) {
+                origin = Some((borrow.place.root, borrow.span.clone()));
+            }
+            borrow.holder = Some(id);
+            self.borrows.push(borrow);
+        }
+        // Copying a reference copies where it points
+        if origin.is_none()
+            && let Some(source) = self.value_local(value)
+        {
+            origin = self.locals[source].origin.clone();
+        }
+        self.locals[id].origin = origin;
+    }
+
+    /// Spans of the borrows that end up inside a value, rather than only
+    /// being passed to a call while computing it
+    fn held_borrows(&self, value: &Expr) -> Vec<Span> {
+        match value {
+            Expr::Addr { span, .. }
+            | Expr::Builtin {
+                op: Builtin::AsSlice,
+                span,
+                ..
+            } => vec![span.clone()],
+            Expr::Builtin {
+                op: Builtin::Subslice,
+                args,
+                ..
+            } => self.held_borrows(&args[0]),
+            Expr::Paren { inner, .. } => self.held_borrows(inner),
+            Expr::Some { value, .. } | Expr::Ok { value, .. } | Expr::Err { value, .. } => {
+                self.held_borrows(value)
+            }
+            Expr::StructLit { fields, .. } => fields
+                .iter()
+                .flat_map(|f| self.held_borrows(&f.value))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The local a value is copied from, if it is one
+    fn value_local(&self, value: &Expr) -> Option<usize> {
+        match value {
+            Expr::Ident { name, .. } => self.lookup(name),
+            Expr::Paren { inner, .. } => self.value_local(inner),
+            // A subslice points into the same storage
+            Expr::Builtin {
+                op: Builtin::Subslice,
+                args,
+                ..
+            } => self.value_local(&args[0]),
+            _ => None,
+        }
+    }
+
+    /// Whether a place lives in the function's own storage, so that a
+    /// reference to it must not outlive the call
+    fn is_local_storage(&self, place: &Place) -> bool {
+        let through_pointer = matches!(
+            self.locals[place.root].ty,
+            Some(
+                TypeExpr::Ref(_)
+                    | TypeExpr::Mref(_)
+                    | TypeExpr::Raw(_)
+                    | TypeExpr::Rawm(_)
+                    | TypeExpr::Own(_)
+                    | TypeExpr::Slice(_)
+            )
+        );
+        place.path.is_empty() || !(through_pointer || place.path.iter().any(|p| p == "*"))
+    }
+
+    /// Reject a returned value that references a local
+    fn check_returned(&mut self, value: &Expr) {
+        match value {
+            Expr::Addr { operand, span } => self.check_returned_borrow(operand, span),
+            Expr::Builtin { op, args, span } => match op {
+                Builtin::AsSlice => self.check_returned_borrow(&args[0], span),
+                Builtin::Subslice => self.check_returned(&args[0]),
+                _ => {}
+            },
+            Expr::Ident { name, span } => {
+                let Some(id) = self.lookup(name) else {
+                    return;
+                };
+                if let Some((root, borrow_span)) = self.locals[id].origin.clone() {
+                    let root_name = self.locals[root].name.clone();
+                    self.report(CompileError::borrow_with_hint(
+                        ErrorCode::ReturnLocalRef,
+                        format!("cannot return a reference to local '{}'", root_name),
+                        (span.clone(), "returned here"),
+                        (borrow_span, format!("'{}' is borrowed here", root_name)),
+                        self.source,
+                        format!("'{}' no longer exists once the function returns", root_name),
+                    ));
+                }
+            }
+            Expr::Paren { inner, .. } => self.check_returned(inner),
+            Expr::Some { value, .. } | Expr::Ok { value, .. } | Expr::Err { value, .. } => {
+                self.check_returned(value)
+            }
+            Expr::StructLit { fields, .. } => {
+                for field in fields {
+                    self.check_returned(&field.value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Reject returning a borrow (`addr` or `as_slice`) of a local
+    fn check_returned_borrow(&mut self, operand: &Expr, span: &Span) {
+        if let Some(place) = self.place(operand)
+            && self.is_local_storage(&place)
+        {
+            let local = &self.locals[place.root];
+            self.report(CompileError::borrow_with_hint(
+                ErrorCode::ReturnLocalRef,
+                format!("cannot return a reference to local '{}'", local.name),
+                (span.clone(), "returns a reference to a local"),
+                (
+                    local.decl_span.clone(),
+                    format!("'{}' is declared here", local.name),
+                ),
+                self.source,
+                format!(
+                    "'{}' no longer exists once the function returns",
+                    local.name
+                ),
+            ));
+        }
+    }
+
+    fn report(&mut self, error: CompileError) {
+        let offset = match &error {
+            CompileError::Borrow { labels, .. } => labels.first().map_or(0, |l| l.offset()),
+            _ => 0,
+        };
+        let key = (error.to_string(), offset);
+        if self.reported.insert(key) {
+            self.errors.push(error);
+        }
+    }
+}
+
+/// Union of moved locals, keeping the first move span seen
+fn merge_map(into: &mut MoveState, from: &MoveState) {
+    for (id, span) in from {
+        into.entry(*id).or_insert_with(|| span.clone());
+    }
+}
+
+fn merge_into(into: &mut Option<MoveState>, state: MoveState) {
+    match into {
+        Some(existing) => merge_map(existing, &state),
+        None => *into = Some(state),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::driver::compile;
+
+    fn check_error(source: &str, expected_substr: &str) {
+        let result = compile(source, "test.fc");
+        assert!(result.is_err(), "Expected error for: {}", source);
+        let err_msg = format!("{:?}", result.unwrap_err());
+        assert!(
+            err_msg.contains(expected_substr),
+            "Expected error containing '{}', got: {}",
+            expected_substr,
+            err_msg
+        );
+    }
+
+    fn check_ok(source: &str) {
+        let result = compile(source, "test.fc");
+        assert!(
+            result.is_ok(),
+            "Expected success for: {}\nGot error: {:?}",
+            source,
+            result.err()
+        );
+    }
+
+    // === Borrow tests ===
+
+    #[test]
+    fn test_two_mref_borrows_held() {
+        check_error(
+            r#"
+            fn f() -> void {
+                let x: i32 = 1;
+                let a: mref(i32) = addr(x);
+                let b: mref(i32) = addr(x);
+            }
+            "#,
+            "cannot borrow 'x' mutably more than once at a time",
+        );
+    }
+
+    #[test]
+    fn test_two_mref_args_in_one_call() {
+        check_error(
+            r#"
+            fn swap(a: mref(i32), b: mref(i32)) -> void {}
+            fn f() -> void {
+                let x: i32 = 1;
+                swap(addr(x), addr(x));
+            }
+            "#,
+            "cannot borrow 'x' mutably more than once at a time",
+        );
+    }
+
+    #[test]
+    fn test_mref_while_borrowed_immutably() {
+        check_error(
+            r#"
+            fn f() -> void {
+                let x: i32 = 1;
+                let a: ref(i32) = addr(x);
+                let b: mref(i32) = addr(x);
+            }
+            "#,
+            "cannot borrow 'x' mutably while it is borrowed immutably",
+        );
+    }
+
+    #[test]
+    fn test_mref_of_field_conflicts_with_whole() {
+        check_error(
+            r#"
+            struct P { a: i32, b: i32 }
+            fn f() -> void {
+                let p: P = P { a: 1, b: 2 };
+                let whole: mref(P) = addr(p);
+                let part: ref(i32) = addr(p.a);
+            }
+            "#,
+            "cannot borrow 'p' immutably while it is borrowed mutably",
+        );
+    }
+
+    #[test]
+    fn test_mref_of_disjoint_fields() {
+        check_ok(
+            r#"
+            struct P { a: i32, b: i32 }
+            fn f() -> void {
+                let p: P = P { a: 1, b: 2 };
+                let a: mref(i32) = addr(p.a);
+                let b: mref(i32) = addr(p.b);
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_shared_borrows_alias() {
+        check_ok(
+            r#"
+            fn f() -> void {
+                let x: i32 = 1;
+                let a: ref(i32) = addr(x);
+                let b: ref(i32) = addr(x);
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_sequential_mref_calls() {
+        check_ok(
+            r#"
+            fn bump(p: mref(i32)) -> void {}
+            fn f() -> void {
+                let x: i32 = 1;
+                bump(addr(x));
+                bump(addr(x));
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_borrow_ends_with_scope() {
+        check_ok(
+            r#"
+            fn f() -> void {
+                let x: i32 = 1;
+                {
+                    let a: mref(i32) = addr(x);
+                }
+                let b: mref(i32) = addr(x);
+            }
+            "#,
+        );
+    }
+
+    // === Move tests ===
+
+    #[test]
+    fn test_use_after_move() {
+        check_error(
+            r#"
+            fn take(p: own(i32)) -> void {}
+            fn f(p: own(i32)) -> void {
+                take(p);
+                take(p);
+            }
+            "#,
+            "use of moved value 'p'",
+        );
+    }
+
+    #[test]
+    fn test_use_after_move_into_let() {
+        check_error(
+            r#"
+            fn f(p: own(i32)) -> own(i32) {
+                let q: own(i32) = p;
+                return p;
+            }
+            "#,
+            "use of moved value 'p'",
+        );
+    }
+
+    #[test]
+    fn test_move_in_one_branch() {
+        check_error(
+            r#"
+            fn take(p: own(i32)) -> void {}
+            fn f(p: own(i32), c: bool) -> void {
+                if (c) {
+                    take(p);
+                }
+                take(p);
+            }
+            "#,
+            "use of moved value 'p'",
+        );
+    }
+
+    #[test]
+    fn test_move_in_loop() {
+        check_error(
+            r#"
+            fn take(p: own(i32)) -> void {}
+            fn f(p: own(i32), c: bool) -> void {
+                while (c) {
+                    take(p);
+                }
+            }
+            "#,
+            "use of moved value 'p'",
+        );
+    }
+
+    #[test]
+    fn test_move_in_diverging_branch() {
+        check_ok(
+            r#"
+            fn take(p: own(i32)) -> void {}
+            fn f(p: own(i32), c: bool) -> void {
+                if (c) {
+                    take(p);
+                    return;
+                }
+                take(p);
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_reassign_after_move() {
+        check_ok(
+            r#"
+            fn take(p: own(i32)) -> void {}
+            fn f(p: own(i32), q: own(i32)) -> void {
+                take(p);
+                p = q;
+                take(p);
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_copy_types_not_moved() {
+        check_ok(
+            r#"
+            fn use_it(x: i32) -> void {}
+            fn f(x: i32) -> void {
+                use_it(x);
+                use_it(x);
+            }
+            "#,
+        );
+    }
+
+    // === Returned reference tests ===
+
+    #[test]
+    fn test_return_ref_to_local() {
+        check_error(
+            r#"
+            fn f() -> ref(i32) {
+                let x: i32 = 1;
+                return addr(x);
+            }
+            "#,
+            "cannot return a reference to local 'x'",
+        );
+    }
+
+    #[test]
+    fn test_return_ref_to_param() {
+        check_error(
+            r#"
+            fn f(x: i32) -> ref(i32) {
+                return addr(x);
+            }
+            "#,
+            "cannot return a reference to local 'x'",
+        );
+    }
+
+    #[test]
+    fn test_return_slice_of_local() {
+        check_error(
+            r#"
+            fn f(a: arr(i32, 4)) -> slice(i32) {
+                let s: slice(i32) = as_slice(a);
+                return subslice(s, 1, 3);
+            }
+            "#,
+            "cannot return a reference to local 'a'",
+        );
+        check_error(
+            "fn f(a: arr(i32, 4)) -> slice(i32) { return as_slice(a); }",
+            "cannot return a reference to local 'a'",
+        );
+    }
+
+    #[test]
+    fn test_return_ref_through_local() {
+        check_error(
+            r#"
+            fn f() -> ref(i32) {
+                let x: i32 = 1;
+                let r: ref(i32) = addr(x);
+                return r;
+            }
+            "#,
+            "cannot return a reference to local 'x'",
+        );
+    }
+
+    #[test]
+    fn test_return_ref_through_param() {
+        check_ok(
+            r#"
+            struct P { a: i32, b: i32 }
+            fn f(p: ref(P)) -> ref(i32) {
+                return addr(deref(p).a);
+            }
+            "#,
+        );
+    }
+}
@@ -6,7 +6,8 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use crate::deps::{Fetcher, LockedPackage, Lockfile, Manifest};
-use crate::diag::CompileError;
+use crate::diag::{CompileError, Reporter};
+use crate::p10::P10Config;
 
 /// Build context for orchestrating project compilation
 pub struct BuildContext {
@@ -33,6 +34,8 @@ pub enum BuildError {
     FetchError(String),
     /// Compile error
     CompileError(CompileError),
+    /// Compilation failed; its diagnostics were already reported
+    CompileFailed(String),
     /// Cache initialization error
     CacheError,
     /// C compiler error
@@ -51,6 +54,7 @@ impl std::fmt::Display for BuildError {
             BuildError::ManifestError(msg) => write!(f, "manifest error: {}", msg),
             BuildError::FetchError(msg) => write!(f, "fetch error: {}", msg),
             BuildError::CompileError(e) => write!(f, "{}", e),
+            BuildError::CompileFailed(file) => write!(f, "could not compile {}", file),
             BuildError::CacheError => write!(f, "failed to initialize cache directory"),
             BuildError::CcError(msg) => write!(f, "C compiler error: {}", msg),
             BuildError::RuntimeError(code) => write!(f, "program exited with code {}", code),
@@ -212,8 +216,13 @@ impl BuildContext {
 
     /// Compile the project to C code
     ///
-    /// Returns the path to the generated C file
-    pub fn compile(&self, output_dir: &Path, _release: bool) -> Result<PathBuf, BuildError> {
+    /// Diagnostics go to `reporter`. Returns the path to the generated C file
+    pub fn compile(
+        &self,
+        output_dir: &Path,
+        _release: bool,
+        reporter: &mut Reporter,
+    ) -> Result<PathBuf, BuildError> {
         // Determine source file
         let src_dir = self.project_root.join("src");
         let main_file = src_dir.join("main.fc");
@@ -237,7 +246,22 @@ impl BuildContext {
 
         // Compile
         let filename = source_file.display().to_string();
-        let (c_code, header) = crate::compile_with_options(&source, &filename, true)?;
+        let output = match crate::compile_with_p10(&source, &filename, true, P10Config::standard())
+        {
+            Ok(output) => output,
+            Err(e) => {
+                reporter
+                    .report(e, &filename)
+                    .map_err(|e| BuildError::Io(e.to_string()))?;
+                return Err(BuildError::CompileFailed(filename));
+            }
+        };
+        for warning in output.warnings {
+            reporter
+                .report(warning, &filename)
+                .map_err(|e| BuildError::Io(e.to_string()))?;
+        }
+        let (c_code, header) = (output.c_code, output.header);
 
         // Create output directory
         std::fs::create_dir_all(output_dir).map_err(|e| BuildError::Io(e.to_string()))?;
@@ -289,6 +313,14 @@ impl BuildContext {
         // Add runtime include path
         // Try to find the runtime directory relative to the executable or use env var
         if let Some(runtime_path) = Self::find_runtime_include() {
+            // Link the runtime implementation unit (trap reporter, std I/O)
+            let runtime_c = Path::new(&runtime_path).join("fastc_runtime.c");
+            if runtime_c.exists() {
+                let leaked: &'static str =
+                    Box::leak(runtime_c.to_string_lossy().into_owned().into_boxed_str());
+                args.push(leaked);
+            }
+
             args.push("-I");
             // We need to leak this string to get a &str with 'static lifetime
             // This is acceptable for a CLI tool
@@ -5,12 +5,13 @@
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
-use crate::ast::{File, Item, ModDecl};
-use crate::diag::CompileError;
+use crate::ast::{File, Item, ModDecl, SourceFile};
+use crate::diag::{CompileError, ErrorCode};
 use crate::lexer::{Lexer, strip_comments};
 use crate::parser::Parser;
 
 use super::resolver::{ModuleError, ModuleResolver};
+use super::stdlib;
 
 /// Module loader that expands `mod name;` declarations
 pub struct ModuleLoader {
@@ -85,6 +86,16 @@ impl ModuleLoader {
         let canonical = module_path
             .canonicalize()
             .unwrap_or_else(|_| module_path.clone());
+        if self.loaded.contains(&canonical) && canonical == stdlib::root_module() {
+            // Every file may import std; its items are already in the namespace
+            return Ok(ModDecl {
+                is_pub: mod_decl.is_pub,
+                name: mod_decl.name.clone(),
+                body: Some(Vec::new()),
+                file: None,
+                span: mod_decl.span.clone(),
+            });
+        }
         if self.loaded.contains(&canonical) {
             return Err(LoaderError::CircularImport {
                 module: mod_decl.name.clone(),
@@ -94,7 +105,7 @@ impl ModuleLoader {
         self.loaded.insert(canonical.clone());
 
         // Load and parse the module file
-        let source = std::fs::read_to_string(&module_path).map_err(|e| LoaderError::Io {
+        let source = stdlib::read_module(&module_path).map_err(|e| LoaderError::Io {
             path: module_path.clone(),
             error: e.to_string(),
         })?;
@@ -112,12 +123,29 @@ impl ModuleLoader {
             is_pub: mod_decl.is_pub,
             name: mod_decl.name.clone(),
             body: Some(ast.items),
+            file: Some(SourceFile {
+                path: module_path.to_string_lossy().to_string(),
+                text: source.into(),
+            }),
             span: mod_decl.span.clone(),
         })
     }
 
     /// Resolve a module name to its file path
     fn resolve_module_path(&self, name: &str, source_dir: &Path) -> Result<PathBuf, LoaderError> {
+        // Submodules of the bundled std resolve only within it
+        if stdlib::is_std_path(source_dir) {
+            return stdlib::find_module(name, source_dir).map_err(|searched| {
+                LoaderError::ModuleNotFound {
+                    name: name.to_string(),
+                    searched,
+                }
+            });
+        }
+        if name == stdlib::STD_MODULE {
+            return Ok(stdlib::root_module());
+        }
+
         // First try relative to source directory
         let direct_path = source_dir.join(format!("{}.fc", name));
         if direct_path.exists() {
@@ -219,7 +247,13 @@ impl From<ModuleError> for LoaderError {
 
 impl From<LoaderError> for CompileError {
     fn from(e: LoaderError) -> Self {
-        CompileError::parse(e.to_string(), 0..0, "")
+        let code = match &e {
+            LoaderError::ModuleNotFound { .. }
+            | LoaderError::Resolution(ModuleError::NotFound { .. }) => ErrorCode::ModuleNotFound,
+            LoaderError::CircularImport { .. } => ErrorCode::CircularModule,
+            _ => ErrorCode::ModuleLoad,
+        };
+        CompileError::parse(code, e.to_string(), 0..0, "")
     }
 }
 
@@ -304,4 +338,45 @@ mod tests {
 
         assert!(matches!(result, Err(LoaderError::CircularImport { .. })));
     }
+
+    #[test]
+    fn test_expand_std_once() {
+        let project = setup_test_project();
+
+        // Both the main file and utils import std, without a dependency entry
+        fs::write(project.path().join("src/utils.fc"), "mod std;").unwrap();
+
+        let source = "mod std;\nmod utils;";
+        let lexer = Lexer::new(source);
+        let tokens = strip_comments(lexer.collect());
+        let mut parser = Parser::new(&tokens, source, "main.fc");
+        let mut ast = parser.parse_file().unwrap();
+
+        let mut loader = ModuleLoader::new(project.path());
+        loader
+            .expand_modules(&mut ast, &project.path().join("src"))
+            .unwrap();
+
+        let Item::Mod(std_mod) = &ast.items[0] else {
+            panic!("expected mod item");
+        };
+        let names: Vec<&str> = std_mod
+            .body
+            .iter()
+            .flatten()
+            .filter_map(|item| match item {
+                Item::Mod(m) => Some(m.name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["slices", "str", "io"]);
+
+        let Item::Mod(utils) = &ast.items[1] else {
+            panic!("expected mod item");
+        };
+        let Some(Item::Mod(inner_std)) = utils.body.as_ref().unwrap().first() else {
+            panic!("expected std inside utils");
+        };
+        assert_eq!(inner_std.body.as_ref().unwrap().len(), 0);
+    }
 }
@@ -4,6 +4,7 @@
 //! - Parsing fastc.toml manifest files
 //! - Resolving module paths (mod declarations)
 //! - Loading and expanding module files
+//! - Bundling the standard library (`mod std;`)
 //! - Fetching Git-based dependencies
 //! - Managing the dependency cache
 //! - Lock file management for reproducible builds
@@ -14,6 +15,7 @@ mod loader;
 mod lockfile;
 mod manifest;
 mod resolver;
+mod stdlib;
 
 pub use cache::Cache;
 pub use fetcher::{FetchError, Fetcher};
@@ -5,6 +5,8 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+use super::stdlib;
+
 /// Resolved module information
 #[derive(Debug, Clone)]
 pub struct ResolvedModule {
@@ -62,6 +64,8 @@ impl ModuleResolver {
     /// Given `mod utils;`, this will look for:
     /// 1. src/utils.fc
     /// 2. src/utils/mod.fc (for submodules)
+    ///
+    /// `mod std;` always resolves to the bundled standard library.
     pub fn resolve(&mut self, module_name: &str) -> Result<&ResolvedModule, ModuleError> {
         // Check cache first
         if self.cache.contains_key(module_name) {
@@ -94,6 +98,10 @@ impl ModuleResolver {
 
     /// Find the source file for a module
     pub fn find_module_file(&self, module_name: &str) -> Result<PathBuf, ModuleError> {
+        if module_name == stdlib::STD_MODULE {
+            return Ok(stdlib::root_module());
+        }
+
         // Try src/module_name.fc first
         let direct_path = self.src_dir.join(format!("{}.fc", module_name));
         if direct_path.exists() {
@@ -123,7 +131,7 @@ impl ModuleResolver {
 
         // Load content if not already loaded
         if module.content.is_none() {
-            let content = std::fs::read_to_string(&module.path).map_err(|e| ModuleError::Io {
+            let content = stdlib::read_module(&module.path).map_err(|e| ModuleError::Io {
                 path: module.path.clone(),
                 error: e.to_string(),
             })?;
@@ -260,4 +268,13 @@ name = "test_project"
         let content = resolver.load_content("utils").unwrap();
         assert!(content.contains("helper"));
     }
+
+    #[test]
+    fn test_resolve_std_without_manifest() {
+        let dir = TempDir::new().unwrap();
+        let mut resolver = ModuleResolver::new(dir.path().to_path_buf());
+
+        let content = resolver.load_content("std").unwrap();
+        assert!(content.contains("pub mod slices;"));
+    }
 }
@@ -0,0 +1,88 @@
+//! The bundled FastC standard library
+//!
+//! `mod std;` resolves to sources compiled into fastc itself, so projects
+//! and standalone files can use the standard library without listing it in
+//! `fastc.toml`. Bundled files live under a virtual directory that never
+//! exists on disk.
+
+use std::path::{Path, PathBuf};
+
+/// Module name that always refers to the bundled standard library
+pub const STD_MODULE: &str = "std";
+
+/// Virtual directory holding the bundled sources
+const STD_ROOT: &str = "<std>";
+
+/// Bundled sources by path relative to [`STD_ROOT`]
+const FILES: &[(&str, &str)] = &[
+    ("mod.fc", include_str!("../../std/mod.fc")),
+    ("slices.fc", include_str!("../../std/slices.fc")),
+    ("str.fc", include_str!("../../std/str.fc")),
+    ("io.fc", include_str!("../../std/io.fc")),
+];
+
+/// Path of the standard library's root module
+pub fn root_module() -> PathBuf {
+    Path::new(STD_ROOT).join("mod.fc")
+}
+
+/// Whether `path` names a bundled file or directory
+pub fn is_std_path(path: &Path) -> bool {
+    path.starts_with(STD_ROOT)
+}
+
+/// Source of the bundled file at `path`
+pub fn source(path: &Path) -> Option<&'static str> {
+    let relative = path.strip_prefix(STD_ROOT).ok()?;
+    FILES
+        .iter()
+        .find(|(name, _)| Path::new(name) == relative)
+        .map(|(_, source)| *source)
+}
+
+/// Find submodule `name` of the bundled module directory `dir`
+///
+/// Mirrors on-disk lookup: `dir/name.fc`, then `dir/name/mod.fc`.
+pub fn find_module(name: &str, dir: &Path) -> Result<PathBuf, Vec<PathBuf>> {
+    let searched = vec![
+        dir.join(format!("{}.fc", name)),
+        dir.join(name).join("mod.fc"),
+    ];
+    match searched.iter().find(|path| source(path).is_some()) {
+        Some(path) => Ok(path.clone()),
+        None => Err(searched),
+    }
+}
+
+/// Read a module file from the bundled library or from disk
+pub fn read_module(path: &Path) -> std::io::Result<String> {
+    if is_std_path(path) {
+        return source(path).map(str::to_string).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "no such std module")
+        });
+    }
+    std::fs::read_to_string(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_module_is_bundled() {
+        assert!(source(&root_module()).unwrap().contains("pub mod io;"));
+    }
+
+    #[test]
+    fn test_find_submodule() {
+        let dir = root_module().parent().unwrap().to_path_buf();
+        assert_eq!(find_module("slices", &dir).unwrap(), dir.join("slices.fc"));
+        assert!(find_module("missing", &dir).is_err());
+    }
+
+    #[test]
+    fn test_disk_paths_are_not_bundled() {
+        assert!(!is_std_path(Path::new("src/std.fc")));
+        assert!(source(Path::new("mod.fc")).is_none());
+    }
+}
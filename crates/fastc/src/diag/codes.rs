@@ -0,0 +1,219 @@
+//! Stable error codes
+//!
+//! Every distinct compile error carries a numbered code that stays the same
+//! across releases, so tools and tests can match on it instead of on the
+//! message text. `fastc explain <CODE>` prints the long-form explanation
+//! kept in `explain/<CODE>.md`.
+//!
+//! Codes are grouped by the phase that reports them: `E00xx` parsing and
+//! modules, `E01xx` name resolution, `E02xx` types, `E03xx` unsafe code,
+//! `E04xx` borrows and moves, and `E05xx` generics.
+
+use std::fmt;
+
+macro_rules! error_codes {
+    ($($name:ident = $code:literal, $title:literal;)*) => {
+        /// A stable error code
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum ErrorCode {
+            $($name,)*
+        }
+
+        impl ErrorCode {
+            /// Every code, in numeric order
+            pub const ALL: &[ErrorCode] = &[$(ErrorCode::$name,)*];
+
+            /// The code as written in diagnostics (e.g. `E0201`)
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(ErrorCode::$name => $code,)*
+                }
+            }
+
+            /// One-line summary of the error
+            pub fn title(self) -> &'static str {
+                match self {
+                    $(ErrorCode::$name => $title,)*
+                }
+            }
+
+            /// Long-form explanation with an erroneous and a fixed example
+            pub fn explanation(self) -> &'static str {
+                match self {
+                    $(ErrorCode::$name => include_str!(concat!("explain/", $code, ".md")),)*
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    UnexpectedToken = "E0001", "unexpected token";
+    ChainedOperators = "E0002", "binary operators chained without parentheses";
+    DuplicateTypeParam = "E0003", "type parameter declared twice";
+    UnsafeDrop = "E0004", "`@drop` on an unsafe function";
+    ModuleNotFound = "E0005", "module not found";
+    CircularModule = "E0006", "circular module import";
+    ModuleLoad = "E0007", "module could not be loaded";
+
+    UndefinedName = "E0101", "undefined name";
+    Redefinition = "E0102", "name defined twice in one scope";
+    UndefinedType = "E0103", "undefined type";
+    NotAType = "E0104", "name used as a type is not a type";
+    UndefinedConstant = "E0105", "undefined constant";
+    NotAConstant = "E0106", "name used in a constant expression is not a constant";
+    CyclicConstant = "E0107", "constant defined in terms of itself";
+
+    TypeMismatch = "E0201", "type mismatch";
+    NonBoolCondition = "E0202", "condition is not bool";
+    ArgumentCount = "E0203", "wrong number of arguments";
+    NotCallable = "E0204", "call of a value that is not a function";
+    OperandType = "E0205", "operand has the wrong type for its operator";
+    InvalidCast = "E0206", "invalid cast";
+    FieldOnNonStruct = "E0207", "field access on a value that is not a struct";
+    UnknownField = "E0208", "struct has no such field";
+    MissingFields = "E0209", "struct literal is missing fields";
+    DuplicateField = "E0210", "struct literal initializes a field twice";
+    NotAStruct = "E0211", "struct literal names a type that is not a struct";
+    DerefNonPointer = "E0212", "dereference of a value that is not a pointer";
+    InvalidIndex = "E0213", "invalid indexing";
+    IndexOutOfBounds = "E0214", "constant index out of bounds";
+    LiteralOutOfRange = "E0215", "integer literal out of range";
+    NotAssignable = "E0216", "assignment to an expression that is not a place";
+    AddressOfValue = "E0217", "address of an expression that is not a place";
+    MissingReturnValue = "E0218", "return without a value in a function that returns one";
+    ExitFromDefer = "E0219", "return, break, or continue inside `defer`";
+    IfLetOperand = "E0220", "`if let` on a value that is not opt or res";
+    BuiltinArgument = "E0221", "builtin called with the wrong kind of argument";
+    UninferredResult = "E0222", "cannot infer the type of `ok` or `err`";
+    SwitchOperand = "E0223", "switch on a value that is not an integer or enum";
+    SwitchOnPayloadEnum = "E0224", "switch on an enum with payload variants";
+    NonExhaustive = "E0225", "switch or match does not cover every variant";
+    MatchOperand = "E0226", "match on a value that is not an enum";
+    PatternArity = "E0227", "pattern binds the wrong number of payload fields";
+    DuplicateArm = "E0228", "match has two arms for one variant";
+    UnknownVariant = "E0229", "match arm names a variant of another enum";
+    PayloadVariantValue = "E0230", "payload variant used without its payload";
+    UnitVariantCall = "E0231", "variant without a payload called with arguments";
+    DropSignature = "E0232", "invalid `@drop` function signature";
+    DuplicateDrop = "E0233", "second drop function for one type";
+    ExternOptRes = "E0234", "opt or res in an extern signature";
+    ExternStructRepr = "E0235", "struct passed by value to C without `@repr(C)`";
+    InvalidArraySize = "E0236", "array size is not a non-negative integer constant";
+
+    UnsafeCall = "E0301", "unsafe function called outside `unsafe`";
+    RawDeref = "E0302", "raw pointer dereferenced outside `unsafe`";
+    UnsafeBuiltin = "E0303", "unsafe builtin used outside `unsafe`";
+
+    UseAfterMove = "E0401", "use of a moved value";
+    DoubleMutableBorrow = "E0402", "value borrowed mutably twice";
+    ConflictingBorrow = "E0403", "mutable and immutable borrows overlap";
+    ReturnLocalRef = "E0404", "reference to a local returned";
+
+    GenericDrop = "E0501", "generic `@drop` function";
+    InstanceDepth = "E0502", "generic instances nested too deeply";
+    NotGeneric = "E0503", "type arguments given to a name that is not generic";
+    TypeArgumentCount = "E0504", "wrong number of type arguments";
+    GenericStructCall = "E0505", "generic struct called like a function";
+    MissingFnTypeArgs = "E0506", "generic function used without type arguments";
+    MissingStructTypeArgs = "E0507", "generic struct used without type arguments";
+}
+
+impl ErrorCode {
+    /// Look up a code by its written form, ignoring case (`e0201` works)
+    pub fn parse(code: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|c| c.as_str().eq_ignore_ascii_case(code))
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diag::CompileError;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_codes_are_unique_and_sorted() {
+        let codes: Vec<&str> = ErrorCode::ALL.iter().map(|c| c.as_str()).collect();
+        let unique: HashSet<&str> = codes.iter().copied().collect();
+        assert_eq!(unique.len(), codes.len());
+
+        let mut sorted = codes.clone();
+        sorted.sort();
+        assert_eq!(sorted, codes);
+    }
+
+    #[test]
+    fn test_parse_round_trips() {
+        for &code in ErrorCode::ALL {
+            assert_eq!(ErrorCode::parse(code.as_str()), Some(code));
+        }
+        assert_eq!(ErrorCode::parse("e0201"), Some(ErrorCode::TypeMismatch));
+        assert_eq!(ErrorCode::parse("E9999"), None);
+    }
+
+    /// Fenced `fastc` blocks of an explanation, with whether each is
+    /// marked `ignore` (multi-file examples that cannot be checked alone)
+    fn examples(text: &str) -> Vec<(bool, String)> {
+        let mut blocks = Vec::new();
+        let mut current: Option<(bool, String)> = None;
+        for line in text.lines() {
+            if let Some((ignore, body)) = current.take() {
+                if line == "```" {
+                    blocks.push((ignore, body));
+                } else {
+                    current = Some((ignore, body + line + "\n"));
+                }
+            } else if let Some(info) = line.strip_prefix("```fastc") {
+                current = Some((info.contains("ignore"), String::new()));
+            }
+        }
+        blocks
+    }
+
+    fn codes_of(error: &CompileError) -> Vec<ErrorCode> {
+        match error {
+            CompileError::Multiple { errors } => errors.iter().flat_map(codes_of).collect(),
+            _ => error.error_code().into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn test_explanation_examples() {
+        for &code in ErrorCode::ALL {
+            let blocks = examples(code.explanation());
+            assert_eq!(
+                blocks.len(),
+                2,
+                "{} needs an erroneous and a fixed example",
+                code
+            );
+
+            let (ignore, bad) = &blocks[0];
+            if !ignore {
+                let error = crate::check(bad, "example.fc")
+                    .expect_err(&format!("{} example should fail", code));
+                assert!(
+                    codes_of(&error).contains(&code),
+                    "{} example reported {:?}",
+                    code,
+                    codes_of(&error)
+                );
+            }
+
+            let (ignore, fixed) = &blocks[1];
+            if !ignore && let Err(e) = crate::check(fixed, "example.fc") {
+                panic!("{} fixed example should check: {:?}", code, e);
+            }
+        }
+    }
+}
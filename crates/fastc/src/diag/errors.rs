@@ -1,81 +1,216 @@
 //! Error types for FastC compilation
-#![allow(unused_assignments)]
 
-use miette::Diagnostic;
+use std::fmt::Display;
+
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+use serde::Serialize;
 use thiserror::Error;
 
+use super::{ErrorCode, Fix};
 use crate::lexer::Span;
 
 /// Main compilation error type
-#[derive(Debug, Error, Diagnostic)]
+///
+/// Every variant except `Multiple` carries a code: an [`ErrorCode`] for
+/// compiler errors, a lint or Power of 10 code otherwise.
+#[derive(Debug, Error)]
 pub enum CompileError {
     #[error("Parse error: {message}")]
-    #[diagnostic(code(fastc::parse), help("{}", hint.as_deref().unwrap_or("")))]
     Parse {
+        code: ErrorCode,
         message: String,
-        #[label("here")]
         span: Span,
-        #[source_code]
         src: String,
         hint: Option<String>,
     },
 
     #[error("Resolution error: {message}")]
-    #[diagnostic(code(fastc::resolve), help("{}", hint.as_deref().unwrap_or("")))]
     Resolve {
+        code: ErrorCode,
         message: String,
-        #[label("here")]
         span: Span,
-        #[source_code]
         src: String,
         hint: Option<String>,
+        fix: Option<Box<Fix>>,
     },
 
     #[error("Type error: {message}")]
-    #[diagnostic(code(fastc::typecheck), help("{}", hint.as_deref().unwrap_or("")))]
     Type {
+        code: ErrorCode,
         message: String,
-        #[label("here")]
         span: Span,
-        #[source_code]
         src: String,
         hint: Option<String>,
+        fix: Option<Box<Fix>>,
     },
 
     #[error("Safety error: {message}")]
-    #[diagnostic(code(fastc::safety), help("{}", hint.as_deref().unwrap_or("")))]
     Safety {
+        code: ErrorCode,
         message: String,
-        #[label("here")]
         span: Span,
-        #[source_code]
+        src: String,
+        hint: Option<String>,
+        fix: Option<Box<Fix>>,
+    },
+
+    #[error("Borrow error: {message}")]
+    Borrow {
+        code: ErrorCode,
+        message: String,
+        /// The offending borrow or use first, then the earlier borrow, move,
+        /// or declaration it conflicts with
+        labels: Vec<LabeledSpan>,
         src: String,
         hint: Option<String>,
     },
 
     #[error("Power of 10 violation [{code}]: {message}")]
-    #[diagnostic(code(fastc::p10), help("{}", hint.as_deref().unwrap_or("")))]
     P10 {
         code: String,
         message: String,
-        #[label("here")]
         span: Span,
-        #[source_code]
         src: String,
         hint: Option<String>,
     },
 
+    #[error(transparent)]
+    Lint(Box<Lint>),
+
     #[error("Multiple errors occurred")]
-    #[diagnostic(code(fastc::multiple))]
-    Multiple {
-        #[related]
-        errors: Vec<CompileError>,
-    },
+    Multiple { errors: Vec<CompileError> },
+}
+
+impl Diagnostic for CompileError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        match self {
+            CompileError::Parse { code, .. }
+            | CompileError::Resolve { code, .. }
+            | CompileError::Type { code, .. }
+            | CompileError::Safety { code, .. }
+            | CompileError::Borrow { code, .. } => Some(Box::new(code)),
+            CompileError::P10 { code, .. } => Some(Box::new(code)),
+            CompileError::Lint(lint) => lint.code(),
+            CompileError::Multiple { .. } => Some(Box::new("fastc::multiple")),
+        }
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        match self {
+            CompileError::Lint(lint) => lint.severity(),
+            _ => None,
+        }
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        match self {
+            CompileError::Parse { hint, .. }
+            | CompileError::Resolve { hint, .. }
+            | CompileError::Type { hint, .. }
+            | CompileError::Safety { hint, .. }
+            | CompileError::Borrow { hint, .. }
+            | CompileError::P10 { hint, .. } => hint
+                .as_ref()
+                .map(|hint| Box::new(hint) as Box<dyn Display + 'a>),
+            CompileError::Lint(lint) => lint.help(),
+            CompileError::Multiple { .. } => None,
+        }
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        match self {
+            CompileError::Parse { src, .. }
+            | CompileError::Resolve { src, .. }
+            | CompileError::Type { src, .. }
+            | CompileError::Safety { src, .. }
+            | CompileError::Borrow { src, .. }
+            | CompileError::P10 { src, .. } => Some(src),
+            CompileError::Lint(lint) => lint.source_code(),
+            CompileError::Multiple { .. } => None,
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        match self {
+            CompileError::Parse { span, .. }
+            | CompileError::Resolve { span, .. }
+            | CompileError::Type { span, .. }
+            | CompileError::Safety { span, .. }
+            | CompileError::P10 { span, .. } => Some(Box::new(std::iter::once(here(span)))),
+            CompileError::Borrow { labels, .. } => Some(Box::new(labels.iter().cloned())),
+            CompileError::Lint(lint) => lint.labels(),
+            CompileError::Multiple { .. } => None,
+        }
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        match self {
+            CompileError::Multiple { errors } => Some(Box::new(
+                errors.iter().map(|error| error as &dyn Diagnostic),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Compilation fails
+    Error,
+    /// Reported, but compilation continues
+    Warning,
+}
+
+/// A lint finding
+///
+/// Lints are warnings unless denied with `-D` or promoted by strict mode,
+/// so unlike the other diagnostics their severity is decided at runtime.
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub struct Lint {
+    /// Lint code (e.g. `unused_variable`, `P10-004`)
+    pub code: String,
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+    pub src: String,
+    pub hint: Option<String>,
+    pub fix: Option<Box<Fix>>,
+}
+
+impl Diagnostic for Lint {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(&self.code))
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(match self.severity {
+            Severity::Error => miette::Severity::Error,
+            Severity::Warning => miette::Severity::Warning,
+        })
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.hint
+            .as_ref()
+            .map(|hint| Box::new(hint) as Box<dyn Display + 'a>)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.src)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(std::iter::once(here(&self.span))))
+    }
 }
 
 impl CompileError {
-    pub fn parse(message: impl Into<String>, span: Span, src: &str) -> Self {
+    pub fn parse(code: ErrorCode, message: impl Into<String>, span: Span, src: &str) -> Self {
         CompileError::Parse {
+            code,
             message: message.into(),
             span,
             src: src.to_string(),
@@ -84,12 +219,14 @@ impl CompileError {
     }
 
     pub fn parse_with_hint(
+        code: ErrorCode,
         message: impl Into<String>,
         span: Span,
         src: &str,
         hint: impl Into<String>,
     ) -> Self {
         CompileError::Parse {
+            code,
             message: message.into(),
             span,
             src: src.to_string(),
@@ -97,72 +234,219 @@ impl CompileError {
         }
     }
 
-    pub fn resolve(message: impl Into<String>, span: Span, src: &str) -> Self {
+    pub fn resolve(code: ErrorCode, message: impl Into<String>, span: Span, src: &str) -> Self {
         CompileError::Resolve {
+            code,
             message: message.into(),
             span,
             src: src.to_string(),
             hint: None,
+            fix: None,
         }
     }
 
     pub fn resolve_with_hint(
+        code: ErrorCode,
         message: impl Into<String>,
         span: Span,
         src: &str,
         hint: impl Into<String>,
     ) -> Self {
         CompileError::Resolve {
+            code,
             message: message.into(),
             span,
             src: src.to_string(),
             hint: Some(hint.into()),
+            fix: None,
         }
     }
 
-    pub fn type_error(message: impl Into<String>, span: Span, src: &str) -> Self {
+    pub fn type_error(code: ErrorCode, message: impl Into<String>, span: Span, src: &str) -> Self {
         CompileError::Type {
+            code,
             message: message.into(),
             span,
             src: src.to_string(),
             hint: None,
+            fix: None,
         }
     }
 
     pub fn type_error_with_hint(
+        code: ErrorCode,
         message: impl Into<String>,
         span: Span,
         src: &str,
         hint: impl Into<String>,
     ) -> Self {
         CompileError::Type {
+            code,
             message: message.into(),
             span,
             src: src.to_string(),
             hint: Some(hint.into()),
+            fix: None,
         }
     }
 
-    pub fn safety(message: impl Into<String>, span: Span, src: &str) -> Self {
+    pub fn safety(code: ErrorCode, message: impl Into<String>, span: Span, src: &str) -> Self {
         CompileError::Safety {
+            code,
             message: message.into(),
             span,
             src: src.to_string(),
             hint: None,
+            fix: None,
         }
     }
 
     pub fn safety_with_hint(
+        code: ErrorCode,
         message: impl Into<String>,
         span: Span,
         src: &str,
         hint: impl Into<String>,
     ) -> Self {
         CompileError::Safety {
+            code,
             message: message.into(),
             span,
             src: src.to_string(),
             hint: Some(hint.into()),
+            fix: None,
+        }
+    }
+
+    pub fn borrow(
+        code: ErrorCode,
+        message: impl Into<String>,
+        span: (Span, impl Into<String>),
+        related: (Span, impl Into<String>),
+        src: &str,
+    ) -> Self {
+        CompileError::Borrow {
+            code,
+            message: message.into(),
+            labels: borrow_labels(span, related),
+            src: src.to_string(),
+            hint: None,
+        }
+    }
+
+    pub fn borrow_with_hint(
+        code: ErrorCode,
+        message: impl Into<String>,
+        span: (Span, impl Into<String>),
+        related: (Span, impl Into<String>),
+        src: &str,
+        hint: impl Into<String>,
+    ) -> Self {
+        CompileError::Borrow {
+            code,
+            message: message.into(),
+            labels: borrow_labels(span, related),
+            src: src.to_string(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    pub fn lint(
+        code: impl Into<String>,
+        severity: Severity,
+        message: impl Into<String>,
+        span: Span,
+        src: &str,
+        hint: Option<String>,
+    ) -> Self {
+        CompileError::Lint(Box::new(Lint {
+            code: code.into(),
+            severity,
+            message: message.into(),
+            span,
+            src: src.to_string(),
+            hint,
+            fix: None,
+        }))
+    }
+
+    /// Stable code of a compiler error; `None` for lints and `Multiple`
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        match self {
+            CompileError::Parse { code, .. }
+            | CompileError::Resolve { code, .. }
+            | CompileError::Type { code, .. }
+            | CompileError::Safety { code, .. }
+            | CompileError::Borrow { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Machine-applicable fix, if the error has an unambiguous one
+    pub fn fix(&self) -> Option<&Fix> {
+        match self {
+            CompileError::Resolve { fix, .. }
+            | CompileError::Type { fix, .. }
+            | CompileError::Safety { fix, .. } => fix.as_deref(),
+            CompileError::Lint(lint) => lint.fix.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Fixes of this error, or of each error it wraps
+    pub fn fixes(&self) -> Vec<&Fix> {
+        match self {
+            CompileError::Multiple { errors } => errors.iter().flat_map(|e| e.fixes()).collect(),
+            _ => self.fix().into_iter().collect(),
+        }
+    }
+
+    /// Attach a fix; parse, borrow, and Power of 10 errors never carry one
+    pub fn with_fix(mut self, new_fix: Option<Fix>) -> Self {
+        let new_fix = new_fix.map(Box::new);
+        match &mut self {
+            CompileError::Resolve { fix, .. }
+            | CompileError::Type { fix, .. }
+            | CompileError::Safety { fix, .. } => *fix = new_fix,
+            CompileError::Lint(lint) => lint.fix = new_fix,
+            _ => {}
+        }
+        self
+    }
+
+    /// Severity of this diagnostic; only lints can be warnings
+    pub fn severity(&self) -> Severity {
+        match self {
+            CompileError::Lint(lint) => lint.severity,
+            _ => Severity::Error,
+        }
+    }
+
+    /// Source text the error's spans point into; empty for `Multiple`
+    pub(crate) fn src(&self) -> &str {
+        match self {
+            CompileError::Parse { src, .. }
+            | CompileError::Resolve { src, .. }
+            | CompileError::Type { src, .. }
+            | CompileError::Safety { src, .. }
+            | CompileError::Borrow { src, .. }
+            | CompileError::P10 { src, .. } => src,
+            CompileError::Lint(lint) => &lint.src,
+            CompileError::Multiple { .. } => "",
+        }
+    }
+
+    /// Byte offset of the error's primary span; `None` for `Multiple`
+    pub(crate) fn offset(&self) -> Option<usize> {
+        match self {
+            CompileError::Parse { span, .. }
+            | CompileError::Resolve { span, .. }
+            | CompileError::Type { span, .. }
+            | CompileError::Safety { span, .. }
+            | CompileError::P10 { span, .. } => Some(span.start),
+            CompileError::Borrow { labels, .. } => labels.first().map(|l| l.offset()),
+            CompileError::Lint(lint) => Some(lint.span.start),
+            CompileError::Multiple { .. } => None,
         }
     }
 
@@ -177,3 +461,19 @@ impl CompileError {
         }
     }
 }
+
+/// Primary "here" label on `span`
+fn here(span: &Span) -> LabeledSpan {
+    LabeledSpan::new_primary_with_span(Some("here".to_string()), span.clone())
+}
+
+/// Primary label on the offending span, secondary label on the related one
+fn borrow_labels(
+    (span, label): (Span, impl Into<String>),
+    (related_span, related_label): (Span, impl Into<String>),
+) -> Vec<LabeledSpan> {
+    vec![
+        LabeledSpan::new_primary_with_span(Some(label.into()), span),
+        LabeledSpan::new_with_span(Some(related_label.into()), related_span),
+    ]
+}
@@ -0,0 +1,132 @@
+//! Machine-applicable fixes
+//!
+//! A diagnostic carries a [`Fix`] when there is exactly one obvious way to
+//! resolve it: the suggested name for a typo, an `unsafe` block around a
+//! call, and so on. `fastc fix` applies them with [`apply_fixes`].
+
+use crate::lexer::Span;
+
+/// Replace the source text at `span` with `replacement`
+///
+/// An empty span inserts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub span: Span,
+    pub replacement: String,
+}
+
+/// Edits that together resolve one diagnostic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    /// What the fix does (e.g. "replace with `count`")
+    pub message: String,
+    /// Non-overlapping edits, in source order
+    pub edits: Vec<Edit>,
+}
+
+impl Fix {
+    /// Replace `span` with `replacement`
+    pub fn replace(message: impl Into<String>, span: Span, replacement: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            edits: vec![Edit {
+                span,
+                replacement: replacement.into(),
+            }],
+        }
+    }
+
+    /// Surround `span` with `before` and `after`
+    pub fn wrap(
+        message: impl Into<String>,
+        span: Span,
+        before: impl Into<String>,
+        after: impl Into<String>,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            edits: vec![
+                Edit {
+                    span: span.start..span.start,
+                    replacement: before.into(),
+                },
+                Edit {
+                    span: span.end..span.end,
+                    replacement: after.into(),
+                },
+            ],
+        }
+    }
+
+    /// Source range covered by the edits
+    fn extent(&self) -> Span {
+        let start = self.edits.iter().map(|e| e.span.start).min().unwrap_or(0);
+        let end = self.edits.iter().map(|e| e.span.end).max().unwrap_or(0);
+        start..end
+    }
+}
+
+/// Apply `fixes` to `source`
+///
+/// A fix whose edits touch the range of an already accepted fix is
+/// skipped; checking the result again reports it if it still applies.
+/// Returns the new source and how many fixes were applied.
+pub fn apply_fixes<'a>(source: &str, fixes: impl IntoIterator<Item = &'a Fix>) -> (String, usize) {
+    let mut accepted: Vec<&Fix> = Vec::new();
+    for fix in fixes {
+        let extent = fix.extent();
+        let in_bounds = fix
+            .edits
+            .iter()
+            .all(|e| source.is_char_boundary(e.span.start) && source.is_char_boundary(e.span.end));
+        let overlaps = accepted.iter().any(|other| {
+            let other = other.extent();
+            extent.start <= other.end && other.start <= extent.end
+        });
+        if in_bounds && !overlaps {
+            accepted.push(fix);
+        }
+    }
+
+    let mut edits: Vec<&Edit> = accepted.iter().flat_map(|fix| &fix.edits).collect();
+    edits.sort_by_key(|e| (e.span.start, e.span.end));
+
+    let mut output = String::with_capacity(source.len());
+    let mut pos = 0;
+    for edit in edits {
+        output.push_str(&source[pos..edit.span.start]);
+        output.push_str(&edit.replacement);
+        pos = edit.span.end;
+    }
+    output.push_str(&source[pos..]);
+    (output, accepted.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_and_wrap() {
+        let source = "let x: i32 = cuont; f(x);";
+        let fixes = [
+            Fix::replace("replace", 13..18, "count"),
+            Fix::wrap("discard", 20..24, "discard(", ")"),
+        ];
+        let (fixed, applied) = apply_fixes(source, &fixes);
+        assert_eq!(fixed, "let x: i32 = count; discard(f(x));");
+        assert_eq!(applied, 2);
+    }
+
+    #[test]
+    fn test_overlapping_fix_is_skipped() {
+        let source = "f(cuont);";
+        let fixes = [
+            Fix::wrap("discard", 0..8, "discard(", ")"),
+            Fix::replace("replace", 2..7, "count"),
+        ];
+        let (fixed, applied) = apply_fixes(source, &fixes);
+        assert_eq!(fixed, "discard(f(cuont));");
+        assert_eq!(applied, 1);
+    }
+}
@@ -0,0 +1,456 @@
+//! Machine-readable diagnostics
+//!
+//! `--message-format=json` writes one [`DiagnosticRecord`] per line as it
+//! is reported; `--message-format=sarif` collects them into a single SARIF
+//! 2.1.0 log written when reporting finishes. Positions are 1-based lines and
+//! character columns, with byte offsets alongside.
+
+use std::io::{self, Write};
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use super::{CompileError, ErrorCode, Fix, LineIndex, Severity};
+use crate::lexer::Span;
+use crate::lint;
+
+/// Reference page listing every error code
+const ERROR_CODES_URI: &str = "https://docs.skelfresearch.com/fastc/reference/error-codes/";
+
+/// How diagnostics are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    /// Rendered source snippets on stderr
+    #[default]
+    Human,
+    /// One JSON object per diagnostic per line
+    Json,
+    /// A SARIF 2.1.0 log
+    Sarif,
+}
+
+/// A source range, as line/column and as bytes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub offset: usize,
+    pub length: usize,
+}
+
+impl Location {
+    pub(crate) fn new(span: Span, index: &LineIndex) -> Self {
+        let (line, column) = index.position(span.start);
+        let (end_line, end_column) = index.position(span.end);
+        Self {
+            line,
+            column,
+            end_line,
+            end_column,
+            offset: span.start,
+            length: span.end.saturating_sub(span.start),
+        }
+    }
+}
+
+/// A secondary location, such as the earlier borrow in a borrow error
+#[derive(Debug, Clone, Serialize)]
+pub struct RelatedLocation {
+    pub message: String,
+    #[serde(flatten)]
+    pub location: Location,
+}
+
+/// One edit of a fix: replace the text at the location
+#[derive(Debug, Clone, Serialize)]
+pub struct EditRecord {
+    #[serde(flatten)]
+    pub location: Location,
+    pub replacement: String,
+}
+
+/// A machine-applicable fix
+#[derive(Debug, Clone, Serialize)]
+pub struct FixRecord {
+    pub message: String,
+    pub edits: Vec<EditRecord>,
+}
+
+impl FixRecord {
+    fn new(fix: &Fix, index: &LineIndex) -> Self {
+        Self {
+            message: fix.message.clone(),
+            edits: fix
+                .edits
+                .iter()
+                .map(|edit| EditRecord {
+                    location: Location::new(edit.span.clone(), index),
+                    replacement: edit.replacement.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// One diagnostic, flattened for serialization
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticRecord {
+    pub file: String,
+    pub severity: Severity,
+    pub code: Option<String>,
+    pub message: String,
+    #[serde(flatten)]
+    pub location: Location,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<RelatedLocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<FixRecord>,
+}
+
+impl DiagnosticRecord {
+    /// Records for `error`, one per error when it wraps several
+    pub fn from_error(error: &CompileError, file: &str) -> Vec<Self> {
+        use miette::Diagnostic;
+
+        if let CompileError::Multiple { errors } = error {
+            return errors
+                .iter()
+                .flat_map(|e| Self::from_error(e, file))
+                .collect();
+        }
+
+        let index = LineIndex::new(error.src());
+        let mut labels: Vec<_> = error.labels().into_iter().flatten().collect();
+        // The primary label locates the diagnostic; the rest are related
+        let primary = labels.iter().position(|l| l.primary()).unwrap_or(0);
+        let location = if labels.is_empty() {
+            Location::new(0..0, &index)
+        } else {
+            let label = labels.remove(primary);
+            Location::new(label.offset()..label.offset() + label.len(), &index)
+        };
+        let related = labels
+            .into_iter()
+            .map(|label| RelatedLocation {
+                message: label.label().unwrap_or_default().to_string(),
+                location: Location::new(label.offset()..label.offset() + label.len(), &index),
+            })
+            .collect();
+
+        vec![Self {
+            file: file.to_string(),
+            severity: error.severity(),
+            code: error.code().map(|code| code.to_string()),
+            message: error.to_string(),
+            location,
+            hint: error.help().map(|hint| hint.to_string()),
+            related,
+            fix: error.fix().map(|fix| FixRecord::new(fix, &index)),
+        }]
+    }
+}
+
+/// One-line description of a diagnostic code
+fn describe(code: &str) -> String {
+    if let Some(error) = ErrorCode::parse(code) {
+        return error.title().to_string();
+    }
+    if let Some((_, description)) = lint::LINTS.iter().find(|(name, _)| *name == code) {
+        return description.to_string();
+    }
+    match code.strip_prefix("P10-") {
+        Some(rule) => format!("Power of 10 rule {}", rule.trim_start_matches('0')),
+        None => code.to_string(),
+    }
+}
+
+/// SARIF region of `location`
+fn sarif_region(location: &Location) -> Value {
+    json!({
+        "startLine": location.line,
+        "startColumn": location.column,
+        "endLine": location.end_line,
+        "endColumn": location.end_column,
+        "byteOffset": location.offset,
+        "byteLength": location.length,
+    })
+}
+
+/// SARIF physical location of `location` in `file`
+fn sarif_location(file: &str, location: &Location) -> Value {
+    json!({
+        "artifactLocation": { "uri": file },
+        "region": sarif_region(location),
+    })
+}
+
+/// A SARIF 2.1.0 log with one run holding `records`
+pub fn to_sarif(records: &[DiagnosticRecord]) -> Value {
+    let mut rule_ids: Vec<&str> = Vec::new();
+    let results: Vec<Value> = records
+        .iter()
+        .map(|record| {
+            let mut result = json!({
+                "level": match record.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                },
+                "message": {
+                    "text": match &record.hint {
+                        Some(hint) => format!("{}\nhelp: {}", record.message, hint),
+                        None => record.message.clone(),
+                    },
+                },
+                "locations": [{ "physicalLocation": sarif_location(&record.file, &record.location) }],
+            });
+            if let Some(code) = &record.code {
+                let index = match rule_ids.iter().position(|id| id == code) {
+                    Some(index) => index,
+                    None => {
+                        rule_ids.push(code);
+                        rule_ids.len() - 1
+                    }
+                };
+                result["ruleId"] = json!(code);
+                result["ruleIndex"] = json!(index);
+            }
+            if let Some(fix) = &record.fix {
+                result["fixes"] = json!([{
+                    "description": { "text": fix.message },
+                    "artifactChanges": [{
+                        "artifactLocation": { "uri": record.file },
+                        "replacements": fix
+                            .edits
+                            .iter()
+                            .map(|edit| {
+                                json!({
+                                    "deletedRegion": sarif_region(&edit.location),
+                                    "insertedContent": { "text": edit.replacement },
+                                })
+                            })
+                            .collect::<Vec<_>>(),
+                    }],
+                }]);
+            }
+            if !record.related.is_empty() {
+                result["relatedLocations"] = record
+                    .related
+                    .iter()
+                    .enumerate()
+                    .map(|(id, related)| {
+                        json!({
+                            "id": id,
+                            "message": { "text": related.message },
+                            "physicalLocation": sarif_location(&record.file, &related.location),
+                        })
+                    })
+                    .collect();
+            }
+            result
+        })
+        .collect();
+
+    let rules: Vec<Value> = rule_ids
+        .iter()
+        .map(|id| {
+            let mut rule = json!({
+                "id": id,
+                "shortDescription": { "text": describe(id) },
+            });
+            if let Some(code) = ErrorCode::parse(id) {
+                rule["help"] =
+                    json!({ "text": code.explanation(), "markdown": code.explanation() });
+                rule["helpUri"] = json!(ERROR_CODES_URI);
+            }
+            rule
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "fastc",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// Writes diagnostics in one format
+///
+/// Human output always goes to stderr. JSON and SARIF go to the writer
+/// given to [`Reporter::new`]; call [`Reporter::finish`] once everything
+/// has been reported so the SARIF log is written.
+pub struct Reporter {
+    format: MessageFormat,
+    out: Box<dyn Write>,
+    records: Vec<DiagnosticRecord>,
+}
+
+impl Reporter {
+    pub fn new(format: MessageFormat, out: Box<dyn Write>) -> Self {
+        Self {
+            format,
+            out,
+            records: Vec::new(),
+        }
+    }
+
+    /// Human output on stderr
+    pub fn human() -> Self {
+        Self::new(MessageFormat::Human, Box::new(io::sink()))
+    }
+
+    pub fn format(&self) -> MessageFormat {
+        self.format
+    }
+
+    /// Report a diagnostic from `file`
+    pub fn report(&mut self, error: CompileError, file: &str) -> io::Result<()> {
+        match self.format {
+            MessageFormat::Human => eprintln!("{:?}", miette::Report::new(error)),
+            MessageFormat::Json => {
+                for record in DiagnosticRecord::from_error(&error, file) {
+                    serde_json::to_writer(&mut self.out, &record)?;
+                    writeln!(self.out)?;
+                }
+            }
+            MessageFormat::Sarif => self
+                .records
+                .extend(DiagnosticRecord::from_error(&error, file)),
+        }
+        Ok(())
+    }
+
+    /// Write the SARIF log, if any, and flush
+    pub fn finish(mut self) -> io::Result<()> {
+        if self.format == MessageFormat::Sarif {
+            serde_json::to_writer_pretty(&mut self.out, &to_sarif(&self.records))?;
+            writeln!(self.out)?;
+        }
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(source: &str) -> CompileError {
+        crate::check(source, "test.fc").unwrap_err()
+    }
+
+    #[test]
+    fn test_record_positions() {
+        let error = errors("fn main() -> i32 {\n    return missing;\n}\n");
+        let records = DiagnosticRecord::from_error(&error, "test.fc");
+        assert_eq!(records.len(), 1);
+
+        let record = &records[0];
+        assert_eq!(record.code.as_deref(), Some("E0101"));
+        assert_eq!(record.severity, Severity::Error);
+        assert_eq!((record.location.line, record.location.column), (2, 12));
+        assert_eq!(
+            (record.location.end_line, record.location.end_column),
+            (2, 19)
+        );
+    }
+
+    #[test]
+    fn test_multiple_errors_flatten() {
+        let error = errors("fn main() -> i32 { let x: bool = 1; return x; }");
+        let records = DiagnosticRecord::from_error(&error, "test.fc");
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| r.code.as_deref() == Some("E0201")));
+    }
+
+    #[test]
+    fn test_borrow_related_location() {
+        let error = errors(
+            "fn main() -> i32 { let x: i32 = 0; let a: mref(i32) = addr(x); let b: mref(i32) = addr(x); deref(a) = 1; return x; }",
+        );
+        let record = &DiagnosticRecord::from_error(&error, "test.fc")[0];
+        assert_eq!(record.related.len(), 1);
+        assert_eq!(record.related[0].message, "first mutable borrow here");
+        assert!(record.related[0].location.offset < record.location.offset);
+    }
+
+    #[test]
+    fn test_json_line_fields() {
+        let error = errors("fn main() -> i32 { return missing; }");
+        let record = &DiagnosticRecord::from_error(&error, "test.fc")[0];
+        let value = serde_json::to_value(record).unwrap();
+        assert_eq!(value["file"], "test.fc");
+        assert_eq!(value["severity"], "error");
+        assert_eq!(value["code"], "E0101");
+        assert_eq!(value["line"], 1);
+        assert!(value.get("related").is_none());
+    }
+
+    #[test]
+    fn test_sarif_log() {
+        let mut records = DiagnosticRecord::from_error(
+            &errors("fn main() -> i32 { let x: bool = 1; return x; }"),
+            "a.fc",
+        );
+        records.extend(DiagnosticRecord::from_error(
+            &errors("fn main() -> i32 { return missing; }"),
+            "b.fc",
+        ));
+        let log = to_sarif(&records);
+
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0]["id"], "E0201");
+        assert_eq!(rules[0]["shortDescription"]["text"], "type mismatch");
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[2]["ruleId"], "E0101");
+        assert_eq!(results[2]["ruleIndex"], 1);
+        assert_eq!(
+            results[2]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "b.fc"
+        );
+    }
+
+    #[test]
+    fn test_fix_in_record() {
+        let error = errors("fn main() -> i32 {\n    let count: i32 = 1;\n    return cuont;\n}\n");
+        let record = &DiagnosticRecord::from_error(&error, "test.fc")[0];
+        let fix = record.fix.as_ref().unwrap();
+        assert_eq!(fix.edits.len(), 1);
+        assert_eq!(fix.edits[0].replacement, "count");
+        assert_eq!(
+            (fix.edits[0].location.line, fix.edits[0].location.column),
+            (3, 12)
+        );
+
+        let log = to_sarif(std::slice::from_ref(record));
+        let replacement =
+            &log["runs"][0]["results"][0]["fixes"][0]["artifactChanges"][0]["replacements"][0];
+        assert_eq!(replacement["insertedContent"]["text"], "count");
+    }
+
+    #[test]
+    fn test_describe_codes() {
+        assert_eq!(
+            describe("unused_import"),
+            "imported name is never referenced"
+        );
+        assert_eq!(describe("P10-004"), "Power of 10 rule 4");
+    }
+}
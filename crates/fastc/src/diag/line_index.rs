@@ -0,0 +1,48 @@
+//! Byte offset to line/column conversion
+
+/// Resolves byte offsets in a source file to lines and columns
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    source: String,
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source: source.to_string(),
+            line_starts,
+        }
+    }
+
+    /// 1-based line and column (in characters) of a byte offset
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let start = self.line_starts[line - 1];
+        let column = self
+            .source
+            .get(start..offset)
+            .map_or(1, |text| text.chars().count() + 1);
+        (line, column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index_positions() {
+        let index = LineIndex::new("ab\n\u{e9}cd\n");
+        assert_eq!(index.position(0), (1, 1));
+        assert_eq!(index.position(2), (1, 3));
+        assert_eq!(index.position(3), (2, 1));
+        // Columns count characters, not bytes
+        assert_eq!(index.position(5), (2, 2));
+        assert_eq!(index.position(100), (3, 1));
+    }
+}
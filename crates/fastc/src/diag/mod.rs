@@ -1,5 +1,18 @@
 //! Diagnostic infrastructure
 
+mod codes;
 mod errors;
+mod fix;
+mod format;
+mod line_index;
+mod suggest;
 
+pub use codes::ErrorCode;
 pub use errors::*;
+pub use fix::{Edit, Fix, apply_fixes};
+pub use format::{
+    DiagnosticRecord, EditRecord, FixRecord, Location, MessageFormat, RelatedLocation, Reporter,
+    to_sarif,
+};
+pub use line_index::LineIndex;
+pub use suggest::*;
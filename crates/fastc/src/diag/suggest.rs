@@ -0,0 +1,65 @@
+//! "Did you mean" suggestions for misspelled names
+
+/// Find the candidate closest to `target` (for "did you mean" hints)
+///
+/// Only suggests a name within a small edit distance that is shorter than
+/// the target itself, so unrelated short names are never offered.
+pub fn find_similar<I, S>(target: &str, candidates: I) -> Option<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut best_match: Option<(String, usize)> = None;
+
+    for name in candidates {
+        let name = name.as_ref();
+        let dist = edit_distance(target, name);
+        // Only suggest if the edit distance is reasonable (e.g., <= 3)
+        // and the name is somewhat similar in length
+        if dist <= 3 && dist < target.len() {
+            match &best_match {
+                None => best_match = Some((name.to_string(), dist)),
+                Some((_, best_dist)) if dist < *best_dist => {
+                    best_match = Some((name.to_string(), dist))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    best_match.map(|(name, _)| name)
+}
+
+/// Calculate Levenshtein edit distance between two strings
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a_len = a.len();
+    let b_len = b.len();
+
+    if a_len == 0 {
+        return b_len;
+    }
+    if b_len == 0 {
+        return a_len;
+    }
+
+    let a_chars: Vec<char> = a.chars().collect();
+    let b_chars: Vec<char> = b.chars().collect();
+
+    let mut prev_row: Vec<usize> = (0..=b_len).collect();
+    let mut curr_row: Vec<usize> = vec![0; b_len + 1];
+
+    for (i, a_char) in a_chars.iter().enumerate().take(a_len) {
+        curr_row[0] = i + 1;
+
+        for j in 0..b_len {
+            let cost = if *a_char == b_chars[j] { 0 } else { 1 };
+            curr_row[j + 1] = (prev_row[j + 1] + 1)
+                .min(curr_row[j] + 1)
+                .min(prev_row[j] + cost);
+        }
+
+        std::mem::swap(&mut prev_row, &mut curr_row);
+    }
+
+    prev_row[b_len]
+}
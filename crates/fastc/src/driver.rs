@@ -2,12 +2,16 @@
 
 use std::path::Path;
 
-use crate::ast::File;
+use crate::ast::{File, Item};
+use crate::borrow::BorrowChecker;
 use crate::deps::{Manifest, ModuleLoader};
-use crate::diag::CompileError;
-use crate::emit::Emitter;
-use crate::lexer::{Lexer, strip_comments};
+use crate::diag::{CompileError, apply_fixes};
+use crate::emit::{Emitter, SourceMap};
+use crate::fmt::format;
+use crate::lexer::{Lexer, Span, strip_comments};
+use crate::lint::{Finding, LintReport, Linter};
 use crate::lower::Lower;
+use crate::mono::Monomorphizer;
 use crate::p10::{P10Checker, P10Config};
 use crate::parser::Parser;
 use crate::resolve::Resolver;
@@ -29,34 +33,92 @@ pub fn parse(source: &str, filename: &str) -> Result<File, CompileError> {
     Ok(ast)
 }
 
+/// Lex and parse FastC source, recovering from syntax errors
+///
+/// If the file has syntax errors, the items that parsed cleanly are still
+/// resolved and type-checked so their errors are reported alongside the
+/// syntax errors. A function with a syntax error keeps only its signature,
+/// and errors inside it are dropped: they are most likely caused by it.
+fn parse_recovering(source: &str, filename: &str) -> Result<File, CompileError> {
+    let lexer = Lexer::new(source);
+    let tokens = strip_comments(lexer.collect());
+
+    let mut parser = Parser::new(&tokens, source, filename);
+    let (mut ast, mut errors) = parser.parse_file_recovering();
+    if errors.is_empty() {
+        return Ok(ast);
+    }
+
+    // A syntax error belongs to the item being parsed when it was found:
+    // a missing `}` is only noticed at the start of the next item
+    let mut broken: Vec<Span> = Vec::new();
+    for offset in errors.iter().filter_map(CompileError::offset) {
+        let owner = ast.items.iter_mut().rev().find(|item| {
+            let start = item.span().start;
+            start < offset || (start == offset && matches!(item, Item::Error { .. }))
+        });
+        if let Some(item) = owner {
+            if let Item::Fn(decl) = item {
+                decl.body.stmts.clear();
+            }
+            broken.push(item.span());
+        }
+    }
+
+    let checked = expand_modules(&mut ast, filename)
+        .and_then(|()| Monomorphizer::new(source).monomorphize(&mut ast))
+        .and_then(|()| {
+            let mut resolver = Resolver::new(source);
+            resolver.resolve(&ast)?;
+            TypeChecker::new(source, resolver.into_symbols()).check(&ast)
+        });
+    if let Err(error) = checked {
+        let in_broken_item = |error: &CompileError| {
+            error.src() == source
+                && error
+                    .offset()
+                    .is_some_and(|offset| broken.iter().any(|span| span.contains(&offset)))
+        };
+        let semantic = match error {
+            CompileError::Multiple { errors } => errors,
+            error => vec![error],
+        };
+        errors.extend(semantic.into_iter().filter(|e| !in_broken_item(e)));
+    }
+
+    Err(CompileError::multiple(errors))
+}
+
 /// Type-check FastC source without emitting C
 ///
 /// Runs phases 1-4 plus Power of 10 checking with standard config.
 /// Returns `Ok(())` if the source is valid, or an error otherwise.
+/// Warnings are discarded.
 pub fn check(source: &str, filename: &str) -> Result<(), CompileError> {
-    check_with_p10(source, filename, P10Config::standard())
+    check_with_p10(source, filename, P10Config::standard()).map(|_| ())
 }
 
 /// Type-check FastC source with Power of 10 rule enforcement
 ///
-/// Runs phases 1-4 plus Power of 10 checking.
-/// Returns `Ok(())` if the source is valid, or an error otherwise.
-pub fn check_with_p10(source: &str, filename: &str, p10_config: P10Config) -> Result<(), CompileError> {
-    // Phase 1: Lex (strip comments for parser)
-    let lexer = Lexer::new(source);
-    let tokens = strip_comments(lexer.collect());
+/// Runs phases 1-4 plus lints and Power of 10 checking.
+/// Returns the warnings if the source is valid, or an error otherwise.
+pub fn check_with_p10(
+    source: &str,
+    filename: &str,
+    p10_config: P10Config,
+) -> Result<Vec<CompileError>, CompileError> {
+    // Phases 1-2: Lex and parse
+    let mut ast = parse_recovering(source, filename)?;
 
-    // Phase 2: Parse
-    let mut parser = Parser::new(&tokens, source, filename);
-    let mut ast = parser.parse_file()?;
+    // Lints run on the file as written; findings are reported once the
+    // file has checked cleanly
+    let lints = Linter::new(source).check(&ast);
 
-    // Phase 2.5: Module expansion (if in a project)
-    let source_path = Path::new(filename);
-    if let Some(project_root) = find_project_root(source_path) {
-        let source_dir = source_path.parent().unwrap_or(Path::new("."));
-        let mut loader = ModuleLoader::new(&project_root);
-        loader.expand_modules(&mut ast, source_dir)?;
-    }
+    // Phase 2.5: Module expansion
+    expand_modules(&mut ast, filename)?;
+
+    // Phase 2.75: Monomorphize generics
+    Monomorphizer::new(source).monomorphize(&mut ast)?;
 
     // Phase 3: Resolve names
     let mut resolver = Resolver::new(source);
@@ -68,12 +130,13 @@ pub fn check_with_p10(source: &str, filename: &str, p10_config: P10Config) -> Re
     // Phase 4: Type check
     let mut typechecker = TypeChecker::new(source, symbols);
     typechecker.check(&ast)?;
+    let types = typechecker.into_types();
 
-    // Phase 4.5: Power of 10 rule checking
-    let p10_checker = P10Checker::new(p10_config);
-    p10_checker.check_and_report(&ast, source)?;
+    // Phase 4.25: Borrow and move checking
+    BorrowChecker::new(source, &types).check(&ast)?;
 
-    Ok(())
+    // Phase 4.5: Lints and Power of 10 rule checking
+    report_lints(lints.with_types(&types), &ast, source, p10_config)
 }
 
 /// Compile FastC source code to C11
@@ -82,28 +145,61 @@ pub fn compile(source: &str, filename: &str) -> Result<String, CompileError> {
     Ok(c_code)
 }
 
+/// What to emit alongside the C code
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmitOptions {
+    /// Also emit a C header file
+    pub emit_header: bool,
+    /// Write `#line` directives so C tools report FastC source lines
+    pub line_directives: bool,
+    /// Build a source map from emitted C lines to FastC source lines
+    pub source_map: bool,
+}
+
+/// Emitted C code, the artifacts requested by [`EmitOptions`], and the
+/// warnings found along the way
+#[derive(Debug)]
+pub struct CompileOutput {
+    pub c_code: String,
+    pub header: Option<String>,
+    pub source_map: Option<SourceMap>,
+    pub warnings: Vec<CompileError>,
+}
+
 /// Compile FastC source code to C11 with Power of 10 rule enforcement
 pub fn compile_with_p10(
     source: &str,
     filename: &str,
     emit_header: bool,
     p10_config: P10Config,
-) -> Result<(String, Option<String>), CompileError> {
-    // Phase 1: Lex (strip comments for parser)
-    let lexer = Lexer::new(source);
-    let tokens = strip_comments(lexer.collect());
+) -> Result<CompileOutput, CompileError> {
+    let options = EmitOptions {
+        emit_header,
+        ..EmitOptions::default()
+    };
+    compile_with_emit_options(source, filename, options, p10_config)
+}
 
-    // Phase 2: Parse
-    let mut parser = Parser::new(&tokens, source, filename);
-    let mut ast = parser.parse_file()?;
+/// Compile FastC source code to C11, optionally with a header, `#line`
+/// directives, and a source map
+pub fn compile_with_emit_options(
+    source: &str,
+    filename: &str,
+    options: EmitOptions,
+    p10_config: P10Config,
+) -> Result<CompileOutput, CompileError> {
+    // Phases 1-2: Lex and parse
+    let mut ast = parse_recovering(source, filename)?;
 
-    // Phase 2.5: Module expansion (if in a project)
-    let source_path = Path::new(filename);
-    if let Some(project_root) = find_project_root(source_path) {
-        let source_dir = source_path.parent().unwrap_or(Path::new("."));
-        let mut loader = ModuleLoader::new(&project_root);
-        loader.expand_modules(&mut ast, source_dir)?;
-    }
+    // Lints run on the file as written; findings are reported once the
+    // file has checked cleanly
+    let lints = Linter::new(source).check(&ast);
+
+    // Phase 2.5: Module expansion
+    expand_modules(&mut ast, filename)?;
+
+    // Phase 2.75: Monomorphize generics
+    Monomorphizer::new(source).monomorphize(&mut ast)?;
 
     // Phase 3: Resolve names
     let mut resolver = Resolver::new(source);
@@ -115,21 +211,33 @@ pub fn compile_with_p10(
     // Phase 4: Type check
     let mut typechecker = TypeChecker::new(source, symbols);
     typechecker.check(&ast)?;
+    let types = typechecker.into_types();
 
-    // Phase 4.5: Power of 10 rule checking
-    let p10_checker = P10Checker::new(p10_config);
-    p10_checker.check_and_report(&ast, source)?;
+    // Phase 4.25: Borrow and move checking
+    BorrowChecker::new(source, &types).check(&ast)?;
+
+    // Phase 4.5: Lints and Power of 10 rule checking
+    let warnings = report_lints(lints.with_types(&types), &ast, source, p10_config)?;
 
     // Phase 5: Lower to C AST
-    let mut lowerer = Lower::new();
+    let mut lowerer = Lower::new(types, filename, source);
     let c_ast = lowerer.lower(&ast);
 
     // Phase 6: Emit C code
-    let mut emitter = Emitter::new();
+    let mut emitter = if options.line_directives || options.source_map {
+        Emitter::with_locations(options.line_directives)
+    } else {
+        Emitter::new()
+    };
     let c_code = emitter.emit(&c_ast);
+    let source_map = if options.source_map {
+        emitter.source_map()
+    } else {
+        None
+    };
 
     // Phase 7 (optional): Emit header
-    let header = if emit_header {
+    let header = if options.emit_header {
         // Extract module name from filename (without extension)
         let module_name = std::path::Path::new(filename)
             .file_stem()
@@ -140,18 +248,95 @@ pub fn compile_with_p10(
         None
     };
 
-    Ok((c_code, header))
+    Ok(CompileOutput {
+        c_code,
+        header,
+        source_map,
+        warnings,
+    })
 }
 
 /// Compile FastC source code to C11 with optional header generation
 ///
-/// Uses standard Power of 10 config by default.
+/// Uses standard Power of 10 config by default. Warnings are discarded.
 pub fn compile_with_options(
     source: &str,
     filename: &str,
     emit_header: bool,
 ) -> Result<(String, Option<String>), CompileError> {
-    compile_with_p10(source, filename, emit_header, P10Config::standard())
+    let output = compile_with_p10(source, filename, emit_header, P10Config::standard())?;
+    Ok((output.c_code, output.header))
+}
+
+/// Rounds of fixes `fix` applies before giving up
+const MAX_FIX_ROUNDS: usize = 10;
+
+/// Result of [`fix`]
+#[derive(Debug)]
+pub struct FixOutput {
+    /// Fixed and formatted source
+    pub source: String,
+    /// Number of fixes applied
+    pub applied: usize,
+}
+
+/// Apply the machine-applicable fixes of every diagnostic, then format
+///
+/// Fixing one error can let a later phase run and report another, so the
+/// source is checked again after each round until no fix applies. Fails
+/// only if the fixed source cannot be formatted.
+pub fn fix(source: &str, filename: &str, p10_config: P10Config) -> Result<FixOutput, CompileError> {
+    let mut current = source.to_string();
+    let mut applied = 0;
+    for _ in 0..MAX_FIX_ROUNDS {
+        let diagnostics = match check_with_p10(&current, filename, p10_config.clone()) {
+            Ok(warnings) => warnings,
+            Err(error) => vec![error],
+        };
+        let fixes = diagnostics.iter().flat_map(|d| d.fixes());
+        let (fixed, count) = apply_fixes(&current, fixes);
+        if count == 0 {
+            break;
+        }
+        current = fixed;
+        applied += count;
+    }
+
+    Ok(FixOutput {
+        source: format(&current, filename)?,
+        applied,
+    })
+}
+
+/// Apply lint levels to the linter's findings and to Power of 10
+/// violations, failing if any of them is an error
+///
+/// Returns the warnings.
+fn report_lints(
+    lints: Vec<Finding>,
+    ast: &File,
+    source: &str,
+    p10_config: P10Config,
+) -> Result<Vec<CompileError>, CompileError> {
+    let mut report = LintReport::default();
+    for finding in lints {
+        let level = p10_config.lint_level(finding.code);
+        report.push(level, |severity| finding.into_error(severity, source));
+    }
+    report.extend(P10Checker::new(p10_config).report(ast, source));
+    report.into_result()
+}
+
+/// Expand `mod name;` declarations, resolving against the enclosing project
+/// if there is one and the source file's directory otherwise
+///
+/// `mod std;` always loads the bundled standard library.
+fn expand_modules(ast: &mut File, filename: &str) -> Result<(), CompileError> {
+    let source_path = Path::new(filename);
+    let source_dir = source_path.parent().unwrap_or(Path::new("."));
+    let root = find_project_root(source_path).unwrap_or_else(|| source_dir.to_path_buf());
+    ModuleLoader::new(&root).expand_modules(ast, source_dir)?;
+    Ok(())
 }
 
 /// Find the project root by looking for fastc.toml
@@ -2,14 +2,33 @@
 
 mod decl;
 mod expr;
+mod source_map;
 mod stmt;
 
-use crate::lower::{CBinOp, CDecl, CExpr, CFile, CFnDef, CStmt, CType, CUnaryOp};
+pub use source_map::{Mapping, SourceMap};
+
+use crate::lower::{CBinOp, CConst, CDecl, CExpr, CFile, CFnDef, CLoc, CStmt, CType, CUnaryOp};
 
 /// C code emitter
 pub struct Emitter {
     output: String,
     indent: usize,
+    /// Lines written to `output` so far
+    lines: usize,
+    /// Source location tracking, when requested
+    origin: Option<Origin>,
+}
+
+/// Where emitted function bodies come from in the FastC source
+struct Origin {
+    line_directives: bool,
+    /// FastC files of the C file being emitted, by id
+    sources: Vec<String>,
+    /// Source position of the C being emitted (inside functions only)
+    current: Option<CLoc>,
+    /// File and line named by the last `#line` directive
+    directive: Option<(usize, usize)>,
+    mappings: Vec<Mapping>,
 }
 
 impl Emitter {
@@ -17,12 +36,40 @@ impl Emitter {
         Self {
             output: String::new(),
             indent: 0,
+            lines: 0,
+            origin: None,
         }
     }
 
+    /// Emitter that maps function bodies back to the FastC source through
+    /// the locations in the C AST, optionally writing `#line` directives
+    /// into the C
+    pub fn with_locations(line_directives: bool) -> Self {
+        Self {
+            origin: Some(Origin {
+                line_directives,
+                sources: Vec::new(),
+                current: None,
+                directive: None,
+                mappings: Vec::new(),
+            }),
+            ..Self::new()
+        }
+    }
+
+    /// Source map of the last emitted C file, if the emitter tracks locations
+    pub fn source_map(&self) -> Option<SourceMap> {
+        self.origin.as_ref().map(|origin| SourceMap {
+            version: 1,
+            sources: origin.sources.clone(),
+            mappings: origin.mappings.clone(),
+        })
+    }
+
     /// Emit a C header file with include guards, type definitions, and function prototypes
     pub fn emit_header(&mut self, file: &CFile, module_name: &str) -> String {
         self.output.clear();
+        self.lines = 0;
         let guard = format!("{}_H", module_name.to_uppercase().replace('-', "_"));
 
         // Include guard
@@ -36,12 +83,28 @@ impl Emitter {
         self.line("#include <stdbool.h>");
         self.blank();
 
+        // Opaque types
+        for decl in &file.forward_decls {
+            self.emit_decl(decl);
+        }
+        if !file.forward_decls.is_empty() {
+            self.blank();
+        }
+
         // Type definitions (structs)
         for def in &file.type_defs {
             self.emit_decl(def);
             self.blank();
         }
 
+        // Constants
+        for c in &file.consts {
+            self.emit_const(c);
+        }
+        if !file.consts.is_empty() {
+            self.blank();
+        }
+
         // Function prototypes (sorted for determinism)
         let mut fn_defs: Vec<_> = file.fn_defs.iter().collect();
         fn_defs.sort_by_key(|f| &f.name);
@@ -59,6 +122,12 @@ impl Emitter {
     /// Emit a C file
     pub fn emit(&mut self, file: &CFile) -> String {
         self.output.clear();
+        self.lines = 0;
+        if let Some(origin) = &mut self.origin {
+            origin.sources = file.sources.clone();
+            origin.directive = None;
+            origin.mappings.clear();
+        }
 
         // Header comment
         self.line("/* Generated by fastc - do not edit */");
@@ -76,6 +145,9 @@ impl Emitter {
         for decl in &file.forward_decls {
             self.emit_decl(decl);
         }
+        if !file.forward_decls.is_empty() {
+            self.blank();
+        }
 
         // Type definitions
         for def in &file.type_defs {
@@ -83,6 +155,14 @@ impl Emitter {
             self.blank();
         }
 
+        // Constants
+        for c in &file.consts {
+            self.emit_const(c);
+        }
+        if !file.consts.is_empty() {
+            self.blank();
+        }
+
         // Function prototypes
         for proto in &file.fn_protos {
             self.emit_fn_proto(proto);
@@ -130,6 +210,49 @@ impl Emitter {
             CDecl::Typedef { name, ty } => {
                 self.line(&format!("typedef {} {};", self.type_to_string(ty), name));
             }
+            CDecl::Opaque { name } => {
+                self.line(&format!("typedef struct {} {};", name, name));
+            }
+            CDecl::TaggedUnion {
+                name,
+                tag_ty,
+                tags,
+                variants,
+            } => {
+                self.line("enum {");
+                self.indent += 1;
+                for (i, tag) in tags.iter().enumerate() {
+                    if i < tags.len() - 1 {
+                        self.line(&format!("{},", tag));
+                    } else {
+                        self.line(tag);
+                    }
+                }
+                self.indent -= 1;
+                self.line("};");
+                self.blank();
+
+                self.line(&format!("typedef struct {} {{", name));
+                self.indent += 1;
+                self.line(&format!("{} tag;", self.type_to_string(tag_ty)));
+                self.line("union {");
+                self.indent += 1;
+                for (variant, fields) in variants {
+                    self.line("struct {");
+                    self.indent += 1;
+                    for field in fields {
+                        let base = self.type_base_string(&field.ty);
+                        let suffix = self.type_suffix_string(&field.ty);
+                        self.line(&format!("{} {}{};", base, field.name, suffix));
+                    }
+                    self.indent -= 1;
+                    self.line(&format!("}} {};", variant));
+                }
+                self.indent -= 1;
+                self.line("} data;");
+                self.indent -= 1;
+                self.line(&format!("}} {};", name));
+            }
             CDecl::Enum { name, variants } => {
                 self.line(&format!("typedef enum {} {{", name));
                 self.indent += 1;
@@ -146,6 +269,35 @@ impl Emitter {
         }
     }
 
+    fn emit_const(&mut self, c: &CConst) {
+        // `const` goes after pointer types so it applies to the object itself
+        let decl = match &c.ty {
+            CType::Ptr(_) | CType::ConstPtr(_) => {
+                format!("static {} const {}", self.type_to_string(&c.ty), c.name)
+            }
+            _ => format!(
+                "static {}{} {}{}",
+                if c.mutable { "" } else { "const " },
+                self.type_base_string(&c.ty),
+                c.name,
+                self.type_suffix_string(&c.ty)
+            ),
+        };
+        // Static initializers use a brace list rather than a compound literal
+        let value = match &c.value {
+            CExpr::Compound { fields, .. } => format!(
+                "{{ {} }}",
+                fields
+                    .iter()
+                    .map(|(name, val)| format!(".{} = {}", name, self.expr_to_string(val)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            value => self.expr_to_string(value),
+        };
+        self.line(&format!("{} = {};", decl, value));
+    }
+
     fn emit_fn_proto(&mut self, proto: &crate::lower::CFnProto) {
         let params = if proto.params.is_empty() {
             "void".to_string()
@@ -208,6 +360,7 @@ impl Emitter {
                 .join(", ")
         };
 
+        self.set_location(def.loc);
         self.line(&format!(
             "{} {}({}) {{",
             self.type_to_string(&def.return_type),
@@ -222,6 +375,9 @@ impl Emitter {
 
         self.indent -= 1;
         self.line("}");
+        if let Some(origin) = &mut self.origin {
+            origin.current = None;
+        }
     }
 
     fn emit_stmt(&mut self, stmt: &CStmt) {
@@ -291,13 +447,17 @@ impl Emitter {
                     .unwrap_or_default();
                 let step_str = step
                     .as_ref()
-                    .map(|e| self.expr_to_string(e))
+                    .map(|s| self.stmt_to_inline_string(s))
                     .unwrap_or_default();
 
-                self.line(&format!(
-                    "for ({}; {}; {}) {{",
-                    init_str, cond_str, step_str
-                ));
+                if init_str.is_empty() && cond_str.is_empty() && step_str.is_empty() {
+                    self.line("for (;;) {");
+                } else {
+                    self.line(&format!(
+                        "for ({}; {}; {}) {{",
+                        init_str, cond_str, step_str
+                    ));
+                }
                 self.indent += 1;
                 for s in body {
                     self.emit_stmt(s);
@@ -363,6 +523,50 @@ impl Emitter {
             CStmt::Break => {
                 self.line("break;");
             }
+            CStmt::Continue => {
+                self.line("continue;");
+            }
+            CStmt::Loc(loc) => self.set_location(*loc),
+        }
+    }
+
+    /// Attribute the lines that follow to the source at `loc`
+    fn set_location(&mut self, loc: CLoc) {
+        if let Some(origin) = &mut self.origin {
+            origin.current = Some(loc);
+        }
+    }
+
+    /// Record the source position of the line about to be written, preceded
+    /// by a `#line` directive when it moves to another file or line. Lines
+    /// lowered from one statement share a directive.
+    fn locate(&mut self) {
+        let Some(origin) = &mut self.origin else {
+            return;
+        };
+        let Some(loc) = origin.current else {
+            return;
+        };
+        if origin.line_directives && origin.directive != Some((loc.file, loc.line)) {
+            let filename = origin.sources[loc.file]
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+            self.output
+                .push_str(&format!("#line {} \"{}\"\n", loc.line, filename));
+            self.lines += 1;
+            origin.directive = Some((loc.file, loc.line));
+        }
+        if origin
+            .mappings
+            .last()
+            .is_none_or(|m| (m.source, m.line, m.column) != (loc.file, loc.line, loc.column))
+        {
+            origin.mappings.push(Mapping {
+                c_line: self.lines + 1,
+                source: loc.file,
+                line: loc.line,
+                column: loc.column,
+            });
         }
     }
 
@@ -400,7 +604,7 @@ impl Emitter {
             CExpr::IntLit(s) => s.clone(),
             CExpr::FloatLit(s) => s.clone(),
             CExpr::BoolLit(b) => if *b { "true" } else { "false" }.to_string(),
-            CExpr::StringLit(s) => format!("\"{}\"", s.escape_default()),
+            CExpr::StringLit(s) => format!("\"{}\"", escape_c_string(s)),
             CExpr::Ident(name) => name.clone(),
             CExpr::Binary { op, lhs, rhs } => {
                 format!(
@@ -581,15 +785,21 @@ impl Emitter {
     }
 
     fn line(&mut self, s: &str) {
+        self.locate();
         for _ in 0..self.indent {
             self.output.push_str("    ");
         }
         self.output.push_str(s);
-        self.output.push('\n');
+        self.newline();
     }
 
     fn blank(&mut self) {
+        self.newline();
+    }
+
+    fn newline(&mut self) {
         self.output.push('\n');
+        self.lines += 1;
     }
 }
 
@@ -598,3 +808,23 @@ impl Default for Emitter {
         Self::new()
     }
 }
+
+/// Escape a string's UTF-8 bytes for a C string literal
+///
+/// Bytes outside printable ASCII become three-digit octal escapes, which
+/// unlike `\x` cannot run into a following hex digit.
+fn escape_c_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            b' '..=b'~' => out.push(byte as char),
+            _ => out.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    out
+}
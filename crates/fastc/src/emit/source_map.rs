@@ -0,0 +1,33 @@
+//! Source maps from emitted C lines back to FastC source
+
+use serde::Serialize;
+
+/// Maps lines of an emitted C file to the FastC source they came from
+///
+/// Each mapping covers C lines from `c_line` up to the next mapping.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceMap {
+    pub version: u32,
+    /// FastC source files the mappings point into: the compiled file, then
+    /// the module files it loads
+    pub sources: Vec<String>,
+    pub mappings: Vec<Mapping>,
+}
+
+/// A C line and the source position it was lowered from (lines and columns
+/// are 1-based)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Mapping {
+    pub c_line: usize,
+    /// Index into `sources`
+    pub source: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SourceMap {
+    /// Render as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("source map serializes")
+    }
+}
@@ -2,8 +2,8 @@
 
 use crate::ast::{
     BinOp, Block, Case, ConstDecl, ConstExpr, ElseBranch, EnumDecl, Expr, ExternBlock, ExternItem,
-    Field, FieldInit, File, FnDecl, FnProto, ForInit, ForStep, Item, ModDecl, OpaqueDecl, Param,
-    PrimitiveType, Repr, Stmt, StructDecl, TypeExpr, UnaryOp, UseDecl, UseItems, Variant,
+    Field, FieldInit, File, FnDecl, FnProto, ForInit, ForStep, Item, MatchArm, ModDecl, OpaqueDecl,
+    Param, Repr, Stmt, StructDecl, TypeExpr, UnaryOp, UseDecl, UseItems, Variant,
 };
 
 /// Formatter for FastC source code
@@ -79,17 +79,25 @@ impl Formatter {
             Item::Extern(block) => self.format_extern(block),
             Item::Use(decl) => self.format_use(decl),
             Item::Mod(decl) => self.format_mod(decl),
+            // Files with syntax errors are never formatted
+            Item::Error { .. } => {}
         }
     }
 
     /// Format a function declaration
     fn format_fn(&mut self, decl: &FnDecl) {
+        if decl.is_drop {
+            self.write_indent();
+            self.write("@drop");
+            self.newline();
+        }
         self.write_indent();
         if decl.is_unsafe {
             self.write("unsafe ");
         }
         self.write("fn ");
         self.write(&decl.name);
+        self.format_type_params(&decl.type_params);
         self.write("(");
         self.format_params(&decl.params);
         self.write(")");
@@ -104,6 +112,30 @@ impl Formatter {
         self.newline();
     }
 
+    /// Format type parameters: [T, U]
+    fn format_type_params(&mut self, type_params: &[String]) {
+        if !type_params.is_empty() {
+            self.write("[");
+            self.write(&type_params.join(", "));
+            self.write("]");
+        }
+    }
+
+    /// Format type arguments: [i32, opt(T)]
+    fn format_type_args(&mut self, type_args: &[TypeExpr]) {
+        if type_args.is_empty() {
+            return;
+        }
+        self.write("[");
+        for (i, ty) in type_args.iter().enumerate() {
+            if i > 0 {
+                self.write(", ");
+            }
+            self.format_type(ty);
+        }
+        self.write("]");
+    }
+
     /// Format function parameters
     fn format_params(&mut self, params: &[Param]) {
         for (i, param) in params.iter().enumerate() {
@@ -124,6 +156,7 @@ impl Formatter {
         self.write_indent();
         self.write("struct ");
         self.write(&decl.name);
+        self.format_type_params(&decl.type_params);
         self.write(" {");
         self.newline();
 
@@ -346,8 +379,12 @@ impl Formatter {
     /// Format a type expression
     fn format_type(&mut self, ty: &TypeExpr) {
         match ty {
-            TypeExpr::Primitive(p) => self.write(primitive_name(*p)),
+            TypeExpr::Primitive(p) => self.write(p.name()),
             TypeExpr::Named(name) => self.write(name),
+            TypeExpr::Generic(name, args) => {
+                self.write(name);
+                self.format_type_args(args);
+            }
             TypeExpr::Ref(inner) => {
                 self.write("ref(");
                 self.format_type(inner);
@@ -419,6 +456,8 @@ impl Formatter {
                 }
             }
             TypeExpr::Void => self.write("void"),
+            // Only the type checker produces it; never part of a parsed file
+            TypeExpr::Error => self.write("error"),
         }
     }
 
@@ -569,6 +608,36 @@ impl Formatter {
 
                 self.line("}");
             }
+            Stmt::Match {
+                expr,
+                arms,
+                default,
+                ..
+            } => {
+                self.write_indent();
+                self.write("match (");
+                self.format_expr(expr);
+                self.write(") {");
+                self.newline();
+
+                self.indent += 1;
+                for arm in arms {
+                    self.format_match_arm(arm);
+                }
+                if let Some(stmts) = default {
+                    self.write_indent();
+                    self.write("default:");
+                    self.newline();
+                    self.indent += 1;
+                    for stmt in stmts {
+                        self.format_stmt(stmt);
+                    }
+                    self.indent -= 1;
+                }
+                self.indent -= 1;
+
+                self.line("}");
+            }
             Stmt::Return { value, .. } => {
                 self.write_indent();
                 self.write("return");
@@ -615,6 +684,7 @@ impl Formatter {
                 self.format_block(block);
                 self.newline();
             }
+            Stmt::Error { .. } => {}
         }
     }
 
@@ -698,6 +768,25 @@ impl Formatter {
         self.indent -= 1;
     }
 
+    fn format_match_arm(&mut self, arm: &MatchArm) {
+        self.write_indent();
+        self.write("case ");
+        self.write(&arm.variant);
+        if !arm.bindings.is_empty() {
+            self.write("(");
+            self.write(&arm.bindings.join(", "));
+            self.write(")");
+        }
+        self.write(":");
+        self.newline();
+
+        self.indent += 1;
+        for stmt in &arm.stmts {
+            self.format_stmt(stmt);
+        }
+        self.indent -= 1;
+    }
+
     /// Format an expression
     fn format_expr(&mut self, expr: &Expr) {
         match expr {
@@ -799,8 +888,31 @@ impl Formatter {
                 self.format_expr(value);
                 self.write(")");
             }
-            Expr::StructLit { name, fields, .. } => {
+            Expr::Builtin { op, args, .. } => {
+                self.write(op.name());
+                self.write("(");
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.format_expr(arg);
+                }
+                self.write(")");
+            }
+            Expr::Instance {
+                name, type_args, ..
+            } => {
                 self.write(name);
+                self.format_type_args(type_args);
+            }
+            Expr::StructLit {
+                name,
+                type_args,
+                fields,
+                ..
+            } => {
+                self.write(name);
+                self.format_type_args(type_args);
                 self.write(" { ");
                 for (i, field) in fields.iter().enumerate() {
                     if i > 0 {
@@ -911,25 +1023,6 @@ fn unaryop_str(op: UnaryOp) -> &'static str {
     }
 }
 
-/// Get the string representation of a primitive type
-fn primitive_name(p: PrimitiveType) -> &'static str {
-    match p {
-        PrimitiveType::I8 => "i8",
-        PrimitiveType::I16 => "i16",
-        PrimitiveType::I32 => "i32",
-        PrimitiveType::I64 => "i64",
-        PrimitiveType::U8 => "u8",
-        PrimitiveType::U16 => "u16",
-        PrimitiveType::U32 => "u32",
-        PrimitiveType::U64 => "u64",
-        PrimitiveType::F32 => "f32",
-        PrimitiveType::F64 => "f64",
-        PrimitiveType::Bool => "bool",
-        PrimitiveType::Usize => "usize",
-        PrimitiveType::Isize => "isize",
-    }
-}
-
 /// Escape special characters in a string
 fn escape_string(s: &str) -> String {
     let mut result = String::new();
@@ -49,6 +49,8 @@ pub enum Token {
     For,
     #[token("switch")]
     Switch,
+    #[token("match")]
+    Match,
     #[token("case")]
     Case,
     #[token("default")]
@@ -180,6 +182,14 @@ pub enum Token {
     #[token("from_rawm_unchecked")]
     FromRawmUnchecked,
 
+    // Slice helpers
+    #[token("as_slice")]
+    AsSlice,
+    #[token("subslice")]
+    Subslice,
+    #[token("slice_from_raw")]
+    SliceFromRaw,
+
     // Boolean literals
     #[token("true")]
     True,
@@ -210,9 +220,11 @@ pub enum Token {
     #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice().to_string())]
     Ident(String),
 
-    // Attribute
+    // Attributes
     #[token("@repr")]
     AtRepr,
+    #[token("@drop")]
+    AtDrop,
 
     // Operators
     #[token("+")]
@@ -353,6 +365,7 @@ impl fmt::Display for Token {
             Token::While => write!(f, "while"),
             Token::For => write!(f, "for"),
             Token::Switch => write!(f, "switch"),
+            Token::Match => write!(f, "match"),
             Token::Case => write!(f, "case"),
             Token::Default => write!(f, "default"),
             Token::Break => write!(f, "break"),
@@ -414,6 +427,9 @@ impl fmt::Display for Token {
             Token::FromRawm => write!(f, "from_rawm"),
             Token::FromRawUnchecked => write!(f, "from_raw_unchecked"),
             Token::FromRawmUnchecked => write!(f, "from_rawm_unchecked"),
+            Token::AsSlice => write!(f, "as_slice"),
+            Token::Subslice => write!(f, "subslice"),
+            Token::SliceFromRaw => write!(f, "slice_from_raw"),
             Token::True => write!(f, "true"),
             Token::False => write!(f, "false"),
             Token::IntLit(n) => write!(f, "{}", n),
@@ -424,6 +440,7 @@ impl fmt::Display for Token {
             Token::StringLit(s) => write!(f, "\"{}\"", s),
             Token::Ident(s) => write!(f, "{}", s),
             Token::AtRepr => write!(f, "@repr"),
+            Token::AtDrop => write!(f, "@drop"),
             Token::Plus => write!(f, "+"),
             Token::Minus => write!(f, "-"),
             Token::Star => write!(f, "*"),
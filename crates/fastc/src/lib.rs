@@ -5,13 +5,16 @@
 //! predictable behavior.
 
 pub mod ast;
+pub mod borrow;
 pub mod build;
 pub mod deps;
 pub mod diag;
 pub mod emit;
 pub mod fmt;
 pub mod lexer;
+pub mod lint;
 pub mod lower;
+pub mod mono;
 pub mod p10;
 pub mod parser;
 pub mod resolve;
@@ -23,7 +26,10 @@ mod driver;
 pub use ast::Item;
 pub use build::{BuildContext, BuildError};
 pub use deps::{Cache, Fetcher, Lockfile, Manifest, ModuleLoader, ModuleResolver};
-pub use driver::{check, check_with_p10, compile, compile_with_options, compile_with_p10, parse};
+pub use driver::{
+    CompileOutput, EmitOptions, FixOutput, check, check_with_p10, compile,
+    compile_with_emit_options, compile_with_options, compile_with_p10, fix, parse,
+};
 pub use fmt::{check_formatted, format};
 pub use p10::{ComplianceReport, P10Checker, P10Config, ProjectReport, SafetyLevel};
 pub use scaffold::{BuildTemplate, ProjectType, create_project, init_project};
@@ -0,0 +1,233 @@
+//! Lints: warnings about code that compiles but is probably a mistake
+//!
+//! Every lint has a stable code. Findings are warnings by default; `-A`,
+//! `-W`, and `-D` set the level of a single code (or of every code through
+//! the `warnings` group), and strict mode promotes whatever is still a
+//! warning to an error. Power of 10 findings go through the same levels
+//! under their `P10-00N` codes.
+
+mod rules;
+
+pub use rules::Linter;
+
+use crate::ast::TypeExpr;
+use crate::diag::{CompileError, Fix, Severity};
+use crate::lexer::Span;
+use crate::typecheck::TypeContext;
+
+/// A local variable is never read
+pub const UNUSED_VARIABLE: &str = "unused_variable";
+/// A `use` declaration imports a name that is never referenced
+pub const UNUSED_IMPORT: &str = "unused_import";
+/// A local variable hides one from an enclosing scope
+pub const SHADOWING: &str = "shadowing";
+/// A statement follows `return`, `break`, or `continue`
+pub const UNREACHABLE_CODE: &str = "unreachable_code";
+/// A call statement drops a result that is not void
+pub const UNUSED_RESULT: &str = "unused_result";
+
+/// Group name that matches every lint code
+pub const WARNINGS: &str = "warnings";
+
+/// Every lint code with a one-line description
+pub const LINTS: &[(&str, &str)] = &[
+    (UNUSED_VARIABLE, "local variable is never read"),
+    (UNUSED_IMPORT, "imported name is never referenced"),
+    (SHADOWING, "local shadows an outer variable"),
+    (UNREACHABLE_CODE, "code after return, break, or continue"),
+    (UNUSED_RESULT, "result of a call is ignored"),
+];
+
+/// Whether `code` names a lint, a Power of 10 rule, or the `warnings` group
+pub fn is_known(code: &str) -> bool {
+    code == WARNINGS
+        || LINTS.iter().any(|(name, _)| *name == code)
+        || code
+            .strip_prefix("P10-")
+            .and_then(|n| n.parse::<u8>().ok())
+            .is_some_and(|n| (1..=10).contains(&n) && code.len() == 7)
+}
+
+/// What to do with a lint's findings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// Drop them
+    Allow,
+    /// Report them as warnings
+    Warn,
+    /// Report them as errors
+    Deny,
+}
+
+/// Lint levels set with `-A`, `-W`, and `-D`
+///
+/// A level for a specific code beats one for the `warnings` group, and a
+/// later setting for the same code replaces an earlier one. Codes without a
+/// setting warn.
+#[derive(Debug, Clone, Default)]
+pub struct LintLevels {
+    levels: Vec<(String, Level)>,
+}
+
+impl LintLevels {
+    /// Set the level for `code` (or for every code, given `warnings`)
+    pub fn set(&mut self, code: impl Into<String>, level: Level) {
+        let code = code.into();
+        self.levels.retain(|(c, _)| *c != code);
+        self.levels.push((code, level));
+    }
+
+    /// Level for `code`, before strict mode promotes warnings
+    pub fn get(&self, code: &str) -> Level {
+        let find = |name: &str| {
+            self.levels
+                .iter()
+                .find(|(c, _)| c == name)
+                .map(|(_, level)| *level)
+        };
+        find(code).or_else(|| find(WARNINGS)).unwrap_or(Level::Warn)
+    }
+}
+
+/// A lint finding before its level is applied
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub code: &'static str,
+    pub message: String,
+    pub span: Span,
+    pub hint: Option<String>,
+    pub fix: Option<Fix>,
+}
+
+impl Finding {
+    /// Convert to a diagnostic with the given severity
+    pub fn into_error(self, severity: Severity, source: &str) -> CompileError {
+        CompileError::lint(
+            self.code,
+            severity,
+            self.message,
+            self.span,
+            source,
+            self.hint,
+        )
+        .with_fix(self.fix)
+    }
+}
+
+/// The [`Linter`]'s findings, plus call statements whose lint waits on
+/// type checking
+#[derive(Debug, Default)]
+pub struct Lints {
+    pub findings: Vec<Finding>,
+    /// Enclosing function and span of each call used as a statement
+    pub calls: Vec<(String, Span)>,
+}
+
+impl Lints {
+    /// All findings, adding `unused_result` for each call statement whose
+    /// type is known and not void
+    pub fn with_types(self, types: &TypeContext) -> Vec<Finding> {
+        let mut findings = self.findings;
+        for (fn_name, span) in self.calls {
+            let Some(ty) = types.expr_type(&fn_name, &span) else {
+                continue;
+            };
+            if *ty == TypeExpr::Void {
+                continue;
+            }
+            findings.push(Finding {
+                code: UNUSED_RESULT,
+                message: "result of this call is ignored".to_string(),
+                span: span.clone(),
+                hint: Some("pass it to `discard(...)` if it is intentionally unused".to_string()),
+                fix: Some(Fix::wrap(
+                    "wrap the call in `discard(...)`",
+                    span,
+                    "discard(",
+                    ")",
+                )),
+            });
+        }
+        findings.sort_by_key(|f| f.span.start);
+        findings
+    }
+}
+
+/// Errors and warnings left once lint levels are applied
+#[derive(Debug, Default)]
+pub struct LintReport {
+    pub errors: Vec<CompileError>,
+    pub warnings: Vec<CompileError>,
+}
+
+impl LintReport {
+    /// File a diagnostic under `level`
+    pub fn push(&mut self, level: Level, error: impl FnOnce(Severity) -> CompileError) {
+        match level {
+            Level::Allow => {}
+            Level::Warn => self.warnings.push(error(Severity::Warning)),
+            Level::Deny => self.errors.push(error(Severity::Error)),
+        }
+    }
+
+    /// Append another report
+    pub fn extend(&mut self, other: LintReport) {
+        self.errors.extend(other.errors);
+        self.warnings.extend(other.warnings);
+    }
+
+    /// Fail with the errors if there are any, otherwise return the warnings
+    pub fn into_result(self) -> Result<Vec<CompileError>, CompileError> {
+        if self.errors.is_empty() {
+            Ok(self.warnings)
+        } else {
+            Err(CompileError::multiple(self.errors))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_default_to_warn() {
+        let levels = LintLevels::default();
+        assert_eq!(levels.get(UNUSED_VARIABLE), Level::Warn);
+        assert_eq!(levels.get("P10-004"), Level::Warn);
+    }
+
+    #[test]
+    fn test_specific_level_beats_group() {
+        let mut levels = LintLevels::default();
+        levels.set(SHADOWING, Level::Deny);
+        levels.set(WARNINGS, Level::Allow);
+        assert_eq!(levels.get(SHADOWING), Level::Deny);
+        assert_eq!(levels.get(UNUSED_IMPORT), Level::Allow);
+
+        levels.set(SHADOWING, Level::Warn);
+        assert_eq!(levels.get(SHADOWING), Level::Warn);
+    }
+
+    #[test]
+    fn test_known_codes() {
+        assert!(is_known(UNREACHABLE_CODE));
+        assert!(is_known(WARNINGS));
+        assert!(is_known("P10-009"));
+        assert!(!is_known("P10-011"));
+        assert!(!is_known("P10-4"));
+        assert!(!is_known("unused"));
+    }
+
+    #[test]
+    fn test_unused_result_only_for_values() {
+        let source = "fn get() -> i32 { return 1; }\nfn put() -> void { return; }\nfn main() -> i32 { get(); put(); return 0; }";
+        let warnings =
+            crate::check_with_p10(source, "test.fc", crate::P10Config::standard()).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            crate::diag::apply_fixes(source, warnings[0].fix()).0,
+            source.replace("get();", "discard(get());")
+        );
+    }
+}
@@ -0,0 +1,506 @@
+//! Lint checks over a parsed file
+//!
+//! Runs on the file as written, before module expansion and
+//! monomorphization, so every span points into the file's own source and
+//! generic bodies are checked once rather than once per instance.
+
+use std::collections::HashSet;
+
+use super::{Finding, Lints, SHADOWING, UNREACHABLE_CODE, UNUSED_IMPORT, UNUSED_VARIABLE};
+use crate::ast::{
+    Block, ElseBranch, Expr, FieldInit, File, FnDecl, ForInit, ForStep, Item, Stmt, UseDecl,
+    UseItems,
+};
+use crate::lexer::{Lexer, Span, Token};
+
+/// A local variable and whether it has been read
+struct Binding {
+    name: String,
+    span: Span,
+    used: bool,
+}
+
+/// Lint checker
+pub struct Linter<'a> {
+    source: &'a str,
+    /// Innermost scope last, mirroring the resolver's scopes
+    scopes: Vec<Vec<Binding>>,
+    findings: Vec<Finding>,
+    /// Function whose body is being checked
+    current_fn: String,
+    /// Call statements, checked for `unused_result` once types are known
+    calls: Vec<(String, Span)>,
+}
+
+impl<'a> Linter<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            scopes: Vec::new(),
+            findings: Vec::new(),
+            current_fn: String::new(),
+            calls: Vec::new(),
+        }
+    }
+
+    /// Collect lint findings for `file`, in source order
+    pub fn check(mut self, file: &File) -> Lints {
+        self.check_items(&file.items);
+        self.check_imports(file);
+        self.findings.sort_by_key(|f| f.span.start);
+        Lints {
+            findings: self.findings,
+            calls: self.calls,
+        }
+    }
+
+    fn check_items(&mut self, items: &[Item]) {
+        for item in items {
+            match item {
+                Item::Fn(func) => self.check_fn(func),
+                // Before expansion, only inline modules have a body
+                Item::Mod(module) => {
+                    if let Some(body) = &module.body {
+                        self.check_items(body);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn check_fn(&mut self, func: &FnDecl) {
+        self.current_fn = func.name.clone();
+        self.enter_scope();
+        for param in &func.params {
+            // Unused parameters are part of the signature, not a mistake
+            self.scopes.last_mut().unwrap().push(Binding {
+                name: param.name.clone(),
+                span: param.span.clone(),
+                used: true,
+            });
+        }
+        self.check_block(&func.body);
+        self.exit_scope();
+    }
+
+    fn check_block(&mut self, block: &Block) {
+        self.enter_scope();
+        self.check_stmts(&block.stmts);
+        self.exit_scope();
+    }
+
+    /// Check statements that share the current scope
+    fn check_stmts(&mut self, stmts: &[Stmt]) {
+        for (i, stmt) in stmts.iter().enumerate() {
+            self.check_stmt(stmt);
+
+            let exit = match stmt {
+                Stmt::Return { .. } => "return",
+                Stmt::Break { .. } => "break",
+                Stmt::Continue { .. } => "continue",
+                _ => continue,
+            };
+            if let (Some(first), Some(last)) = (stmts.get(i + 1), stmts.last()) {
+                self.findings.push(Finding {
+                    code: UNREACHABLE_CODE,
+                    message: "unreachable statement".to_string(),
+                    span: first.span().start..last.span().end,
+                    hint: Some(format!("this code follows a `{}` and never runs", exit)),
+                    fix: None,
+                });
+            }
+            break;
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Let {
+                name, init, span, ..
+            } => {
+                self.check_expr(init);
+                self.bind(name, span.clone());
+            }
+            Stmt::Assign { lhs, rhs, .. } => {
+                // Storing to a variable is not a read of it
+                if !matches!(lhs, Expr::Ident { .. }) {
+                    self.check_expr(lhs);
+                }
+                self.check_expr(rhs);
+            }
+            Stmt::If {
+                cond,
+                then_block,
+                else_block,
+                ..
+            } => {
+                self.check_expr(cond);
+                self.check_block(then_block);
+                match else_block {
+                    Some(ElseBranch::ElseIf(stmt)) => self.check_stmt(stmt),
+                    Some(ElseBranch::Else(block)) => self.check_block(block),
+                    None => {}
+                }
+            }
+            Stmt::IfLet {
+                name,
+                expr,
+                then_block,
+                else_block,
+                span,
+            } => {
+                self.check_expr(expr);
+                self.enter_scope();
+                self.bind(name, span.clone());
+                self.check_stmts(&then_block.stmts);
+                self.exit_scope();
+                if let Some(block) = else_block {
+                    self.check_block(block);
+                }
+            }
+            Stmt::While { cond, body, .. } => {
+                self.check_expr(cond);
+                self.check_block(body);
+            }
+            Stmt::For {
+                init,
+                cond,
+                step,
+                body,
+                span,
+            } => {
+                self.enter_scope();
+                match init {
+                    Some(ForInit::Let { name, init, .. }) => {
+                        self.check_expr(init);
+                        self.bind(name, span.clone());
+                    }
+                    Some(ForInit::Assign { lhs, rhs }) => {
+                        self.check_expr(lhs);
+                        self.check_expr(rhs);
+                    }
+                    Some(ForInit::Call(expr)) => self.check_expr(expr),
+                    None => {}
+                }
+                if let Some(cond) = cond {
+                    self.check_expr(cond);
+                }
+                match step {
+                    Some(ForStep::Assign { lhs, rhs }) => {
+                        self.check_expr(lhs);
+                        self.check_expr(rhs);
+                    }
+                    Some(ForStep::Call(expr)) => self.check_expr(expr),
+                    None => {}
+                }
+                self.check_stmts(&body.stmts);
+                self.exit_scope();
+            }
+            Stmt::Switch {
+                expr,
+                cases,
+                default,
+                ..
+            } => {
+                self.check_expr(expr);
+                for case in cases {
+                    self.check_stmts(&case.stmts);
+                }
+                if let Some(stmts) = default {
+                    self.check_stmts(stmts);
+                }
+            }
+            Stmt::Match {
+                expr,
+                arms,
+                default,
+                ..
+            } => {
+                self.check_expr(expr);
+                for arm in arms {
+                    self.enter_scope();
+                    for binding in arm.bindings.iter().filter(|b| b.as_str() != "_") {
+                        self.bind(binding, arm.span.clone());
+                    }
+                    self.check_stmts(&arm.stmts);
+                    self.exit_scope();
+                }
+                if let Some(stmts) = default {
+                    self.check_stmts(stmts);
+                }
+            }
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.check_expr(value);
+                }
+            }
+            Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Error { .. } => {}
+            Stmt::Expr { expr, .. } => {
+                if let Expr::Call { span, .. } = expr {
+                    self.calls.push((self.current_fn.clone(), span.clone()));
+                }
+                self.check_expr(expr);
+            }
+            Stmt::Discard { expr, .. } => self.check_expr(expr),
+            Stmt::Defer { body, .. } | Stmt::Unsafe { body, .. } | Stmt::Block(body) => {
+                self.check_block(body)
+            }
+        }
+    }
+
+    fn check_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Ident { name, .. } => self.mark_used(name),
+            Expr::Binary { lhs, rhs, .. } => {
+                self.check_expr(lhs);
+                self.check_expr(rhs);
+            }
+            Expr::Call { callee, args, .. } => {
+                self.check_expr(callee);
+                for arg in args {
+                    self.check_expr(arg);
+                }
+            }
+            Expr::At { base, index, .. } => {
+                self.check_expr(base);
+                self.check_expr(index);
+            }
+            Expr::Builtin { args, .. } => {
+                for arg in args {
+                    self.check_expr(arg);
+                }
+            }
+            Expr::StructLit { fields, .. } => {
+                for FieldInit { value, .. } in fields {
+                    self.check_expr(value);
+                }
+            }
+            Expr::Unary { operand, .. }
+            | Expr::Addr { operand, .. }
+            | Expr::Deref { operand, .. } => self.check_expr(operand),
+            Expr::Paren { inner, .. } => self.check_expr(inner),
+            Expr::Field { base, .. } => self.check_expr(base),
+            Expr::Cast { expr, .. } => self.check_expr(expr),
+            Expr::Some { value, .. } | Expr::Ok { value, .. } | Expr::Err { value, .. } => {
+                self.check_expr(value)
+            }
+            Expr::IntLit { .. }
+            | Expr::FloatLit { .. }
+            | Expr::BoolLit { .. }
+            | Expr::CStr { .. }
+            | Expr::Bytes { .. }
+            | Expr::None { .. }
+            | Expr::Instance { .. } => {}
+        }
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn exit_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap_or_default();
+        for binding in scope {
+            if !binding.used && !binding.name.starts_with('_') {
+                self.findings.push(Finding {
+                    code: UNUSED_VARIABLE,
+                    message: format!("unused variable `{}`", binding.name),
+                    span: binding.span,
+                    hint: Some(format!(
+                        "remove it, or name it `_{}` if it is intentionally unused",
+                        binding.name
+                    )),
+                    fix: None,
+                });
+            }
+        }
+    }
+
+    /// Declare a local in the current scope; `span` is the declaring
+    /// statement, narrowed to the name where it can be found
+    fn bind(&mut self, name: &str, span: Span) {
+        let span = name_span(self.source, span, name);
+        let (current, outer) = self.scopes.split_last_mut().unwrap();
+        if outer.iter().flatten().any(|b| b.name == name) {
+            self.findings.push(Finding {
+                code: SHADOWING,
+                message: format!("`{}` shadows a variable from an enclosing scope", name),
+                span: span.clone(),
+                hint: Some("rename one of them so each name has one meaning".to_string()),
+                fix: None,
+            });
+        }
+        current.push(Binding {
+            name: name.to_string(),
+            span,
+            used: false,
+        });
+    }
+
+    fn mark_used(&mut self, name: &str) {
+        if let Some(binding) = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|b| b.name == name)
+        {
+            binding.used = true;
+        }
+    }
+
+    /// Flag names imported by `use` that no identifier outside a `use`
+    /// declaration refers to
+    ///
+    /// Module items share the file's namespace, so an enum counts as used
+    /// when one of its variants (`Color_Red`) is.
+    fn check_imports(&mut self, file: &File) {
+        let uses: Vec<&UseDecl> = file
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Use(decl) => Some(decl),
+                _ => None,
+            })
+            .collect();
+        if uses.is_empty() {
+            return;
+        }
+
+        let referenced: HashSet<String> = Lexer::tokenize(self.source)
+            .into_iter()
+            .filter(|tok| !uses.iter().any(|u| u.span.contains(&tok.span.start)))
+            .filter_map(|tok| match tok.node {
+                Token::Ident(name) => Some(name),
+                _ => None,
+            })
+            .collect();
+        let is_referenced = |name: &str| {
+            referenced.contains(name)
+                || referenced.iter().any(|r| {
+                    r.strip_prefix(name)
+                        .is_some_and(|rest| rest.starts_with('_'))
+                })
+        };
+
+        for decl in uses {
+            let names: &[String] = match &decl.items {
+                UseItems::Single(name) => std::slice::from_ref(name),
+                UseItems::Multiple(names) => names,
+                // Globs and whole-module imports name nothing to check
+                UseItems::Glob | UseItems::Module => continue,
+            };
+            for name in names.iter().filter(|n| !is_referenced(n)) {
+                self.findings.push(Finding {
+                    code: UNUSED_IMPORT,
+                    message: format!("unused import `{}`", name),
+                    span: last_name_span(self.source, decl.span.clone(), name),
+                    hint: Some("remove it from the `use` declaration".to_string()),
+                    fix: None,
+                });
+            }
+        }
+    }
+}
+
+/// The first whole-word occurrence of `name` in `span`, or `span` itself
+fn name_span(source: &str, span: Span, name: &str) -> Span {
+    word_offsets(source, &span, name)
+        .next()
+        .map_or(span, |start| start..start + name.len())
+}
+
+/// The last whole-word occurrence of `name` in `span`, or `span` itself
+///
+/// In `use a::b::{b, c};` the imported names come after the path.
+fn last_name_span(source: &str, span: Span, name: &str) -> Span {
+    word_offsets(source, &span, name)
+        .last()
+        .map_or(span, |start| start..start + name.len())
+}
+
+/// Offsets of `name` as a whole identifier within `span`
+fn word_offsets<'s>(
+    source: &'s str,
+    span: &Span,
+    name: &'s str,
+) -> impl Iterator<Item = usize> + 's {
+    let base = span.start;
+    let text = source.get(span.clone()).unwrap_or("");
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(name).filter_map(move |(i, _)| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + name.len()..].chars().next();
+        (!before.is_some_and(is_ident) && !after.is_some_and(is_ident)).then_some(base + i)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn lint(source: &str) -> Vec<(&'static str, String)> {
+        let file = parse(source, "test.fc").unwrap();
+        Linter::new(source)
+            .check(&file)
+            .findings
+            .into_iter()
+            .map(|f| (f.code, source[f.span].to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_unused_variable() {
+        let found = lint(
+            "fn f(a: i32) -> i32 { let x: i32 = 1; let _y: i32 = 2; let z: i32 = a; return z; }",
+        );
+        assert_eq!(found, vec![(UNUSED_VARIABLE, "x".to_string())]);
+    }
+
+    #[test]
+    fn test_shadowing_outer_scope() {
+        let found = lint(
+            "fn f(x: i32) -> i32 { let y: i32 = x; if (true) { let y: i32 = 2; return y; } return y; }",
+        );
+        assert_eq!(found, vec![(SHADOWING, "y".to_string())]);
+    }
+
+    #[test]
+    fn test_shadowing_parameter() {
+        let found = lint("fn f(x: i32) -> i32 { let x: i32 = 1; return x; }");
+        assert_eq!(found, vec![(SHADOWING, "x".to_string())]);
+    }
+
+    #[test]
+    fn test_unreachable_after_return() {
+        let found = lint("fn f() -> i32 { return 1; discard(2); discard(3); }");
+        assert_eq!(
+            found,
+            vec![(UNREACHABLE_CODE, "discard(2); discard(3);".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_unreachable_after_break() {
+        let found = lint("fn f() -> void { while (true) { break; discard(1); } }");
+        assert_eq!(found, vec![(UNREACHABLE_CODE, "discard(1);".to_string())]);
+    }
+
+    #[test]
+    fn test_unused_import() {
+        let found = lint(
+            "use shapes::{Color, Point, area};\nfn f(p: Point) -> i32 { discard(Color_Red); return 0; }",
+        );
+        assert_eq!(found, vec![(UNUSED_IMPORT, "area".to_string())]);
+    }
+
+    #[test]
+    fn test_clean_code_has_no_findings() {
+        let found = lint(
+            "fn f(n: i32) -> i32 { let total: i32 = 0; for (let i: i32 = 0; i < n; i = i + 1) { total = total + i; } return total; }",
+        );
+        assert!(found.is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+//! Static facts that let array bounds checks be elided
+
+use crate::ast::{self, BinOp, Expr, ForInit, ForStep, Stmt};
+
+/// Counter of a `for` loop that stays in `0..bound` throughout its body
+///
+/// Recognizes `for (let i: T = start; i < bound; i = i + step)` where
+/// `bound` and `step` are constants, `start` is a constant `>= 0` (or `T` is
+/// unsigned), the step cannot wrap `T`, and the body neither assigns `i`,
+/// takes its address, nor shadows it. `eval` folds constant expressions.
+pub fn for_counter_bound(
+    init: Option<&ForInit>,
+    cond: Option<&Expr>,
+    step: Option<&ForStep>,
+    body: &ast::Block,
+    eval: impl Fn(&Expr) -> Option<i128>,
+) -> Option<(String, i128)> {
+    let Some(ForInit::Let { name, ty, init }) = init else {
+        return None;
+    };
+    let ast::TypeExpr::Primitive(prim) = ty else {
+        return None;
+    };
+    let (min, max) = prim.int_range()?;
+    if min < 0 && eval(init).is_none_or(|start| start < 0) {
+        return None;
+    }
+
+    let Expr::Binary {
+        op: BinOp::Lt,
+        lhs,
+        rhs,
+        ..
+    } = strip_parens(cond?)
+    else {
+        return None;
+    };
+    if !is_var(lhs, name) {
+        return None;
+    }
+    let bound = eval(rhs)?;
+
+    let increment = match step {
+        None => 0,
+        Some(ForStep::Assign { lhs, rhs }) if is_var(lhs, name) => match strip_parens(rhs) {
+            Expr::Binary {
+                op: BinOp::Add,
+                lhs,
+                rhs,
+                ..
+            } if is_var(lhs, name) => eval(rhs)?,
+            _ => return None,
+        },
+        Some(_) => return None,
+    };
+    if increment < 0 || bound.checked_add(increment)? > max {
+        return None;
+    }
+
+    if body.stmts.iter().any(|s| stmt_touches(s, name)) {
+        return None;
+    }
+    Some((name.clone(), bound))
+}
+
+fn strip_parens(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Paren { inner, .. } => strip_parens(inner),
+        _ => expr,
+    }
+}
+
+fn is_var(expr: &Expr, name: &str) -> bool {
+    matches!(strip_parens(expr), Expr::Ident { name: n, .. } if n == name)
+}
+
+/// Whether a statement may change `name` or declare another variable with it
+fn stmt_touches(stmt: &Stmt, name: &str) -> bool {
+    let block = |b: &ast::Block| b.stmts.iter().any(|s| stmt_touches(s, name));
+    let stmts = |ss: &[Stmt]| ss.iter().any(|s| stmt_touches(s, name));
+    match stmt {
+        Stmt::Let {
+            name: declared,
+            init,
+            ..
+        } => declared == name || expr_touches(init, name),
+        Stmt::Assign { lhs, rhs, .. } => {
+            is_var(lhs, name) || expr_touches(lhs, name) || expr_touches(rhs, name)
+        }
+        Stmt::If {
+            cond,
+            then_block,
+            else_block,
+            ..
+        } => {
+            expr_touches(cond, name)
+                || block(then_block)
+                || match else_block {
+                    Some(ast::ElseBranch::ElseIf(stmt)) => stmt_touches(stmt, name),
+                    Some(ast::ElseBranch::Else(b)) => block(b),
+                    None => false,
+                }
+        }
+        Stmt::IfLet {
+            name: declared,
+            expr,
+            then_block,
+            else_block,
+            ..
+        } => {
+            declared == name
+                || expr_touches(expr, name)
+                || block(then_block)
+                || else_block.as_ref().is_some_and(block)
+        }
+        Stmt::While { cond, body, .. } => expr_touches(cond, name) || block(body),
+        Stmt::For {
+            init,
+            cond,
+            step,
+            body,
+            ..
+        } => {
+            let init = match init {
+                Some(ForInit::Let {
+                    name: declared,
+                    init,
+                    ..
+                }) => declared == name || expr_touches(init, name),
+                Some(ForInit::Assign { lhs, rhs }) => {
+                    is_var(lhs, name) || expr_touches(lhs, name) || expr_touches(rhs, name)
+                }
+                Some(ForInit::Call(expr)) => expr_touches(expr, name),
+                None => false,
+            };
+            let step = match step {
+                Some(ForStep::Assign { lhs, rhs }) => {
+                    is_var(lhs, name) || expr_touches(lhs, name) || expr_touches(rhs, name)
+                }
+                Some(ForStep::Call(expr)) => expr_touches(expr, name),
+                None => false,
+            };
+            init || step || cond.as_ref().is_some_and(|c| expr_touches(c, name)) || block(body)
+        }
+        Stmt::Switch {
+            expr,
+            cases,
+            default,
+            ..
+        } => {
+            expr_touches(expr, name)
+                || cases.iter().any(|c| stmts(&c.stmts))
+                || default.as_deref().is_some_and(stmts)
+        }
+        Stmt::Match {
+            expr,
+            arms,
+            default,
+            ..
+        } => {
+            expr_touches(expr, name)
+                || arms
+                    .iter()
+                    .any(|a| a.bindings.iter().any(|b| b == name) || stmts(&a.stmts))
+                || default.as_deref().is_some_and(stmts)
+        }
+        Stmt::Return { value, .. } => value.as_ref().is_some_and(|v| expr_touches(v, name)),
+        Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Error { .. } => false,
+        Stmt::Defer { body, .. } | Stmt::Unsafe { body, .. } | Stmt::Block(body) => block(body),
+        Stmt::Expr { expr, .. } | Stmt::Discard { expr, .. } => expr_touches(expr, name),
+    }
+}
+
+/// Whether an expression takes the address of `name`
+fn expr_touches(expr: &Expr, name: &str) -> bool {
+    let touches = |e: &Expr| expr_touches(e, name);
+    match expr {
+        Expr::Addr { operand, .. } => is_var(operand, name) || touches(operand),
+        Expr::Binary { lhs, rhs, .. } => touches(lhs) || touches(rhs),
+        Expr::Unary { operand, .. } | Expr::Deref { operand, .. } => touches(operand),
+        Expr::Paren { inner, .. } => touches(inner),
+        Expr::Call { callee, args, .. } => touches(callee) || args.iter().any(touches),
+        Expr::Field { base, .. } => touches(base),
+        Expr::At { base, index, .. } => touches(base) || touches(index),
+        Expr::Cast { expr, .. } => touches(expr),
+        Expr::Some { value, .. } | Expr::Ok { value, .. } | Expr::Err { value, .. } => {
+            touches(value)
+        }
+        Expr::Builtin { args, .. } => args.iter().any(touches),
+        Expr::StructLit { fields, .. } => fields.iter().any(|f| touches(&f.value)),
+        Expr::IntLit { .. }
+        | Expr::FloatLit { .. }
+        | Expr::BoolLit { .. }
+        | Expr::Ident { .. }
+        | Expr::CStr { .. }
+        | Expr::Bytes { .. }
+        | Expr::None { .. }
+        | Expr::Instance { .. } => false,
+    }
+}
@@ -6,8 +6,11 @@ pub struct CFile {
     pub includes: Vec<String>,
     pub forward_decls: Vec<CDecl>,
     pub type_defs: Vec<CDecl>,
+    pub consts: Vec<CConst>,
     pub fn_protos: Vec<CFnProto>,
     pub fn_defs: Vec<CFnDef>,
+    /// FastC files that `CLoc`s point into, by id; the compiled file is 0
+    pub sources: Vec<String>,
 }
 
 impl CFile {
@@ -16,8 +19,10 @@ impl CFile {
             includes: Vec::new(),
             forward_decls: Vec::new(),
             type_defs: Vec::new(),
+            consts: Vec::new(),
             fn_protos: Vec::new(),
             fn_defs: Vec::new(),
+            sources: Vec::new(),
         }
     }
 }
@@ -31,9 +36,40 @@ impl Default for CFile {
 /// C declarations
 #[derive(Debug, Clone)]
 pub enum CDecl {
-    Struct { name: String, fields: Vec<CField> },
-    Typedef { name: String, ty: CType },
-    Enum { name: String, variants: Vec<String> },
+    Struct {
+        name: String,
+        fields: Vec<CField>,
+    },
+    Typedef {
+        name: String,
+        ty: CType,
+    },
+    Enum {
+        name: String,
+        variants: Vec<String>,
+    },
+    /// Incomplete struct: `typedef struct Name Name;`
+    Opaque {
+        name: String,
+    },
+    /// Enum with payloads: tag constants plus `struct { tag; union { ... } data; }`
+    TaggedUnion {
+        name: String,
+        tag_ty: CType,
+        tags: Vec<String>,
+        /// Payload struct per variant (unit variants have none)
+        variants: Vec<(String, Vec<CField>)>,
+    },
+}
+
+/// C file-scope constant: `static const T NAME = value;`
+#[derive(Debug, Clone)]
+pub struct CConst {
+    pub name: String,
+    pub ty: CType,
+    pub value: CExpr,
+    /// Emit `static T NAME` instead, for storage that may be written
+    pub mutable: bool,
 }
 
 /// C struct field
@@ -58,6 +94,16 @@ pub struct CFnDef {
     pub params: Vec<CParam>,
     pub return_type: CType,
     pub body: Vec<CStmt>,
+    /// Where the FastC function this was lowered from starts
+    pub loc: CLoc,
+}
+
+/// A FastC source position (1-based line and column) in `CFile::sources[file]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CLoc {
+    pub file: usize,
+    pub line: usize,
+    pub column: usize,
 }
 
 /// C function parameter
@@ -117,7 +163,7 @@ pub enum CStmt {
     For {
         init: Option<Box<CStmt>>,
         cond: Option<CExpr>,
-        step: Option<CExpr>,
+        step: Option<Box<CStmt>>,
         body: Vec<CStmt>,
     },
     Return(Option<CExpr>),
@@ -131,6 +177,10 @@ pub enum CStmt {
         default: Option<Vec<CStmt>>,
     },
     Break,
+    Continue,
+    /// Source location of the statements that follow, up to the next `Loc`;
+    /// emits no C of its own
+    Loc(CLoc),
 }
 
 /// C expressions
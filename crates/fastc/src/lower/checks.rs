@@ -1,53 +1,180 @@
 //! Runtime check insertion for safety
+//!
+//! Every check traps through `fc_trap_at(kind, file, line, col)`, naming why
+//! it failed and the FastC source it was lowered from.
 
-use super::{CBinOp, CExpr, CStmt, CType};
+use super::{CBinOp, CExpr, CStmt, CType, Lower};
+
+/// Why a runtime check traps; mirrors the compiler-emitted kinds of `fc_trap_kind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrapKind {
+    /// Index or subslice outside its slice or array
+    Bounds,
+    /// Signed arithmetic overflow
+    Overflow,
+    /// Division or remainder by zero
+    DivZero,
+    /// Shift count out of range, or signed left shift that overflows
+    Shift,
+    /// `unwrap` of `none` or an `err`, `unwrap_err` of an `ok`
+    Unwrap,
+    /// Null pointer
+    Null,
+    /// Enum value with no matching variant
+    BadTag,
+}
+
+impl TrapKind {
+    fn c_name(self) -> &'static str {
+        match self {
+            TrapKind::Bounds => "FC_TRAP_BOUNDS",
+            TrapKind::Overflow => "FC_TRAP_OVERFLOW",
+            TrapKind::DivZero => "FC_TRAP_DIV_ZERO",
+            TrapKind::Shift => "FC_TRAP_SHIFT",
+            TrapKind::Unwrap => "FC_TRAP_UNWRAP",
+            TrapKind::Null => "FC_TRAP_NULL",
+            TrapKind::BadTag => "FC_TRAP_BAD_TAG",
+        }
+    }
+}
+
+/// FastC source location a trap reports (1-based line and column)
+#[derive(Debug, Clone)]
+pub struct TrapSite {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// `fc_trap_at(kind, "file.fc", line, col);`
+pub fn trap(kind: TrapKind, site: &TrapSite) -> CStmt {
+    CStmt::Expr(CExpr::Call {
+        func: Box::new(CExpr::Ident("fc_trap_at".to_string())),
+        args: vec![
+            CExpr::Ident(kind.c_name().to_string()),
+            CExpr::StringLit(site.file.clone()),
+            CExpr::IntLit(site.line.to_string()),
+            CExpr::IntLit(site.column.to_string()),
+        ],
+    })
+}
 
 /// Insert a bounds check
-pub fn bounds_check(index: CExpr, len: CExpr) -> CStmt {
-    CStmt::If {
-        cond: CExpr::Binary {
+pub fn bounds_check(index: CExpr, len: CExpr, site: &TrapSite) -> CStmt {
+    trap_if(
+        CExpr::Binary {
             op: CBinOp::Ge,
             lhs: Box::new(index),
             rhs: Box::new(len),
         },
-        then: vec![CStmt::Expr(CExpr::Call {
-            func: Box::new(CExpr::Ident("fc_trap".to_string())),
-            args: vec![],
-        })],
-        else_: None,
+        TrapKind::Bounds,
+        site,
+    )
+}
+
+/// Insert a bounds check against the constant length of an `arr(T, N)`,
+/// also trapping on a negative signed index
+pub fn array_bounds_check(index: CExpr, index_ty: &CType, len: usize, site: &TrapSite) -> CStmt {
+    let mut cond = CExpr::Binary {
+        op: CBinOp::Ge,
+        lhs: Box::new(index.clone()),
+        rhs: Box::new(CExpr::IntLit(len.to_string())),
+    };
+    if Lower::is_signed_integer(index_ty) {
+        cond = CExpr::Binary {
+            op: CBinOp::Or,
+            lhs: Box::new(CExpr::Binary {
+                op: CBinOp::Lt,
+                lhs: Box::new(index),
+                rhs: Box::new(CExpr::IntLit("0".to_string())),
+            }),
+            rhs: Box::new(cond),
+        };
     }
+    trap_if(cond, TrapKind::Bounds, site)
+}
+
+/// Insert a subslice check: trap unless `0 <= start <= end <= len`
+///
+/// `len` is `None` when `end` is the length itself.
+pub fn subslice_check(
+    (start, start_ty): (CExpr, &CType),
+    (end, end_ty): (CExpr, &CType),
+    len: Option<CExpr>,
+    site: &TrapSite,
+) -> CStmt {
+    let compare = |op, lhs: CExpr, rhs: CExpr| CExpr::Binary {
+        op,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    };
+    let mut cond = compare(CBinOp::Gt, start.clone(), end.clone());
+    if let Some(len) = len {
+        cond = compare(CBinOp::Or, cond, compare(CBinOp::Gt, end.clone(), len));
+    }
+    // A negative bound compares wrongly against an unsigned one
+    for (bound, ty) in [(end, end_ty), (start, start_ty)] {
+        if Lower::is_signed_integer(ty) {
+            cond = compare(
+                CBinOp::Or,
+                compare(CBinOp::Lt, bound, CExpr::IntLit("0".to_string())),
+                cond,
+            );
+        }
+    }
+    trap_if(cond, TrapKind::Bounds, site)
+}
+
+/// Insert the check behind unwrap/unwrap_err: trap when `failed` holds
+pub fn unwrap_check(failed: CExpr, site: &TrapSite) -> CStmt {
+    trap_if(failed, TrapKind::Unwrap, site)
 }
 
 /// Insert a null check
 #[allow(dead_code)]
-pub fn null_check(ptr: CExpr) -> CStmt {
-    CStmt::If {
-        cond: CExpr::Binary {
+pub fn null_check(ptr: CExpr, site: &TrapSite) -> CStmt {
+    trap_if(
+        CExpr::Binary {
             op: CBinOp::Eq,
             lhs: Box::new(ptr),
             rhs: Box::new(CExpr::Ident("NULL".to_string())),
         },
-        then: vec![CStmt::Expr(CExpr::Call {
-            func: Box::new(CExpr::Ident("fc_trap".to_string())),
-            args: vec![],
-        })],
-        else_: None,
-    }
+        TrapKind::Null,
+        site,
+    )
 }
 
 /// Insert a signed overflow check using __builtin_add_overflow
-pub fn overflow_check_add(lhs: CExpr, rhs: CExpr, result_var: &str, ty: CType) -> (CStmt, CStmt) {
-    overflow_check_with_builtin("__builtin_add_overflow", lhs, rhs, result_var, ty)
+pub fn overflow_check_add(
+    lhs: CExpr,
+    rhs: CExpr,
+    result_var: &str,
+    ty: CType,
+    site: &TrapSite,
+) -> (CStmt, CStmt) {
+    overflow_check_with_builtin("__builtin_add_overflow", lhs, rhs, result_var, ty, site)
 }
 
 /// Insert a signed overflow check using __builtin_sub_overflow
-pub fn overflow_check_sub(lhs: CExpr, rhs: CExpr, result_var: &str, ty: CType) -> (CStmt, CStmt) {
-    overflow_check_with_builtin("__builtin_sub_overflow", lhs, rhs, result_var, ty)
+pub fn overflow_check_sub(
+    lhs: CExpr,
+    rhs: CExpr,
+    result_var: &str,
+    ty: CType,
+    site: &TrapSite,
+) -> (CStmt, CStmt) {
+    overflow_check_with_builtin("__builtin_sub_overflow", lhs, rhs, result_var, ty, site)
 }
 
 /// Insert a signed overflow check using __builtin_mul_overflow
-pub fn overflow_check_mul(lhs: CExpr, rhs: CExpr, result_var: &str, ty: CType) -> (CStmt, CStmt) {
-    overflow_check_with_builtin("__builtin_mul_overflow", lhs, rhs, result_var, ty)
+pub fn overflow_check_mul(
+    lhs: CExpr,
+    rhs: CExpr,
+    result_var: &str,
+    ty: CType,
+    site: &TrapSite,
+) -> (CStmt, CStmt) {
+    overflow_check_with_builtin("__builtin_mul_overflow", lhs, rhs, result_var, ty, site)
 }
 
 /// Helper for overflow checks with builtin functions
@@ -57,6 +184,7 @@ fn overflow_check_with_builtin(
     rhs: CExpr,
     result_var: &str,
     ty: CType,
+    site: &TrapSite,
 ) -> (CStmt, CStmt) {
     let decl = CStmt::VarDecl {
         name: result_var.to_string(),
@@ -64,8 +192,8 @@ fn overflow_check_with_builtin(
         init: None,
     };
 
-    let check = CStmt::If {
-        cond: CExpr::Call {
+    let check = trap_if(
+        CExpr::Call {
             func: Box::new(CExpr::Ident(builtin.to_string())),
             args: vec![
                 lhs,
@@ -73,28 +201,142 @@ fn overflow_check_with_builtin(
                 CExpr::AddrOf(Box::new(CExpr::Ident(result_var.to_string()))),
             ],
         },
-        then: vec![CStmt::Expr(CExpr::Call {
-            func: Box::new(CExpr::Ident("fc_trap".to_string())),
-            args: vec![],
-        })],
-        else_: None,
-    };
+        TrapKind::Overflow,
+        site,
+    );
 
     (decl, check)
 }
 
 /// Insert a division by zero check
-pub fn div_zero_check(divisor: CExpr) -> CStmt {
-    CStmt::If {
-        cond: CExpr::Binary {
+pub fn div_zero_check(divisor: CExpr, site: &TrapSite) -> CStmt {
+    trap_if(
+        CExpr::Binary {
             op: CBinOp::Eq,
             lhs: Box::new(divisor),
             rhs: Box::new(CExpr::IntLit("0".to_string())),
         },
-        then: vec![CStmt::Expr(CExpr::Call {
-            func: Box::new(CExpr::Ident("fc_trap".to_string())),
-            args: vec![],
-        })],
+        TrapKind::DivZero,
+        site,
+    )
+}
+
+/// Insert a shift check: trap on a negative count or one at least the width
+/// of the shifted type, and on a signed left shift of a negative value or of
+/// one whose set bits would pass the sign bit (`value > (MAX >> count)`)
+pub fn shift_check(
+    value: CExpr,
+    count: CExpr,
+    value_ty: &CType,
+    count_ty: &CType,
+    is_left: bool,
+    site: &TrapSite,
+) -> CStmt {
+    let compare = |op, lhs: CExpr, rhs: CExpr| CExpr::Binary {
+        op,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    };
+    let zero = || CExpr::IntLit("0".to_string());
+
+    let mut cond = compare(CBinOp::Ge, count.clone(), bit_width(value_ty));
+    if Lower::is_signed_integer(count_ty) {
+        cond = compare(CBinOp::Or, compare(CBinOp::Lt, count.clone(), zero()), cond);
+    }
+    if is_left && Lower::is_signed_integer(value_ty) {
+        cond = compare(CBinOp::Or, cond, compare(CBinOp::Lt, value.clone(), zero()));
+        let limit = compare(CBinOp::Shr, max_value(value_ty), count);
+        cond = compare(CBinOp::Or, cond, compare(CBinOp::Gt, value, limit));
+    }
+    trap_if(cond, TrapKind::Shift, site)
+}
+
+/// Insert a signed division overflow check: `MIN / -1` and `MIN % -1`
+pub fn div_overflow_check(dividend: CExpr, divisor: CExpr, ty: &CType, site: &TrapSite) -> CStmt {
+    let cond = CExpr::Binary {
+        op: CBinOp::And,
+        lhs: Box::new(CExpr::Binary {
+            op: CBinOp::Eq,
+            lhs: Box::new(dividend),
+            rhs: Box::new(min_value(ty)),
+        }),
+        rhs: Box::new(CExpr::Binary {
+            op: CBinOp::Eq,
+            lhs: Box::new(divisor),
+            rhs: Box::new(CExpr::IntLit("-1".to_string())),
+        }),
+    };
+    trap_if(cond, TrapKind::Overflow, site)
+}
+
+/// Insert a negation overflow check: `-MIN`
+pub fn neg_overflow_check(operand: CExpr, ty: &CType, site: &TrapSite) -> CStmt {
+    trap_if(
+        CExpr::Binary {
+            op: CBinOp::Eq,
+            lhs: Box::new(operand),
+            rhs: Box::new(min_value(ty)),
+        },
+        TrapKind::Overflow,
+        site,
+    )
+}
+
+/// `if (cond) { fc_trap_at(kind, file, line, col); }`
+fn trap_if(cond: CExpr, kind: TrapKind, site: &TrapSite) -> CStmt {
+    CStmt::If {
+        cond,
+        then: vec![trap(kind, site)],
         else_: None,
     }
 }
+
+/// Minimum value of a signed integer type, from `<stdint.h>`
+fn min_value(ty: &CType) -> CExpr {
+    let name = match ty {
+        CType::Int8 => "INT8_MIN",
+        CType::Int16 => "INT16_MIN",
+        CType::Int64 => "INT64_MIN",
+        CType::PtrDiffT => "PTRDIFF_MIN",
+        _ => "INT32_MIN",
+    };
+    CExpr::Ident(name.to_string())
+}
+
+/// Maximum value of a signed integer type, from `<stdint.h>`
+fn max_value(ty: &CType) -> CExpr {
+    let name = match ty {
+        CType::Int8 => "INT8_MAX",
+        CType::Int16 => "INT16_MAX",
+        CType::Int64 => "INT64_MAX",
+        CType::PtrDiffT => "PTRDIFF_MAX",
+        _ => "INT32_MAX",
+    };
+    CExpr::Ident(name.to_string())
+}
+
+/// Width in bits of an integer type
+fn bit_width(ty: &CType) -> CExpr {
+    let bits = match ty {
+        CType::Int8 | CType::UInt8 => 8,
+        CType::Int16 | CType::UInt16 => 16,
+        CType::Int32 | CType::UInt32 => 32,
+        CType::Int64 | CType::UInt64 => 64,
+        // Pointer-sized types: (sizeof(T) * 8)
+        _ => {
+            let name = match ty {
+                CType::PtrDiffT => "ptrdiff_t",
+                _ => "size_t",
+            };
+            return CExpr::Binary {
+                op: CBinOp::Mul,
+                lhs: Box::new(CExpr::Call {
+                    func: Box::new(CExpr::Ident("sizeof".to_string())),
+                    args: vec![CExpr::Ident(name.to_string())],
+                }),
+                rhs: Box::new(CExpr::IntLit("8".to_string())),
+            };
+        }
+    };
+    CExpr::IntLit(bits.to_string())
+}
@@ -0,0 +1,138 @@
+//! C library functions that extern blocks may declare
+//!
+//! A prototype in FastC's types conflicts with the declaration C compilers
+//! build in for functions like `memcpy` and `strlen`, so these are declared
+//! by including their header instead. Where the header spells a parameter or
+//! result with `char` pointers and FastC has `u8` ones, calls cast between
+//! the two.
+
+const STDIO: &str = "<stdio.h>";
+const STDLIB: &str = "<stdlib.h>";
+const STRING: &str = "<string.h>";
+const MATH: &str = "<math.h>";
+
+const CONST_CHAR: &str = "const char*";
+const CHAR: &str = "char*";
+const CHAR_PTR: &str = "char**";
+
+/// A C library function and how its header declares it
+pub struct LibcFn {
+    pub name: &'static str,
+    pub header: &'static str,
+    /// Parameters, by position, whose argument is cast to the named C type
+    pub casts: &'static [(usize, &'static str)],
+    /// Whether the result is a `char` pointer, cast to the declared type
+    pub char_result: bool,
+}
+
+impl LibcFn {
+    const fn new(name: &'static str, header: &'static str) -> Self {
+        Self {
+            name,
+            header,
+            casts: &[],
+            char_result: false,
+        }
+    }
+
+    const fn casts(self, casts: &'static [(usize, &'static str)]) -> Self {
+        Self { casts, ..self }
+    }
+
+    const fn char_result(self) -> Self {
+        Self {
+            char_result: true,
+            ..self
+        }
+    }
+}
+
+const LIBC_FNS: &[LibcFn] = &[
+    // <stdio.h>
+    LibcFn::new("getchar", STDIO),
+    LibcFn::new("perror", STDIO).casts(&[(0, CONST_CHAR)]),
+    LibcFn::new("putchar", STDIO),
+    LibcFn::new("puts", STDIO).casts(&[(0, CONST_CHAR)]),
+    LibcFn::new("remove", STDIO).casts(&[(0, CONST_CHAR)]),
+    LibcFn::new("rename", STDIO).casts(&[(0, CONST_CHAR), (1, CONST_CHAR)]),
+    // <stdlib.h>
+    LibcFn::new("abort", STDLIB),
+    LibcFn::new("abs", STDLIB),
+    LibcFn::new("aligned_alloc", STDLIB),
+    LibcFn::new("atexit", STDLIB),
+    LibcFn::new("atof", STDLIB).casts(&[(0, CONST_CHAR)]),
+    LibcFn::new("atoi", STDLIB).casts(&[(0, CONST_CHAR)]),
+    LibcFn::new("atol", STDLIB).casts(&[(0, CONST_CHAR)]),
+    LibcFn::new("bsearch", STDLIB),
+    LibcFn::new("calloc", STDLIB),
+    LibcFn::new("exit", STDLIB),
+    LibcFn::new("free", STDLIB),
+    LibcFn::new("getenv", STDLIB)
+        .casts(&[(0, CONST_CHAR)])
+        .char_result(),
+    LibcFn::new("labs", STDLIB),
+    LibcFn::new("malloc", STDLIB),
+    LibcFn::new("qsort", STDLIB),
+    LibcFn::new("rand", STDLIB),
+    LibcFn::new("realloc", STDLIB),
+    LibcFn::new("srand", STDLIB),
+    LibcFn::new("strtod", STDLIB).casts(&[(0, CONST_CHAR), (1, CHAR_PTR)]),
+    LibcFn::new("strtol", STDLIB).casts(&[(0, CONST_CHAR), (1, CHAR_PTR)]),
+    LibcFn::new("strtoll", STDLIB).casts(&[(0, CONST_CHAR), (1, CHAR_PTR)]),
+    LibcFn::new("strtoul", STDLIB).casts(&[(0, CONST_CHAR), (1, CHAR_PTR)]),
+    LibcFn::new("strtoull", STDLIB).casts(&[(0, CONST_CHAR), (1, CHAR_PTR)]),
+    LibcFn::new("system", STDLIB).casts(&[(0, CONST_CHAR)]),
+    // <string.h>
+    LibcFn::new("memchr", STRING),
+    LibcFn::new("memcmp", STRING),
+    LibcFn::new("memcpy", STRING),
+    LibcFn::new("memmove", STRING),
+    LibcFn::new("memset", STRING),
+    LibcFn::new("strcat", STRING)
+        .casts(&[(0, CHAR), (1, CONST_CHAR)])
+        .char_result(),
+    LibcFn::new("strchr", STRING)
+        .casts(&[(0, CONST_CHAR)])
+        .char_result(),
+    LibcFn::new("strcmp", STRING).casts(&[(0, CONST_CHAR), (1, CONST_CHAR)]),
+    LibcFn::new("strcpy", STRING)
+        .casts(&[(0, CHAR), (1, CONST_CHAR)])
+        .char_result(),
+    LibcFn::new("strlen", STRING).casts(&[(0, CONST_CHAR)]),
+    LibcFn::new("strncat", STRING)
+        .casts(&[(0, CHAR), (1, CONST_CHAR)])
+        .char_result(),
+    LibcFn::new("strncmp", STRING).casts(&[(0, CONST_CHAR), (1, CONST_CHAR)]),
+    LibcFn::new("strncpy", STRING)
+        .casts(&[(0, CHAR), (1, CONST_CHAR)])
+        .char_result(),
+    LibcFn::new("strrchr", STRING)
+        .casts(&[(0, CONST_CHAR)])
+        .char_result(),
+    LibcFn::new("strstr", STRING)
+        .casts(&[(0, CONST_CHAR), (1, CONST_CHAR)])
+        .char_result(),
+    // <math.h>
+    LibcFn::new("atan2", MATH),
+    LibcFn::new("ceil", MATH),
+    LibcFn::new("cos", MATH),
+    LibcFn::new("exp", MATH),
+    LibcFn::new("fabs", MATH),
+    LibcFn::new("floor", MATH),
+    LibcFn::new("fmax", MATH),
+    LibcFn::new("fmin", MATH),
+    LibcFn::new("fmod", MATH),
+    LibcFn::new("log", MATH),
+    LibcFn::new("log10", MATH),
+    LibcFn::new("pow", MATH),
+    LibcFn::new("round", MATH),
+    LibcFn::new("sin", MATH),
+    LibcFn::new("sqrt", MATH),
+    LibcFn::new("sqrtf", MATH),
+    LibcFn::new("tan", MATH),
+];
+
+/// The C library function named `name`, if it is one
+pub fn find(name: &str) -> Option<&'static LibcFn> {
+    LIBC_FNS.iter().find(|f| f.name == name)
+}
@@ -1,40 +1,130 @@
 //! Lowering from FastC AST to C AST
 
+mod bounds;
 mod c_ast;
 mod checks;
+mod libc;
+mod scopes;
 mod temporaries;
 
 pub use c_ast::*;
 
 use crate::ast;
-use std::collections::{HashMap, HashSet};
+use crate::diag::LineIndex;
+use crate::lexer::Span;
+use crate::typecheck::TypeContext;
+use checks::{TrapKind, TrapSite};
+use scopes::{Cleanup, ScopeKind, ScopeStack};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 /// Lowering pass
 pub struct Lower {
     temp_counter: usize,
     in_unsafe: bool, // Track if currently in unsafe block (for runtime checks)
-    opt_types: HashSet<String>, // Track used opt types for typedef generation
-    res_types: HashSet<String>, // Track used res types for typedef generation
-    slice_types: HashSet<String>, // Track used slice types for typedef generation
-    var_types: HashMap<String, CType>, // Track variable types for type inference
+    opt_types: BTreeMap<String, CType>, // Used opt types (by typedef name) for typedef generation
+    res_types: BTreeMap<String, (CType, CType)>, // Used res types for typedef generation
+    slice_types: BTreeMap<String, CType>, // Used slice types for typedef generation
+    types: TypeContext, // Expression types inferred by the typechecker
+    current_fn: String, // Function being lowered (keys into `types`)
+    locals: HashSet<String>, // Locals and params of the function being lowered
+    scopes: ScopeStack, // Lexical scopes with pending defers and drops
+    return_type: CType, // Return type of the function being lowered
+    consts: HashMap<String, (ast::TypeExpr, ast::ConstExpr)>, // Const decls for folding
+    used_consts: HashSet<String>, // Consts referenced at runtime (not folded away)
+    enum_decls: HashMap<String, ast::EnumDecl>, // Enum decls for variant construction/matching
+    drop_fns: Vec<(ast::TypeExpr, String)>, // `@drop` functions by the type they release
+    libc_fns: HashMap<String, ast::TypeExpr>, // Extern C library functions by declared return type
+    bounded_counters: Vec<(String, i128)>, // Enclosing `for` counters known to stay in 0..bound
+    byte_arrays: Vec<(Option<String>, CConst)>, // Storage behind `bytes("...")`, by owning const
+    files: Vec<(String, LineIndex)>, // Source files by id: the compiled file, then loaded modules
+    file: usize,     // Id of the file the items being lowered come from
 }
 
+/// Runtime functions `fastc_runtime.h` declares, which every generated file
+/// includes. Re-declaring them with FastC's typed signatures would conflict,
+/// so extern prototypes for them are skipped.
+const PREDECLARED_FNS: &[&str] = &[
+    "fc_alloc",
+    "fc_free",
+    "fc_freeze_allocations",
+    "fc_io_close",
+    "fc_io_file_alloc",
+    "fc_io_flush",
+    "fc_io_open",
+    "fc_io_read",
+    "fc_io_stderr",
+    "fc_io_stdin",
+    "fc_io_stdout",
+    "fc_io_write",
+    "fc_io_write_i64",
+    "fc_io_write_u64",
+    "fc_realloc",
+];
+
+/// Runtime allocation functions, which trap once allocations are frozen, and
+/// the forms that take the location to report. Calls pass their FastC call
+/// site, as runtime checks do.
+const SITED_FNS: &[(&str, &str)] = &[
+    ("fc_alloc", "fc_alloc_at"),
+    ("fc_io_file_alloc", "fc_io_file_alloc_at"),
+    ("fc_realloc", "fc_realloc_at"),
+];
+
 impl Lower {
-    pub fn new() -> Self {
+    pub fn new(types: TypeContext, filename: &str, source: &str) -> Self {
         Self {
             temp_counter: 0,
             in_unsafe: false,
-            opt_types: HashSet::new(),
-            res_types: HashSet::new(),
-            slice_types: HashSet::new(),
-            var_types: HashMap::new(),
+            opt_types: BTreeMap::new(),
+            res_types: BTreeMap::new(),
+            slice_types: BTreeMap::new(),
+            types,
+            current_fn: String::new(),
+            locals: HashSet::new(),
+            scopes: ScopeStack::new(),
+            return_type: CType::Void,
+            consts: HashMap::new(),
+            used_consts: HashSet::new(),
+            enum_decls: HashMap::new(),
+            drop_fns: Vec::new(),
+            libc_fns: HashMap::new(),
+            bounded_counters: Vec::new(),
+            byte_arrays: Vec::new(),
+            files: vec![(filename.to_string(), LineIndex::new(source))],
+            file: 0,
+        }
+    }
+
+    /// Source location a runtime check at `span` reports when it traps
+    fn trap_site(&self, span: &Span) -> TrapSite {
+        let loc = self.loc(span);
+        TrapSite {
+            file: self.files[loc.file].0.clone(),
+            line: loc.line,
+            column: loc.column,
+        }
+    }
+
+    /// Source position of `span` in the file being lowered
+    fn loc(&self, span: &Span) -> CLoc {
+        let (line, column) = self.files[self.file].1.position(span.start);
+        CLoc {
+            file: self.file,
+            line,
+            column,
         }
     }
 
     /// Check if an expression has side effects (requires evaluation order)
     fn has_side_effects(&self, expr: &ast::Expr) -> bool {
         match expr {
-            ast::Expr::Call { .. } => true,
+            // A variant constructor only builds a value
+            ast::Expr::Call { callee, args, .. } => match callee.as_ref() {
+                ast::Expr::Ident { name, .. } if self.enum_variant(name).is_some() => {
+                    args.iter().any(|arg| self.has_side_effects(arg))
+                }
+                _ => true,
+            },
             ast::Expr::Binary { lhs, rhs, .. } => {
                 self.has_side_effects(lhs) || self.has_side_effects(rhs)
             }
@@ -50,56 +140,17 @@ impl Lower {
             ast::Expr::Some { value, .. } => self.has_side_effects(value),
             ast::Expr::Ok { value, .. } => self.has_side_effects(value),
             ast::Expr::Err { value, .. } => self.has_side_effects(value),
+            ast::Expr::Builtin { args, .. } => args.iter().any(|arg| self.has_side_effects(arg)),
             _ => false,
         }
     }
 
-    /// Infer the type of an expression (simple inference for lowering)
-    fn infer_expr_type(&self, expr: &ast::Expr) -> CType {
-        match expr {
-            ast::Expr::IntLit { .. } => CType::Int32, // Default to i32 for int literals
-            ast::Expr::FloatLit { .. } => CType::Double, // Default to f64 for float literals
-            ast::Expr::BoolLit { .. } => CType::Bool,
-            ast::Expr::Cast { ty, .. } => self.lower_type(ty),
-            ast::Expr::Paren { inner, .. } => self.infer_expr_type(inner),
-            ast::Expr::Unary { operand, .. } => self.infer_expr_type(operand),
-            ast::Expr::Binary { op, lhs, .. } => {
-                // Comparison and logical operators return bool
-                match op {
-                    ast::BinOp::Eq
-                    | ast::BinOp::Ne
-                    | ast::BinOp::Lt
-                    | ast::BinOp::Le
-                    | ast::BinOp::Gt
-                    | ast::BinOp::Ge
-                    | ast::BinOp::And
-                    | ast::BinOp::Or => CType::Bool,
-                    // Arithmetic operators return the same type as the operands
-                    _ => self.infer_expr_type(lhs),
-                }
-            }
-            ast::Expr::Ident { name, .. } => {
-                // Look up the variable type from our tracking map
-                self.var_types.get(name).cloned().unwrap_or(CType::Void)
-            }
-            ast::Expr::Call { callee, .. } => {
-                // For function calls, we'd need to look up the return type
-                // For now, try to infer from callee name
-                if let ast::Expr::Ident { name, .. } = callee.as_ref() {
-                    // Special case for common patterns
-                    if name == "get_some" || name == "get_none" {
-                        // These are opt-returning functions from our examples
-                        CType::Opt(Box::new(CType::Int32))
-                    } else {
-                        CType::Void
-                    }
-                } else {
-                    CType::Void
-                }
-            }
-            // For other expressions, we'd need full type checking - default to Void
-            _ => CType::Void,
-        }
+    /// Type of an expression, as inferred by the typechecker
+    fn expr_type(&self, expr: &ast::Expr) -> CType {
+        self.types
+            .expr_type(&self.current_fn, &expr.span())
+            .map(|ty| self.lower_type(ty))
+            .unwrap_or(CType::Void)
     }
 
     /// Lower a FastC file to a C file
@@ -112,6 +163,9 @@ impl Lower {
         c_file.includes.push("<stdbool.h>".to_string());
         c_file.includes.push("\"fastc_runtime.h\"".to_string());
 
+        // Consts and enums may be referenced before their declaration
+        self.collect_decls(&file.items);
+
         self.lower_items(&file.items, &mut c_file);
 
         // Sort user-defined type_defs by name for deterministic output
@@ -121,14 +175,34 @@ impl Lower {
                     CDecl::Struct { name, .. } => name,
                     CDecl::Typedef { name, .. } => name,
                     CDecl::Enum { name, .. } => name,
+                    CDecl::Opaque { name } => name,
+                    CDecl::TaggedUnion { name, .. } => name,
                 }
             }
             get_name(a).cmp(get_name(b))
         });
 
+        self.dedup_extern_decls(&mut c_file);
+
+        // Const references in constant contexts are folded, so only consts read
+        // at runtime need a definition (an unused `static const` warns in C)
+        c_file.consts.retain(|c| self.used_consts.contains(&c.name));
+        // Byte arrays come first: consts point into them
+        let used_consts = &self.used_consts;
+        let mut statics: Vec<CConst> = self
+            .byte_arrays
+            .drain(..)
+            .filter(|(owner, _)| owner.as_ref().is_none_or(|name| used_consts.contains(name)))
+            .map(|(_, array)| array)
+            .collect();
+        statics.append(&mut c_file.consts);
+        c_file.consts = statics;
+
         // Generate typedefs for opt/res types used in the file
         self.generate_opt_res_typedefs(&mut c_file);
 
+        c_file.sources = self.files.iter().map(|(path, _)| path.clone()).collect();
+
         c_file
     }
 
@@ -145,18 +219,204 @@ impl Lower {
                 ast::Item::Enum(enum_decl) => {
                     c_file.type_defs.push(self.lower_enum(enum_decl));
                 }
+                ast::Item::Const(const_decl) => {
+                    c_file.consts.push(self.lower_const(const_decl));
+                }
+                ast::Item::Opaque(opaque_decl) => {
+                    c_file.forward_decls.push(CDecl::Opaque {
+                        name: opaque_decl.name.clone(),
+                    });
+                }
+                ast::Item::Extern(extern_block) => {
+                    self.lower_extern(extern_block, c_file);
+                }
                 ast::Item::Mod(mod_decl) => {
-                    // Recursively lower items inside the module
+                    // Recursively lower items inside the module, whose spans
+                    // point into the file it was loaded from
+                    let outer = self.file;
+                    if let Some(file) = &mod_decl.file {
+                        self.file = self.file_id(file);
+                    }
                     if let Some(body) = &mod_decl.body {
                         self.lower_items(body, c_file);
                     }
+                    self.file = outer;
+                }
+                // Imports only affect name resolution
+                ast::Item::Use(_) => {}
+                // Files with syntax errors never reach lowering
+                ast::Item::Error { .. } => {}
+            }
+        }
+    }
+
+    /// Id of a loaded module file, registering it on first use
+    fn file_id(&mut self, file: &ast::SourceFile) -> usize {
+        if let Some(id) = self.files.iter().position(|(path, _)| *path == file.path) {
+            return id;
+        }
+        self.files
+            .push((file.path.clone(), LineIndex::new(&file.text)));
+        self.files.len() - 1
+    }
+
+    /// Record const, enum, and drop function declarations (including those in
+    /// inline modules)
+    fn collect_decls(&mut self, items: &[ast::Item]) {
+        for item in items {
+            match item {
+                ast::Item::Const(c) => {
+                    self.consts
+                        .insert(c.name.clone(), (c.ty.clone(), c.value.clone()));
+                }
+                ast::Item::Enum(e) => {
+                    self.enum_decls.insert(e.name.clone(), e.clone());
+                }
+                ast::Item::Fn(f) if f.is_drop => {
+                    if let [param] = f.params.as_slice()
+                        && let ast::TypeExpr::Own(inner) = &param.ty
+                    {
+                        self.drop_fns.push((inner.as_ref().clone(), f.name.clone()));
+                    }
+                }
+                ast::Item::Extern(block) => {
+                    for extern_item in &block.items {
+                        match extern_item {
+                            ast::ExternItem::Enum(e) => {
+                                self.enum_decls.insert(e.name.clone(), e.clone());
+                            }
+                            ast::ExternItem::Fn(proto) if libc::find(&proto.name).is_some() => {
+                                self.libc_fns
+                                    .insert(proto.name.clone(), proto.return_type.clone());
+                            }
+                            _ => {}
+                        }
+                    }
                 }
-                // TODO: Handle other items (Const, Opaque, Extern, Use)
+                ast::Item::Mod(ast::ModDecl {
+                    body: Some(body), ..
+                }) => self.collect_decls(body),
                 _ => {}
             }
         }
     }
 
+    /// Lower a const declaration to a `static const` definition
+    fn lower_const(&mut self, const_decl: &ast::ConstDecl) -> CConst {
+        let ty = self.lower_type(&const_decl.ty);
+        let value = match &const_decl.value {
+            // String literals are `char` arrays in C; cast to the declared pointer type
+            ast::ConstExpr::CStr(s) => CExpr::Cast {
+                ty: ty.clone(),
+                expr: Box::new(CExpr::StringLit(s.clone())),
+            },
+            ast::ConstExpr::Bytes(s) => CExpr::Compound {
+                ty: ty.clone(),
+                fields: vec![
+                    (
+                        "data".to_string(),
+                        self.bytes_storage(s, Some(&const_decl.name)),
+                    ),
+                    ("len".to_string(), CExpr::IntLit(s.len().to_string())),
+                ],
+            },
+            value => self.lower_const_expr(value),
+        };
+        CConst {
+            name: const_decl.name.clone(),
+            ty,
+            value,
+            mutable: false,
+        }
+    }
+
+    /// Static storage for a `bytes("...")` literal, owned by the const it
+    /// initializes if any. It is writable, since a `slice(u8)` may be
+    /// written through in safe code; returns a pointer to the first byte.
+    fn bytes_storage(&mut self, value: &str, owner: Option<&str>) -> CExpr {
+        if value.is_empty() {
+            // C has no zero-length arrays
+            return CExpr::Ident("NULL".to_string());
+        }
+        let name = self.fresh_label("bytes");
+        let array = CConst {
+            name: name.clone(),
+            ty: CType::Array(Box::new(CType::UInt8), value.len()),
+            value: CExpr::StringLit(value.to_string()),
+            mutable: true,
+        };
+        self.byte_arrays.push((owner.map(str::to_string), array));
+        CExpr::Ident(name)
+    }
+
+    /// Lower an extern block: prototypes for functions, definitions for types
+    fn lower_extern(&mut self, extern_block: &ast::ExternBlock, c_file: &mut CFile) {
+        for item in &extern_block.items {
+            match item {
+                ast::ExternItem::Fn(proto) => {
+                    let params = proto
+                        .params
+                        .iter()
+                        .map(|p| CParam {
+                            name: p.name.clone(),
+                            ty: self.lower_type(&p.ty),
+                        })
+                        .collect();
+                    c_file.fn_protos.push(CFnProto {
+                        name: proto.name.clone(),
+                        params,
+                        return_type: self.lower_type(&proto.return_type),
+                    });
+                }
+                ast::ExternItem::Struct(struct_decl) => {
+                    c_file.type_defs.push(self.lower_struct(struct_decl));
+                }
+                ast::ExternItem::Enum(enum_decl) => {
+                    c_file.type_defs.push(self.lower_enum(enum_decl));
+                }
+                ast::ExternItem::Opaque(opaque_decl) => {
+                    c_file.forward_decls.push(CDecl::Opaque {
+                        name: opaque_decl.name.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Drop duplicate extern prototypes and opaque typedefs
+    ///
+    /// An extern function is declared once, and not at all when this file
+    /// defines it or an included header already declares it. C library
+    /// functions are declared by including their header instead.
+    fn dedup_extern_decls(&self, c_file: &mut CFile) {
+        let mut seen: HashSet<String> = c_file.fn_defs.iter().map(|f| f.name.clone()).collect();
+        seen.extend(PREDECLARED_FNS.iter().map(|s| s.to_string()));
+        let mut headers = Vec::new();
+        c_file.fn_protos.retain(|proto| {
+            if !seen.insert(proto.name.clone()) {
+                return false;
+            }
+            match libc::find(&proto.name) {
+                Some(libc_fn) => {
+                    headers.push(libc_fn.header.to_string());
+                    false
+                }
+                None => true,
+            }
+        });
+        for header in headers {
+            if !c_file.includes.contains(&header) {
+                c_file.includes.push(header);
+            }
+        }
+
+        let mut seen_types = HashSet::new();
+        c_file.forward_decls.retain(|decl| match decl {
+            CDecl::Opaque { name } => seen_types.insert(name.clone()),
+            _ => true,
+        });
+    }
+
     /// Generate struct typedefs for slice(T), opt(T), and res(T,E) types used in the file
     fn generate_opt_res_typedefs(&mut self, c_file: &mut CFile) {
         // Collect all types used
@@ -172,40 +432,130 @@ impl Lower {
         .cloned()
         .collect();
 
-        let mut slice_types: Vec<_> = self
+        let slice_types: Vec<_> = self
             .slice_types
             .iter()
-            .filter(|t| !builtin_slice_types.contains(t.as_str()))
-            .cloned()
+            .filter(|(name, _)| !builtin_slice_types.contains(name.as_str()))
+            .map(|(name, ty)| Self::make_slice_typedef(name, ty.clone()))
             .collect();
-        slice_types.sort();
-        for type_name in slice_types {
-            if let Some(decl) = self.make_slice_typedef(&type_name) {
-                c_file.type_defs.insert(0, decl);
-            }
+        for decl in slice_types {
+            c_file.type_defs.insert(0, decl);
         }
 
         // Generate opt typedefs (sorted for determinism)
-        let mut opt_types: Vec<_> = self.opt_types.iter().cloned().collect();
-        opt_types.sort();
-        for type_name in opt_types {
-            if let Some(decl) = self.make_opt_typedef(&type_name) {
-                c_file.type_defs.insert(0, decl);
-            }
+        let opt_types: Vec<_> = self
+            .opt_types
+            .iter()
+            .map(|(name, ty)| Self::make_opt_typedef(name, ty.clone()))
+            .collect();
+        for decl in opt_types {
+            c_file.type_defs.insert(0, decl);
         }
 
         // Generate res typedefs (sorted for determinism)
-        let mut res_types: Vec<_> = self.res_types.iter().cloned().collect();
-        res_types.sort();
-        for type_name in res_types {
-            if let Some(decl) = self.make_res_typedef(&type_name) {
-                c_file.type_defs.insert(0, decl);
+        let res_types: Vec<_> = self
+            .res_types
+            .iter()
+            .map(|(name, (ok, err))| Self::make_res_typedef(name, ok.clone(), err.clone()))
+            .collect();
+        for decl in res_types {
+            c_file.type_defs.insert(0, decl);
+        }
+
+        Self::order_type_defs(&mut c_file.type_defs);
+    }
+
+    /// Order type definitions so each one follows the types it names
+    ///
+    /// C needs a typedef name declared before use, and a complete type for
+    /// by-value fields. This is a stable topological sort: definitions keep
+    /// their relative order unless a dependency forces one later. Cyclic
+    /// definitions are left in their original order.
+    fn order_type_defs(type_defs: &mut Vec<CDecl>) {
+        fn decl_name(decl: &CDecl) -> &str {
+            match decl {
+                CDecl::Struct { name, .. }
+                | CDecl::Typedef { name, .. }
+                | CDecl::Enum { name, .. }
+                | CDecl::Opaque { name }
+                | CDecl::TaggedUnion { name, .. } => name,
+            }
+        }
+        fn type_deps(ty: &CType, deps: &mut Vec<String>) {
+            match ty {
+                CType::Named(name) => deps.push(name.clone()),
+                CType::Opt(_) | CType::Res(_, _) | CType::Slice(_) => {
+                    deps.push(Lower::c_type_typedef_name(ty));
+                }
+                CType::Ptr(inner) | CType::ConstPtr(inner) | CType::Array(inner, _) => {
+                    type_deps(inner, deps)
+                }
+                _ => {}
+            }
+        }
+        fn decl_deps(decl: &CDecl) -> Vec<String> {
+            let mut deps = Vec::new();
+            match decl {
+                CDecl::Struct { fields, .. } => {
+                    for field in fields {
+                        type_deps(&field.ty, &mut deps);
+                    }
+                }
+                CDecl::TaggedUnion { variants, .. } => {
+                    for field in variants.iter().flat_map(|(_, fields)| fields) {
+                        type_deps(&field.ty, &mut deps);
+                    }
+                }
+                CDecl::Typedef { ty, .. } => type_deps(ty, &mut deps),
+                CDecl::Enum { .. } | CDecl::Opaque { .. } => {}
             }
+            deps
+        }
+
+        let defined: HashSet<String> = type_defs.iter().map(|d| decl_name(d).to_string()).collect();
+        let mut pending: Vec<CDecl> = std::mem::take(type_defs);
+        let mut emitted: HashSet<String> = HashSet::new();
+        while !pending.is_empty() {
+            let ready = pending.iter().position(|decl| {
+                let name = decl_name(decl);
+                decl_deps(decl)
+                    .iter()
+                    .all(|dep| dep == name || !defined.contains(dep) || emitted.contains(dep))
+            });
+            // A cycle: keep the remaining definitions as they are
+            let index = ready.unwrap_or(0);
+            let decl = pending.remove(index);
+            emitted.insert(decl_name(&decl).to_string());
+            type_defs.push(decl);
         }
     }
 
     /// Collect all opt/res types used in the file
     fn collect_types_from_file(&mut self, c_file: &CFile) {
+        for decl in &c_file.type_defs {
+            match decl {
+                CDecl::Struct { fields, .. } => {
+                    for field in fields {
+                        self.collect_types_from_type(&field.ty);
+                    }
+                }
+                CDecl::TaggedUnion { variants, .. } => {
+                    for field in variants.iter().flat_map(|(_, fields)| fields) {
+                        self.collect_types_from_type(&field.ty);
+                    }
+                }
+                _ => {}
+            }
+        }
+        for c in &c_file.consts {
+            self.collect_types_from_type(&c.ty);
+        }
+        for proto in &c_file.fn_protos {
+            self.collect_types_from_type(&proto.return_type);
+            for param in &proto.params {
+                self.collect_types_from_type(&param.ty);
+            }
+        }
         for fn_def in &c_file.fn_defs {
             self.collect_types_from_type(&fn_def.return_type);
             for param in &fn_def.params {
@@ -261,8 +611,8 @@ impl Lower {
                 if let Some(c) = cond {
                     self.collect_types_from_expr(c);
                 }
-                if let Some(s) = step {
-                    self.collect_types_from_expr(s);
+                if let Some(step_stmt) = step {
+                    self.collect_types_from_stmt(step_stmt);
                 }
                 for s in body {
                     self.collect_types_from_stmt(s);
@@ -326,7 +676,7 @@ impl Lower {
         match ty {
             CType::Opt(inner) => {
                 let name = Self::c_type_to_name(inner);
-                self.opt_types.insert(name);
+                self.opt_types.insert(name, (**inner).clone());
                 self.collect_types_from_type(inner);
             }
             CType::Res(ok_ty, err_ty) => {
@@ -335,13 +685,14 @@ impl Lower {
                     Self::c_type_to_name(ok_ty),
                     Self::c_type_to_name(err_ty)
                 );
-                self.res_types.insert(name);
+                self.res_types
+                    .insert(name, ((**ok_ty).clone(), (**err_ty).clone()));
                 self.collect_types_from_type(ok_ty);
                 self.collect_types_from_type(err_ty);
             }
             CType::Slice(inner) => {
                 let name = Self::c_type_to_name(inner);
-                self.slice_types.insert(name);
+                self.slice_types.insert(name, (**inner).clone());
                 self.collect_types_from_type(inner);
             }
             CType::Ptr(inner) | CType::ConstPtr(inner) => {
@@ -385,13 +736,24 @@ impl Lower {
         }
     }
 
-    /// Create a typedef for fc_opt_T
+    /// Name of the generated typedef for an opt, res, or slice type
+    fn c_type_typedef_name(ty: &CType) -> String {
+        match ty {
+            CType::Opt(inner) => format!("fc_opt_{}", Self::c_type_to_name(inner)),
+            CType::Res(ok, err) => format!(
+                "fc_res_{}_{}",
+                Self::c_type_to_name(ok),
+                Self::c_type_to_name(err)
+            ),
+            CType::Slice(inner) => format!("fc_slice_{}", Self::c_type_to_name(inner)),
+            _ => Self::c_type_to_name(ty),
+        }
+    }
+
     /// Create a typedef for fc_slice_T
-    fn make_slice_typedef(&self, inner_type_name: &str) -> Option<CDecl> {
-        let inner_ty = Self::name_to_c_type(inner_type_name)?;
-        let struct_name = format!("fc_slice_{}", inner_type_name);
-        Some(CDecl::Struct {
-            name: struct_name,
+    fn make_slice_typedef(inner_type_name: &str, inner_ty: CType) -> CDecl {
+        CDecl::Struct {
+            name: format!("fc_slice_{}", inner_type_name),
             fields: vec![
                 CField {
                     name: "data".to_string(),
@@ -402,15 +764,13 @@ impl Lower {
                     ty: CType::SizeT,
                 },
             ],
-        })
+        }
     }
 
     /// Create a typedef for fc_opt_T
-    fn make_opt_typedef(&self, inner_type_name: &str) -> Option<CDecl> {
-        let inner_ty = Self::name_to_c_type(inner_type_name)?;
-        let struct_name = format!("fc_opt_{}", inner_type_name);
-        Some(CDecl::Struct {
-            name: struct_name,
+    fn make_opt_typedef(inner_type_name: &str, inner_ty: CType) -> CDecl {
+        CDecl::Struct {
+            name: format!("fc_opt_{}", inner_type_name),
             fields: vec![
                 CField {
                     name: "has_value".to_string(),
@@ -421,26 +781,14 @@ impl Lower {
                     ty: inner_ty,
                 },
             ],
-        })
+        }
     }
 
     /// Create a typedef for fc_res_T_E
-    fn make_res_typedef(&self, type_name: &str) -> Option<CDecl> {
-        // Parse "T_E" format - find the separator
-        // This is a simplistic approach; for complex types we'd need better parsing
-        let parts: Vec<&str> = type_name.splitn(2, '_').collect();
-        if parts.len() != 2 {
-            return None;
-        }
-        let ok_ty = Self::name_to_c_type(parts[0])?;
-        let err_ty = Self::name_to_c_type(parts[1])?;
-        let struct_name = format!("fc_res_{}", type_name);
-
-        // For result types, we use a struct with a tag and union
-        // For simplicity, use two fields: is_ok and data (as a union would require more C AST support)
-        // Actually, C compound literals with designated initializers work well here
-        Some(CDecl::Struct {
-            name: struct_name,
+    fn make_res_typedef(type_name: &str, ok_ty: CType, err_ty: CType) -> CDecl {
+        // A plain struct rather than a union keeps designated initializers simple
+        CDecl::Struct {
+            name: format!("fc_res_{}", type_name),
             fields: vec![
                 CField {
                     name: "is_ok".to_string(),
@@ -455,41 +803,21 @@ impl Lower {
                     ty: err_ty,
                 },
             ],
-        })
-    }
-
-    /// Convert a type name back to a CType
-    fn name_to_c_type(name: &str) -> Option<CType> {
-        Some(match name {
-            "void" => CType::Void,
-            "bool" => CType::Bool,
-            "int8_t" => CType::Int8,
-            "int16_t" => CType::Int16,
-            "int32_t" => CType::Int32,
-            "int64_t" => CType::Int64,
-            "uint8_t" => CType::UInt8,
-            "uint16_t" => CType::UInt16,
-            "uint32_t" => CType::UInt32,
-            "uint64_t" => CType::UInt64,
-            "float" => CType::Float,
-            "double" => CType::Double,
-            "size_t" => CType::SizeT,
-            "ptrdiff_t" => CType::PtrDiffT,
-            _ => CType::Named(name.to_string()),
-        })
+        }
     }
 
     fn lower_fn(&mut self, fn_decl: &ast::FnDecl) -> CFnDef {
-        // Clear variable types from previous function
-        self.var_types.clear();
+        // Clear locals from previous function
+        self.locals.clear();
+        self.current_fn = fn_decl.name.clone();
 
-        // Register parameter types
+        // Register parameters as locals
         let params: Vec<CParam> = fn_decl
             .params
             .iter()
             .map(|p| {
                 let ty = self.lower_type(&p.ty);
-                self.var_types.insert(p.name.clone(), ty.clone());
+                self.locals.insert(p.name.clone());
                 CParam {
                     name: p.name.clone(),
                     ty,
@@ -497,13 +825,34 @@ impl Lower {
             })
             .collect();
 
-        let body = self.lower_block(&fn_decl.body);
+        self.scopes = ScopeStack::new();
+        self.return_type = self.lower_type(&fn_decl.return_type);
+
+        // Owned parameters are released when the function returns. A drop
+        // function frees its own parameter's storage after its body runs.
+        self.scopes.push(ScopeKind::Block);
+        for param in &fn_decl.params {
+            if let ast::TypeExpr::Own(_) = &param.ty {
+                let drop_fn = if fn_decl.is_drop {
+                    None
+                } else {
+                    self.drop_fn_for(&param.ty)
+                };
+                self.scopes.own(param.name.clone(), drop_fn);
+            }
+        }
+        let mut body = self.lower_block(&fn_decl.body);
+        if !Self::exits(&fn_decl.body.stmts) {
+            body.extend(self.lower_cleanup(0));
+        }
+        self.scopes.pop();
 
         CFnDef {
             name: fn_decl.name.clone(),
             params,
-            return_type: self.lower_type(&fn_decl.return_type),
+            return_type: self.return_type.clone(),
             body,
+            loc: self.loc(&fn_decl.span),
         }
     }
 
@@ -524,34 +873,47 @@ impl Lower {
     }
 
     fn lower_enum(&mut self, enum_decl: &ast::EnumDecl) -> CDecl {
-        // Check if any variant has associated data
-        let has_data = enum_decl.variants.iter().any(|v| v.fields.is_some());
-
-        if has_data {
-            // Tagged enum with associated data - lower to struct with tag + union
-            // This is more complex and requires union support in C AST
-            // For now, generate a struct with tag and fields for each variant
-            // TODO: Implement proper union-based lowering
-            let name = enum_decl.name.clone();
-            let mut fields = vec![CField {
-                name: "tag".to_string(),
-                ty: CType::Int32,
-            }];
-
-            // Add a field for each variant's data
-            for variant in &enum_decl.variants {
-                if let Some(variant_fields) = &variant.fields {
-                    // For now, only handle single-field variants
-                    if let Some(ty) = variant_fields.first() {
-                        fields.push(CField {
-                            name: format!("{}_data", variant.name.to_lowercase()),
+        if enum_decl.has_payload() {
+            // Tagged union: the tag width follows @repr (C-enum sized by default)
+            let tag_ty = match enum_decl.repr {
+                Some(ast::Repr::I8) => CType::Int8,
+                Some(ast::Repr::U8) => CType::UInt8,
+                Some(ast::Repr::I16) => CType::Int16,
+                Some(ast::Repr::U16) => CType::UInt16,
+                Some(ast::Repr::U32) => CType::UInt32,
+                Some(ast::Repr::I64) => CType::Int64,
+                Some(ast::Repr::U64) => CType::UInt64,
+                Some(ast::Repr::C) | Some(ast::Repr::I32) | None => CType::Int32,
+            };
+            let tags = enum_decl
+                .variants
+                .iter()
+                .map(|v| format!("{}_{}", enum_decl.name, v.name))
+                .collect();
+            let variants = enum_decl
+                .variants
+                .iter()
+                .filter(|v| !v.payload().is_empty())
+                .map(|v| {
+                    let fields = v
+                        .payload()
+                        .iter()
+                        .enumerate()
+                        .map(|(i, ty)| CField {
+                            name: format!("_{}", i),
                             ty: self.lower_type(ty),
-                        });
-                    }
-                }
-            }
+                        })
+                        .collect();
+                    (v.name.clone(), fields)
+                })
+                .collect();
 
-            CDecl::Struct { name, fields }
+            CDecl::TaggedUnion {
+                name: enum_decl.name.clone(),
+                tag_ty,
+                tags,
+                variants,
+            }
         } else {
             // Simple enum with no associated data - lower to C enum
             let variants: Vec<String> = enum_decl
@@ -567,11 +929,303 @@ impl Lower {
         }
     }
 
+    /// Look up an enum variant by its qualified name (e.g. `Shape_Circle`)
+    fn enum_variant(&self, name: &str) -> Option<(&ast::EnumDecl, &ast::Variant)> {
+        self.enum_decls
+            .values()
+            .find_map(|e| e.variant(name).map(|v| (e, v)))
+    }
+
+    /// Lower a variant of a tagged enum to a compound literal:
+    ///   ((Shape){ .tag = Shape_Circle, .data.Circle._0 = r })
+    fn lower_variant_ctor(
+        &mut self,
+        enum_name: &str,
+        variant: &ast::Variant,
+        args: &[ast::Expr],
+        pre_stmts: &mut Vec<CStmt>,
+    ) -> CExpr {
+        let mut fields = vec![(
+            "tag".to_string(),
+            CExpr::Ident(format!("{}_{}", enum_name, variant.name)),
+        )];
+        for (i, (arg, ty)) in args.iter().zip(variant.payload()).enumerate() {
+            let mut c_arg = self.lower_move(arg, pre_stmts);
+            // Initializer order is unspecified in C; stage side effects in order
+            if self.has_side_effects(arg) {
+                let tmp = self.fresh_temp();
+                pre_stmts.push(CStmt::VarDecl {
+                    name: tmp.clone(),
+                    ty: self.lower_type(ty),
+                    init: Some(c_arg),
+                });
+                c_arg = CExpr::Ident(tmp);
+            }
+            fields.push((format!("data.{}._{}", variant.name, i), c_arg));
+        }
+        CExpr::Compound {
+            ty: CType::Named(enum_name.to_string()),
+            fields,
+        }
+    }
+
+    /// Lower a match on an enum to a switch on its tag:
+    ///   Shape __tmp = expr;
+    ///   switch (__tmp.tag) {
+    ///       case Shape_Circle: { double r = __tmp.data.Circle._0; ... } break;
+    ///   }
+    fn lower_match(
+        &mut self,
+        expr: &ast::Expr,
+        arms: &[ast::MatchArm],
+        default: Option<&[ast::Stmt]>,
+    ) -> Vec<CStmt> {
+        let mut pre_stmts = Vec::new();
+        let c_expr = self.lower_expr(expr, &mut pre_stmts);
+
+        // The scrutinee's type names the enum, even when only `default:` is given
+        let enum_decl = match self.types.expr_type(&self.current_fn, &expr.span()) {
+            Some(ast::TypeExpr::Named(name)) => self.enum_decls.get(name).cloned(),
+            _ => None,
+        };
+
+        let (scrutinee, switch_expr) = match &enum_decl {
+            Some(e) if e.has_payload() => {
+                let tmp = self.fresh_temp();
+                pre_stmts.push(CStmt::VarDecl {
+                    name: tmp.clone(),
+                    ty: CType::Named(e.name.clone()),
+                    init: Some(c_expr),
+                });
+                let tag = CExpr::Field {
+                    base: Box::new(CExpr::Ident(tmp.clone())),
+                    field: "tag".to_string(),
+                };
+                (Some(tmp), tag)
+            }
+            _ => (None, c_expr),
+        };
+
+        self.scopes.push(ScopeKind::Switch);
+        let mut c_cases = Vec::new();
+        for arm in arms {
+            let mut body = Vec::new();
+            let payload = enum_decl
+                .as_ref()
+                .and_then(|e| e.variant(&arm.variant))
+                .map(|v| (v.name.clone(), v.payload().to_vec()));
+            if let (Some(tmp), Some((variant, field_tys))) = (&scrutinee, payload) {
+                for (i, (binding, ty)) in arm.bindings.iter().zip(&field_tys).enumerate() {
+                    if binding == "_" {
+                        continue;
+                    }
+                    let c_ty = self.lower_type(ty);
+                    self.locals.insert(binding.clone());
+                    body.push(CStmt::VarDecl {
+                        name: binding.clone(),
+                        ty: c_ty,
+                        init: Some(CExpr::Field {
+                            base: Box::new(CExpr::Ident(tmp.clone())),
+                            field: format!("data.{}._{}", variant, i),
+                        }),
+                    });
+                }
+            }
+            body.extend(self.lower_scoped_stmts(&arm.stmts));
+            c_cases.push((
+                CExpr::Ident(arm.variant.clone()),
+                vec![CStmt::Block(body), CStmt::Break],
+            ));
+        }
+
+        // Matches are checked exhaustive, so any other tag is a corrupted value
+        let c_default = match default {
+            Some(stmts) => {
+                let mut d = self.lower_scoped_stmts(stmts);
+                d.push(CStmt::Break);
+                Some(d)
+            }
+            None => Some(vec![checks::trap(
+                TrapKind::BadTag,
+                &self.trap_site(&expr.span()),
+            )]),
+        };
+        self.scopes.pop();
+
+        pre_stmts.push(CStmt::Switch {
+            expr: switch_expr,
+            cases: c_cases,
+            default: c_default,
+        });
+        pre_stmts
+    }
+
     fn lower_block(&mut self, block: &ast::Block) -> Vec<CStmt> {
+        self.lower_scoped_stmts(&block.stmts)
+    }
+
+    /// Lower statements in their own scope, running the scope's defers on fallthrough
+    fn lower_scoped_stmts(&mut self, body: &[ast::Stmt]) -> Vec<CStmt> {
+        self.scopes.push(ScopeKind::Block);
+        let depth = self.scopes.depth();
+
         let mut stmts = Vec::new();
-        for stmt in &block.stmts {
+        for stmt in body {
+            stmts.push(CStmt::Loc(self.loc(&stmt.span())));
             stmts.extend(self.lower_stmt(stmt));
         }
+
+        // Exits via return/break/continue already ran the cleanup
+        if !Self::exits(body) {
+            stmts.extend(self.lower_cleanup(depth - 1));
+        }
+
+        self.scopes.pop();
+        stmts
+    }
+
+    /// Whether statements end in a return, break, or continue
+    fn exits(body: &[ast::Stmt]) -> bool {
+        matches!(
+            body.last(),
+            Some(ast::Stmt::Return { .. } | ast::Stmt::Break { .. } | ast::Stmt::Continue { .. })
+        )
+    }
+
+    /// Lower the cleanups of all scopes above `depth`, innermost first
+    fn lower_cleanup(&mut self, depth: usize) -> Vec<CStmt> {
+        self.scopes
+            .pending_cleanups(depth)
+            .iter()
+            .map(|cleanup| match cleanup {
+                Cleanup::Defer(block) => CStmt::Block(self.lower_block(block)),
+                Cleanup::Drop { name, drop_fn } => Self::lower_drop(name, drop_fn.as_deref()),
+            })
+            .collect()
+    }
+
+    /// Release an owned pointer: `fc_free(p);`, or `if (p != NULL) { drop(p); }`
+    /// for a type with a `@drop` function. Moved-from locals hold NULL.
+    fn lower_drop(name: &str, drop_fn: Option<&str>) -> CStmt {
+        let call = |func: &str| {
+            CStmt::Expr(CExpr::Call {
+                func: Box::new(CExpr::Ident(func.to_string())),
+                args: vec![CExpr::Ident(name.to_string())],
+            })
+        };
+        match drop_fn {
+            Some(drop_fn) => CStmt::If {
+                cond: CExpr::Binary {
+                    op: CBinOp::Ne,
+                    lhs: Box::new(CExpr::Ident(name.to_string())),
+                    rhs: Box::new(CExpr::Ident("NULL".to_string())),
+                },
+                then: vec![call(drop_fn)],
+                else_: None,
+            },
+            None => call("fc_free"),
+        }
+    }
+
+    /// The `@drop` function for an `own(T)` type, if one is declared
+    fn drop_fn_for(&self, ty: &ast::TypeExpr) -> Option<String> {
+        let ast::TypeExpr::Own(inner) = ty else {
+            return None;
+        };
+        self.drop_fns
+            .iter()
+            .find(|(pointee, _)| pointee == inner.as_ref())
+            .map(|(_, name)| name.clone())
+    }
+
+    /// Lower a value in a position that moves it. An `own(T)` local is read
+    /// into a temporary and cleared, so that its scope no longer releases it:
+    ///   T* __tmp = p; p = NULL;
+    fn lower_move(&mut self, expr: &ast::Expr, pre_stmts: &mut Vec<CStmt>) -> CExpr {
+        match expr {
+            ast::Expr::Paren { inner, .. } => self.lower_move(inner, pre_stmts),
+            ast::Expr::Ident { name, span }
+                if self.locals.contains(name)
+                    && matches!(
+                        self.types.expr_type(&self.current_fn, span),
+                        Some(ast::TypeExpr::Own(_))
+                    ) =>
+            {
+                let tmp = self.fresh_temp();
+                pre_stmts.push(CStmt::VarDecl {
+                    name: tmp.clone(),
+                    ty: self.expr_type(expr),
+                    init: Some(CExpr::Ident(name.clone())),
+                });
+                pre_stmts.push(CStmt::Assign {
+                    lhs: CExpr::Ident(name.clone()),
+                    rhs: CExpr::Ident("NULL".to_string()),
+                });
+                CExpr::Ident(tmp)
+            }
+            // Fields, elements, and pointees are cleared the same way, so a
+            // second move passes NULL instead of freeing the value twice. A
+            // place with side effects is cleared through its address so it
+            // is evaluated once: T** __tmp1 = &a[f()]; T* __tmp2 = *__tmp1;
+            ast::Expr::Field { span, .. }
+            | ast::Expr::Deref { span, .. }
+            | ast::Expr::At { span, .. }
+                if self.is_place(expr)
+                    && matches!(
+                        self.types.expr_type(&self.current_fn, span),
+                        Some(ast::TypeExpr::Own(_))
+                    ) =>
+            {
+                let ty = self.expr_type(expr);
+                let mut place = self.lower_expr(expr, pre_stmts);
+                if self.has_side_effects(expr) {
+                    let addr = self.fresh_temp();
+                    pre_stmts.push(CStmt::VarDecl {
+                        name: addr.clone(),
+                        ty: CType::Ptr(Box::new(ty.clone())),
+                        init: Some(CExpr::AddrOf(Box::new(place))),
+                    });
+                    place = CExpr::Deref(Box::new(CExpr::Ident(addr)));
+                }
+                let tmp = self.fresh_temp();
+                pre_stmts.push(CStmt::VarDecl {
+                    name: tmp.clone(),
+                    ty,
+                    init: Some(place.clone()),
+                });
+                pre_stmts.push(CStmt::Assign {
+                    lhs: place,
+                    rhs: CExpr::Ident("NULL".to_string()),
+                });
+                CExpr::Ident(tmp)
+            }
+            _ => self.lower_expr(expr, pre_stmts),
+        }
+    }
+
+    /// Whether an expression names storage that outlives it: a variable, a
+    /// field or element of one, or memory behind a pointer or slice. Moving
+    /// out of a temporary needs no clearing.
+    fn is_place(&self, expr: &ast::Expr) -> bool {
+        match expr {
+            ast::Expr::Ident { .. } | ast::Expr::Deref { .. } => true,
+            ast::Expr::Paren { inner, .. } => self.is_place(inner),
+            ast::Expr::Field { base, .. } => {
+                self.is_place(base)
+                    || matches!(self.expr_type(base), CType::Ptr(_) | CType::ConstPtr(_))
+            }
+            ast::Expr::At { base, .. } => {
+                self.is_place(base) || matches!(self.expr_type(base), CType::Slice(_))
+            }
+            _ => false,
+        }
+    }
+
+    /// Lower a loop body with a loop marker scope around it
+    fn lower_loop_body(&mut self, body: &ast::Block) -> Vec<CStmt> {
+        self.scopes.push(ScopeKind::Loop);
+        let stmts = self.lower_block(body);
+        self.scopes.pop();
         stmts
     }
 
@@ -580,20 +1234,39 @@ impl Lower {
             ast::Stmt::Let { name, ty, init, .. } => {
                 let mut pre_stmts = Vec::new();
                 let c_ty = self.lower_type(ty);
-                // Track the variable type for inference
-                self.var_types.insert(name.clone(), c_ty.clone());
-                let c_init = self.lower_expr(init, &mut pre_stmts);
+                self.locals.insert(name.clone());
+                let c_init = self.lower_move(init, &mut pre_stmts);
                 pre_stmts.push(CStmt::VarDecl {
                     name: name.clone(),
                     ty: c_ty,
                     init: Some(c_init),
                 });
+                if let ast::TypeExpr::Own(_) = ty {
+                    self.scopes.own(name.clone(), self.drop_fn_for(ty));
+                }
                 pre_stmts
             }
             ast::Stmt::Assign { lhs, rhs, .. } => {
                 let mut pre_stmts = Vec::new();
                 let c_lhs = self.lower_expr(lhs, &mut pre_stmts);
-                let c_rhs = self.lower_expr(rhs, &mut pre_stmts);
+                let mut c_rhs = self.lower_move(rhs, &mut pre_stmts);
+
+                // Overwriting an owned local releases its old value, after
+                // the new one is computed: T* __tmp = rhs; fc_free(p); p = __tmp;
+                let lhs_ty = self.types.expr_type(&self.current_fn, &lhs.span()).cloned();
+                if let (ast::Expr::Ident { name, .. }, Some(ty @ ast::TypeExpr::Own(_))) =
+                    (lhs, &lhs_ty)
+                {
+                    let tmp = self.fresh_temp();
+                    pre_stmts.push(CStmt::VarDecl {
+                        name: tmp.clone(),
+                        ty: self.lower_type(ty),
+                        init: Some(c_rhs),
+                    });
+                    pre_stmts.push(Self::lower_drop(name, self.drop_fn_for(ty).as_deref()));
+                    c_rhs = CExpr::Ident(tmp);
+                }
+
                 pre_stmts.push(CStmt::Assign {
                     lhs: c_lhs,
                     rhs: c_rhs,
@@ -602,10 +1275,44 @@ impl Lower {
             }
             ast::Stmt::Return { value, .. } => {
                 let mut pre_stmts = Vec::new();
-                let c_value = value.as_ref().map(|v| self.lower_expr(v, &mut pre_stmts));
+                let mut c_value = value.as_ref().map(|v| self.lower_move(v, &mut pre_stmts));
+
+                if self.scopes.has_cleanups(0) {
+                    // Stage the return value before running defers:
+                    //   T __tmp = value; { deferred... } return __tmp;
+                    if let Some(v) = c_value.take() {
+                        let tmp = self.fresh_temp();
+                        pre_stmts.push(CStmt::VarDecl {
+                            name: tmp.clone(),
+                            ty: self.return_type.clone(),
+                            init: Some(v),
+                        });
+                        c_value = Some(CExpr::Ident(tmp));
+                    }
+                    pre_stmts.extend(self.lower_cleanup(0));
+                }
+
                 pre_stmts.push(CStmt::Return(c_value));
                 pre_stmts
             }
+            ast::Stmt::Break { .. } => {
+                let mut stmts = self.lower_cleanup(self.scopes.break_depth());
+                stmts.push(CStmt::Break);
+                stmts
+            }
+            ast::Stmt::Continue { .. } => {
+                let mut stmts = self.lower_cleanup(self.scopes.continue_depth());
+                match self.scopes.continue_label() {
+                    Some(label) => stmts.push(CStmt::Goto(label)),
+                    None => stmts.push(CStmt::Continue),
+                }
+                stmts
+            }
+            ast::Stmt::Defer { body, .. } => {
+                // Deferred blocks are emitted at every exit of the enclosing scope
+                self.scopes.defer(body.clone());
+                vec![]
+            }
             ast::Stmt::If {
                 cond,
                 then_block,
@@ -627,15 +1334,47 @@ impl Lower {
                 pre_stmts
             }
             ast::Stmt::While { cond, body, .. } => {
-                let mut pre_stmts = Vec::new();
-                let c_cond = self.lower_expr(cond, &mut pre_stmts);
-                let c_body = self.lower_block(body);
-                pre_stmts.push(CStmt::While {
-                    cond: c_cond,
-                    body: c_body,
+                let mut cond_pre = Vec::new();
+                let c_cond = self.lower_expr(cond, &mut cond_pre);
+                let c_body = self.lower_loop_body(body);
+                if cond_pre.is_empty() {
+                    return vec![CStmt::While {
+                        cond: c_cond,
+                        body: c_body,
+                    }];
+                }
+                // Statements the condition needs run before every test:
+                //   for (;;) { cond_pre; if (!cond) break; body }
+                let mut loop_body = cond_pre;
+                loop_body.push(CStmt::If {
+                    cond: CExpr::Unary {
+                        op: CUnaryOp::Not,
+                        operand: Box::new(c_cond),
+                    },
+                    then: vec![CStmt::Break],
+                    else_: None,
                 });
-                pre_stmts
-            }
+                loop_body.extend(c_body);
+                vec![CStmt::For {
+                    init: None,
+                    cond: None,
+                    step: None,
+                    body: loop_body,
+                }]
+            }
+            ast::Stmt::For {
+                init,
+                cond,
+                step,
+                body,
+                ..
+            } => self.lower_for(init.as_ref(), cond.as_ref(), step.as_ref(), body),
+            ast::Stmt::Match {
+                expr,
+                arms,
+                default,
+                ..
+            } => self.lower_match(expr, arms, default.as_deref()),
             ast::Stmt::Expr { expr, .. } => {
                 let mut pre_stmts = Vec::new();
                 let c_expr = self.lower_expr(expr, &mut pre_stmts);
@@ -645,6 +1384,7 @@ impl Lower {
             ast::Stmt::Block(block) => {
                 vec![CStmt::Block(self.lower_block(block))]
             }
+            ast::Stmt::Error { .. } => Vec::new(),
             ast::Stmt::Unsafe { body, .. } => {
                 // In C, unsafe just means the type checker allowed it
                 // Track unsafe context to disable runtime checks
@@ -680,34 +1420,49 @@ impl Lower {
                 //   } else {
                 //       ... else_block ...
                 //   }
+                // A res binds its ok value, testing `is_ok` and reading `ok`.
                 let mut pre_stmts = Vec::new();
                 let c_expr = self.lower_expr(expr, &mut pre_stmts);
 
-                // Infer the opt type from the expression
-                let opt_ty = self.infer_expr_type(expr);
-                let inner_ty = match &opt_ty {
-                    CType::Opt(inner) => (**inner).clone(),
-                    _ => CType::Int32, // Fallback if type inference fails
+                let wrapped_ty = self.expr_type(expr);
+                let (inner_ty, flag, field) = match &wrapped_ty {
+                    CType::Res(ok, _) => ((**ok).clone(), "is_ok", "ok"),
+                    CType::Opt(inner) => ((**inner).clone(), "has_value", "value"),
+                    _ => (CType::Void, "has_value", "value"),
                 };
 
-                // Create temporary for the opt value
+                // Create temporary for the wrapped value
                 let tmp = self.fresh_temp();
                 pre_stmts.push(CStmt::VarDecl {
                     name: tmp.clone(),
-                    ty: opt_ty.clone(),
+                    ty: wrapped_ty.clone(),
                     init: Some(c_expr),
                 });
 
-                // Build the then block with the unwrapped value
+                // Build the then block with the unwrapped value, in a scope
+                // that releases it if it is owned
+                self.locals.insert(name.clone());
+                self.scopes.push(ScopeKind::Block);
+                let depth = self.scopes.depth();
+                if let Some(ast::TypeExpr::Opt(inner) | ast::TypeExpr::Res(inner, _)) =
+                    self.types.expr_type(&self.current_fn, &expr.span())
+                    && let ast::TypeExpr::Own(_) = inner.as_ref()
+                {
+                    self.scopes.own(name.clone(), self.drop_fn_for(inner));
+                }
                 let mut then_stmts = vec![CStmt::VarDecl {
                     name: name.clone(),
                     ty: inner_ty,
                     init: Some(CExpr::Field {
                         base: Box::new(CExpr::Ident(tmp.clone())),
-                        field: "value".to_string(),
+                        field: field.to_string(),
                     }),
                 }];
                 then_stmts.extend(self.lower_block(then_block));
+                if !Self::exits(&then_block.stmts) {
+                    then_stmts.extend(self.lower_cleanup(depth - 1));
+                }
+                self.scopes.pop();
 
                 // Build the else block if present
                 let else_stmts = else_block.as_ref().map(|eb| self.lower_block(eb));
@@ -715,7 +1470,7 @@ impl Lower {
                 pre_stmts.push(CStmt::If {
                     cond: CExpr::Field {
                         base: Box::new(CExpr::Ident(tmp)),
-                        field: "has_value".to_string(),
+                        field: flag.to_string(),
                     },
                     then: then_stmts,
                     else_: else_stmts,
@@ -732,22 +1487,23 @@ impl Lower {
                 let mut pre_stmts = Vec::new();
                 let c_expr = self.lower_expr(expr, &mut pre_stmts);
 
+                self.scopes.push(ScopeKind::Switch);
                 let c_cases: Vec<(CExpr, Vec<CStmt>)> = cases
                     .iter()
                     .map(|case| {
                         let value = self.lower_const_expr(&case.value);
-                        let mut case_stmts: Vec<CStmt> =
-                            case.stmts.iter().flat_map(|s| self.lower_stmt(s)).collect();
+                        let mut case_stmts = self.lower_scoped_stmts(&case.stmts);
                         case_stmts.push(CStmt::Break);
                         (value, case_stmts)
                     })
                     .collect();
 
                 let c_default = default.as_ref().map(|stmts| {
-                    let mut d: Vec<CStmt> = stmts.iter().flat_map(|s| self.lower_stmt(s)).collect();
+                    let mut d = self.lower_scoped_stmts(stmts);
                     d.push(CStmt::Break);
                     d
                 });
+                self.scopes.pop();
 
                 pre_stmts.push(CStmt::Switch {
                     expr: c_expr,
@@ -757,27 +1513,172 @@ impl Lower {
 
                 pre_stmts
             }
-            _ => {
-                // TODO: Handle other statements (for, defer, etc.)
-                vec![]
+        }
+    }
+
+    /// Lower a C-style `for` loop
+    ///
+    /// When the condition and step lower to plain expressions this maps
+    /// directly onto a C `for`. Otherwise (e.g. an overflow-checked
+    /// `i = i + 1`) the checks must run on every iteration, so the loop
+    /// becomes:
+    ///   { init; for (;;) { cond_pre; if (!cond) break; body; __continueN: { step } } }
+    /// with `continue` jumping to the step label.
+    fn lower_for(
+        &mut self,
+        init: Option<&ast::ForInit>,
+        cond: Option<&ast::Expr>,
+        step: Option<&ast::ForStep>,
+        body: &ast::Block,
+    ) -> Vec<CStmt> {
+        let counter = bounds::for_counter_bound(init, cond, step, body, |e| self.const_int(e));
+
+        let mut stmts = Vec::new();
+        let c_init = init.map(|i| self.lower_for_init(i, &mut stmts));
+
+        let mut cond_pre = Vec::new();
+        let c_cond = cond.map(|c| self.lower_expr(c, &mut cond_pre));
+
+        let mut step_stmts = Vec::new();
+        let c_step = step.map(|s| self.lower_for_step(s, &mut step_stmts));
+
+        // Indexing by the counter inside the body needs no bounds check
+        // against arrays at least `bound` long
+        let counters = self.bounded_counters.len();
+        self.bounded_counters.extend(counter);
+
+        if cond_pre.is_empty() && step_stmts.is_empty() {
+            let for_stmt = CStmt::For {
+                init: c_init.map(Box::new),
+                cond: c_cond,
+                step: c_step.map(Box::new),
+                body: self.lower_loop_body(body),
+            };
+            self.bounded_counters.truncate(counters);
+            if stmts.is_empty() {
+                return vec![for_stmt];
+            }
+            stmts.push(for_stmt);
+            return vec![CStmt::Block(stmts)];
+        }
+
+        stmts.extend(c_init);
+
+        let mut loop_body = cond_pre;
+        if let Some(c) = c_cond {
+            loop_body.push(CStmt::If {
+                cond: CExpr::Unary {
+                    op: CUnaryOp::Not,
+                    operand: Box::new(c),
+                },
+                then: vec![CStmt::Break],
+                else_: None,
+            });
+        }
+
+        let label = self.fresh_label("continue");
+        self.scopes.push_loop(Some(label.clone()));
+        loop_body.extend(self.lower_block(body));
+        let continue_used = self.scopes.pop().is_some_and(|s| s.continue_used);
+        self.bounded_counters.truncate(counters);
+
+        step_stmts.extend(c_step);
+        if continue_used {
+            // A label must precede a statement, not a declaration
+            loop_body.push(CStmt::Label(label));
+            loop_body.push(CStmt::Block(step_stmts));
+        } else {
+            loop_body.extend(step_stmts);
+        }
+
+        stmts.push(CStmt::For {
+            init: None,
+            cond: None,
+            step: None,
+            body: loop_body,
+        });
+        vec![CStmt::Block(stmts)]
+    }
+
+    fn lower_for_init(&mut self, init: &ast::ForInit, pre_stmts: &mut Vec<CStmt>) -> CStmt {
+        match init {
+            ast::ForInit::Let { name, ty, init } => {
+                let c_ty = self.lower_type(ty);
+                self.locals.insert(name.clone());
+                let c_init = self.lower_expr(init, pre_stmts);
+                CStmt::VarDecl {
+                    name: name.clone(),
+                    ty: c_ty,
+                    init: Some(c_init),
+                }
+            }
+            ast::ForInit::Assign { lhs, rhs } => {
+                let c_lhs = self.lower_expr(lhs, pre_stmts);
+                let c_rhs = self.lower_expr(rhs, pre_stmts);
+                CStmt::Assign {
+                    lhs: c_lhs,
+                    rhs: c_rhs,
+                }
             }
+            ast::ForInit::Call(expr) => CStmt::Expr(self.lower_expr(expr, pre_stmts)),
+        }
+    }
+
+    fn lower_for_step(&mut self, step: &ast::ForStep, pre_stmts: &mut Vec<CStmt>) -> CStmt {
+        match step {
+            ast::ForStep::Assign { lhs, rhs } => {
+                let c_lhs = self.lower_expr(lhs, pre_stmts);
+                let c_rhs = self.lower_expr(rhs, pre_stmts);
+                CStmt::Assign {
+                    lhs: c_lhs,
+                    rhs: c_rhs,
+                }
+            }
+            ast::ForStep::Call(expr) => CStmt::Expr(self.lower_expr(expr, pre_stmts)),
         }
     }
 
     fn lower_expr(&mut self, expr: &ast::Expr, pre_stmts: &mut Vec<CStmt>) -> CExpr {
         match expr {
-            ast::Expr::IntLit { value, .. } => CExpr::IntLit(value.to_string()),
+            ast::Expr::IntLit { value, .. } => {
+                // Decimal literals past INT64_MAX need a suffix to be unsigned in C
+                if *value > i128::from(i64::MAX) {
+                    CExpr::IntLit(format!("{}u", value))
+                } else {
+                    CExpr::IntLit(value.to_string())
+                }
+            }
             ast::Expr::FloatLit { raw, .. } => CExpr::FloatLit(raw.clone()),
             ast::Expr::BoolLit { value, .. } => CExpr::BoolLit(*value),
-            ast::Expr::Ident { name, .. } => CExpr::Ident(name.clone()),
+            ast::Expr::Ident { name, .. } => {
+                if !self.locals.contains(name) {
+                    if self.consts.contains_key(name) {
+                        self.used_consts.insert(name.clone());
+                    }
+                    // A unit variant of a tagged enum is a value of the enum struct
+                    if let Some((e, v)) = self.enum_variant(name)
+                        && e.has_payload()
+                    {
+                        let (e_name, v) = (e.name.clone(), v.clone());
+                        return self.lower_variant_ctor(&e_name, &v, &[], pre_stmts);
+                    }
+                }
+                CExpr::Ident(name.clone())
+            }
             ast::Expr::Binary { op, lhs, rhs, .. } => {
                 // Handle short-circuit operators with temporaries
                 match op {
                     ast::BinOp::And => {
                         // a && b becomes: bool __tmp; if (a) { __tmp = b; } else { __tmp = false; }
+                        // b's own statements (checks, temporaries) run only when it does
                         let tmp = self.fresh_temp();
                         let c_lhs = self.lower_expr(lhs, pre_stmts);
-                        let c_rhs = self.lower_expr(rhs, pre_stmts);
+                        let mut rhs_stmts = Vec::new();
+                        let c_rhs = self.lower_expr(rhs, &mut rhs_stmts);
+                        rhs_stmts.push(CStmt::Assign {
+                            lhs: CExpr::Ident(tmp.clone()),
+                            rhs: c_rhs,
+                        });
 
                         pre_stmts.push(CStmt::VarDecl {
                             name: tmp.clone(),
@@ -786,10 +1687,7 @@ impl Lower {
                         });
                         pre_stmts.push(CStmt::If {
                             cond: c_lhs,
-                            then: vec![CStmt::Assign {
-                                lhs: CExpr::Ident(tmp.clone()),
-                                rhs: c_rhs,
-                            }],
+                            then: rhs_stmts,
                             else_: Some(vec![CStmt::Assign {
                                 lhs: CExpr::Ident(tmp.clone()),
                                 rhs: CExpr::BoolLit(false),
@@ -801,7 +1699,12 @@ impl Lower {
                         // a || b becomes: bool __tmp; if (a) { __tmp = true; } else { __tmp = b; }
                         let tmp = self.fresh_temp();
                         let c_lhs = self.lower_expr(lhs, pre_stmts);
-                        let c_rhs = self.lower_expr(rhs, pre_stmts);
+                        let mut rhs_stmts = Vec::new();
+                        let c_rhs = self.lower_expr(rhs, &mut rhs_stmts);
+                        rhs_stmts.push(CStmt::Assign {
+                            lhs: CExpr::Ident(tmp.clone()),
+                            rhs: c_rhs,
+                        });
 
                         pre_stmts.push(CStmt::VarDecl {
                             name: tmp.clone(),
@@ -814,21 +1717,49 @@ impl Lower {
                                 lhs: CExpr::Ident(tmp.clone()),
                                 rhs: CExpr::BoolLit(true),
                             }],
-                            else_: Some(vec![CStmt::Assign {
-                                lhs: CExpr::Ident(tmp.clone()),
-                                rhs: c_rhs,
-                            }]),
+                            else_: Some(rhs_stmts),
                         });
                         CExpr::Ident(tmp)
                     }
-                    ast::BinOp::Div | ast::BinOp::Rem => {
-                        // Add division by zero check in safe code
-                        let c_lhs = self.lower_expr(lhs, pre_stmts);
-                        let c_rhs = self.lower_expr(rhs, pre_stmts);
+                    ast::BinOp::Div | ast::BinOp::Rem if !self.in_unsafe => {
+                        // Division by zero and signed MIN / -1 trap in safe
+                        // code; the checks read both operands again
+                        let expr_ty = self.expr_type(lhs);
+                        let c_lhs = self.lower_reused(lhs, pre_stmts);
+                        let c_rhs = self.lower_reused(rhs, pre_stmts);
+
+                        let site = self.trap_site(&expr.span());
+                        pre_stmts.push(checks::div_zero_check(c_rhs.clone(), &site));
+                        if Self::is_signed_integer(&expr_ty) {
+                            pre_stmts.push(checks::div_overflow_check(
+                                c_lhs.clone(),
+                                c_rhs.clone(),
+                                &expr_ty,
+                                &site,
+                            ));
+                        }
 
-                        if !self.in_unsafe {
-                            pre_stmts.push(checks::div_zero_check(c_rhs.clone()));
+                        CExpr::Binary {
+                            op: self.lower_binop(*op),
+                            lhs: Box::new(c_lhs),
+                            rhs: Box::new(c_rhs),
                         }
+                    }
+                    ast::BinOp::Shl | ast::BinOp::Shr if !self.in_unsafe => {
+                        // The check reads both operands again
+                        let value_ty = self.expr_type(lhs);
+                        let count_ty = self.expr_type(rhs);
+                        let c_lhs = self.lower_reused(lhs, pre_stmts);
+                        let c_rhs = self.lower_reused(rhs, pre_stmts);
+
+                        pre_stmts.push(checks::shift_check(
+                            c_lhs.clone(),
+                            c_rhs.clone(),
+                            &value_ty,
+                            &count_ty,
+                            *op == ast::BinOp::Shl,
+                            &self.trap_site(&expr.span()),
+                        ));
 
                         CExpr::Binary {
                             op: self.lower_binop(*op),
@@ -838,22 +1769,23 @@ impl Lower {
                     }
                     ast::BinOp::Add | ast::BinOp::Sub | ast::BinOp::Mul => {
                         // Add overflow check for signed integer types in safe code
-                        let expr_ty = self.infer_expr_type(lhs);
+                        let expr_ty = self.expr_type(lhs);
                         let c_lhs = self.lower_expr(lhs, pre_stmts);
                         let c_rhs = self.lower_expr(rhs, pre_stmts);
 
                         // Check if this is a signed integer type
                         if !self.in_unsafe && Self::is_signed_integer(&expr_ty) {
                             let tmp = self.fresh_temp();
+                            let site = self.trap_site(&expr.span());
                             let (decl, check) = match op {
                                 ast::BinOp::Add => {
-                                    checks::overflow_check_add(c_lhs, c_rhs, &tmp, expr_ty)
+                                    checks::overflow_check_add(c_lhs, c_rhs, &tmp, expr_ty, &site)
                                 }
                                 ast::BinOp::Sub => {
-                                    checks::overflow_check_sub(c_lhs, c_rhs, &tmp, expr_ty)
+                                    checks::overflow_check_sub(c_lhs, c_rhs, &tmp, expr_ty, &site)
                                 }
                                 ast::BinOp::Mul => {
-                                    checks::overflow_check_mul(c_lhs, c_rhs, &tmp, expr_ty)
+                                    checks::overflow_check_mul(c_lhs, c_rhs, &tmp, expr_ty, &site)
                                 }
                                 _ => unreachable!(),
                             };
@@ -880,7 +1812,23 @@ impl Lower {
                 }
             }
             ast::Expr::Unary { op, operand, .. } => {
-                let c_operand = self.lower_expr(operand, pre_stmts);
+                // Negating a signed MIN overflows; literals are range-checked
+                let operand_ty = self.expr_type(operand);
+                let checked = *op == ast::UnaryOp::Neg
+                    && !self.in_unsafe
+                    && Self::is_signed_integer(&operand_ty)
+                    && !Self::is_int_literal(operand);
+                let c_operand = if checked {
+                    let c_operand = self.lower_reused(operand, pre_stmts);
+                    pre_stmts.push(checks::neg_overflow_check(
+                        c_operand.clone(),
+                        &operand_ty,
+                        &self.trap_site(&expr.span()),
+                    ));
+                    c_operand
+                } else {
+                    self.lower_expr(operand, pre_stmts)
+                };
                 CExpr::Unary {
                     op: self.lower_unaryop(*op),
                     operand: Box::new(c_operand),
@@ -891,20 +1839,27 @@ impl Lower {
                 CExpr::Paren(Box::new(c_inner))
             }
             ast::Expr::Call { callee, args, .. } => {
-                let c_callee = self.lower_expr(callee, pre_stmts);
+                if let ast::Expr::Ident { name, .. } = callee.as_ref()
+                    && let Some((e, v)) = self.enum_variant(name)
+                {
+                    let (e_name, v) = (e.name.clone(), v.clone());
+                    return self.lower_variant_ctor(&e_name, &v, args, pre_stmts);
+                }
+
+                let mut c_callee = self.lower_expr(callee, pre_stmts);
 
                 // Create temporaries for arguments with side effects to guarantee
                 // left-to-right evaluation order (C doesn't guarantee this)
-                let c_args: Vec<CExpr> = args
+                let mut c_args: Vec<CExpr> = args
                     .iter()
                     .map(|arg| {
-                        let c_arg = self.lower_expr(arg, pre_stmts);
+                        let c_arg = self.lower_move(arg, pre_stmts);
                         // Only create temporary if arg has side effects
                         if self.has_side_effects(arg) {
                             let tmp = self.fresh_temp();
                             pre_stmts.push(CStmt::VarDecl {
                                 name: tmp.clone(),
-                                ty: CType::Int32, // Default type, actual type would need inference
+                                ty: self.expr_type(arg),
                                 init: Some(c_arg),
                             });
                             CExpr::Ident(tmp)
@@ -914,13 +1869,57 @@ impl Lower {
                     })
                     .collect();
 
-                CExpr::Call {
+                if let ast::Expr::Ident { name, span } = callee.as_ref()
+                    && !self.locals.contains(name)
+                    && let Some((_, sited)) = SITED_FNS.iter().find(|(f, _)| f == name)
+                {
+                    let site = self.trap_site(span);
+                    c_callee = CExpr::Ident(sited.to_string());
+                    c_args.extend([
+                        CExpr::StringLit(site.file),
+                        CExpr::IntLit(site.line.to_string()),
+                        CExpr::IntLit(site.column.to_string()),
+                    ]);
+                }
+
+                // C library headers spell some `u8` pointers as `char` ones
+                let mut result_ty = None;
+                if let ast::Expr::Ident { name, .. } = callee.as_ref()
+                    && !self.locals.contains(name)
+                    && let Some(ret) = self.libc_fns.get(name)
+                    && let Some(libc_fn) = libc::find(name)
+                {
+                    for &(index, ty) in libc_fn.casts {
+                        if let Some(arg) = c_args.get_mut(index) {
+                            *arg = CExpr::Cast {
+                                ty: CType::Named(ty.to_string()),
+                                expr: Box::new(arg.clone()),
+                            };
+                        }
+                    }
+                    if libc_fn.char_result {
+                        result_ty = Some(self.lower_type(ret));
+                    }
+                }
+
+                let call = CExpr::Call {
                     func: Box::new(c_callee),
                     args: c_args,
+                };
+                match result_ty {
+                    Some(ty) => CExpr::Cast {
+                        ty,
+                        expr: Box::new(call),
+                    },
+                    None => call,
                 }
             }
             ast::Expr::Field { base, field, .. } => {
-                let c_base = self.lower_expr(base, pre_stmts);
+                let mut c_base = self.lower_expr(base, pre_stmts);
+                // Field access through a reference auto-derefs
+                if matches!(self.expr_type(base), CType::Ptr(_) | CType::ConstPtr(_)) {
+                    c_base = CExpr::Deref(Box::new(c_base));
+                }
                 CExpr::Field {
                     base: Box::new(c_base),
                     field: field.clone(),
@@ -935,20 +1934,26 @@ impl Lower {
                 CExpr::Deref(Box::new(c_operand))
             }
             ast::Expr::At { base, index, .. } => {
-                let base_ty = self.infer_expr_type(base);
+                let base_ty = self.expr_type(base);
                 let c_base = self.lower_expr(base, pre_stmts);
-                let c_index = self.lower_expr(index, pre_stmts);
+                // The index is read by the bounds check and the access
+                let c_index = if self.in_unsafe {
+                    self.lower_expr(index, pre_stmts)
+                } else {
+                    self.lower_reused(index, pre_stmts)
+                };
 
                 // Check if base is a slice type - slices need bounds checks
                 if let CType::Slice(_) = base_ty {
                     if !self.in_unsafe {
-                        // Insert bounds check: if (index >= base.len) { fc_trap(); }
+                        // Insert bounds check: if (index >= base.len) { fc_trap_at(...); }
                         pre_stmts.push(checks::bounds_check(
                             c_index.clone(),
                             CExpr::Field {
                                 base: Box::new(c_base.clone()),
                                 field: "len".to_string(),
                             },
+                            &self.trap_site(&expr.span()),
                         ));
                     }
                     // Slice access: base.data[index]
@@ -960,6 +1965,18 @@ impl Lower {
                         index: Box::new(c_index),
                     }
                 } else {
+                    if let CType::Array(_, len) = base_ty
+                        && !self.in_unsafe
+                        && !self.index_in_bounds(index, len)
+                    {
+                        // Insert bounds check: if (index >= N) { fc_trap_at(...); }
+                        pre_stmts.push(checks::array_bounds_check(
+                            c_index.clone(),
+                            &self.expr_type(index),
+                            len,
+                            &self.trap_site(&expr.span()),
+                        ));
+                    }
                     // Array access: base[index]
                     CExpr::Index {
                         base: Box::new(c_base),
@@ -976,12 +1993,24 @@ impl Lower {
             }
             ast::Expr::CStr { value, .. } => CExpr::StringLit(value.clone()),
 
+            // bytes("...") -> static uint8_t __bytesN[len] = "...";
+            //                 (fc_slice_uint8_t){ .data = __bytesN, .len = len }
+            ast::Expr::Bytes { value, .. } => {
+                let data = self.bytes_storage(value, None);
+                CExpr::Compound {
+                    ty: CType::Slice(Box::new(CType::UInt8)),
+                    fields: vec![
+                        ("data".to_string(), data),
+                        ("len".to_string(), CExpr::IntLit(value.len().to_string())),
+                    ],
+                }
+            }
+
             // some(value) -> (fc_opt_T){ .has_value = true, .value = value }
             ast::Expr::Some { value, .. } => {
-                let inner_ty = self.infer_expr_type(value);
-                let c_value = self.lower_expr(value, pre_stmts);
+                let c_value = self.lower_move(value, pre_stmts);
                 CExpr::Compound {
-                    ty: CType::Opt(Box::new(inner_ty)),
+                    ty: self.expr_type(expr),
                     fields: vec![
                         ("has_value".to_string(), CExpr::BoolLit(true)),
                         ("value".to_string(), c_value),
@@ -999,12 +2028,10 @@ impl Lower {
             }
 
             // ok(value) -> (fc_res_T_E){ .is_ok = true, .ok = value }
-            // Note: Without full type info, we can only infer the ok type, err is Void
             ast::Expr::Ok { value, .. } => {
-                let ok_ty = self.infer_expr_type(value);
-                let c_value = self.lower_expr(value, pre_stmts);
+                let c_value = self.lower_move(value, pre_stmts);
                 CExpr::Compound {
-                    ty: CType::Res(Box::new(ok_ty), Box::new(CType::Void)),
+                    ty: self.expr_type(expr),
                     fields: vec![
                         ("is_ok".to_string(), CExpr::BoolLit(true)),
                         ("ok".to_string(), c_value),
@@ -1013,12 +2040,10 @@ impl Lower {
             }
 
             // err(value) -> (fc_res_T_E){ .is_ok = false, .err = value }
-            // Note: Without full type info, we can only infer the err type, ok is Void
             ast::Expr::Err { value, .. } => {
-                let err_ty = self.infer_expr_type(value);
-                let c_value = self.lower_expr(value, pre_stmts);
+                let c_value = self.lower_move(value, pre_stmts);
                 CExpr::Compound {
-                    ty: CType::Res(Box::new(CType::Void), Box::new(err_ty)),
+                    ty: self.expr_type(expr),
                     fields: vec![
                         ("is_ok".to_string(), CExpr::BoolLit(false)),
                         ("err".to_string(), c_value),
@@ -1026,6 +2051,8 @@ impl Lower {
                 }
             }
 
+            ast::Expr::Builtin { op, args, .. } => self.lower_builtin(*op, args, expr, pre_stmts),
+
             ast::Expr::StructLit { name, fields, .. } => {
                 // Struct literal: Point { x: 10, y: 20 }
                 // Lowers to: (Point){ .x = 10, .y = 20 }
@@ -1034,7 +2061,7 @@ impl Lower {
                     .map(|field_init| {
                         (
                             field_init.name.clone(),
-                            self.lower_expr(&field_init.value, pre_stmts),
+                            self.lower_move(&field_init.value, pre_stmts),
                         )
                     })
                     .collect();
@@ -1052,6 +2079,253 @@ impl Lower {
         }
     }
 
+    /// Lower an operand the emitted C reads more than once, evaluating one
+    /// with side effects into a temporary first
+    fn lower_reused(&mut self, expr: &ast::Expr, pre_stmts: &mut Vec<CStmt>) -> CExpr {
+        let c_expr = self.lower_expr(expr, pre_stmts);
+        if !self.has_side_effects(expr) {
+            return c_expr;
+        }
+        let tmp = self.fresh_temp();
+        pre_stmts.push(CStmt::VarDecl {
+            name: tmp.clone(),
+            ty: self.expr_type(expr),
+            init: Some(c_expr),
+        });
+        CExpr::Ident(tmp)
+    }
+
+    /// Value of an integer constant expression (literals and consts)
+    fn const_int(&self, expr: &ast::Expr) -> Option<i128> {
+        expr.eval_int(&|name: &str| {
+            if self.locals.contains(name) {
+                return None;
+            }
+            self.consts.get(name).map(|(_, value)| value)
+        })
+    }
+
+    /// Whether an index into an `arr` of length `len` is statically in
+    /// bounds: a constant (range-checked by the typechecker) or the counter
+    /// of an enclosing `for` loop bounded by at most `len`
+    fn index_in_bounds(&self, index: &ast::Expr, len: usize) -> bool {
+        if let Some(value) = self.const_int(index) {
+            return value >= 0 && value < len as i128;
+        }
+        let mut index = index;
+        while let ast::Expr::Paren { inner, .. } = index {
+            index = inner;
+        }
+        let ast::Expr::Ident { name, .. } = index else {
+            return false;
+        };
+        self.bounded_counters
+            .iter()
+            .rev()
+            .find(|(counter, _)| counter == name)
+            .is_some_and(|(_, bound)| *bound <= len as i128)
+    }
+
+    /// Lower an opt/res builtin. The first argument is read more than once,
+    /// so one with side effects is evaluated into a temporary first.
+    fn lower_builtin(
+        &mut self,
+        op: ast::Builtin,
+        args: &[ast::Expr],
+        expr: &ast::Expr,
+        pre_stmts: &mut Vec<CStmt>,
+    ) -> CExpr {
+        if matches!(
+            op,
+            ast::Builtin::AsSlice
+                | ast::Builtin::Subslice
+                | ast::Builtin::SliceFromRaw
+                | ast::Builtin::Len
+        ) {
+            return self.lower_slice_builtin(op, args, expr, pre_stmts);
+        }
+
+        let mut c_arg = self.lower_expr(&args[0], pre_stmts);
+        if self.has_side_effects(&args[0]) {
+            let tmp = self.fresh_temp();
+            pre_stmts.push(CStmt::VarDecl {
+                name: tmp.clone(),
+                ty: self.expr_type(&args[0]),
+                init: Some(c_arg),
+            });
+            c_arg = CExpr::Ident(tmp);
+        }
+        let site = self.trap_site(&expr.span());
+        let field = |name: &str| CExpr::Field {
+            base: Box::new(c_arg.clone()),
+            field: name.to_string(),
+        };
+        let not = |operand: CExpr| CExpr::Unary {
+            op: CUnaryOp::Not,
+            operand: Box::new(operand),
+        };
+        // Flag and payload field of the held value: opt or res
+        let (flag, value) = match self.expr_type(&args[0]) {
+            CType::Res(_, _) => ("is_ok", "ok"),
+            _ => ("has_value", "value"),
+        };
+
+        match op {
+            // is_some(o) -> o.has_value, is_err(r) -> !r.is_ok
+            ast::Builtin::IsSome | ast::Builtin::IsOk => field(flag),
+            ast::Builtin::IsNone | ast::Builtin::IsErr => not(field(flag)),
+
+            // unwrap(o) -> if (!o.has_value) fc_trap_at(...); ... o.value
+            ast::Builtin::Unwrap => {
+                pre_stmts.push(checks::unwrap_check(not(field(flag)), &site));
+                field(value)
+            }
+
+            // unwrap_err(r) -> if (r.is_ok) fc_trap_at(...); ... r.err
+            ast::Builtin::UnwrapErr => {
+                pre_stmts.push(checks::unwrap_check(field("is_ok"), &site));
+                field("err")
+            }
+
+            // unwrap_or(o, d) -> T tmp = d; if (o.has_value) tmp = o.value;
+            ast::Builtin::UnwrapOr => {
+                let c_default = self.lower_expr(&args[1], pre_stmts);
+                let tmp = self.fresh_temp();
+                pre_stmts.push(CStmt::VarDecl {
+                    name: tmp.clone(),
+                    ty: self.expr_type(expr),
+                    init: Some(c_default),
+                });
+                pre_stmts.push(CStmt::If {
+                    cond: field(flag),
+                    then: vec![CStmt::Assign {
+                        lhs: CExpr::Ident(tmp.clone()),
+                        rhs: field(value),
+                    }],
+                    else_: None,
+                });
+                CExpr::Ident(tmp)
+            }
+
+            // from_raw(p) -> (fc_opt_T){ .has_value = p != NULL, .value = p }
+            ast::Builtin::FromRaw | ast::Builtin::FromRawm => CExpr::Compound {
+                ty: self.expr_type(expr),
+                fields: vec![
+                    (
+                        "has_value".to_string(),
+                        CExpr::Binary {
+                            op: CBinOp::Ne,
+                            lhs: Box::new(c_arg.clone()),
+                            rhs: Box::new(CExpr::Ident("NULL".to_string())),
+                        },
+                    ),
+                    ("value".to_string(), c_arg),
+                ],
+            },
+
+            // References and raw pointers share a C representation
+            ast::Builtin::ToRaw
+            | ast::Builtin::ToRawm
+            | ast::Builtin::FromRawUnchecked
+            | ast::Builtin::FromRawmUnchecked => c_arg,
+
+            _ => unreachable!("slice builtins are lowered by lower_slice_builtin"),
+        }
+    }
+
+    /// Lower a slice builtin onto the `fc_slice_T` structs. An `arr(T, N)`
+    /// operand decays to its element pointer with length `N`.
+    fn lower_slice_builtin(
+        &mut self,
+        op: ast::Builtin,
+        args: &[ast::Expr],
+        expr: &ast::Expr,
+        pre_stmts: &mut Vec<CStmt>,
+    ) -> CExpr {
+        // subslice reads its operand twice: for the pointer and the length
+        let c_arg = if op == ast::Builtin::Subslice {
+            self.lower_reused(&args[0], pre_stmts)
+        } else {
+            self.lower_expr(&args[0], pre_stmts)
+        };
+        let field = |name: &str| CExpr::Field {
+            base: Box::new(c_arg.clone()),
+            field: name.to_string(),
+        };
+        let (data, len) = match self.expr_type(&args[0]) {
+            CType::Array(_, n) => (c_arg.clone(), CExpr::IntLit(n.to_string())),
+            _ => (field("data"), field("len")),
+        };
+        let slice = |ty: CType, data: CExpr, len: CExpr| CExpr::Compound {
+            ty,
+            fields: vec![("data".to_string(), data), ("len".to_string(), len)],
+        };
+
+        match op {
+            // len(s) -> s.len, len(a) -> N
+            ast::Builtin::Len => len,
+
+            // as_slice(a) -> (fc_slice_T){ .data = a, .len = N }
+            ast::Builtin::AsSlice => slice(self.expr_type(expr), data, len),
+
+            // subslice(s, start, end) -> if (start > end || end > s.len) fc_trap_at(...);
+            //   (fc_slice_T){ .data = s.data + start, .len = end - start }
+            ast::Builtin::Subslice => {
+                let start = self.lower_reused(&args[1], pre_stmts);
+                let end = self.lower_reused(&args[2], pre_stmts);
+                if !self.in_unsafe {
+                    // `subslice(s, i, len(s))` needs no `end > len` check
+                    let end_is_len = match (&args[0], &args[2]) {
+                        (
+                            ast::Expr::Ident { name, .. },
+                            ast::Expr::Builtin {
+                                op: ast::Builtin::Len,
+                                args: len_args,
+                                ..
+                            },
+                        ) => matches!(&len_args[0], ast::Expr::Ident { name: n, .. } if n == name),
+                        _ => false,
+                    };
+                    pre_stmts.push(checks::subslice_check(
+                        (start.clone(), &self.expr_type(&args[1])),
+                        (end.clone(), &self.expr_type(&args[2])),
+                        (!end_is_len).then_some(len.clone()),
+                        &self.trap_site(&expr.span()),
+                    ));
+                }
+                slice(
+                    self.expr_type(expr),
+                    CExpr::Binary {
+                        op: CBinOp::Add,
+                        lhs: Box::new(data),
+                        rhs: Box::new(start.clone()),
+                    },
+                    CExpr::Binary {
+                        op: CBinOp::Sub,
+                        lhs: Box::new(end),
+                        rhs: Box::new(start),
+                    },
+                )
+            }
+
+            // slice_from_raw(p, n) -> (fc_slice_T){ .data = (T*)p, .len = n }
+            ast::Builtin::SliceFromRaw => {
+                let c_len = self.lower_expr(&args[1], pre_stmts);
+                let ty = self.expr_type(expr);
+                let CType::Slice(elem) = &ty else {
+                    unreachable!("slice_from_raw is typed as a slice");
+                };
+                let data = CExpr::Cast {
+                    ty: CType::Ptr(elem.clone()),
+                    expr: Box::new(c_arg.clone()),
+                };
+                slice(ty, data, c_len)
+            }
+
+            _ => unreachable!("not a slice builtin"),
+        }
+    }
+
     fn lower_type(&self, ty: &ast::TypeExpr) -> CType {
         match ty {
             ast::TypeExpr::Primitive(p) => match p {
@@ -1110,24 +2384,53 @@ impl Lower {
 
     /// Lower a constant expression to C expression
     fn lower_const_expr(&self, expr: &ast::ConstExpr) -> CExpr {
+        self.lower_const_expr_in(expr, &mut Vec::new())
+    }
+
+    /// Lower a constant expression, folding references to other consts
+    ///
+    /// A `static const` object is not a C constant expression, so const
+    /// references in initializers and case labels are replaced by their
+    /// (cast) values. `visiting` guards against cyclic definitions.
+    fn lower_const_expr_in<'c>(
+        &'c self,
+        expr: &ast::ConstExpr,
+        visiting: &mut Vec<&'c str>,
+    ) -> CExpr {
         match expr {
             ast::ConstExpr::IntLit(n) => CExpr::IntLit(n.to_string()),
             ast::ConstExpr::FloatLit(n) => CExpr::FloatLit(n.to_string()),
             ast::ConstExpr::BoolLit(b) => CExpr::BoolLit(*b),
-            ast::ConstExpr::Ident(name) => CExpr::Ident(name.clone()),
+            ast::ConstExpr::Ident(name) => match self.consts.get_key_value(name) {
+                Some((key, (ty, value)))
+                    if !visiting.contains(&key.as_str())
+                        && !matches!(value, ast::ConstExpr::CStr(_) | ast::ConstExpr::Bytes(_)) =>
+                {
+                    visiting.push(key);
+                    let folded = self.lower_const_expr_in(value, visiting);
+                    visiting.pop();
+                    CExpr::Cast {
+                        ty: self.lower_type(ty),
+                        expr: Box::new(folded),
+                    }
+                }
+                _ => CExpr::Ident(name.clone()),
+            },
             ast::ConstExpr::Binary { op, lhs, rhs } => CExpr::Binary {
                 op: self.lower_binop(*op),
-                lhs: Box::new(self.lower_const_expr(lhs)),
-                rhs: Box::new(self.lower_const_expr(rhs)),
+                lhs: Box::new(self.lower_const_expr_in(lhs, visiting)),
+                rhs: Box::new(self.lower_const_expr_in(rhs, visiting)),
             },
             ast::ConstExpr::Unary { op, operand } => CExpr::Unary {
                 op: self.lower_unaryop(*op),
-                operand: Box::new(self.lower_const_expr(operand)),
+                operand: Box::new(self.lower_const_expr_in(operand, visiting)),
             },
-            ast::ConstExpr::Paren(inner) => CExpr::Paren(Box::new(self.lower_const_expr(inner))),
+            ast::ConstExpr::Paren(inner) => {
+                CExpr::Paren(Box::new(self.lower_const_expr_in(inner, visiting)))
+            }
             ast::ConstExpr::Cast { ty, expr } => CExpr::Cast {
                 ty: self.lower_type(ty),
-                expr: Box::new(self.lower_const_expr(expr)),
+                expr: Box::new(self.lower_const_expr_in(expr, visiting)),
             },
             ast::ConstExpr::CStr(s) => CExpr::StringLit(s.clone()),
             ast::ConstExpr::Bytes(s) => CExpr::StringLit(s.clone()),
@@ -1135,36 +2438,11 @@ impl Lower {
     }
 
     /// Evaluate a constant expression to a usize (for array sizes)
+    ///
+    /// The type checker reports sizes that do not evaluate, so none reach here.
     fn eval_const_size(&self, expr: &ast::ConstExpr) -> usize {
-        match expr {
-            ast::ConstExpr::IntLit(n) => {
-                if *n < 0 {
-                    panic!("array size cannot be negative");
-                }
-                *n as usize
-            }
-            ast::ConstExpr::Paren(inner) => self.eval_const_size(inner),
-            ast::ConstExpr::Binary { op, lhs, rhs } => {
-                let l = self.eval_const_size(lhs);
-                let r = self.eval_const_size(rhs);
-                match op {
-                    ast::BinOp::Add => l + r,
-                    ast::BinOp::Sub => l.saturating_sub(r),
-                    ast::BinOp::Mul => l * r,
-                    ast::BinOp::Div => l / r,
-                    _ => panic!("unsupported operator in array size"),
-                }
-            }
-            ast::ConstExpr::Unary { op, operand } => {
-                let v = self.eval_const_size(operand);
-                match op {
-                    ast::UnaryOp::Neg => panic!("array size cannot be negative"),
-                    _ => v,
-                }
-            }
-            // TODO: Support ConstExpr::Ident via symbol table lookup
-            _ => panic!("unsupported constant expression in array size"),
-        }
+        let lookup = |name: &str| self.consts.get(name).map(|(_, value)| value);
+        expr.eval_size(&lookup).unwrap_or(0)
     }
 
     fn lower_binop(&self, op: ast::BinOp) -> CBinOp {
@@ -1199,6 +2477,14 @@ impl Lower {
     }
 
     /// Check if a CType is a signed integer type
+    fn is_int_literal(expr: &ast::Expr) -> bool {
+        match expr {
+            ast::Expr::IntLit { .. } => true,
+            ast::Expr::Paren { inner, .. } => Self::is_int_literal(inner),
+            _ => false,
+        }
+    }
+
     fn is_signed_integer(ty: &CType) -> bool {
         matches!(
             ty,
@@ -1211,10 +2497,784 @@ impl Lower {
         self.temp_counter += 1;
         name
     }
+
+    fn fresh_label(&mut self, prefix: &str) -> String {
+        let name = format!("__{}{}", prefix, self.temp_counter);
+        self.temp_counter += 1;
+        name
+    }
 }
 
 impl Default for Lower {
     fn default() -> Self {
-        Self::new()
+        Self::new(TypeContext::new(), "<unknown>", "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::driver::compile;
+
+    fn lower_ok(source: &str) -> String {
+        match compile(source, "test.fc") {
+            Ok(c_code) => c_code,
+            Err(e) => panic!("Expected success for: {}\nGot error: {:?}", source, e),
+        }
+    }
+
+    /// Assert that each needle appears in `haystack` after the previous one
+    fn assert_in_order(haystack: &str, needles: &[&str]) {
+        let mut from = 0;
+        for needle in needles {
+            match haystack[from..].find(needle) {
+                Some(pos) => from += pos + needle.len(),
+                None => panic!(
+                    "Expected '{}' after offset {} in:\n{}",
+                    needle, from, haystack
+                ),
+            }
+        }
+    }
+
+    // === Defer tests ===
+
+    #[test]
+    fn test_defer_runs_at_scope_end() {
+        let c =
+            lower_ok("fn f(x: i32) -> void { return; } fn g() -> void { defer { f(1); } f(0); }");
+        assert_in_order(&c, &["void g(void) {", "f(0);", "f(1);", "}"]);
+    }
+
+    #[test]
+    fn test_defers_run_lifo() {
+        let c = lower_ok(
+            "fn f(x: i32) -> void { return; } fn g() -> void { defer { f(1); } defer { f(2); } }",
+        );
+        assert_in_order(&c, &["void g(void) {", "f(2);", "f(1);"]);
+    }
+
+    #[test]
+    fn test_defer_stages_return_value() {
+        let c = lower_ok(
+            "fn f(x: i32) -> void { return; } fn g(x: i32) -> i32 { defer { f(1); } return x; }",
+        );
+        assert_in_order(&c, &["int32_t __tmp0 = x;", "f(1);", "return __tmp0;"]);
+    }
+
+    #[test]
+    fn test_defer_runs_before_break_and_continue() {
+        let c = lower_ok(
+            "fn f(x: i32) -> void { return; }
+             fn g(b: bool) -> void {
+                 while (b) {
+                     defer { f(1); }
+                     if (b) { continue; }
+                     break;
+                 }
+             }",
+        );
+        assert_in_order(&c, &["f(1);", "continue;", "f(1);", "break;"]);
+    }
+
+    #[test]
+    fn test_break_does_not_run_outer_defers() {
+        let c = lower_ok(
+            "fn f(x: i32) -> void { return; }
+             fn g(b: bool) -> void {
+                 defer { f(1); }
+                 while (b) { defer { f(2); } break; }
+             }",
+        );
+        assert_in_order(&c, &["f(2);", "break;", "}", "f(1);"]);
+        assert_eq!(c.matches("f(1);").count(), 1);
+    }
+
+    // === For loop tests ===
+
+    #[test]
+    fn test_for_loop_maps_to_c_for() {
+        let c = lower_ok(
+            "fn f(x: i32) -> void { return; }
+             fn g(n: i32) -> void {
+                 unsafe { for (let i: i32 = 0; i < n; i = i + 1) { f(i); } }
+             }",
+        );
+        assert!(c.contains("for (int32_t i = 0; (i < n); i = (i + 1)) {"));
+    }
+
+    #[test]
+    fn test_for_loop_checked_step_runs_each_iteration() {
+        let c = lower_ok(
+            "fn f(x: i32) -> void { return; }
+             fn g(n: i32) -> void { for (let i: i32 = 0; i < n; i = i + 1) { f(i); } }",
+        );
+        assert_in_order(
+            &c,
+            &[
+                "int32_t i = 0;",
+                "for (;;) {",
+                "if ((!(i < n))) {",
+                "break;",
+                "f(i);",
+                "__builtin_add_overflow(i, 1",
+                "i = ",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_for_loop_continue_runs_step() {
+        let c = lower_ok(
+            "fn f(x: i32) -> void { return; }
+             fn g(n: i32) -> void {
+                 for (let i: i32 = 0; i < n; i = i + 1) {
+                     defer { f(0); }
+                     if (i == 1) { continue; }
+                     f(i);
+                 }
+             }",
+        );
+        assert_in_order(
+            &c,
+            &[
+                "f(0);",
+                "goto __continue",
+                "f(i);",
+                "f(0);",
+                "__continue",
+                ":",
+            ],
+        );
+        assert!(!c.contains("continue;"));
+    }
+
+    #[test]
+    fn test_for_loop_without_continue_has_no_label() {
+        let c = lower_ok("fn g(n: i32) -> void { for (let i: i32 = 0; i < n; i = i + 1) { } }");
+        assert!(!c.contains("__continue"));
+    }
+
+    // === Item tests ===
+
+    #[test]
+    fn test_const_is_static_const() {
+        let c = lower_ok("const LIMIT: i32 = 10; fn f() -> i32 { return LIMIT; }");
+        assert!(c.contains("static const int32_t LIMIT = 10;"));
+    }
+
+    #[test]
+    fn test_unused_const_is_omitted() {
+        let c = lower_ok("const LIMIT: i32 = 10; fn f() -> i32 { return 0; }");
+        assert!(!c.contains("LIMIT"));
+    }
+
+    #[test]
+    fn test_const_folded_in_constant_contexts() {
+        let c = lower_ok(
+            "const N: usize = 4;
+             const M: usize = (N * 2);
+             const K: i32 = 3;
+             fn f(a: arr(i32, M), x: i32) -> i32 {
+                 switch (x) { case K: return 1; default: return 0; }
+             }",
+        );
+        assert!(c.contains("int32_t a[8]"));
+        assert!(c.contains("case ((int32_t)3):"));
+    }
+
+    #[test]
+    fn test_cstr_and_bytes_consts() {
+        let c = lower_ok(
+            "const NAME: raw(u8) = cstr(\"fc\");
+             const DATA: slice(u8) = bytes(\"abc\");
+             fn f() -> void { discard(NAME); discard(DATA); }",
+        );
+        assert!(c.contains("static const uint8_t* const NAME = ((const uint8_t*)\"fc\");"));
+        assert_in_order(
+            &c,
+            &[
+                "static uint8_t __bytes0[3] = \"abc\";",
+                "static const fc_slice_uint8_t DATA = { .data = __bytes0, .len = 3 };",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_opaque_typedef() {
+        let c = lower_ok("opaque Handle; fn f(h: rawm(Handle)) -> void { return; }");
+        assert!(c.contains("typedef struct Handle Handle;"));
+        assert!(c.contains("void f(Handle* h)"));
+    }
+
+    #[test]
+    fn test_extern_prototypes_deduplicated() {
+        let c = lower_ok(
+            "extern \"C\" { unsafe fn ext_add(a: i32, b: i32) -> i32; unsafe fn free(p: rawm(u8)) -> void; }
+             extern \"C\" { unsafe fn ext_add(a: i32, b: i32) -> i32; }
+             fn f() -> i32 { unsafe { return ext_add(1, 2); } }",
+        );
+        assert_eq!(
+            c.matches("int32_t ext_add(int32_t a, int32_t b);").count(),
+            1
+        );
+        // Declared by <stdlib.h>
+        assert!(!c.contains("void free("));
+    }
+
+    #[test]
+    fn test_libc_externs_include_their_header() {
+        let c = lower_ok(
+            "extern \"C\" {
+                 unsafe fn memcpy(dest: rawm(u8), src: raw(u8), n: usize) -> rawm(u8);
+                 unsafe fn strlen(s: raw(u8)) -> usize;
+                 unsafe fn strchr(s: raw(u8), c: i32) -> raw(u8);
+             }
+             unsafe fn f(d: rawm(u8), s: raw(u8)) -> raw(u8) {
+                 discard(memcpy(d, s, strlen(s)));
+                 return strchr(s, 47);
+             }",
+        );
+        assert!(c.contains("#include <string.h>"));
+        assert!(!c.contains("memcpy(uint8_t*"));
+        assert!(!c.contains("size_t strlen("));
+        assert!(c.contains("= strlen(((const char*)s));"));
+        assert!(c.contains("return ((const uint8_t*)strchr(((const char*)s), 47));"));
+    }
+
+    #[test]
+    fn test_tagged_union_layout() {
+        let c = lower_ok(
+            "@repr(u8) enum Shape { Circle(i32), Rect(i32, i32), Empty }
+             fn f(s: Shape) -> void { return; }",
+        );
+        assert_in_order(
+            &c,
+            &[
+                "Shape_Circle,",
+                "Shape_Rect,",
+                "Shape_Empty",
+                "typedef struct Shape {",
+                "uint8_t tag;",
+                "struct {",
+                "int32_t _0;",
+                "} Circle;",
+                "int32_t _1;",
+                "} Rect;",
+                "} data;",
+                "} Shape;",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_variant_constructors() {
+        let c = lower_ok(
+            "enum Shape { Circle(i32), Empty }
+             fn f() -> Shape { return Shape_Circle(3); }
+             fn g() -> Shape { return Shape_Empty; }",
+        );
+        assert!(c.contains("(Shape){ .tag = Shape_Circle, .data.Circle._0 = 3 }"));
+        assert!(c.contains("(Shape){ .tag = Shape_Empty }"));
+    }
+
+    #[test]
+    fn test_match_binds_payload() {
+        let c = lower_ok(
+            "enum Shape { Circle(i32), Rect(i32, i32), Empty }
+             fn f(s: Shape) -> i32 {
+                 match (s) {
+                     case Shape_Circle(r): return r;
+                     case Shape_Rect(_, h): return h;
+                     case Shape_Empty: return 0;
+                 }
+             }",
+        );
+        assert_in_order(
+            &c,
+            &[
+                "Shape __tmp",
+                ".tag) {",
+                "case Shape_Circle:",
+                "int32_t r = ",
+                ".data.Circle._0;",
+                "case Shape_Rect:",
+                "int32_t h = ",
+                ".data.Rect._1;",
+                "case Shape_Empty:",
+                "default:",
+                "fc_trap_at(FC_TRAP_BAD_TAG, \"test.fc\", 3, 25);",
+            ],
+        );
+        assert!(!c.contains(".data.Rect._0"));
+    }
+
+    #[test]
+    fn test_match_default_only_switches_on_tag() {
+        let c = lower_ok(
+            "enum Shape { Circle(i32), Empty }
+             fn f(s: Shape) -> i32 {
+                 match (s) {
+                     default: return 1;
+                 }
+             }",
+        );
+        assert_in_order(
+            &c,
+            &["Shape __tmp0 = s;", "switch (__tmp0.tag) {", "default:"],
+        );
+    }
+
+    #[test]
+    fn test_field_access_through_ref_derefs() {
+        let c = lower_ok(
+            "struct Point { x: i32, y: i32 }
+             fn f(p: ref(Point)) -> i32 { return p.x; }
+             fn g(p: Point) -> i32 { return p.y; }",
+        );
+        assert!(c.contains("return (*p).x;"));
+        assert!(c.contains("return p.y;"));
+    }
+
+    #[test]
+    fn test_call_arg_temp_uses_arg_type() {
+        let c = lower_ok(
+            "fn big() -> i64 { return cast(i64, 1); }
+             fn id(x: i64) -> i64 { return x; }
+             fn f() -> i64 { return id(big()); }",
+        );
+        assert!(c.contains("int64_t __tmp0 = big();"));
+    }
+
+    #[test]
+    fn test_overflow_check_on_call_result() {
+        let c = lower_ok(
+            "fn big() -> i64 { return cast(i64, 1); }
+             fn f() -> i64 { return (big() + big()); }",
+        );
+        assert!(c.contains("int64_t __tmp0;"));
+        assert!(c.contains("__builtin_add_overflow(big(), big(), (&__tmp0))"));
+    }
+
+    #[test]
+    fn test_if_let_on_call_result() {
+        let c = lower_ok(
+            "struct Point { x: i32, y: i32 }
+             fn find() -> opt(Point) { return some(Point { x: 1, y: 2 }); }
+             fn f() -> i32 { if let p = unwrap_checked(find()) { return p.y; } return 0; }",
+        );
+        assert!(c.contains("fc_opt_Point __tmp"));
+        assert!(c.contains("Point p = __tmp"));
+        assert!(c.contains("((fc_opt_Point){ .has_value = true"));
+        assert!(!c.contains("fc_opt_void"));
+    }
+
+    #[test]
+    fn test_typedefs_follow_their_dependencies() {
+        let c = lower_ok(
+            "struct Point { x: i32, y: i32 }
+             struct Line { a: Point, b: Point }
+             fn f(l: Line, p: opt(Point)) -> void { return; }",
+        );
+        assert_in_order(&c, &["} Point;", "} fc_opt_Point;"]);
+        assert_in_order(&c, &["} Point;", "} Line;"]);
+    }
+
+    #[test]
+    fn test_res_typedef_of_c_type_names() {
+        let c = lower_ok("fn f(r: res(i32, bool)) -> bool { return true; }");
+        assert_in_order(
+            &c,
+            &[
+                "typedef struct fc_res_int32_t_bool {",
+                "bool is_ok;",
+                "int32_t ok;",
+                "bool err;",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_literal_beyond_int64_is_unsigned() {
+        let c = lower_ok("fn f() -> u64 { return 18446744073709551615; }");
+        assert!(c.contains("return 18446744073709551615u;"));
+    }
+
+    #[test]
+    fn test_ok_err_use_declared_res_type() {
+        let c = lower_ok(
+            "enum E { Bad }
+             fn f(x: i32) -> res(i32, E) { if (x < 0) { return err(E_Bad); } return ok(5); }",
+        );
+        assert!(c.contains("((fc_res_int32_t_E){ .is_ok = false, .err = E_Bad })"));
+        assert!(c.contains("((fc_res_int32_t_E){ .is_ok = true, .ok = 5 })"));
+        assert!(!c.contains("void"));
+    }
+
+    #[test]
+    fn test_allocation_passes_call_site() {
+        let c = lower_ok(
+            "extern \"C\" { unsafe fn fc_alloc(size: usize, align: usize) -> rawm(u8); }
+             fn f() -> rawm(u8) { unsafe { return fc_alloc(8, 8); } }",
+        );
+        assert!(c.contains("return fc_alloc_at(8, 8, \"test.fc\", 2, 51);"));
+    }
+
+    #[test]
+    fn test_traps_in_modules_name_their_file() {
+        // Instances of a std generic keep the file and lines of the template
+        let c = lower_ok(
+            "mod std; fn f(a: slice(u8), b: slice(u8)) -> usize { return slice_copy[u8](a, b); }",
+        );
+        assert!(c.contains("fc_trap_at(FC_TRAP_BOUNDS, \"<std>/slices.fc\", 14, "));
+        assert!(!c.contains("FC_TRAP_BOUNDS, \"test.fc\""));
+    }
+
+    // === opt/res builtin tests ===
+
+    #[test]
+    fn test_unwrap_traps_when_empty() {
+        let c = lower_ok("fn f(o: opt(i32)) -> i32 { return unwrap(o); }");
+        assert_in_order(
+            &c,
+            &[
+                "if ((!o.has_value))",
+                "fc_trap_at(FC_TRAP_UNWRAP, \"test.fc\", 1, 35);",
+                "return o.value;",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_unwrap_err_traps_on_ok() {
+        let c = lower_ok("fn f(r: res(i32, u8)) -> u8 { return unwrap_err(r); }");
+        assert_in_order(
+            &c,
+            &[
+                "if (r.is_ok)",
+                "fc_trap_at(FC_TRAP_UNWRAP, ",
+                "return r.err;",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_unwrap_or_evaluates_operand_once() {
+        let c = lower_ok(
+            "fn g() -> opt(i32) { return none(i32); }
+             fn f() -> i32 { return unwrap_or(g(), 3); }",
+        );
+        assert_in_order(
+            &c,
+            &[
+                "fc_opt_int32_t __tmp0 = g();",
+                "int32_t __tmp1 = 3;",
+                "if (__tmp0.has_value)",
+                "__tmp1 = __tmp0.value;",
+                "return __tmp1;",
+            ],
+        );
+        assert_eq!(c.matches("g()").count(), 1);
+    }
+
+    #[test]
+    fn test_from_raw_tests_for_null() {
+        let c = lower_ok("fn f(p: raw(i32)) -> opt(ref(i32)) { return from_raw(p); }");
+        assert!(c.contains("((fc_opt_cptr_int32_t){ .has_value = (p != NULL), .value = p })"));
+    }
+
+    // === Ownership tests ===
+
+    #[test]
+    fn test_own_move_clears_source() {
+        let c = lower_ok(
+            "fn take(p: own(i32)) -> void { return; }
+             fn f(p: own(i32)) -> void { let q: own(i32) = p; take(q); }",
+        );
+        assert_in_order(
+            &c,
+            &[
+                "int32_t* __tmp0 = p;",
+                "p = NULL;",
+                "int32_t* q = __tmp0;",
+                "int32_t* __tmp1 = q;",
+                "q = NULL;",
+                "take(__tmp1);",
+                "fc_free(q);",
+                "fc_free(p);",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_own_released_with_defers_in_lifo_order() {
+        let c = lower_ok(
+            "fn g(x: i32) -> void { return; }
+             fn f(p: own(i32)) -> i32 { defer { g(1); } let q: own(i32) = p; return 0; }",
+        );
+        assert_in_order(
+            &c,
+            &[
+                "int32_t __tmp1 = 0;",
+                "fc_free(q);",
+                "g(1);",
+                "fc_free(p);",
+                "return __tmp1;",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_own_released_through_drop_fn() {
+        let c = lower_ok(
+            "struct Buf { len: i32 }
+             @drop fn buf_drop(b: own(Buf)) -> void { b.len = 0; }
+             fn f(b: own(Buf)) -> void { return; }",
+        );
+        // The drop function frees its own parameter's storage
+        assert_in_order(
+            &c,
+            &["void buf_drop(Buf* b) {", "(*b).len = 0;", "fc_free(b);"],
+        );
+        assert_in_order(
+            &c,
+            &["void f(Buf* b) {", "if ((b != NULL)) {", "buf_drop(b);"],
+        );
+    }
+
+    #[test]
+    fn test_own_assign_releases_old_value() {
+        let c = lower_ok("fn f(p: own(i32), q: own(i32)) -> void { p = q; }");
+        assert_in_order(
+            &c,
+            &[
+                "int32_t* __tmp0 = q;",
+                "q = NULL;",
+                "int32_t* __tmp1 = __tmp0;",
+                "fc_free(p);",
+                "p = __tmp1;",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_if_let_releases_owned_binding() {
+        let c = lower_ok(
+            "fn f(r: opt(own(i32))) -> i32 {
+                 if let p = unwrap_checked(r) { let x: i32 = 1; }
+                 return 0;
+             }",
+        );
+        assert_in_order(
+            &c,
+            &[
+                "int32_t* p = __tmp0.value;",
+                "int32_t x = 1;",
+                "fc_free(p);",
+            ],
+        );
+    }
+
+    // === Shift check tests ===
+
+    #[test]
+    fn test_shift_checks_count_and_sign() {
+        let c = lower_ok("fn f(x: i32, n: i32) -> i32 { return (x << n); }");
+        assert!(
+            c.contains("if (((((n < 0) || (n >= 32)) || (x < 0)) || (x > (INT32_MAX >> n)))) {")
+        );
+        let c = lower_ok("fn f(x: u64, n: u64) -> u64 { return (x >> n); }");
+        assert!(c.contains("if ((n >= 64)) {"));
+    }
+
+    #[test]
+    fn test_shift_checks_skipped_in_unsafe() {
+        let c = lower_ok("fn f(x: i32, n: i32) -> i32 { unsafe { return (x << n); } }");
+        assert!(!c.contains("fc_trap"));
+    }
+
+    #[test]
+    fn test_shift_check_evaluates_count_once() {
+        let c = lower_ok(
+            "fn g() -> u32 { return 3; }
+             fn f(x: u32) -> u32 { return (x >> g()); }",
+        );
+        assert_in_order(
+            &c,
+            &[
+                "uint32_t __tmp0 = g();",
+                "if ((__tmp0 >= 32)) {",
+                "(x >> __tmp0)",
+            ],
+        );
+        assert_eq!(c.matches("g()").count(), 1);
+    }
+
+    // === Division and negation overflow tests ===
+
+    #[test]
+    fn test_signed_div_checks_min_by_minus_one() {
+        let c = lower_ok("fn f(a: i64, b: i64) -> i64 { return (a % b); }");
+        assert_in_order(
+            &c,
+            &[
+                "if ((b == 0)) {",
+                "if (((a == INT64_MIN) && (b == -1))) {",
+                "return (a % b);",
+            ],
+        );
+        let c = lower_ok("fn f(a: u32, b: u32) -> u32 { return (a / b); }");
+        assert!(c.contains("if ((b == 0)) {"));
+        assert!(!c.contains("_MIN"));
+    }
+
+    #[test]
+    fn test_neg_checks_min() {
+        let c = lower_ok("fn f(x: i8) -> i8 { return -x; }");
+        assert_in_order(&c, &["if ((x == INT8_MIN)) {", "return (-x);"]);
+        // Negative literals were range-checked by the typechecker
+        let c = lower_ok("fn f() -> i32 { return -5; }");
+        assert!(!c.contains("fc_trap"));
+    }
+
+    #[test]
+    fn test_div_and_neg_checks_skipped_in_unsafe() {
+        let c = lower_ok("fn f(a: i32, b: i32) -> i32 { unsafe { return (-a / b); } }");
+        assert!(!c.contains("fc_trap"));
+    }
+
+    // === Slice builtin tests ===
+
+    #[test]
+    fn test_bytes_literal_uses_static_array() {
+        let c = lower_ok("fn f() -> slice(u8) { return bytes(\"hi\"); }");
+        assert_in_order(
+            &c,
+            &[
+                "static uint8_t __bytes0[2] = \"hi\";",
+                "return ((fc_slice_uint8_t){ .data = __bytes0, .len = 2 });",
+            ],
+        );
+        assert!(!c.contains("TODO"));
+    }
+
+    #[test]
+    fn test_non_ascii_strings_use_octal_escapes() {
+        let c = lower_ok("fn f() -> slice(u8) { return bytes(\"é1\\\"\"); }");
+        assert!(c.contains("static uint8_t __bytes0[4] = \"\\303\\2511\\\"\";"));
+    }
+
+    #[test]
+    fn test_as_slice_and_len_of_arr() {
+        let c = lower_ok(
+            "fn f(a: arr(i32, 4)) -> i32 { let s: slice(i32) = as_slice(a); return at(s, 0); }
+             fn g(a: arr(i32, 4)) -> usize { return len(a); }",
+        );
+        assert!(c.contains("fc_slice_int32_t s = ((fc_slice_int32_t){ .data = a, .len = 4 });"));
+        assert!(c.contains("return 4;"));
+    }
+
+    #[test]
+    fn test_subslice_checks_bounds() {
+        let c = lower_ok(
+            "fn f(s: slice(i32), i: usize, j: usize) -> slice(i32) { return subslice(s, i, j); }",
+        );
+        assert_in_order(
+            &c,
+            &[
+                "if (((i > j) || (j > s.len))) {",
+                "fc_trap_at(FC_TRAP_BOUNDS, ",
+                "{ .data = (s.data + i), .len = (j - i) }",
+            ],
+        );
+        let c = lower_ok(
+            "fn f(s: slice(i32), i: i32) -> slice(i32) { return subslice(s, i, len(s)); }",
+        );
+        assert!(c.contains("if (((i < 0) || (i > s.len))) {"));
+        let c = lower_ok(
+            "fn f(s: slice(i32), i: usize) -> slice(i32) { unsafe { return subslice(s, i, len(s)); } }",
+        );
+        assert!(!c.contains("fc_trap"));
+    }
+
+    #[test]
+    fn test_slice_from_raw_builds_slice() {
+        let c = lower_ok(
+            "unsafe fn f(p: raw(u16), n: usize) -> slice(u16) { return slice_from_raw(p, n); }",
+        );
+        assert!(c.contains("{ .data = ((uint16_t*)p), .len = n }"));
+    }
+
+    // === Array bounds check tests ===
+
+    #[test]
+    fn test_array_index_checked_against_length() {
+        let c = lower_ok("fn f(a: arr(i32, 4), i: usize) -> i32 { return at(a, i); }");
+        assert_in_order(
+            &c,
+            &[
+                "if ((i >= 4)) {",
+                "fc_trap_at(FC_TRAP_BOUNDS, ",
+                "return a[i];",
+            ],
+        );
+        let c = lower_ok("fn f(a: arr(i32, 4), i: i32) -> i32 { return at(a, i); }");
+        assert!(c.contains("if (((i < 0) || (i >= 4))) {"));
+    }
+
+    #[test]
+    fn test_array_index_check_skipped_in_unsafe() {
+        let c = lower_ok("fn f(a: arr(i32, 4), i: usize) -> i32 { unsafe { return at(a, i); } }");
+        assert!(!c.contains("fc_trap"));
+    }
+
+    #[test]
+    fn test_constant_array_index_not_checked() {
+        let c = lower_ok(
+            "const LAST: usize = 3;
+             fn f(a: arr(i32, 4)) -> i32 { return (at(a, 0) + at(a, (LAST - 1))); }",
+        );
+        assert!(!c.contains(">= 4"));
+    }
+
+    #[test]
+    fn test_for_counter_index_not_checked() {
+        let c = lower_ok(
+            "const N: usize = 4;
+             fn f(a: arr(i32, 4)) -> void {
+                 for (let i: usize = 0; i < N; i = (i + 1)) { at(a, i) = 0; }
+                 for (let j: i32 = 1; j < 3; j = (j + 1)) { at(a, j) = 0; }
+             }",
+        );
+        assert!(!c.contains(">= 4"));
+    }
+
+    #[test]
+    fn test_loose_for_counter_index_checked() {
+        // Bound past the length, counter written in the body, negative start
+        for source in [
+            "fn f(a: arr(i32, 4)) -> void { for (let i: usize = 0; i < 5; i = (i + 1)) { at(a, i) = 0; } }",
+            "fn f(a: arr(i32, 4)) -> void { for (let i: usize = 0; i < 4; i = (i + 1)) { i = 7; at(a, i) = 0; } }",
+            "fn f(a: arr(i32, 4), s: i32) -> void { for (let i: i32 = s; i < 4; i = (i + 1)) { at(a, i) = 0; } }",
+        ] {
+            let c = lower_ok(source);
+            assert!(c.contains(">= 4)"), "expected a bounds check in:\n{}", c);
+        }
+    }
+
+    #[test]
+    fn test_array_index_evaluated_once() {
+        let c = lower_ok(
+            "fn g() -> usize { return 1; }
+             fn f(a: arr(i32, 4)) -> i32 { return at(a, g()); }",
+        );
+        assert_in_order(
+            &c,
+            &[
+                "size_t __tmp0 = g();",
+                "if ((__tmp0 >= 4)) {",
+                "return a[__tmp0];",
+            ],
+        );
+        assert_eq!(c.matches("g()").count(), 1);
     }
 }
@@ -0,0 +1,140 @@
+//! Lexical scope tracking for `defer` and `own(T)` cleanup
+
+use crate::ast::Block;
+
+/// Kind of lexical scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeKind {
+    /// A braced block (function body, if/else branch, unsafe block, ...)
+    Block,
+    /// Loop marker: target of `break` and `continue`
+    Loop,
+    /// Switch marker: target of `break`
+    Switch,
+}
+
+/// Work registered in a scope that runs when the scope exits
+#[derive(Debug, Clone)]
+pub enum Cleanup {
+    /// A `defer` block
+    Defer(Block),
+    /// Release an `own(T)` local, through the type's `@drop` function if it
+    /// has one and `fc_free` otherwise
+    Drop {
+        name: String,
+        drop_fn: Option<String>,
+    },
+}
+
+/// A lexical scope with its pending cleanups
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    /// Cleanups in registration order (run in reverse)
+    pub cleanups: Vec<Cleanup>,
+    /// Label `continue` jumps to instead of a plain C `continue` (loops only)
+    pub continue_label: Option<String>,
+    /// Whether a `continue` was lowered to a jump to `continue_label`
+    pub continue_used: bool,
+}
+
+/// Stack of lexical scopes for the function being lowered
+#[derive(Debug, Default)]
+pub struct ScopeStack {
+    scopes: Vec<Scope>,
+}
+
+impl ScopeStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enter a new scope
+    pub fn push(&mut self, kind: ScopeKind) {
+        self.scopes.push(Scope {
+            kind,
+            cleanups: Vec::new(),
+            continue_label: None,
+            continue_used: false,
+        });
+    }
+
+    /// Enter a loop marker whose `continue` jumps to `label` when set
+    pub fn push_loop(&mut self, label: Option<String>) {
+        self.push(ScopeKind::Loop);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.continue_label = label;
+        }
+    }
+
+    /// Exit the innermost scope
+    pub fn pop(&mut self) -> Option<Scope> {
+        self.scopes.pop()
+    }
+
+    /// Register a deferred block in the innermost scope
+    pub fn defer(&mut self, block: Block) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.cleanups.push(Cleanup::Defer(block));
+        }
+    }
+
+    /// Register an `own(T)` local to release when the innermost scope exits
+    pub fn own(&mut self, name: String, drop_fn: Option<String>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.cleanups.push(Cleanup::Drop { name, drop_fn });
+        }
+    }
+
+    /// Number of scopes currently open
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Depth to unwind to for a `break` (innermost loop or switch)
+    pub fn break_depth(&self) -> usize {
+        self.scopes
+            .iter()
+            .rposition(|s| matches!(s.kind, ScopeKind::Loop | ScopeKind::Switch))
+            .map(|i| i + 1)
+            .unwrap_or(0)
+    }
+
+    /// Depth to unwind to for a `continue` (innermost loop)
+    pub fn continue_depth(&self) -> usize {
+        self.scopes
+            .iter()
+            .rposition(|s| s.kind == ScopeKind::Loop)
+            .map(|i| i + 1)
+            .unwrap_or(0)
+    }
+
+    /// Label of the innermost loop's `continue` target, if it needs a jump
+    ///
+    /// Marks the label as used so the loop knows to emit it.
+    pub fn continue_label(&mut self) -> Option<String> {
+        let scope = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find(|s| s.kind == ScopeKind::Loop)?;
+        scope.continue_used |= scope.continue_label.is_some();
+        scope.continue_label.clone()
+    }
+
+    /// Cleanups to run when unwinding down to `depth`, in LIFO order
+    pub fn pending_cleanups(&self, depth: usize) -> Vec<Cleanup> {
+        self.scopes[depth.min(self.scopes.len())..]
+            .iter()
+            .rev()
+            .flat_map(|s| s.cleanups.iter().rev().cloned())
+            .collect()
+    }
+
+    /// Check whether any cleanup is pending above `depth`
+    pub fn has_cleanups(&self, depth: usize) -> bool {
+        self.scopes[depth.min(self.scopes.len())..]
+            .iter()
+            .any(|s| !s.cleanups.is_empty())
+    }
+}
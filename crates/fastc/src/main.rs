@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use miette::{IntoDiagnostic, Result};
 use std::path::PathBuf;
 
@@ -84,6 +84,148 @@ enum CliReportFormat {
     Compact,
     /// Human-readable text
     Text,
+    /// SARIF 2.1.0 log of the violations
+    Sarif,
+}
+
+/// Diagnostic output format
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+enum CliMessageFormat {
+    /// Rendered source snippets on stderr (default)
+    #[default]
+    Human,
+    /// One JSON object per diagnostic per line
+    Json,
+    /// A SARIF 2.1.0 log
+    Sarif,
+}
+
+impl From<CliMessageFormat> for fastc::diag::MessageFormat {
+    fn from(format: CliMessageFormat) -> Self {
+        match format {
+            CliMessageFormat::Human => fastc::diag::MessageFormat::Human,
+            CliMessageFormat::Json => fastc::diag::MessageFormat::Json,
+            CliMessageFormat::Sarif => fastc::diag::MessageFormat::Sarif,
+        }
+    }
+}
+
+/// Lint level overrides, applied as -W, then -A, then -D
+#[derive(Args)]
+struct LintArgs {
+    /// Report a lint or P10 code as a warning (`warnings` names every code)
+    #[arg(short = 'W', long = "warn", value_name = "CODE")]
+    warn: Vec<String>,
+
+    /// Silence a lint or P10 code
+    #[arg(short = 'A', long = "allow", value_name = "CODE")]
+    allow: Vec<String>,
+
+    /// Report a lint or P10 code as an error
+    #[arg(short = 'D', long = "deny", value_name = "CODE")]
+    deny: Vec<String>,
+}
+
+impl LintArgs {
+    /// Build the P10 config for a check or compile, rejecting unknown codes
+    fn config(self, safety_level: CliSafetyLevel, strict: bool) -> Result<fastc::P10Config> {
+        use fastc::lint::Level;
+
+        let mut config = fastc::P10Config::from_level(safety_level.into());
+        if strict {
+            config.strict_mode = true;
+        }
+        let levels = [
+            (self.warn, Level::Warn),
+            (self.allow, Level::Allow),
+            (self.deny, Level::Deny),
+        ];
+        for (codes, level) in levels {
+            for code in codes {
+                if !fastc::lint::is_known(&code) {
+                    let known = fastc::lint::LINTS.iter().map(|(name, _)| *name);
+                    let hint = match fastc::diag::find_similar(&code, known) {
+                        Some(name) => format!(" (did you mean `{}`?)", name),
+                        None => String::new(),
+                    };
+                    miette::bail!("unknown lint code `{}`{}", code, hint);
+                }
+                config.lints.set(code, level);
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Print the explanation of `code`, or list every error code
+fn explain(code: Option<&str>) -> Result<()> {
+    use fastc::diag::ErrorCode;
+
+    let Some(code) = code else {
+        for code in ErrorCode::ALL {
+            println!("{}  {}", code, code.title());
+        }
+        return Ok(());
+    };
+
+    if let Some(code) = ErrorCode::parse(code) {
+        print!("{}", code.explanation());
+        return Ok(());
+    }
+    if let Some((name, description)) = fastc::lint::LINTS.iter().find(|(name, _)| *name == code) {
+        println!("`{}` is a lint: {}.", name, description);
+        println!("Set its level with -A, -W, or -D; see `fastc check --help`.");
+        return Ok(());
+    }
+    if fastc::lint::is_known(code) {
+        println!("`{}` is a Power of 10 rule; see `fastc p10-rules`.", code);
+        return Ok(());
+    }
+
+    let known = ErrorCode::ALL.iter().map(|c| c.as_str());
+    let hint = match fastc::diag::find_similar(code, known) {
+        Some(name) => format!(" (did you mean `{}`?)", name),
+        None => String::new(),
+    };
+    miette::bail!("unknown error code `{}`{}", code, hint);
+}
+
+/// Reporter for `format`, writing machine-readable output to stdout, or
+/// to stderr when stdout already carries the generated C
+fn reporter(format: CliMessageFormat, stdout_taken: bool) -> fastc::diag::Reporter {
+    let out: Box<dyn std::io::Write> = if stdout_taken {
+        Box::new(std::io::stderr())
+    } else {
+        Box::new(std::io::stdout())
+    };
+    fastc::diag::Reporter::new(format.into(), out)
+}
+
+/// Report warnings
+fn report_warnings(
+    reporter: &mut fastc::diag::Reporter,
+    warnings: Vec<fastc::diag::CompileError>,
+    file: &str,
+) -> Result<()> {
+    for warning in warnings {
+        reporter.report(warning, file).into_diagnostic()?;
+    }
+    Ok(())
+}
+
+/// Fail with `error`: human output hands it back for miette to render,
+/// machine-readable output reports it and exits with status 1
+fn fail(
+    mut reporter: fastc::diag::Reporter,
+    error: fastc::diag::CompileError,
+    file: &str,
+) -> Result<()> {
+    if reporter.format() == fastc::diag::MessageFormat::Human {
+        return Err(error.into());
+    }
+    reporter.report(error, file).into_diagnostic()?;
+    reporter.finish().into_diagnostic()?;
+    std::process::exit(1);
 }
 
 #[derive(Subcommand)]
@@ -101,6 +243,14 @@ enum Commands {
         #[arg(long)]
         emit_header: bool,
 
+        /// Emit #line directives so C compilers and debuggers report .fc lines
+        #[arg(long)]
+        line_directives: bool,
+
+        /// Write a JSON source map from C lines to .fc lines
+        #[arg(long, value_name = "FILE")]
+        source_map: Option<PathBuf>,
+
         /// Enable Power of 10 safety-critical rules (enabled by default)
         #[arg(long, hide = true)]
         p10: bool,
@@ -112,6 +262,13 @@ enum Commands {
         /// Treat all warnings as errors (strict mode)
         #[arg(long)]
         strict: bool,
+
+        #[command(flatten)]
+        lints: LintArgs,
+
+        /// Diagnostic output format: human (default), json, or sarif
+        #[arg(long, value_enum, default_value = "human")]
+        message_format: CliMessageFormat,
     },
 
     /// Type-check a FastC source file without emitting C
@@ -130,6 +287,13 @@ enum Commands {
         /// Treat all warnings as errors (strict mode)
         #[arg(long)]
         strict: bool,
+
+        #[command(flatten)]
+        lints: LintArgs,
+
+        /// Diagnostic output format: human (default), json, or sarif
+        #[arg(long, value_enum, default_value = "human")]
+        message_format: CliMessageFormat,
     },
 
     /// List Power of 10 rules and their status
@@ -139,13 +303,19 @@ enum Commands {
         safety_level: CliSafetyLevel,
     },
 
+    /// Explain an error code (e.g. E0201), or list every code
+    Explain {
+        /// Error code to explain
+        code: Option<String>,
+    },
+
     /// Generate compliance certification report (for AI agents and audits)
     CertReport {
         /// Input FastC source file(s)
         #[arg(required = true)]
         inputs: Vec<PathBuf>,
 
-        /// Output format: json (default), text, or compact
+        /// Output format: json (default), text, compact, or sarif
         #[arg(long, value_enum, default_value = "json")]
         format: CliReportFormat,
 
@@ -184,6 +354,23 @@ enum Commands {
         check: bool,
     },
 
+    /// Apply machine-applicable fixes to a FastC source file, then format it
+    Fix {
+        /// Input FastC source file
+        input: PathBuf,
+
+        /// Output file (use - for stdout, omit to fix in place)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Safety level: standard (default), critical (strictest), relaxed (no P10 checks)
+        #[arg(long, value_enum, default_value = "standard")]
+        safety_level: CliSafetyLevel,
+
+        #[command(flatten)]
+        lints: LintArgs,
+    },
+
     /// Create a new FastC project
     New {
         /// Project name
@@ -234,6 +421,10 @@ enum Commands {
         /// Additional flags to pass to the C compiler
         #[arg(long)]
         cflags: Option<String>,
+
+        /// Diagnostic output format: human (default), json, or sarif
+        #[arg(long, value_enum, default_value = "human")]
+        message_format: CliMessageFormat,
     },
 
     /// Build, compile, and run the project
@@ -267,19 +458,40 @@ fn main() -> Result<()> {
             input,
             output,
             emit_header,
+            line_directives,
+            source_map,
             p10: _,
             safety_level,
             strict,
+            lints,
+            message_format,
         } => {
             let source = std::fs::read_to_string(&input).into_diagnostic()?;
             let filename = input.display().to_string();
+            let mut reporter = reporter(message_format, output == "-");
 
             // P10 rules are always enabled (use --safety-level=relaxed to disable)
-            let mut config = fastc::P10Config::from_level(safety_level.into());
-            if strict {
-                config.strict_mode = true;
+            let config = lints.config(safety_level, strict)?;
+            let options = fastc::EmitOptions {
+                emit_header,
+                line_directives,
+                source_map: source_map.is_some(),
+            };
+            let fastc::CompileOutput {
+                c_code,
+                header,
+                source_map: map,
+                warnings,
+            } = match fastc::compile_with_emit_options(&source, &filename, options, config) {
+                Ok(output) => output,
+                Err(e) => return fail(reporter, e, &filename),
+            };
+            report_warnings(&mut reporter, warnings, &filename)?;
+            reporter.finish().into_diagnostic()?;
+
+            if let (Some(path), Some(map)) = (&source_map, map) {
+                std::fs::write(path, map.to_json()).into_diagnostic()?;
             }
-            let (c_code, header) = fastc::compile_with_p10(&source, &filename, emit_header, config)?;
 
             if output == "-" {
                 println!("{}", c_code);
@@ -296,17 +508,25 @@ fn main() -> Result<()> {
             }
         }
 
-        Commands::Check { input, p10: _, safety_level, strict } => {
+        Commands::Check { input, p10: _, safety_level, strict, lints, message_format } => {
             let source = std::fs::read_to_string(&input).into_diagnostic()?;
             let filename = input.display().to_string();
+            let mut reporter = reporter(message_format, false);
 
             // P10 rules are always enabled (use --safety-level=relaxed to disable)
-            let mut config = fastc::P10Config::from_level(safety_level.into());
-            if strict {
-                config.strict_mode = true;
+            let config = lints.config(safety_level, strict)?;
+            let warnings = match fastc::check_with_p10(&source, &filename, config) {
+                Ok(warnings) => warnings,
+                Err(e) => return fail(reporter, e, &filename),
+            };
+            let count = warnings.len();
+            report_warnings(&mut reporter, warnings, &filename)?;
+            reporter.finish().into_diagnostic()?;
+            match count {
+                0 => eprintln!("No errors found."),
+                1 => eprintln!("No errors found (1 warning)."),
+                n => eprintln!("No errors found ({} warnings).", n),
             }
-            fastc::check_with_p10(&source, &filename, config)?;
-            eprintln!("No errors found.");
         }
 
         Commands::P10Rules { safety_level } => {
@@ -315,6 +535,8 @@ fn main() -> Result<()> {
             checker.print_rules_summary();
         }
 
+        Commands::Explain { code } => explain(code.as_deref())?,
+
         Commands::CertReport {
             inputs,
             format,
@@ -328,6 +550,7 @@ fn main() -> Result<()> {
             let checker = fastc::P10Checker::new(config.clone());
 
             let mut file_reports = Vec::new();
+            let mut records = Vec::new();
             let mut any_non_compliant = false;
 
             for input in &inputs {
@@ -345,6 +568,9 @@ fn main() -> Result<()> {
 
                 let function_count = ast.items.iter().filter(|item| matches!(item, fastc::Item::Fn(_))).count();
                 let violations = checker.check(&ast, &source);
+                records.extend(violations.iter().map(|v| {
+                    fastc::diag::DiagnosticRecord::from_violation(v, &filename, &source)
+                }));
 
                 let report = fastc::ComplianceReport::new(
                     &filename,
@@ -362,6 +588,9 @@ fn main() -> Result<()> {
             }
 
             // Generate output
+            let sarif_log = || {
+                serde_json::to_string_pretty(&fastc::diag::to_sarif(&records)).unwrap_or_default()
+            };
             let output_text = if project {
                 let project_report = fastc::ProjectReport::from_files(
                     project_name,
@@ -381,6 +610,7 @@ fn main() -> Result<()> {
                             project_report.summary.files_compliant));
                         text
                     }
+                    CliReportFormat::Sarif => sarif_log(),
                 }
             } else if file_reports.len() == 1 {
                 let report = &file_reports[0];
@@ -388,6 +618,7 @@ fn main() -> Result<()> {
                     CliReportFormat::Json => report.to_json(),
                     CliReportFormat::Compact => report.to_json_compact(),
                     CliReportFormat::Text => report.to_text(),
+                    CliReportFormat::Sarif => sarif_log(),
                 }
             } else {
                 // Multiple files without project flag - output as JSON array
@@ -397,6 +628,7 @@ fn main() -> Result<()> {
                     CliReportFormat::Text => {
                         file_reports.iter().map(|r| r.to_text()).collect::<Vec<_>>().join("\n\n")
                     }
+                    CliReportFormat::Sarif => sarif_log(),
                 }
             };
 
@@ -451,6 +683,31 @@ fn main() -> Result<()> {
             }
         }
 
+        Commands::Fix {
+            input,
+            output,
+            safety_level,
+            lints,
+        } => {
+            let source = std::fs::read_to_string(&input).into_diagnostic()?;
+            let filename = input.display().to_string();
+
+            let config = lints.config(safety_level, false)?;
+            let fastc::FixOutput { source: fixed, applied } =
+                fastc::fix(&source, &filename, config)?;
+
+            match output.as_deref() {
+                Some("-") => print!("{}", fixed),
+                Some(path) => std::fs::write(path, &fixed).into_diagnostic()?,
+                None => std::fs::write(&input, &fixed).into_diagnostic()?,
+            }
+            match applied {
+                0 => eprintln!("No fixes to apply."),
+                1 => eprintln!("Applied 1 fix."),
+                n => eprintln!("Applied {} fixes.", n),
+            }
+        }
+
         Commands::New {
             name,
             r#type,
@@ -479,6 +736,7 @@ fn main() -> Result<()> {
             cc,
             compiler,
             cflags,
+            message_format,
         } => {
             let current_dir = std::env::current_dir().into_diagnostic()?;
             let mut ctx =
@@ -489,9 +747,10 @@ fn main() -> Result<()> {
                 .map_err(|e| miette::miette!("{}", e))?;
 
             // Compile the project to C
-            let c_file = ctx
-                .compile(&output, release)
-                .map_err(|e| miette::miette!("{}", e))?;
+            let mut reporter = reporter(message_format, false);
+            let c_file = ctx.compile(&output, release, &mut reporter);
+            reporter.finish().into_diagnostic()?;
+            let c_file = c_file.map_err(|e| miette::miette!("{}", e))?;
 
             // Optionally compile with C compiler
             if cc {
@@ -521,7 +780,7 @@ fn main() -> Result<()> {
             // Compile the project to C
             let output = PathBuf::from("build");
             let c_file = ctx
-                .compile(&output, release)
+                .compile(&output, release, &mut fastc::diag::Reporter::human())
                 .map_err(|e| miette::miette!("{}", e))?;
 
             // Compile with C compiler
@@ -0,0 +1,126 @@
+//! Deterministic C names for generic instances
+
+use crate::ast::{BinOp, ConstExpr, TypeExpr, UnaryOp};
+
+/// C name of `generic[args]`: `Pair[i32, opt(u8)]` becomes `Pair__i32__opt_u8`
+/// and `Pair[i32, Point]` becomes `Pair__i32__5_Point`
+pub fn mangle(generic: &str, args: &[TypeExpr]) -> String {
+    let mut name = generic.to_string();
+    for arg in args {
+        name.push_str("__");
+        name.push_str(&mangle_type(arg));
+    }
+    name
+}
+
+/// Identifier-safe spelling of a type. Struct and enum names are
+/// length-prefixed (`5_Point`), so a name containing `_` never reads as
+/// several components and never matches a built-in spelling like `opt_u8`.
+fn mangle_type(ty: &TypeExpr) -> String {
+    match ty {
+        TypeExpr::Primitive(p) => p.name().to_string(),
+        TypeExpr::Named(name) => length_prefixed(name),
+        TypeExpr::Generic(name, args) => length_prefixed(&mangle(name, args)),
+        TypeExpr::Ref(inner) => format!("ref_{}", mangle_type(inner)),
+        TypeExpr::Mref(inner) => format!("mref_{}", mangle_type(inner)),
+        TypeExpr::Raw(inner) => format!("raw_{}", mangle_type(inner)),
+        TypeExpr::Rawm(inner) => format!("rawm_{}", mangle_type(inner)),
+        TypeExpr::Own(inner) => format!("own_{}", mangle_type(inner)),
+        TypeExpr::Slice(inner) => format!("slice_{}", mangle_type(inner)),
+        TypeExpr::Arr(inner, size) => format!("arr_{}_{}", mangle_type(inner), mangle_size(size)),
+        TypeExpr::Opt(inner) => format!("opt_{}", mangle_type(inner)),
+        TypeExpr::Res(ok, err) => format!("res_{}_{}", mangle_type(ok), mangle_type(err)),
+        TypeExpr::Fn {
+            is_unsafe,
+            params,
+            ret,
+        } => {
+            let mut name = if *is_unsafe { "unsafe_fn" } else { "fn" }.to_string();
+            for param in params {
+                name.push('_');
+                name.push_str(&mangle_type(param));
+            }
+            name.push_str("_to_");
+            name.push_str(&mangle_type(ret));
+            name
+        }
+        TypeExpr::Void => "void".to_string(),
+        TypeExpr::Error => "error".to_string(),
+    }
+}
+
+/// `3_abc`
+fn length_prefixed(s: &str) -> String {
+    format!("{}_{}", s.len(), s)
+}
+
+/// Array sizes are mangled by their literal value. A constant name is
+/// length-prefixed (`N` becomes `e_1_N`) and any other size expression is
+/// spelled out in full (`(N + 1)` becomes `e_add_1_N_1_1`), so distinct
+/// expressions never share a name.
+fn mangle_size(size: &ConstExpr) -> String {
+    match size {
+        ConstExpr::IntLit(n) if *n >= 0 => n.to_string(),
+        ConstExpr::Ident(name) => format!("e_{}", length_prefixed(name)),
+        ConstExpr::Paren(inner) => mangle_size(inner),
+        _ => format!("e_{}", mangle_const(size)),
+    }
+}
+
+/// Prefix spelling of a constant expression. Operands are length-prefixed
+/// (`3_abc`), which keeps the spelling unambiguous when they contain `_`.
+fn mangle_const(expr: &ConstExpr) -> String {
+    let operand = |expr: &ConstExpr| length_prefixed(&mangle_const(expr));
+    match expr {
+        ConstExpr::IntLit(n) if *n < 0 => format!("m{}", n.unsigned_abs()),
+        ConstExpr::IntLit(n) => n.to_string(),
+        ConstExpr::Ident(name) => name.clone(),
+        ConstExpr::Paren(inner) => mangle_const(inner),
+        ConstExpr::Binary { op, lhs, rhs } => {
+            format!("{}_{}_{}", binop_name(*op), operand(lhs), operand(rhs))
+        }
+        ConstExpr::Unary { op, operand: inner } => {
+            let name = match op {
+                UnaryOp::Neg => "neg",
+                UnaryOp::Not => "not",
+                UnaryOp::BitNot => "bitnot",
+            };
+            format!("{}_{}", name, operand(inner))
+        }
+        ConstExpr::Cast { ty, expr } => {
+            format!(
+                "cast_{}_{}",
+                length_prefixed(&mangle_type(ty)),
+                operand(expr)
+            )
+        }
+        // Not integers, so never a valid size
+        ConstExpr::FloatLit(_)
+        | ConstExpr::BoolLit(_)
+        | ConstExpr::CStr(_)
+        | ConstExpr::Bytes(_) => "expr".to_string(),
+    }
+}
+
+fn binop_name(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "add",
+        BinOp::Sub => "sub",
+        BinOp::Mul => "mul",
+        BinOp::Div => "div",
+        BinOp::Rem => "rem",
+        BinOp::Eq => "eq",
+        BinOp::Ne => "ne",
+        BinOp::Lt => "lt",
+        BinOp::Le => "le",
+        BinOp::Gt => "gt",
+        BinOp::Ge => "ge",
+        BinOp::And => "and",
+        BinOp::Or => "or",
+        BinOp::BitAnd => "bitand",
+        BinOp::BitOr => "bitor",
+        BinOp::BitXor => "bitxor",
+        BinOp::Shl => "shl",
+        BinOp::Shr => "shr",
+    }
+}
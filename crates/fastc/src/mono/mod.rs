@@ -0,0 +1,748 @@
+//! Monomorphization pass
+//!
+//! Generic functions and structs are templates. This pass replaces every
+//! instance (`max[i32](a, b)`, `Pair[i32, bool]`) with a concrete copy under
+//! a deterministic mangled name and drops the templates, so name resolution,
+//! type checking, and lowering only ever see concrete code. A generic body
+//! is therefore checked once per instantiation.
+
+mod mangle;
+
+pub use mangle::*;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::ast::{
+    Block, ElseBranch, Expr, ExternItem, File, FnDecl, ForInit, ForStep, Item, ModDecl, SourceFile,
+    Stmt, StructDecl, TypeExpr,
+};
+use crate::diag::{CompileError, ErrorCode, find_similar};
+use crate::lexer::Span;
+
+/// Instances nested deeper than this are reported as unbounded recursion
+const MAX_INSTANCE_DEPTH: usize = 64;
+
+/// An instance waiting to be generated
+struct Pending {
+    generic: String,
+    args: Vec<TypeExpr>,
+    mangled: String,
+    is_struct: bool,
+    depth: usize,
+}
+
+/// Monomorphizer
+pub struct Monomorphizer<'a> {
+    source: &'a str,
+    generic_fns: HashMap<String, FnDecl>,
+    generic_structs: HashMap<String, StructDecl>,
+    /// Files of the templates declared in loaded modules, which their
+    /// instances' spans point into
+    generic_files: HashMap<String, SourceFile>,
+    /// Mangled names of every instance created so far
+    instances: HashSet<String>,
+    queue: VecDeque<Pending>,
+    /// Type parameter bindings of the instance being generated
+    subst: HashMap<String, TypeExpr>,
+    depth: usize,
+    errors: Vec<CompileError>,
+}
+
+impl<'a> Monomorphizer<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            generic_fns: HashMap::new(),
+            generic_structs: HashMap::new(),
+            generic_files: HashMap::new(),
+            instances: HashSet::new(),
+            queue: VecDeque::new(),
+            subst: HashMap::new(),
+            depth: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Replace generic items in `file` with the instances it uses
+    pub fn monomorphize(&mut self, file: &mut File) -> Result<(), CompileError> {
+        self.take_generics(&mut file.items, None);
+
+        for item in &mut file.items {
+            self.rewrite_item(item);
+        }
+
+        // Instances are appended in creation order, which keeps output stable
+        while let Some(pending) = self.queue.pop_front() {
+            if let Some(item) = self.instantiate(pending) {
+                file.items.push(item);
+            }
+        }
+
+        if !self.errors.is_empty() {
+            Err(CompileError::multiple(std::mem::take(&mut self.errors)))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Move generic templates out of `items`, including module bodies;
+    /// `file` is the loaded module file they come from, if any
+    fn take_generics(&mut self, items: &mut Vec<Item>, file: Option<&SourceFile>) {
+        let mut kept = Vec::with_capacity(items.len());
+        for mut item in items.drain(..) {
+            match item {
+                Item::Fn(decl) if !decl.type_params.is_empty() => {
+                    // A generic drop function is never instantiated implicitly
+                    if decl.is_drop {
+                        self.errors.push(CompileError::type_error_with_hint(
+                            ErrorCode::GenericDrop,
+                            format!("'@drop' function '{}' cannot be generic", decl.name),
+                            decl.span.clone(),
+                            self.source,
+                            "declare one '@drop' function per concrete type",
+                        ));
+                    }
+                    self.take_generic_file(&decl.name, file);
+                    self.generic_fns.insert(decl.name.clone(), decl);
+                }
+                Item::Struct(decl) if !decl.type_params.is_empty() => {
+                    self.take_generic_file(&decl.name, file);
+                    self.generic_structs.insert(decl.name.clone(), decl);
+                }
+                _ => {
+                    if let Item::Mod(mod_decl) = &mut item
+                        && let Some(body) = &mut mod_decl.body
+                    {
+                        let file = mod_decl.file.as_ref().or(file);
+                        self.take_generics(body, file);
+                    }
+                    kept.push(item);
+                }
+            }
+        }
+        *items = kept;
+    }
+
+    fn take_generic_file(&mut self, name: &str, file: Option<&SourceFile>) {
+        if let Some(file) = file {
+            self.generic_files.insert(name.to_string(), file.clone());
+        }
+    }
+
+    /// Generate one instance, with its type parameters bound to `args`
+    fn instantiate(&mut self, pending: Pending) -> Option<Item> {
+        let (type_params, span) = if pending.is_struct {
+            let decl = &self.generic_structs[&pending.generic];
+            (decl.type_params.clone(), decl.span.clone())
+        } else {
+            let decl = &self.generic_fns[&pending.generic];
+            (decl.type_params.clone(), decl.span.clone())
+        };
+
+        if pending.depth > MAX_INSTANCE_DEPTH {
+            self.errors.push(CompileError::type_error_with_hint(
+                ErrorCode::InstanceDepth,
+                format!(
+                    "instances of '{}' are nested more than {} levels deep",
+                    pending.generic, MAX_INSTANCE_DEPTH
+                ),
+                span,
+                self.source,
+                "a generic item that instantiates itself with a growing type never terminates",
+            ));
+            return None;
+        }
+
+        self.subst = type_params.into_iter().zip(pending.args).collect();
+        self.depth = pending.depth;
+
+        let mut item = if pending.is_struct {
+            let mut decl = self.generic_structs[&pending.generic].clone();
+            decl.name = pending.mangled;
+            decl.type_params.clear();
+            Item::Struct(decl)
+        } else {
+            let mut decl = self.generic_fns[&pending.generic].clone();
+            decl.name = pending.mangled;
+            decl.type_params.clear();
+            Item::Fn(decl)
+        };
+        self.rewrite_item(&mut item);
+
+        self.subst.clear();
+        self.depth = 0;
+
+        // An instance stays in its template's file, so lowering locates its
+        // runtime checks there
+        if let Some(file) = self.generic_files.get(&pending.generic) {
+            item = Item::Mod(ModDecl {
+                is_pub: true,
+                name: pending.generic,
+                body: Some(vec![item]),
+                file: Some(file.clone()),
+                span,
+            });
+        }
+        Some(item)
+    }
+
+    /// Mangled name of `generic[args]`, queueing the instance if it is new
+    fn instance(&mut self, generic: &str, args: &[TypeExpr], span: &Span) -> String {
+        let (is_struct, arity) = if let Some(decl) = self.generic_structs.get(generic) {
+            (true, decl.type_params.len())
+        } else if let Some(decl) = self.generic_fns.get(generic) {
+            (false, decl.type_params.len())
+        } else {
+            let generics = self.generic_fns.keys().chain(self.generic_structs.keys());
+            let message = format!("'{}' is not generic", generic);
+            match find_similar(generic, generics) {
+                Some(similar) => self.error_with_hint(
+                    ErrorCode::NotGeneric,
+                    message,
+                    span.clone(),
+                    format!("did you mean '{}'?", similar),
+                ),
+                None => self.error(ErrorCode::NotGeneric, message, span.clone()),
+            }
+            return generic.to_string();
+        };
+
+        if args.len() != arity {
+            self.error(
+                ErrorCode::TypeArgumentCount,
+                format!(
+                    "'{}' expects {} type argument(s), got {}",
+                    generic,
+                    arity,
+                    args.len()
+                ),
+                span.clone(),
+            );
+            return generic.to_string();
+        }
+
+        let mangled = mangle(generic, args);
+        if self.instances.insert(mangled.clone()) {
+            self.queue.push_back(Pending {
+                generic: generic.to_string(),
+                args: args.to_vec(),
+                mangled: mangled.clone(),
+                is_struct,
+                depth: self.depth + 1,
+            });
+        }
+        mangled
+    }
+
+    // === Rewriting ===
+
+    fn rewrite_item(&mut self, item: &mut Item) {
+        match item {
+            Item::Fn(decl) => self.rewrite_fn(decl),
+            Item::Struct(decl) => self.rewrite_struct(decl),
+            Item::Enum(decl) => {
+                for variant in &mut decl.variants {
+                    for ty in variant.fields.iter_mut().flatten() {
+                        self.rewrite_type(ty, &variant.span);
+                    }
+                }
+            }
+            Item::Const(decl) => self.rewrite_type(&mut decl.ty, &decl.span),
+            Item::Extern(block) => {
+                for item in &mut block.items {
+                    match item {
+                        ExternItem::Fn(proto) => {
+                            for param in &mut proto.params {
+                                self.rewrite_type(&mut param.ty, &param.span);
+                            }
+                            self.rewrite_type(&mut proto.return_type, &proto.span);
+                        }
+                        ExternItem::Struct(decl) => self.rewrite_struct(decl),
+                        ExternItem::Enum(_) | ExternItem::Opaque(_) => {}
+                    }
+                }
+            }
+            Item::Mod(mod_decl) => {
+                for item in mod_decl.body.iter_mut().flatten() {
+                    self.rewrite_item(item);
+                }
+            }
+            Item::Opaque(_) | Item::Use(_) | Item::Error { .. } => {}
+        }
+    }
+
+    fn rewrite_fn(&mut self, decl: &mut FnDecl) {
+        for param in &mut decl.params {
+            self.rewrite_type(&mut param.ty, &param.span);
+        }
+        self.rewrite_type(&mut decl.return_type, &decl.span);
+        self.rewrite_block(&mut decl.body);
+    }
+
+    fn rewrite_struct(&mut self, decl: &mut StructDecl) {
+        for field in &mut decl.fields {
+            self.rewrite_type(&mut field.ty, &field.span);
+        }
+    }
+
+    fn rewrite_block(&mut self, block: &mut Block) {
+        for stmt in &mut block.stmts {
+            self.rewrite_stmt(stmt);
+        }
+    }
+
+    fn rewrite_stmts(&mut self, stmts: &mut [Stmt]) {
+        for stmt in stmts {
+            self.rewrite_stmt(stmt);
+        }
+    }
+
+    fn rewrite_stmt(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::Let { ty, init, span, .. } => {
+                self.rewrite_type(ty, span);
+                self.rewrite_expr(init);
+            }
+            Stmt::Assign { lhs, rhs, .. } => {
+                self.rewrite_expr(lhs);
+                self.rewrite_expr(rhs);
+            }
+            Stmt::If {
+                cond,
+                then_block,
+                else_block,
+                ..
+            } => {
+                self.rewrite_expr(cond);
+                self.rewrite_block(then_block);
+                match else_block {
+                    Some(ElseBranch::ElseIf(stmt)) => self.rewrite_stmt(stmt),
+                    Some(ElseBranch::Else(block)) => self.rewrite_block(block),
+                    None => {}
+                }
+            }
+            Stmt::IfLet {
+                expr,
+                then_block,
+                else_block,
+                ..
+            } => {
+                self.rewrite_expr(expr);
+                self.rewrite_block(then_block);
+                if let Some(block) = else_block {
+                    self.rewrite_block(block);
+                }
+            }
+            Stmt::While { cond, body, .. } => {
+                self.rewrite_expr(cond);
+                self.rewrite_block(body);
+            }
+            Stmt::For {
+                init,
+                cond,
+                step,
+                body,
+                span,
+            } => {
+                match init {
+                    Some(ForInit::Let { ty, init, .. }) => {
+                        self.rewrite_type(ty, span);
+                        self.rewrite_expr(init);
+                    }
+                    Some(ForInit::Assign { lhs, rhs }) => {
+                        self.rewrite_expr(lhs);
+                        self.rewrite_expr(rhs);
+                    }
+                    Some(ForInit::Call(expr)) => self.rewrite_expr(expr),
+                    None => {}
+                }
+                if let Some(cond) = cond {
+                    self.rewrite_expr(cond);
+                }
+                match step {
+                    Some(ForStep::Assign { lhs, rhs }) => {
+                        self.rewrite_expr(lhs);
+                        self.rewrite_expr(rhs);
+                    }
+                    Some(ForStep::Call(expr)) => self.rewrite_expr(expr),
+                    None => {}
+                }
+                self.rewrite_block(body);
+            }
+            Stmt::Switch {
+                expr,
+                cases,
+                default,
+                ..
+            } => {
+                self.rewrite_expr(expr);
+                for case in cases {
+                    self.rewrite_stmts(&mut case.stmts);
+                }
+                if let Some(stmts) = default {
+                    self.rewrite_stmts(stmts);
+                }
+            }
+            Stmt::Match {
+                expr,
+                arms,
+                default,
+                ..
+            } => {
+                self.rewrite_expr(expr);
+                for arm in arms {
+                    self.rewrite_stmts(&mut arm.stmts);
+                }
+                if let Some(stmts) = default {
+                    self.rewrite_stmts(stmts);
+                }
+            }
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.rewrite_expr(value);
+                }
+            }
+            Stmt::Expr { expr, .. } | Stmt::Discard { expr, .. } => self.rewrite_expr(expr),
+            Stmt::Defer { body, .. } | Stmt::Unsafe { body, .. } => self.rewrite_block(body),
+            Stmt::Block(block) => self.rewrite_block(block),
+            Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Error { .. } => {}
+        }
+    }
+
+    fn rewrite_expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Instance {
+                name,
+                type_args,
+                span,
+            } => {
+                for ty in type_args.iter_mut() {
+                    self.rewrite_type(ty, span);
+                }
+                if self.generic_structs.contains_key(name.as_str()) {
+                    self.error_with_hint(
+                        ErrorCode::GenericStructCall,
+                        format!("'{}' is a generic struct, not a function", name),
+                        span.clone(),
+                        format!("construct it with {}[...] {{ ... }}", name),
+                    );
+                    return;
+                }
+                let mangled = self.instance(name, type_args, span);
+                *expr = Expr::Ident {
+                    name: mangled,
+                    span: span.clone(),
+                };
+            }
+            Expr::Ident { name, span } => {
+                if self.generic_fns.contains_key(name.as_str()) {
+                    self.error_with_hint(
+                        ErrorCode::MissingFnTypeArgs,
+                        format!("generic function '{}' needs explicit type arguments", name),
+                        span.clone(),
+                        format!("instantiate it as {}[T, ...]", name),
+                    );
+                }
+            }
+            Expr::StructLit {
+                name,
+                type_args,
+                fields,
+                span,
+            } => {
+                if !type_args.is_empty() {
+                    for ty in type_args.iter_mut() {
+                        self.rewrite_type(ty, span);
+                    }
+                    *name = self.instance(name, type_args, span);
+                    type_args.clear();
+                } else if self.generic_structs.contains_key(name.as_str()) {
+                    self.error_generic_struct(name, span);
+                }
+                for field in fields {
+                    self.rewrite_expr(&mut field.value);
+                }
+            }
+            Expr::Cast { ty, expr, span } => {
+                self.rewrite_type(ty, span);
+                self.rewrite_expr(expr);
+            }
+            Expr::None { ty, span } => self.rewrite_type(ty, span),
+            Expr::Binary { lhs, rhs, .. } => {
+                self.rewrite_expr(lhs);
+                self.rewrite_expr(rhs);
+            }
+            Expr::Call { callee, args, .. } => {
+                self.rewrite_expr(callee);
+                for arg in args {
+                    self.rewrite_expr(arg);
+                }
+            }
+            Expr::Builtin { args, .. } => {
+                for arg in args {
+                    self.rewrite_expr(arg);
+                }
+            }
+            Expr::At { base, index, .. } => {
+                self.rewrite_expr(base);
+                self.rewrite_expr(index);
+            }
+            Expr::Unary { operand, .. }
+            | Expr::Addr { operand, .. }
+            | Expr::Deref { operand, .. } => self.rewrite_expr(operand),
+            Expr::Paren { inner, .. } => self.rewrite_expr(inner),
+            Expr::Field { base, .. } => self.rewrite_expr(base),
+            Expr::Some { value, .. } | Expr::Ok { value, .. } | Expr::Err { value, .. } => {
+                self.rewrite_expr(value)
+            }
+            Expr::IntLit { .. }
+            | Expr::FloatLit { .. }
+            | Expr::BoolLit { .. }
+            | Expr::CStr { .. }
+            | Expr::Bytes { .. } => {}
+        }
+    }
+
+    /// Substitute bound type parameters and replace generic instances;
+    /// `span` locates errors, since types carry no span of their own
+    fn rewrite_type(&mut self, ty: &mut TypeExpr, span: &Span) {
+        match ty {
+            TypeExpr::Named(name) => {
+                if let Some(bound) = self.subst.get(name.as_str()) {
+                    *ty = bound.clone();
+                } else if self.generic_structs.contains_key(name.as_str()) {
+                    self.error_generic_struct(name, span);
+                }
+            }
+            TypeExpr::Generic(name, args) => {
+                for arg in args.iter_mut() {
+                    self.rewrite_type(arg, span);
+                }
+                *ty = TypeExpr::Named(self.instance(name, args, span));
+            }
+            TypeExpr::Ref(inner)
+            | TypeExpr::Mref(inner)
+            | TypeExpr::Raw(inner)
+            | TypeExpr::Rawm(inner)
+            | TypeExpr::Own(inner)
+            | TypeExpr::Slice(inner)
+            | TypeExpr::Opt(inner)
+            | TypeExpr::Arr(inner, _) => self.rewrite_type(inner, span),
+            TypeExpr::Res(ok, err) => {
+                self.rewrite_type(ok, span);
+                self.rewrite_type(err, span);
+            }
+            TypeExpr::Fn { params, ret, .. } => {
+                for param in params {
+                    self.rewrite_type(param, span);
+                }
+                self.rewrite_type(ret, span);
+            }
+            TypeExpr::Primitive(_) | TypeExpr::Void | TypeExpr::Error => {}
+        }
+    }
+
+    // === Errors ===
+
+    fn error(&mut self, code: ErrorCode, message: String, span: Span) {
+        self.errors
+            .push(CompileError::type_error(code, message, span, self.source));
+    }
+
+    fn error_with_hint(&mut self, code: ErrorCode, message: String, span: Span, hint: String) {
+        self.errors.push(CompileError::type_error_with_hint(
+            code,
+            message,
+            span,
+            self.source,
+            hint,
+        ));
+    }
+
+    fn error_generic_struct(&mut self, name: &str, span: &Span) {
+        self.error_with_hint(
+            ErrorCode::MissingStructTypeArgs,
+            format!("generic struct '{}' needs explicit type arguments", name),
+            span.clone(),
+            format!("write {}[T, ...]", name),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{compile, compile_with_options};
+
+    fn mono_ok(source: &str) -> String {
+        match compile(source, "test.fc") {
+            Ok(c_code) => c_code,
+            Err(e) => panic!("Expected success for: {}\nGot error: {:?}", source, e),
+        }
+    }
+
+    fn mono_error(source: &str, expected_substr: &str) {
+        let result = compile(source, "test.fc");
+        assert!(result.is_err(), "Expected error for: {}", source);
+        let err_msg = format!("{:?}", result.unwrap_err());
+        assert!(
+            err_msg.contains(expected_substr),
+            "Expected error containing '{}', got: {}",
+            expected_substr,
+            err_msg
+        );
+    }
+
+    const MAX: &str = "fn max[T](a: T, b: T) -> T { if (a > b) { return a; } return b; } ";
+
+    #[test]
+    fn test_generic_fn_instances() {
+        let c = mono_ok(&format!(
+            "{MAX}fn main() -> i32 {{ let w: u8 = max[u8](1, 2); discard(w); return max[i32](3, 4); }}"
+        ));
+        assert!(c.contains("uint8_t max__u8(uint8_t a, uint8_t b)"));
+        assert!(c.contains("int32_t max__i32(int32_t a, int32_t b)"));
+        assert!(c.contains("max__i32(3, 4)"));
+        // The template itself is not emitted
+        assert!(!c.contains(" max("));
+    }
+
+    #[test]
+    fn test_instance_generated_once() {
+        let c = mono_ok(&format!(
+            "{MAX}fn f() -> i32 {{ return max[i32](1, 2); }} fn g() -> i32 {{ return max[i32](3, 4); }}"
+        ));
+        assert_eq!(
+            c.matches("int32_t max__i32(int32_t a, int32_t b) {")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_generic_struct_instance() {
+        let c = mono_ok(
+            "struct Pair[A, B] { first: A, second: B }
+             fn swap[A, B](p: Pair[A, B]) -> Pair[B, A] {
+                 return Pair[B, A] { first: p.second, second: p.first };
+             }
+             fn main() -> i32 {
+                 let p: Pair[i32, bool] = Pair[i32, bool] { first: 1, second: true };
+                 let q: Pair[bool, i32] = swap[i32, bool](p);
+                 return q.second;
+             }",
+        );
+        assert!(c.contains("typedef struct Pair__i32__bool {"));
+        assert!(c.contains("Pair__bool__i32 swap__i32__bool(Pair__i32__bool p)"));
+        assert!(c.contains("bool first;"));
+    }
+
+    #[test]
+    fn test_nested_type_args_mangle() {
+        let c = mono_ok(
+            "fn id[T](x: T) -> T { return x; }
+             fn f(o: opt(ref(i32))) -> opt(ref(i32)) { return id[opt(ref(i32))](o); }",
+        );
+        assert!(c.contains("id__opt_ref_i32("));
+    }
+
+    #[test]
+    fn test_computed_array_sizes_mangle_apart() {
+        let c = mono_ok(
+            "struct Box[T] { v: T }
+             fn size[T](b: ref(Box[T])) -> i32 { return 0; }
+             fn f(a: ref(Box[arr(i32, (2 + 2))]), b: ref(Box[arr(i32, (3 + 3))])) -> i32 {
+                 return (size[arr(i32, (2 + 2))](a) + size[arr(i32, (3 + 3))](b));
+             }",
+        );
+        assert!(c.contains("int32_t size__arr_i32_e_add_1_2_1_2("));
+        assert!(c.contains("int32_t size__arr_i32_e_add_1_3_1_3("));
+        assert!(c.contains("int32_t v[4];"));
+        assert!(c.contains("int32_t v[6];"));
+    }
+
+    #[test]
+    fn test_type_names_with_underscores_mangle_apart() {
+        let c = mono_ok(
+            "struct Foo_Bar { v: i32 } struct Baz { v: i32 } struct Foo { v: i32 }
+             struct Bar_Baz { v: i32 } struct opt_u8 { v: u8 }
+             fn id[T](x: T) -> T { return x; }
+             fn f(a: res(Foo_Bar, Baz), b: res(Foo, Bar_Baz)) -> i32 {
+                 discard(id[res(Foo_Bar, Baz)](a));
+                 discard(id[res(Foo, Bar_Baz)](b));
+                 return 0;
+             }
+             fn g(a: opt_u8, b: opt(u8)) -> i32 {
+                 discard(id[opt_u8](a));
+                 discard(id[opt(u8)](b));
+                 return 0;
+             }",
+        );
+        assert!(c.contains(" id__res_7_Foo_Bar_3_Baz("));
+        assert!(c.contains(" id__res_3_Foo_7_Bar_Baz("));
+        assert!(c.contains("opt_u8 id__6_opt_u8("));
+        assert!(c.contains(" id__opt_u8("));
+    }
+
+    #[test]
+    fn test_header_lists_instances() {
+        let (_, header) = compile_with_options(
+            &format!("{MAX}struct Cell[T] {{ v: T }} fn get(c: Cell[u16]) -> u16 {{ return max[u16](c.v, 1); }}"),
+            "lib.fc",
+            true,
+        )
+        .unwrap();
+        let header = header.unwrap();
+        assert!(header.contains("typedef struct Cell__u16 {"));
+        assert!(header.contains("uint16_t max__u16(uint16_t a, uint16_t b);"));
+        assert!(header.contains("uint16_t get(Cell__u16 c);"));
+    }
+
+    #[test]
+    fn test_generic_fn_without_type_args() {
+        mono_error(
+            &format!("{MAX}fn f() -> i32 {{ return max(1, 2); }}"),
+            "generic function 'max' needs explicit type arguments",
+        );
+    }
+
+    #[test]
+    fn test_generic_struct_without_type_args() {
+        mono_error(
+            "struct Box1[T] { v: T } fn f(b: Box1) -> i32 { return 0; }",
+            "generic struct 'Box1' needs explicit type arguments",
+        );
+    }
+
+    #[test]
+    fn test_wrong_type_arg_count() {
+        mono_error(
+            &format!("{MAX}fn f() -> i32 {{ return max[i32, i32](1, 2); }}"),
+            "'max' expects 1 type argument(s), got 2",
+        );
+    }
+
+    #[test]
+    fn test_not_generic() {
+        mono_error(
+            "fn one() -> i32 { return 1; } fn f() -> i32 { return one[i32](); }",
+            "'one' is not generic",
+        );
+    }
+
+    #[test]
+    fn test_instance_body_is_type_checked() {
+        mono_error(
+            "fn first[T](x: T) -> bool { return x; } fn f() -> bool { return first[i32](1); }",
+            "type mismatch",
+        );
+    }
+
+    #[test]
+    fn test_unbounded_instantiation() {
+        mono_error(
+            "fn grow[T](x: T) -> i32 { return grow[opt(T)](some(x)); }
+             fn f() -> i32 { return grow[i32](1); }",
+            "nested more than 64 levels deep",
+        );
+    }
+}
@@ -3,6 +3,8 @@
 //! Based on NASA/JPL's "Power of 10: Rules for Developing Safety-Critical Code"
 //! by Gerard J. Holzmann.
 
+use crate::lint::{Level, LintLevels};
+
 /// Safety level for Power of 10 enforcement
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum SafetyLevel {
@@ -17,6 +19,7 @@ pub enum SafetyLevel {
 
 impl SafetyLevel {
     /// Parse safety level from string
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "standard" => Some(SafetyLevel::Standard),
@@ -53,6 +56,9 @@ pub struct P10Config {
 
     /// Rule 10: Treat all warnings as errors
     pub strict_mode: bool,
+
+    /// Lint levels from `-A`, `-W`, and `-D`, covering lints and P10 rules
+    pub lints: LintLevels,
 }
 
 impl Default for P10Config {
@@ -79,6 +85,7 @@ impl P10Config {
             require_loop_bounds: true,
             allow_runtime_alloc: false,
             strict_mode: false,
+            lints: LintLevels::default(),
         }
     }
 
@@ -93,6 +100,7 @@ impl P10Config {
             require_loop_bounds: true,
             allow_runtime_alloc: false,
             strict_mode: true,
+            lints: LintLevels::default(),
         }
     }
 
@@ -107,6 +115,7 @@ impl P10Config {
             require_loop_bounds: false,
             allow_runtime_alloc: true,
             strict_mode: false,
+            lints: LintLevels::default(),
         }
     }
 
@@ -123,4 +132,12 @@ impl P10Config {
     pub fn is_enabled(&self) -> bool {
         self.level != SafetyLevel::Relaxed
     }
+
+    /// Level for a lint or P10 code, with warnings promoted in strict mode
+    pub fn lint_level(&self, code: &str) -> Level {
+        match self.lints.get(code) {
+            Level::Warn if self.strict_mode => Level::Deny,
+            level => level,
+        }
+    }
 }
@@ -36,8 +36,9 @@ pub use report::{ComplianceReport, ComplianceStatus, ProjectReport, ReportSummar
 pub use rules::{P10Rule, P10Violation, RuleRegistry};
 
 use crate::ast::File;
-use crate::diag::CompileError;
+use crate::diag::{CompileError, DiagnosticRecord, LineIndex, Location, Severity};
 use crate::lexer::Span;
+use crate::lint::LintReport;
 
 /// Power of 10 rule checker
 pub struct P10Checker {
@@ -73,31 +74,38 @@ impl P10Checker {
         self.registry.check_file(ast, &self.config, source)
     }
 
-    /// Check an AST and convert violations to compile errors
-    pub fn check_and_report(&self, ast: &File, source: &str) -> Result<(), CompileError> {
-        let violations = self.check(ast, source);
+    /// Check an AST and sort violations into errors and warnings
+    ///
+    /// Violations are warnings unless their rule is denied or the config is
+    /// strict, in which case they are P10 errors.
+    pub fn report(&self, ast: &File, source: &str) -> LintReport {
+        let mut report = LintReport::default();
 
-        if violations.is_empty() {
-            return Ok(());
+        for violation in self.check(ast, source) {
+            let level = self.config.lint_level(&violation.code);
+            report.push(level, |severity| match severity {
+                Severity::Error => self.violation_to_error(violation, source),
+                Severity::Warning => self.violation_to_warning(violation, source),
+            });
         }
 
-        // Convert violations to compile errors
-        let errors: Vec<CompileError> = violations
-            .into_iter()
-            .map(|v| self.violation_to_error(v, source))
-            .collect();
+        report
+    }
 
-        Err(CompileError::multiple(errors))
+    /// Check an AST, failing on violations that are errors
+    ///
+    /// Returns the violations reported as warnings.
+    pub fn check_and_report(
+        &self,
+        ast: &File,
+        source: &str,
+    ) -> Result<Vec<CompileError>, CompileError> {
+        self.report(ast, source).into_result()
     }
 
     /// Convert a P10 violation to a CompileError
     fn violation_to_error(&self, violation: P10Violation, source: &str) -> CompileError {
-        let hint = match (&violation.help, &violation.note) {
-            (Some(help), Some(note)) => Some(format!("{}\nNote: {}", help, note)),
-            (Some(help), None) => Some(help.clone()),
-            (None, Some(note)) => Some(format!("Note: {}", note)),
-            (None, None) => None,
-        };
+        let hint = violation_hint(&violation);
 
         CompileError::P10 {
             code: violation.code,
@@ -108,6 +116,20 @@ impl P10Checker {
         }
     }
 
+    /// Convert a P10 violation to a lint warning
+    fn violation_to_warning(&self, violation: P10Violation, source: &str) -> CompileError {
+        let hint = violation_hint(&violation);
+
+        CompileError::lint(
+            violation.code,
+            Severity::Warning,
+            format!("Power of 10: {}", violation.message),
+            violation.span,
+            source,
+            hint,
+        )
+    }
+
     /// Get the configuration
     pub fn config(&self) -> &P10Config {
         &self.config
@@ -133,6 +155,16 @@ impl P10Checker {
     }
 }
 
+/// Help and note of a violation, joined into one hint
+fn violation_hint(violation: &P10Violation) -> Option<String> {
+    match (&violation.help, &violation.note) {
+        (Some(help), Some(note)) => Some(format!("{}\nNote: {}", help, note)),
+        (Some(help), None) => Some(help.clone()),
+        (None, Some(note)) => Some(format!("Note: {}", note)),
+        (None, None) => None,
+    }
+}
+
 /// Extension trait for CompileError to support P10 violations
 impl CompileError {
     /// Create a P10 rule violation error
@@ -164,6 +196,22 @@ impl CompileError {
     }
 }
 
+impl DiagnosticRecord {
+    /// Record for a violation found in `source`, reported as an error
+    pub fn from_violation(violation: &P10Violation, file: &str, source: &str) -> Self {
+        DiagnosticRecord {
+            file: file.to_string(),
+            severity: Severity::Error,
+            code: Some(violation.code.clone()),
+            message: violation.message.clone(),
+            location: Location::new(violation.span.clone(), &LineIndex::new(source)),
+            hint: violation_hint(violation),
+            related: Vec::new(),
+            fix: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -185,7 +233,9 @@ mod tests {
 
         FnDecl {
             is_unsafe: false,
+            is_drop: false,
             name: name.to_string(),
+            type_params: vec![],
             params: vec![],
             return_type: TypeExpr::Void,
             body: Block {
@@ -220,4 +270,61 @@ mod tests {
         // Should have rules 1, 2, 3, 4, 9 enabled
         assert!(rules.len() >= 4);
     }
+
+    /// A file whose only function exceeds the 60-line limit (rule 4)
+    fn long_function_source() -> String {
+        let body: String = (0..70).map(|i| format!("    discard({});\n", i)).collect();
+        format!("fn long() -> void {{\n{}}}\n", body)
+    }
+
+    #[test]
+    fn test_standard_violations_are_warnings() {
+        let source = long_function_source();
+        let file = crate::parse(&source, "test.fc").unwrap();
+
+        let warnings = P10Checker::standard()
+            .check_and_report(&file, &source)
+            .unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn test_strict_violations_are_errors() {
+        let source = long_function_source();
+        let file = crate::parse(&source, "test.fc").unwrap();
+
+        let mut config = P10Config::standard();
+        config.strict_mode = true;
+        let error = P10Checker::new(config)
+            .check_and_report(&file, &source)
+            .unwrap_err();
+        assert!(matches!(error, CompileError::P10 { ref code, .. } if code == "P10-004"));
+    }
+
+    #[test]
+    fn test_denied_rule_is_an_error() {
+        let source = long_function_source();
+        let file = crate::parse(&source, "test.fc").unwrap();
+
+        let mut config = P10Config::standard();
+        config.lints.set("P10-004", crate::lint::Level::Deny);
+        assert!(
+            P10Checker::new(config)
+                .check_and_report(&file, &source)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_violation_record() {
+        let source = long_function_source();
+        let file = crate::parse(&source, "test.fc").unwrap();
+
+        let violations = P10Checker::standard().check(&file, &source);
+        let record = DiagnosticRecord::from_violation(&violations[0], "test.fc", &source);
+        assert_eq!(record.code.as_deref(), Some("P10-004"));
+        assert_eq!((record.location.line, record.location.column), (1, 1));
+        assert!(record.hint.is_some());
+    }
 }
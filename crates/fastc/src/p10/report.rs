@@ -209,9 +209,9 @@ impl ComplianceReport {
     pub fn to_text(&self) -> String {
         let mut out = String::new();
 
-        out.push_str(&format!("╔══════════════════════════════════════════════════════════════╗\n"));
-        out.push_str(&format!("║              FASTC COMPLIANCE REPORT                         ║\n"));
-        out.push_str(&format!("╚══════════════════════════════════════════════════════════════╝\n\n"));
+        out.push_str("╔══════════════════════════════════════════════════════════════╗\n");
+        out.push_str("║              FASTC COMPLIANCE REPORT                         ║\n");
+        out.push_str("╚══════════════════════════════════════════════════════════════╝\n\n");
 
         out.push_str(&format!("File:         {}\n", self.file));
         out.push_str(&format!("Safety Level: {}\n", self.safety_level));
@@ -134,6 +134,15 @@ impl ControlFlowRule {
                     calls.extend(self.collect_calls(default_stmts, known_fns));
                 }
             }
+            Stmt::Match { expr, arms, default, .. } => {
+                self.collect_calls_from_expr(expr, known_fns, calls);
+                for arm in arms {
+                    calls.extend(self.collect_calls(&arm.stmts, known_fns));
+                }
+                if let Some(default_stmts) = default {
+                    calls.extend(self.collect_calls(default_stmts, known_fns));
+                }
+            }
             Stmt::Return { value, .. } => {
                 if let Some(val) = value {
                     self.collect_calls_from_expr(val, known_fns, calls);
@@ -151,7 +160,7 @@ impl ControlFlowRule {
             Stmt::Defer { body, .. } => {
                 calls.extend(self.collect_calls(&body.stmts, known_fns));
             }
-            Stmt::Break { .. } | Stmt::Continue { .. } => {}
+            Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Error { .. } => {}
         }
     }
 
@@ -201,6 +210,11 @@ impl ControlFlowRule {
             Expr::Some { value, .. } | Expr::Ok { value, .. } | Expr::Err { value, .. } => {
                 self.collect_calls_from_expr(value, known_fns, calls);
             }
+            Expr::Builtin { args, .. } => {
+                for arg in args {
+                    self.collect_calls_from_expr(arg, known_fns, calls);
+                }
+            }
             Expr::StructLit { fields, .. } => {
                 for field in fields {
                     self.collect_calls_from_expr(&field.value, known_fns, calls);
@@ -211,6 +225,7 @@ impl ControlFlowRule {
             | Expr::FloatLit { .. }
             | Expr::BoolLit { .. }
             | Expr::Ident { .. }
+            | Expr::Instance { .. }
             | Expr::CStr { .. }
             | Expr::Bytes { .. }
             | Expr::None { .. } => {}
@@ -227,6 +242,7 @@ impl ControlFlowRule {
         let mut on_stack: HashSet<String> = HashSet::new();
         let mut sccs: Vec<Vec<String>> = Vec::new();
 
+        #[allow(clippy::too_many_arguments)]
         fn strongconnect(
             v: &str,
             graph: &HashMap<String, HashSet<String>>,
@@ -375,7 +391,9 @@ mod tests {
             .map(|(name, _calls)| {
                 Item::Fn(FnDecl {
                     is_unsafe: false,
+                    is_drop: false,
                     name: name.to_string(),
+                    type_params: vec![],
                     params: vec![],
                     return_type: TypeExpr::Void,
                     body: Block {
@@ -92,7 +92,9 @@ mod tests {
     fn make_func(name: &str, body_span: Span) -> FnDecl {
         FnDecl {
             is_unsafe: false,
+            is_drop: false,
             name: name.to_string(),
+            type_params: vec![],
             params: vec![],
             return_type: TypeExpr::Void,
             body: Block {
@@ -222,6 +222,19 @@ impl RuleRegistry {
                     }
                 }
             }
+            Stmt::Match { expr, arms, default, .. } => {
+                violations.extend(rule.check_expr(expr, config, source));
+                for arm in arms {
+                    for arm_stmt in &arm.stmts {
+                        violations.extend(rule.check_stmt(arm_stmt, config, source));
+                    }
+                }
+                if let Some(default_stmts) = default {
+                    for default_stmt in default_stmts {
+                        violations.extend(rule.check_stmt(default_stmt, config, source));
+                    }
+                }
+            }
             Stmt::Block(block) => {
                 violations.extend(self.check_block_recursive(block, rule, config, source));
             }
@@ -253,7 +266,7 @@ impl RuleRegistry {
                     violations.extend(rule.check_expr(val, config, source));
                 }
             }
-            Stmt::Break { .. } | Stmt::Continue { .. } => {}
+            Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Error { .. } => {}
         }
 
         violations
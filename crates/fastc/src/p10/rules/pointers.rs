@@ -136,7 +136,7 @@ mod tests {
 
     #[test]
     fn test_relaxed_mode_allows_deep_deref() {
-        let expr = Expr::Deref {
+        let _expr = Expr::Deref {
             operand: Box::new(Expr::Deref {
                 operand: Box::new(Expr::Ident {
                     name: "pptr".to_string(),
@@ -4,7 +4,7 @@ use crate::ast::{
     ConstDecl, EnumDecl, ExternBlock, ExternItem, Field, FnDecl, FnProto, Item, ModDecl,
     OpaqueDecl, Param, Repr, StructDecl, UseDecl, UseItems, Variant,
 };
-use crate::diag::CompileError;
+use crate::diag::{CompileError, ErrorCode};
 use crate::lexer::Token;
 
 use super::Parser;
@@ -18,6 +18,10 @@ impl Parser<'_> {
         } else {
             None
         };
+        let is_drop = self.check(&Token::AtDrop);
+        if is_drop {
+            self.advance();
+        }
 
         // Check for visibility modifier
         let is_pub = if self.check(&Token::Pub) {
@@ -27,14 +31,24 @@ impl Parser<'_> {
             false
         };
 
+        if is_drop && !self.check(&Token::Fn) {
+            return Err(self.error(
+                ErrorCode::UnsafeDrop,
+                "'@drop' only applies to safe functions",
+            ));
+        }
+
         match self.current() {
-            Token::Fn => Ok(Item::Fn(self.parse_fn_decl(false)?)),
+            Token::Fn => Ok(Item::Fn(FnDecl {
+                is_drop,
+                ..self.parse_fn_decl(false)?
+            })),
             Token::Unsafe => {
                 self.advance();
                 if self.check(&Token::Fn) {
                     Ok(Item::Fn(self.parse_fn_decl(true)?))
                 } else {
-                    Err(self.error("expected 'fn' after 'unsafe'"))
+                    Err(self.error(ErrorCode::UnexpectedToken, "expected 'fn' after 'unsafe'"))
                 }
             }
             Token::Struct => Ok(Item::Struct(self.parse_struct_decl(repr)?)),
@@ -44,7 +58,7 @@ impl Parser<'_> {
             Token::Extern => Ok(Item::Extern(self.parse_extern_block()?)),
             Token::Use => Ok(Item::Use(self.parse_use_decl()?)),
             Token::Mod => Ok(Item::Mod(self.parse_mod_decl(is_pub)?)),
-            _ => Err(self.error("expected top-level item")),
+            _ => Err(self.error(ErrorCode::UnexpectedToken, "expected top-level item")),
         }
     }
 
@@ -90,7 +104,12 @@ impl Parser<'_> {
                 self.advance();
                 Repr::U64
             }
-            _ => return Err(self.error("expected repr kind (C, i8, u8, etc.)")),
+            _ => {
+                return Err(self.error(
+                    ErrorCode::UnexpectedToken,
+                    "expected repr kind (C, i8, u8, etc.)",
+                ));
+            }
         };
 
         self.consume(&Token::RParen, "expected ')'")?;
@@ -102,6 +121,7 @@ impl Parser<'_> {
         let start = self.current_span().start;
         self.consume(&Token::Fn, "expected 'fn'")?;
         let name = self.expect_ident()?;
+        let type_params = self.parse_type_params()?;
         self.consume(&Token::LParen, "expected '('")?;
 
         let params = self.parse_param_list()?;
@@ -114,7 +134,9 @@ impl Parser<'_> {
 
         Ok(FnDecl {
             is_unsafe,
+            is_drop: false,
             name,
+            type_params,
             params,
             return_type,
             body,
@@ -146,6 +168,33 @@ impl Parser<'_> {
         })
     }
 
+    /// Parse optional type parameters: [T, U]
+    fn parse_type_params(&mut self) -> Result<Vec<String>, CompileError> {
+        let mut type_params = Vec::new();
+        if !self.check(&Token::LBracket) {
+            return Ok(type_params);
+        }
+        self.advance();
+
+        loop {
+            let param = self.expect_ident()?;
+            if type_params.contains(&param) {
+                return Err(self.error(
+                    ErrorCode::DuplicateTypeParam,
+                    &format!("duplicate type parameter '{}'", param),
+                ));
+            }
+            type_params.push(param);
+            if !self.check(&Token::Comma) {
+                break;
+            }
+            self.advance();
+        }
+
+        self.consume(&Token::RBracket, "expected ']'")?;
+        Ok(type_params)
+    }
+
     /// Parse parameter list
     fn parse_param_list(&mut self) -> Result<Vec<Param>, CompileError> {
         let mut params = Vec::new();
@@ -179,6 +228,7 @@ impl Parser<'_> {
         let start = self.current_span().start;
         self.consume(&Token::Struct, "expected 'struct'")?;
         let name = self.expect_ident()?;
+        let type_params = self.parse_type_params()?;
         self.consume(&Token::LBrace, "expected '{'")?;
 
         let mut fields = Vec::new();
@@ -208,6 +258,7 @@ impl Parser<'_> {
         Ok(StructDecl {
             repr,
             name,
+            type_params,
             fields,
             span: start..end,
         })
@@ -313,7 +364,12 @@ impl Parser<'_> {
                 self.advance();
                 s
             }
-            _ => return Err(self.error("expected ABI string (e.g., \"C\")")),
+            _ => {
+                return Err(self.error(
+                    ErrorCode::UnexpectedToken,
+                    "expected ABI string (e.g., \"C\")",
+                ));
+            }
         };
 
         self.consume(&Token::LBrace, "expected '{'")?;
@@ -336,7 +392,7 @@ impl Parser<'_> {
                 Token::Struct => ExternItem::Struct(self.parse_struct_decl(repr)?),
                 Token::Enum => ExternItem::Enum(self.parse_enum_decl(repr)?),
                 Token::Opaque => ExternItem::Opaque(self.parse_opaque_decl()?),
-                _ => return Err(self.error("expected extern item")),
+                _ => return Err(self.error(ErrorCode::UnexpectedToken, "expected extern item")),
             };
             items.push(item);
         }
@@ -458,6 +514,7 @@ impl Parser<'_> {
             is_pub,
             name,
             body,
+            file: None,
             span: start..end,
         })
     }
@@ -1,7 +1,7 @@
 //! Expression parsing with single-binary-operator rule
 
-use crate::ast::{BinOp, Expr, FieldInit, UnaryOp};
-use crate::diag::CompileError;
+use crate::ast::{BinOp, Builtin, Expr, FieldInit, UnaryOp};
+use crate::diag::{CompileError, ErrorCode};
 use crate::lexer::Token;
 
 use super::Parser;
@@ -24,7 +24,10 @@ impl Parser<'_> {
 
             // Check for chained binary operator (not allowed without parens)
             if self.current().is_binary_op() {
-                return Err(self.error("chained binary operators require parentheses"));
+                return Err(self.error(
+                    ErrorCode::ChainedOperators,
+                    "chained binary operators require parentheses",
+                ));
             }
 
             Ok(Expr::Binary {
@@ -124,6 +127,29 @@ impl Parser<'_> {
         Ok(expr)
     }
 
+    /// Parse a builtin call like unwrap(o) or unwrap_or(o, default)
+    fn parse_builtin(&mut self, op: Builtin) -> Result<Expr, CompileError> {
+        let start = self.current_span().start;
+        self.advance();
+        self.consume(
+            &Token::LParen,
+            &format!("expected '(' after '{}'", op.name()),
+        )?;
+        let mut args = vec![self.parse_expr()?];
+        while args.len() < op.arity() {
+            self.consume(&Token::Comma, &format!("expected ',' in '{}'", op.name()))?;
+            args.push(self.parse_expr()?);
+        }
+        self.consume(&Token::RParen, "expected ')'")?;
+        let end = self.previous_span().end;
+
+        Ok(Expr::Builtin {
+            op,
+            args,
+            span: start..end,
+        })
+    }
+
     /// Parse a function call
     fn parse_call(&mut self, callee: Expr) -> Result<Expr, CompileError> {
         let start = callee.span().start;
@@ -169,6 +195,17 @@ impl Parser<'_> {
     fn parse_primary(&mut self) -> Result<Expr, CompileError> {
         let start = self.current_span().start;
 
+        if let Some(op) = builtin_for(self.current()) {
+            return self.parse_builtin(op);
+        }
+        // `len` is not a keyword, so fields and locals may still use the
+        // name; it is the builtin only when called
+        if matches!(self.current(), Token::Ident(name) if name == "len")
+            && matches!(self.peek(), Token::LParen)
+        {
+            return self.parse_builtin(Builtin::Len);
+        }
+
         match self.current().clone() {
             // Literals
             Token::IntLit(n) | Token::HexLit(n) | Token::BinLit(n) | Token::OctLit(n) => {
@@ -278,7 +315,11 @@ impl Parser<'_> {
                         self.advance();
                         s
                     }
-                    _ => return Err(self.error("expected string literal")),
+                    _ => {
+                        return Err(
+                            self.error(ErrorCode::UnexpectedToken, "expected string literal")
+                        );
+                    }
                 };
                 self.consume(&Token::RParen, "expected ')'")?;
                 let end = self.previous_span().end;
@@ -295,7 +336,11 @@ impl Parser<'_> {
                         self.advance();
                         s
                     }
-                    _ => return Err(self.error("expected string literal")),
+                    _ => {
+                        return Err(
+                            self.error(ErrorCode::UnexpectedToken, "expected string literal")
+                        );
+                    }
                 };
                 self.consume(&Token::RParen, "expected ')'")?;
                 let end = self.previous_span().end;
@@ -349,9 +394,14 @@ impl Parser<'_> {
                 })
             }
 
-            // Identifier (possibly struct literal)
+            // Identifier (possibly struct literal or generic instance)
             Token::Ident(name) => {
                 self.advance();
+                let type_args = if self.check(&Token::LBracket) {
+                    self.parse_type_args()?
+                } else {
+                    Vec::new()
+                };
 
                 // Check for struct literal
                 if self.check(&Token::LBrace) {
@@ -387,9 +437,17 @@ impl Parser<'_> {
 
                     Ok(Expr::StructLit {
                         name,
+                        type_args,
                         fields,
                         span: start..end,
                     })
+                } else if !type_args.is_empty() {
+                    let end = self.previous_span().end;
+                    Ok(Expr::Instance {
+                        name,
+                        type_args,
+                        span: start..end,
+                    })
                 } else {
                     let end = self.previous_span().end;
                     Ok(Expr::Ident {
@@ -399,7 +457,30 @@ impl Parser<'_> {
                 }
             }
 
-            _ => Err(self.error("expected expression")),
+            _ => Err(self.error(ErrorCode::UnexpectedToken, "expected expression")),
         }
     }
 }
+
+/// The builtin a keyword token names, if any
+fn builtin_for(token: &Token) -> Option<Builtin> {
+    match token {
+        Token::IsSome => Some(Builtin::IsSome),
+        Token::IsNone => Some(Builtin::IsNone),
+        Token::IsOk => Some(Builtin::IsOk),
+        Token::IsErr => Some(Builtin::IsErr),
+        Token::Unwrap => Some(Builtin::Unwrap),
+        Token::UnwrapOr => Some(Builtin::UnwrapOr),
+        Token::UnwrapErr => Some(Builtin::UnwrapErr),
+        Token::ToRaw => Some(Builtin::ToRaw),
+        Token::ToRawm => Some(Builtin::ToRawm),
+        Token::FromRaw => Some(Builtin::FromRaw),
+        Token::FromRawm => Some(Builtin::FromRawm),
+        Token::FromRawUnchecked => Some(Builtin::FromRawUnchecked),
+        Token::FromRawmUnchecked => Some(Builtin::FromRawmUnchecked),
+        Token::AsSlice => Some(Builtin::AsSlice),
+        Token::Subslice => Some(Builtin::Subslice),
+        Token::SliceFromRaw => Some(Builtin::SliceFromRaw),
+        _ => None,
+    }
+}
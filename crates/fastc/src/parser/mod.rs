@@ -5,8 +5,8 @@ mod expr;
 mod stmt;
 mod types;
 
-use crate::ast::File;
-use crate::diag::CompileError;
+use crate::ast::{File, Item};
+use crate::diag::{CompileError, ErrorCode};
 use crate::lexer::{Span, Spanned, Token};
 
 /// Parser state
@@ -15,6 +15,8 @@ pub struct Parser<'a> {
     pos: usize,
     source: &'a str,
     _filename: &'a str,
+    /// Syntax errors recovered from so far
+    errors: Vec<CompileError>,
 }
 
 impl<'a> Parser<'a> {
@@ -25,18 +27,130 @@ impl<'a> Parser<'a> {
             pos: 0,
             source,
             _filename: filename,
+            errors: Vec::new(),
         }
     }
 
-    /// Parse a complete file
+    /// Parse a complete file, failing with every syntax error found
     pub fn parse_file(&mut self) -> Result<File, CompileError> {
+        let (file, errors) = self.parse_file_recovering();
+        if errors.is_empty() {
+            Ok(file)
+        } else {
+            Err(CompileError::multiple(errors))
+        }
+    }
+
+    /// Parse a complete file, recovering from syntax errors
+    ///
+    /// An item or statement that fails to parse becomes an `Error` node and
+    /// parsing resumes after the `;` or `}` that ends it, or at the next
+    /// `fn`/`struct`/`enum`, so the returned file keeps everything that did
+    /// parse.
+    pub fn parse_file_recovering(&mut self) -> (File, Vec<CompileError>) {
         let mut items = Vec::new();
 
         while !self.is_at_end() {
-            items.push(self.parse_item()?);
+            let start = self.pos;
+            let start_span = self.current_span();
+            // `const` and `use` end with `;`, even after a braced group
+            let ends_with_block = !matches!(
+                (self.current(), self.peek()),
+                (Token::Const | Token::Use, _) | (Token::Pub, Token::Const | Token::Use)
+            );
+            match self.parse_item() {
+                Ok(item) => items.push(item),
+                Err(error) => {
+                    self.report(error);
+                    self.synchronize(start, ends_with_block);
+                    // Unlike a statement, an item owns the `}` it stopped at
+                    if self.check(&Token::RBrace) || self.pos == start {
+                        self.advance();
+                    }
+                    let end = self.previous_span().end.max(start_span.end);
+                    items.push(Item::Error {
+                        span: start_span.start..end,
+                    });
+                }
+            }
+        }
+
+        let errors = std::mem::take(&mut self.errors);
+        (File { items }, errors)
+    }
+
+    // Error recovery
+
+    /// Record a syntax error and carry on
+    ///
+    /// Errors at the same position as the previous one are dropped: a
+    /// missing `}` is reported once, not once per enclosing block.
+    fn report(&mut self, error: CompileError) {
+        let offset = |error: &CompileError| match error {
+            CompileError::Parse { span, .. } => Some(span.start),
+            _ => None,
+        };
+        let last = self.errors.last().and_then(offset);
+        if last.is_none() || last != offset(&error) {
+            self.errors.push(error);
+        }
+    }
+
+    /// Skip to the end of the statement or item that started at token `start`
+    ///
+    /// Stops after a `;` outside the braces the statement opened, or before
+    /// a `}` that closes an enclosing block or the start of the next
+    /// `fn`/`struct`/`enum` item. When `ends_with_block` is set, as for `if`
+    /// and item declarations, it also stops after the statement's braced
+    /// group closes, unless `else` follows. Otherwise a braced group, such as
+    /// an array or struct literal, is part of an expression that runs to the
+    /// `;`.
+    fn synchronize(&mut self, start: usize, ends_with_block: bool) {
+        let mut depth =
+            self.tokens[start..self.pos]
+                .iter()
+                .fold(0usize, |depth, token| match token.node {
+                    Token::LBrace => depth + 1,
+                    Token::RBrace => depth.saturating_sub(1),
+                    _ => depth,
+                });
+        while !self.is_at_end() {
+            match self.current() {
+                Token::Semi if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                Token::LBrace => depth += 1,
+                Token::RBrace if depth == 0 => return,
+                Token::RBrace => {
+                    depth -= 1;
+                    if depth == 0 && ends_with_block && !matches!(self.peek(), Token::Else) {
+                        self.advance();
+                        return;
+                    }
+                }
+                _ if depth == 0 && self.at_item_start() => return,
+                _ => {}
+            }
+            self.advance();
         }
+    }
 
-        Ok(File { items })
+    /// Whether the current token starts a `fn`, `struct`, or `enum` item
+    ///
+    /// `fn` followed by `(` is a function pointer type, not an item.
+    fn at_item_start(&self) -> bool {
+        match self.current() {
+            Token::Fn => matches!(self.peek(), Token::Ident(_)),
+            Token::Struct | Token::Enum => true,
+            _ => false,
+        }
+    }
+
+    /// Whether a statement can start here: not at a `}`, the end of input,
+    /// or the next item after a block that is missing its `}`
+    fn at_stmt(&self) -> bool {
+        !self.check(&Token::RBrace) && !self.is_at_end() && !self.at_item_start()
     }
 
     // Token access helpers
@@ -48,6 +162,14 @@ impl<'a> Parser<'a> {
             .unwrap_or(&Token::Eof)
     }
 
+    /// The token after the current one
+    fn peek(&self) -> &Token {
+        self.tokens
+            .get(self.pos + 1)
+            .map(|t| &t.node)
+            .unwrap_or(&Token::Eof)
+    }
+
     fn current_span(&self) -> Span {
         self.tokens
             .get(self.pos)
@@ -83,12 +205,12 @@ impl<'a> Parser<'a> {
             self.advance();
             Ok(())
         } else {
-            Err(self.error(message))
+            Err(self.error(ErrorCode::UnexpectedToken, message))
         }
     }
 
-    fn error(&self, message: &str) -> CompileError {
-        CompileError::parse(message, self.current_span(), self.source)
+    fn error(&self, code: ErrorCode, message: &str) -> CompileError {
+        CompileError::parse(code, message, self.current_span(), self.source)
     }
 
     fn expect_ident(&mut self) -> Result<String, CompileError> {
@@ -97,7 +219,98 @@ impl<'a> Parser<'a> {
                 self.advance();
                 Ok(name)
             }
-            _ => Err(self.error("expected identifier")),
+            _ => Err(self.error(ErrorCode::UnexpectedToken, "expected identifier")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Stmt;
+    use crate::lexer::{Lexer, strip_comments};
+
+    fn parse(source: &str) -> (File, Vec<CompileError>) {
+        let tokens = strip_comments(Lexer::new(source).collect());
+        Parser::new(&tokens, source, "test.fc").parse_file_recovering()
+    }
+
+    fn fn_names(file: &File) -> Vec<&str> {
+        file.items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Fn(decl) => Some(decl.name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_recovers_at_statement_boundaries() {
+        let (file, errors) = parse(
+            "fn f() -> i32 { let x: i32 = 1 +; let y: i32 = ; return 0; }\n\
+             fn g() -> i32 { return 1; }",
+        );
+        assert_eq!(errors.len(), 2);
+        assert_eq!(fn_names(&file), ["f", "g"]);
+
+        let Item::Fn(f) = &file.items[0] else {
+            panic!("expected a function");
+        };
+        assert!(matches!(f.body.stmts[0], Stmt::Error { .. }));
+        assert!(matches!(f.body.stmts[1], Stmt::Error { .. }));
+        assert!(matches!(f.body.stmts[2], Stmt::Return { .. }));
+    }
+
+    #[test]
+    fn test_braced_group_in_expression_does_not_end_statement() {
+        let (file, errors) = parse(
+            "fn f() -> i32 { let a: arr(i32, 5) = arr(i32,5) {1, 2, 3, 4, 5}; return 0; }\n\
+             fn g(x: i32) -> i32 { if (x >) { x = 1; } else { x = 2; } return x; }\n\
+             fn h() -> i32 { let a: arr(i32, 3) = arr(i32,3) {1, 2 3}; return 0; }\n\
+             use a::{b c};\n\
+             fn k() -> i32 { return 0; }",
+        );
+        assert_eq!(errors.len(), 4);
+        assert_eq!(fn_names(&file), ["f", "g", "h", "k"]);
+
+        for item in &file.items[..3] {
+            let Item::Fn(decl) = item else {
+                panic!("expected a function");
+            };
+            assert_eq!(decl.body.stmts.len(), 2);
+            assert!(matches!(decl.body.stmts[0], Stmt::Error { .. }));
+            assert!(matches!(decl.body.stmts[1], Stmt::Return { .. }));
         }
     }
+
+    #[test]
+    fn test_recovers_at_item_boundaries() {
+        let (file, errors) = parse(
+            "struct P { x: i32 y: i32 }\n\
+             fn f(a i32) -> i32 { return a; }\n\
+             enum E { A, B }",
+        );
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(file.items[0], Item::Error { .. }));
+        assert!(matches!(file.items[1], Item::Error { .. }));
+        assert!(matches!(file.items[2], Item::Enum(_)));
+    }
+
+    #[test]
+    fn test_missing_brace_keeps_function() {
+        let (file, errors) = parse(
+            "fn f() -> i32 {\n    if (true) {\n        return 1;\n\n\
+             fn g() -> i32 { return 2; }",
+        );
+        // One error for both unclosed blocks
+        assert_eq!(errors.len(), 1);
+        assert_eq!(fn_names(&file), ["f", "g"]);
+    }
+
+    #[test]
+    fn test_unterminated_switch_ends() {
+        let (_, errors) = parse("fn f(x: i32) { switch (x) { case 1: x = ");
+        assert!(!errors.is_empty());
+    }
 }
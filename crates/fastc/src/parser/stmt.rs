@@ -1,12 +1,43 @@
 //! Statement parsing
 
-use crate::ast::{Block, Case, ElseBranch, ForInit, ForStep, Stmt};
-use crate::diag::CompileError;
+use crate::ast::{Block, Case, ElseBranch, ForInit, ForStep, MatchArm, Stmt};
+use crate::diag::{CompileError, ErrorCode};
 use crate::lexer::Token;
 
 use super::Parser;
 
 impl Parser<'_> {
+    /// Parse a statement, or record the error and skip past it
+    fn parse_stmt_recovering(&mut self) -> Stmt {
+        let start = self.pos;
+        let start_span = self.current_span();
+        let ends_with_block = matches!(
+            self.current(),
+            Token::If
+                | Token::While
+                | Token::For
+                | Token::Switch
+                | Token::Match
+                | Token::Defer
+                | Token::Unsafe
+                | Token::LBrace
+        );
+        match self.parse_stmt() {
+            Ok(stmt) => stmt,
+            Err(error) => {
+                self.report(error);
+                self.synchronize(start, ends_with_block);
+                if self.pos == start {
+                    self.advance();
+                }
+                let end = self.previous_span().end.max(start_span.end);
+                Stmt::Error {
+                    span: start_span.start..end,
+                }
+            }
+        }
+    }
+
     /// Parse a statement
     pub fn parse_stmt(&mut self) -> Result<Stmt, CompileError> {
         let start = self.current_span().start;
@@ -17,6 +48,7 @@ impl Parser<'_> {
             Token::While => self.parse_while_stmt(),
             Token::For => self.parse_for_stmt(),
             Token::Switch => self.parse_switch_stmt(),
+            Token::Match => self.parse_match_stmt(),
             Token::Return => self.parse_return_stmt(),
             Token::Break => {
                 self.advance();
@@ -226,11 +258,8 @@ impl Parser<'_> {
                 self.consume(&Token::Colon, "expected ':' after case value")?;
 
                 let mut stmts = Vec::new();
-                while !self.check(&Token::Case)
-                    && !self.check(&Token::Default)
-                    && !self.check(&Token::RBrace)
-                {
-                    stmts.push(self.parse_stmt()?);
+                while !self.check(&Token::Case) && !self.check(&Token::Default) && self.at_stmt() {
+                    stmts.push(self.parse_stmt_recovering());
                 }
 
                 let case_end = self.previous_span().end;
@@ -244,16 +273,13 @@ impl Parser<'_> {
                 self.consume(&Token::Colon, "expected ':' after 'default'")?;
 
                 let mut stmts = Vec::new();
-                while !self.check(&Token::Case)
-                    && !self.check(&Token::Default)
-                    && !self.check(&Token::RBrace)
-                {
-                    stmts.push(self.parse_stmt()?);
+                while !self.check(&Token::Case) && !self.check(&Token::Default) && self.at_stmt() {
+                    stmts.push(self.parse_stmt_recovering());
                 }
 
                 default = Some(stmts);
             } else {
-                return Err(self.error("expected 'case' or 'default'"));
+                return Err(self.error(ErrorCode::UnexpectedToken, "expected 'case' or 'default'"));
             }
         }
 
@@ -268,6 +294,76 @@ impl Parser<'_> {
         })
     }
 
+    /// Parse a match statement
+    fn parse_match_stmt(&mut self) -> Result<Stmt, CompileError> {
+        let start = self.current_span().start;
+        self.consume(&Token::Match, "expected 'match'")?;
+        self.consume(&Token::LParen, "expected '(' after 'match'")?;
+        let expr = self.parse_expr()?;
+        self.consume(&Token::RParen, "expected ')'")?;
+        self.consume(&Token::LBrace, "expected '{'")?;
+
+        let mut arms = Vec::new();
+        let mut default = None;
+
+        while !self.check(&Token::RBrace) && !self.is_at_end() {
+            if self.check(&Token::Case) {
+                let arm_start = self.current_span().start;
+                self.advance();
+                let variant = self.expect_ident()?;
+
+                let mut bindings = Vec::new();
+                if self.check(&Token::LParen) {
+                    self.advance();
+                    if !self.check(&Token::RParen) {
+                        bindings.push(self.expect_ident()?);
+                        while self.check(&Token::Comma) {
+                            self.advance();
+                            bindings.push(self.expect_ident()?);
+                        }
+                    }
+                    self.consume(&Token::RParen, "expected ')' after bindings")?;
+                }
+                self.consume(&Token::Colon, "expected ':' after match pattern")?;
+
+                let mut stmts = Vec::new();
+                while !self.check(&Token::Case) && !self.check(&Token::Default) && self.at_stmt() {
+                    stmts.push(self.parse_stmt_recovering());
+                }
+
+                let arm_end = self.previous_span().end;
+                arms.push(MatchArm {
+                    variant,
+                    bindings,
+                    stmts,
+                    span: arm_start..arm_end,
+                });
+            } else if self.check(&Token::Default) {
+                self.advance();
+                self.consume(&Token::Colon, "expected ':' after 'default'")?;
+
+                let mut stmts = Vec::new();
+                while !self.check(&Token::Case) && !self.check(&Token::Default) && self.at_stmt() {
+                    stmts.push(self.parse_stmt_recovering());
+                }
+
+                default = Some(stmts);
+            } else {
+                return Err(self.error(ErrorCode::UnexpectedToken, "expected 'case' or 'default'"));
+            }
+        }
+
+        self.consume(&Token::RBrace, "expected '}'")?;
+        let end = self.previous_span().end;
+
+        Ok(Stmt::Match {
+            expr,
+            arms,
+            default,
+            span: start..end,
+        })
+    }
+
     /// Parse a return statement
     fn parse_return_stmt(&mut self) -> Result<Stmt, CompileError> {
         let start = self.current_span().start;
@@ -363,11 +459,17 @@ impl Parser<'_> {
         self.consume(&Token::LBrace, "expected '{'")?;
 
         let mut stmts = Vec::new();
-        while !self.check(&Token::RBrace) && !self.is_at_end() {
-            stmts.push(self.parse_stmt()?);
+        while self.at_stmt() {
+            stmts.push(self.parse_stmt_recovering());
         }
 
-        self.consume(&Token::RBrace, "expected '}'")?;
+        if self.check(&Token::RBrace) {
+            self.advance();
+        } else {
+            // At the end of input or the next item: keep what was parsed
+            let error = self.error(ErrorCode::UnexpectedToken, "expected '}'");
+            self.report(error);
+        }
         let end = self.previous_span().end;
 
         Ok(Block {
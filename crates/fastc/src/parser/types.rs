@@ -1,7 +1,7 @@
 //! Type parsing
 
 use crate::ast::{ConstExpr, PrimitiveType, TypeExpr};
-use crate::diag::CompileError;
+use crate::diag::{CompileError, ErrorCode};
 use crate::lexer::Token;
 
 use super::Parser;
@@ -170,16 +170,32 @@ impl Parser<'_> {
                 })
             }
 
-            // Named type
+            // Named type, or an instance of a generic struct
             Token::Ident(name) => {
                 self.advance();
-                Ok(TypeExpr::Named(name))
+                if self.check(&Token::LBracket) {
+                    Ok(TypeExpr::Generic(name, self.parse_type_args()?))
+                } else {
+                    Ok(TypeExpr::Named(name))
+                }
             }
 
-            _ => Err(self.error("expected type")),
+            _ => Err(self.error(ErrorCode::UnexpectedToken, "expected type")),
         }
     }
 
+    /// Parse type arguments: [i32, opt(T)]
+    pub fn parse_type_args(&mut self) -> Result<Vec<TypeExpr>, CompileError> {
+        self.consume(&Token::LBracket, "expected '['")?;
+        let mut args = vec![self.parse_type()?];
+        while self.check(&Token::Comma) {
+            self.advance();
+            args.push(self.parse_type()?);
+        }
+        self.consume(&Token::RBracket, "expected ']'")?;
+        Ok(args)
+    }
+
     /// Parse a constant expression
     pub fn parse_const_expr(&mut self) -> Result<ConstExpr, CompileError> {
         self.parse_const_binary()
@@ -194,7 +210,10 @@ impl Parser<'_> {
 
             // Check for chained binary operator (not allowed)
             if self.current().is_binary_op() {
-                return Err(self.error("chained binary operators require parentheses"));
+                return Err(self.error(
+                    ErrorCode::ChainedOperators,
+                    "chained binary operators require parentheses",
+                ));
             }
 
             Ok(ConstExpr::Binary {
@@ -287,7 +306,11 @@ impl Parser<'_> {
                         self.advance();
                         s
                     }
-                    _ => return Err(self.error("expected string literal")),
+                    _ => {
+                        return Err(
+                            self.error(ErrorCode::UnexpectedToken, "expected string literal")
+                        );
+                    }
                 };
                 self.consume(&Token::RParen, "expected ')'")?;
                 Ok(ConstExpr::CStr(s))
@@ -300,12 +323,16 @@ impl Parser<'_> {
                         self.advance();
                         s
                     }
-                    _ => return Err(self.error("expected string literal")),
+                    _ => {
+                        return Err(
+                            self.error(ErrorCode::UnexpectedToken, "expected string literal")
+                        );
+                    }
                 };
                 self.consume(&Token::RParen, "expected ')'")?;
                 Ok(ConstExpr::Bytes(s))
             }
-            _ => Err(self.error("expected constant expression")),
+            _ => Err(self.error(ErrorCode::UnexpectedToken, "expected constant expression")),
         }
     }
 }
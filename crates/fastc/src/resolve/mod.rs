@@ -10,10 +10,12 @@ mod scope;
 pub use scope::*;
 
 use crate::ast::{
-    Block, ConstExpr, Expr, ExternBlock, ExternItem, File, FnDecl, Item, Stmt, StructDecl, TypeExpr,
+    Block, ConstDecl, ConstExpr, Expr, ExternBlock, ExternItem, File, FnDecl, Item, Stmt,
+    StructDecl, TypeExpr,
 };
-use crate::diag::CompileError;
+use crate::diag::{CompileError, ErrorCode, Fix, find_similar};
 use crate::lexer::Span;
+use std::collections::{HashMap, HashSet};
 
 /// Name resolver
 pub struct Resolver<'a> {
@@ -42,6 +44,7 @@ impl<'a> Resolver<'a> {
         for item in &file.items {
             self.resolve_item(item);
         }
+        self.check_const_cycles(&file.items);
 
         // Return all errors collected during resolution
         if !self.errors.is_empty() {
@@ -68,6 +71,7 @@ impl<'a> Resolver<'a> {
             Item::Extern(extern_block) => self.declare_extern(extern_block),
             Item::Use(_) => {} // Use declarations handled later
             Item::Mod(mod_decl) => self.declare_mod(mod_decl),
+            Item::Error { .. } => {}
         }
     }
 
@@ -163,7 +167,11 @@ impl<'a> Resolver<'a> {
         };
 
         if let Err(sym) = self.symbols.define(symbol) {
-            self.error_redefinition(&sym.name, &sym.span);
+            // Repeating an opaque declaration (e.g. in two extern blocks) is harmless
+            let existing = self.symbols.lookup_current(&sym.name);
+            if !existing.is_some_and(|e| matches!(e.kind, SymbolKind::Opaque)) {
+                self.error_redefinition(&sym.name, &sym.span);
+            }
         }
     }
 
@@ -189,7 +197,11 @@ impl<'a> Resolver<'a> {
                     };
 
                     if let Err(sym) = self.symbols.define(symbol) {
-                        self.error_redefinition(&sym.name, &sym.span);
+                        // Extern prototypes may be repeated if the signatures agree
+                        let existing = self.symbols.lookup_current(&sym.name);
+                        if !existing.is_some_and(|e| e.ty == sym.ty) {
+                            self.error_redefinition(&sym.name, &sym.span);
+                        }
                     }
                 }
                 ExternItem::Struct(struct_decl) => self.declare_struct(struct_decl),
@@ -211,6 +223,7 @@ impl<'a> Resolver<'a> {
             Item::Extern(extern_block) => self.resolve_extern(extern_block),
             Item::Use(_) => {} // Use declarations are informational
             Item::Mod(mod_decl) => self.resolve_mod(mod_decl),
+            Item::Error { .. } => {}
         }
     }
 
@@ -442,12 +455,48 @@ impl<'a> Resolver<'a> {
                     }
                 }
             }
+            Stmt::Match {
+                expr,
+                arms,
+                default,
+                ..
+            } => {
+                self.resolve_expr(expr);
+                for arm in arms {
+                    if self.symbols.lookup(&arm.variant).is_none() {
+                        self.error_undefined(&arm.variant, &arm.span);
+                    }
+
+                    // Bindings are only in scope in their arm
+                    self.symbols.enter_scope();
+                    for binding in arm.bindings.iter().filter(|b| b.as_str() != "_") {
+                        let symbol = Symbol {
+                            name: binding.clone(),
+                            kind: SymbolKind::Variable,
+                            ty: TypeExpr::Void, // Placeholder, will be refined by type checker
+                            span: arm.span.clone(),
+                        };
+                        if let Err(sym) = self.symbols.define(symbol) {
+                            self.error_redefinition(&sym.name, &sym.span);
+                        }
+                    }
+                    for stmt in &arm.stmts {
+                        self.resolve_stmt(stmt);
+                    }
+                    self.symbols.exit_scope();
+                }
+                if let Some(default_stmts) = default {
+                    for stmt in default_stmts {
+                        self.resolve_stmt(stmt);
+                    }
+                }
+            }
             Stmt::Return { value, .. } => {
                 if let Some(value) = value {
                     self.resolve_expr(value);
                 }
             }
-            Stmt::Break { .. } | Stmt::Continue { .. } => {}
+            Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Error { .. } => {}
             Stmt::Defer { body, .. } => {
                 self.resolve_block(body);
             }
@@ -526,7 +575,21 @@ impl<'a> Resolver<'a> {
             Expr::Err { value, .. } => {
                 self.resolve_expr(value);
             }
-            Expr::StructLit { name, fields, span } => {
+            Expr::Builtin { args, .. } => {
+                for arg in args {
+                    self.resolve_expr(arg);
+                }
+            }
+            // Generic instances are replaced by monomorphization before
+            // name resolution runs
+            Expr::Instance { type_args, .. } => {
+                for ty in type_args {
+                    self.resolve_type(ty);
+                }
+            }
+            Expr::StructLit {
+                name, fields, span, ..
+            } => {
                 // Check that the struct type exists
                 if self.symbols.lookup(name).is_none() {
                     self.error_undefined(name, span);
@@ -551,6 +614,7 @@ impl<'a> Resolver<'a> {
                 if let Some(sym) = self.symbols.lookup(name) {
                     if sym.kind != SymbolKind::Constant {
                         self.errors.push(CompileError::resolve(
+                            ErrorCode::NotAConstant,
                             format!("'{}' is not a constant", name),
                             0..0,
                             self.source,
@@ -558,6 +622,7 @@ impl<'a> Resolver<'a> {
                     }
                 } else {
                     self.errors.push(CompileError::resolve(
+                        ErrorCode::UndefinedConstant,
                         format!("undefined constant '{}'", name),
                         0..0,
                         self.source,
@@ -584,7 +649,7 @@ impl<'a> Resolver<'a> {
 
     fn resolve_type(&mut self, ty: &TypeExpr) {
         match ty {
-            TypeExpr::Primitive(_) | TypeExpr::Void => {}
+            TypeExpr::Primitive(_) | TypeExpr::Void | TypeExpr::Error => {}
 
             TypeExpr::Named(name) => {
                 if let Some(sym) = self.symbols.lookup(name) {
@@ -592,6 +657,7 @@ impl<'a> Resolver<'a> {
                         SymbolKind::Struct | SymbolKind::Enum | SymbolKind::Opaque => {}
                         _ => {
                             self.errors.push(CompileError::resolve(
+                                ErrorCode::NotAType,
                                 format!("'{}' is not a type", name),
                                 0..0,
                                 self.source,
@@ -600,6 +666,7 @@ impl<'a> Resolver<'a> {
                     }
                 } else {
                     self.errors.push(CompileError::resolve(
+                        ErrorCode::UndefinedType,
                         format!("undefined type '{}'", name),
                         0..0,
                         self.source,
@@ -607,6 +674,12 @@ impl<'a> Resolver<'a> {
                 }
             }
 
+            TypeExpr::Generic(_, args) => {
+                for arg in args {
+                    self.resolve_type(arg);
+                }
+            }
+
             TypeExpr::Ref(inner)
             | TypeExpr::Mref(inner)
             | TypeExpr::Raw(inner)
@@ -617,9 +690,9 @@ impl<'a> Resolver<'a> {
                 self.resolve_type(inner);
             }
 
-            TypeExpr::Arr(elem, _size) => {
+            TypeExpr::Arr(elem, size) => {
                 self.resolve_type(elem);
-                // Size is a const expr, but typically just a literal
+                self.resolve_const_expr(size);
             }
 
             TypeExpr::Res(ok, err) => {
@@ -636,23 +709,83 @@ impl<'a> Resolver<'a> {
         }
     }
 
+    // === Constant cycles ===
+
+    /// Report constants whose value depends on itself, which can never be
+    /// evaluated. Each cycle is reported once, at its first constant by name.
+    fn check_const_cycles(&mut self, items: &[Item]) {
+        let mut consts = HashMap::new();
+        collect_consts(items, &mut consts);
+        let mut names: Vec<&str> = consts.keys().copied().collect();
+        names.sort_unstable();
+
+        let mut done = HashSet::new();
+        for name in names {
+            self.visit_const(name, &consts, &mut Vec::new(), &mut done);
+        }
+    }
+
+    fn visit_const<'c>(
+        &mut self,
+        name: &'c str,
+        consts: &HashMap<&'c str, &'c ConstDecl>,
+        path: &mut Vec<&'c str>,
+        done: &mut HashSet<&'c str>,
+    ) {
+        if done.contains(name) {
+            return;
+        }
+        let Some(decl) = consts.get(name) else {
+            return;
+        };
+        if let Some(start) = path.iter().position(|n| *n == name) {
+            let cycle = path[start..].join(" -> ");
+            self.errors.push(CompileError::resolve(
+                ErrorCode::CyclicConstant,
+                format!(
+                    "constant '{}' is defined in terms of itself ({} -> {})",
+                    name, cycle, name
+                ),
+                decl.span.clone(),
+                self.source,
+            ));
+            return;
+        }
+
+        path.push(name);
+        let mut refs = Vec::new();
+        const_refs(&decl.value, &mut refs);
+        for referenced in refs {
+            self.visit_const(referenced, consts, path, done);
+        }
+        path.pop();
+        done.insert(name);
+    }
+
     // === Error helpers ===
 
     fn error_undefined(&mut self, name: &str, span: &Span) {
         // Try to find a similar name to suggest
-        let hint = self
-            .find_similar_name(name)
-            .map(|similar| format!("did you mean '{}'?", similar));
-
-        if let Some(hint) = hint {
-            self.errors.push(CompileError::resolve_with_hint(
+        if let Some(similar) = self.find_similar_name(name) {
+            // Only offer the replacement when the span is exactly the name
+            let fix = (self.source.get(span.clone()) == Some(name)).then(|| {
+                Fix::replace(
+                    format!("replace with `{}`", similar),
+                    span.clone(),
+                    &similar,
+                )
+            });
+            let error = CompileError::resolve_with_hint(
+                ErrorCode::UndefinedName,
                 format!("undefined name '{}'", name),
                 span.clone(),
                 self.source,
-                hint,
-            ));
+                format!("did you mean '{}'?", similar),
+            );
+            self.errors.push(error.with_fix(fix));
         } else {
             self.errors.push(CompileError::resolve(
+                ErrorCode::UndefinedName,
                 format!("undefined name '{}'", name),
                 span.clone(),
                 self.source,
@@ -662,6 +795,7 @@ impl<'a> Resolver<'a> {
 
     fn error_redefinition(&mut self, name: &str, span: &Span) {
         self.errors.push(CompileError::resolve(
+            ErrorCode::Redefinition,
             format!("redefinition of '{}'", name),
             span.clone(),
             self.source,
@@ -670,63 +804,49 @@ impl<'a> Resolver<'a> {
 
     /// Find a similar name in the symbol table (for "did you mean" hints)
     fn find_similar_name(&self, target: &str) -> Option<String> {
-        let all_names = self.symbols.all_names();
-        let mut best_match: Option<(String, usize)> = None;
-
-        for name in all_names {
-            let dist = Self::edit_distance(target, &name);
-            // Only suggest if the edit distance is reasonable (e.g., <= 3)
-            // and the name is somewhat similar in length
-            if dist <= 3 && dist < target.len() {
-                match &best_match {
-                    None => best_match = Some((name, dist)),
-                    Some((_, best_dist)) if dist < *best_dist => best_match = Some((name, dist)),
-                    _ => {}
-                }
-            }
-        }
-
-        best_match.map(|(name, _)| name)
+        find_similar(target, self.symbols.all_names())
     }
+}
 
-    /// Calculate Levenshtein edit distance between two strings
-    fn edit_distance(a: &str, b: &str) -> usize {
-        let a_len = a.len();
-        let b_len = b.len();
-
-        if a_len == 0 {
-            return b_len;
-        }
-        if b_len == 0 {
-            return a_len;
-        }
-
-        let a_chars: Vec<char> = a.chars().collect();
-        let b_chars: Vec<char> = b.chars().collect();
-
-        let mut prev_row: Vec<usize> = (0..=b_len).collect();
-        let mut curr_row: Vec<usize> = vec![0; b_len + 1];
-
-        for (i, a_char) in a_chars.iter().enumerate().take(a_len) {
-            curr_row[0] = i + 1;
+impl Default for Resolver<'_> {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
 
-            for j in 0..b_len {
-                let cost = if *a_char == b_chars[j] { 0 } else { 1 };
-                curr_row[j + 1] = (prev_row[j + 1] + 1)
-                    .min(curr_row[j] + 1)
-                    .min(prev_row[j] + cost);
+/// Constants declared in `items`, including inline module bodies
+fn collect_consts<'c>(items: &'c [Item], consts: &mut HashMap<&'c str, &'c ConstDecl>) {
+    for item in items {
+        match item {
+            Item::Const(decl) => {
+                consts.insert(decl.name.as_str(), decl);
             }
-
-            std::mem::swap(&mut prev_row, &mut curr_row);
+            Item::Mod(mod_decl) => {
+                if let Some(body) = &mod_decl.body {
+                    collect_consts(body, consts);
+                }
+            }
+            _ => {}
         }
-
-        prev_row[b_len]
     }
 }
 
-impl Default for Resolver<'_> {
-    fn default() -> Self {
-        Self::new("")
+/// Names a constant expression refers to, in order
+fn const_refs<'c>(expr: &'c ConstExpr, refs: &mut Vec<&'c str>) {
+    match expr {
+        ConstExpr::Ident(name) => refs.push(name),
+        ConstExpr::Binary { lhs, rhs, .. } => {
+            const_refs(lhs, refs);
+            const_refs(rhs, refs);
+        }
+        ConstExpr::Unary { operand: inner, .. }
+        | ConstExpr::Paren(inner)
+        | ConstExpr::Cast { expr: inner, .. } => const_refs(inner, refs),
+        ConstExpr::IntLit(_)
+        | ConstExpr::FloatLit(_)
+        | ConstExpr::BoolLit(_)
+        | ConstExpr::CStr(_)
+        | ConstExpr::Bytes(_) => {}
     }
 }
 
@@ -818,4 +938,58 @@ mod tests {
         // Functions should be able to call other functions declared later
         check_ok("fn foo() -> i32 { return bar(); } fn bar() -> i32 { return 1; }");
     }
+
+    #[test]
+    fn test_repeated_extern_prototype() {
+        check_ok(
+            "extern \"C\" { unsafe fn abs(x: i32) -> i32; opaque Handle; }
+             extern \"C\" { unsafe fn abs(x: i32) -> i32; opaque Handle; }",
+        );
+    }
+
+    #[test]
+    fn test_conflicting_extern_prototype() {
+        check_error(
+            "extern \"C\" { unsafe fn abs(x: i32) -> i32; }
+             extern \"C\" { unsafe fn abs(x: i64) -> i64; }",
+            "redefinition of 'abs'",
+        );
+    }
+
+    #[test]
+    fn test_cyclic_constant() {
+        check_error(
+            "const A: usize = (B + 1);
+             const B: usize = A;
+             fn f(x: ref(arr(i32, A))) -> i32 { return 0; }",
+            "constant 'A' is defined in terms of itself (A -> B -> A)",
+        );
+        check_error(
+            "const N: usize = N;",
+            "constant 'N' is defined in terms of itself (N -> N)",
+        );
+    }
+
+    #[test]
+    fn test_array_size_names_a_constant() {
+        check_error(
+            "fn f(x: ref(arr(i32, Z))) -> i32 { return 0; }",
+            "undefined constant 'Z'",
+        );
+        check_error(
+            "fn g() -> usize { return 4; } struct S { a: arr(i32, g) }",
+            "'g' is not a constant",
+        );
+    }
+
+    #[test]
+    fn test_undefined_name_fix() {
+        let source = "fn main() -> i32 { let count: i32 = 3; return cuont; }";
+        let error = compile(source, "test.fc").unwrap_err();
+        let fix = error.fix().expect("typo should have a fix");
+        assert_eq!(
+            crate::diag::apply_fixes(source, [fix]).0,
+            "fn main() -> i32 { let count: i32 = 3; return count; }"
+        );
+    }
 }
@@ -1,11 +1,18 @@
 //! Type context for type checking
 
+use std::collections::HashMap;
+
 use crate::ast::TypeExpr;
+use crate::lexer::Span;
 
-/// Type context for tracking types during type checking
-#[derive(Debug, Default)]
+/// Types inferred for expressions during type checking
+///
+/// This is the side table lowering reads expression types from. Entries are
+/// keyed by the enclosing function and the expression's span: spans are
+/// unique within one function body, but not across module files.
+#[derive(Debug, Default, Clone)]
 pub struct TypeContext {
-    // TODO: Add type tracking
+    fns: HashMap<String, HashMap<Span, TypeExpr>>,
 }
 
 impl TypeContext {
@@ -13,9 +20,16 @@ impl TypeContext {
         Self::default()
     }
 
-    /// Check if two types are equal
-    pub fn types_equal(&self, _a: &TypeExpr, _b: &TypeExpr) -> bool {
-        // TODO: Implement type equality
-        true
+    /// Record the type of the expression at `span` in function `fn_name`
+    pub fn record(&mut self, fn_name: &str, span: Span, ty: TypeExpr) {
+        self.fns
+            .entry(fn_name.to_string())
+            .or_default()
+            .insert(span, ty);
+    }
+
+    /// Type of the expression at `span` in function `fn_name`
+    pub fn expr_type(&self, fn_name: &str, span: &Span) -> Option<&TypeExpr> {
+        self.fns.get(fn_name)?.get(span)
     }
 }
@@ -13,10 +13,10 @@ pub use context::*;
 pub use safety::*;
 
 use crate::ast::{
-    BinOp, Block, ConstExpr, EnumDecl, Expr, ExternItem, File, FnDecl, Item, PrimitiveType, Repr,
-    Stmt, StructDecl, TypeExpr, UnaryOp,
+    BinOp, Block, Builtin, ConstExpr, EnumDecl, Expr, ExternItem, FieldInit, File, FnDecl, Item,
+    MatchArm, PrimitiveType, Repr, Stmt, StructDecl, TypeExpr, UnaryOp, Variant,
 };
-use crate::diag::CompileError;
+use crate::diag::{CompileError, ErrorCode, Fix, find_similar};
 use crate::lexer::Span;
 use crate::resolve::{Symbol, SymbolKind, SymbolTable};
 use std::collections::{HashMap, HashSet};
@@ -30,6 +30,21 @@ pub struct TypeChecker<'a> {
     errors: Vec<CompileError>,
     enum_decls: HashMap<String, EnumDecl>,
     struct_decls: HashMap<String, StructDecl>,
+    /// Const values, for range-checking constant array indices
+    consts: HashMap<String, ConstExpr>,
+    loop_depth: usize,
+    breakable_depth: usize,
+    /// (loop_depth, breakable_depth) at entry of each enclosing defer block
+    defer_bases: Vec<(usize, usize)>,
+    /// Function whose body is being checked
+    current_fn: Option<String>,
+    /// Pointee type and name of each `@drop` function seen so far
+    drop_fns: Vec<(TypeExpr, String)>,
+    /// Inferred expression types, consumed by lowering
+    types: TypeContext,
+    /// Span of the innermost statement, when it can be wrapped in `unsafe`
+    /// on its own: it declares no names and holds no other statements
+    unsafe_fix_span: Option<Span>,
 }
 
 impl<'a> TypeChecker<'a> {
@@ -42,12 +57,42 @@ impl<'a> TypeChecker<'a> {
             errors: Vec::new(),
             enum_decls: HashMap::new(),
             struct_decls: HashMap::new(),
+            consts: HashMap::new(),
+            loop_depth: 0,
+            breakable_depth: 0,
+            defer_bases: Vec::new(),
+            current_fn: None,
+            drop_fns: Vec::new(),
+            types: TypeContext::new(),
+            unsafe_fix_span: None,
         }
     }
 
+    /// Get the inferred expression types (for lowering)
+    pub fn into_types(self) -> TypeContext {
+        self.types
+    }
+
     pub fn check(&mut self, file: &File) -> Result<(), CompileError> {
         // First pass: collect type declarations for validation
+        self.collect_type_decls(&file.items);
+
+        // Second pass: type check items
         for item in &file.items {
+            self.check_item(item);
+        }
+
+        // Return all errors collected during type checking
+        if !self.errors.is_empty() {
+            Err(CompileError::multiple(std::mem::take(&mut self.errors)))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Collect struct, enum, and const declarations, including those in inline modules
+    fn collect_type_decls(&mut self, items: &[Item]) {
+        for item in items {
             match item {
                 Item::Enum(enum_decl) => {
                     self.enum_decls
@@ -57,45 +102,89 @@ impl<'a> TypeChecker<'a> {
                     self.struct_decls
                         .insert(struct_decl.name.clone(), struct_decl.clone());
                 }
+                Item::Const(const_decl) => {
+                    self.consts
+                        .insert(const_decl.name.clone(), const_decl.value.clone());
+                }
+                Item::Extern(extern_block) => {
+                    for extern_item in &extern_block.items {
+                        match extern_item {
+                            ExternItem::Enum(enum_decl) => {
+                                self.enum_decls
+                                    .insert(enum_decl.name.clone(), enum_decl.clone());
+                            }
+                            ExternItem::Struct(struct_decl) => {
+                                self.struct_decls
+                                    .insert(struct_decl.name.clone(), struct_decl.clone());
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Item::Mod(mod_decl) => {
+                    if let Some(body) = &mod_decl.body {
+                        self.collect_type_decls(body);
+                    }
+                }
                 _ => {}
             }
         }
-
-        // Second pass: type check items
-        for item in &file.items {
-            self.check_item(item);
-        }
-
-        // Return all errors collected during type checking
-        if !self.errors.is_empty() {
-            Err(CompileError::multiple(std::mem::take(&mut self.errors)))
-        } else {
-            Ok(())
-        }
     }
 
     fn check_item(&mut self, item: &Item) {
         match item {
             Item::Fn(fn_decl) => self.check_fn(fn_decl),
-            Item::Struct(_) => {} // Struct fields were checked during resolution
-            Item::Enum(_) => {}
-            Item::Const(_) => {} // Const type was declared
+            Item::Struct(struct_decl) => {
+                // Field names were checked during resolution
+                for field in &struct_decl.fields {
+                    self.check_arr_sizes(&field.ty, &field.span);
+                }
+            }
+            Item::Enum(enum_decl) => {
+                for variant in &enum_decl.variants {
+                    for ty in variant.fields.iter().flatten() {
+                        self.check_arr_sizes(ty, &variant.span);
+                    }
+                }
+            }
+            Item::Const(const_decl) => {
+                self.check_arr_sizes(&const_decl.ty, &const_decl.span);
+                self.check_const_literal(&const_decl.value, &const_decl.ty, &const_decl.span);
+            }
             Item::Opaque(_) => {}
             Item::Extern(extern_block) => {
                 // Validate FFI types in extern signatures
                 for extern_item in &extern_block.items {
-                    if let ExternItem::Fn(proto) = extern_item {
-                        // Check return type
-                        self.validate_ffi_type(&proto.return_type, &proto.span);
-                        // Check parameters
-                        for param in &proto.params {
-                            self.validate_ffi_type(&param.ty, &proto.span);
+                    match extern_item {
+                        ExternItem::Fn(proto) => {
+                            // Check return type
+                            self.check_arr_sizes(&proto.return_type, &proto.span);
+                            self.validate_ffi_type(&proto.return_type, &proto.span);
+                            // Check parameters
+                            for param in &proto.params {
+                                self.check_arr_sizes(&param.ty, &param.span);
+                                self.validate_ffi_type(&param.ty, &proto.span);
+                            }
+                        }
+                        ExternItem::Struct(struct_decl) => {
+                            for field in &struct_decl.fields {
+                                self.check_arr_sizes(&field.ty, &field.span);
+                            }
                         }
+                        ExternItem::Enum(_) | ExternItem::Opaque(_) => {}
                     }
                 }
             }
             Item::Use(_) => {} // Module imports don't need type checking
-            Item::Mod(_) => {} // Module declarations handled separately
+            Item::Error { .. } => {}
+            Item::Mod(mod_decl) => {
+                // Inline (and expanded) module items share the file's namespace
+                if let Some(body) = &mod_decl.body {
+                    for item in body {
+                        self.check_item(item);
+                    }
+                }
+            }
         }
     }
 
@@ -110,9 +199,16 @@ impl<'a> TypeChecker<'a> {
 
         // Set current return type
         self.current_fn_return_type = Some(fn_decl.return_type.clone());
+        self.current_fn = Some(fn_decl.name.clone());
+
+        self.check_arr_sizes(&fn_decl.return_type, &fn_decl.span);
+        if fn_decl.is_drop {
+            self.check_drop_fn(fn_decl);
+        }
 
         // Define parameters in scope
         for param in &fn_decl.params {
+            self.check_arr_sizes(&param.ty, &param.span);
             let symbol = Symbol {
                 name: param.name.clone(),
                 kind: SymbolKind::Variable,
@@ -127,12 +223,56 @@ impl<'a> TypeChecker<'a> {
 
         // Reset state
         self.current_fn_return_type = None;
+        self.current_fn = None;
         if fn_decl.is_unsafe {
             self.safety.exit_unsafe();
         }
         self.symbols.exit_scope();
     }
 
+    /// A `@drop` function takes one `own(T)` and returns `void`, and is the
+    /// only one for its `T`
+    fn check_drop_fn(&mut self, fn_decl: &FnDecl) {
+        let pointee = match (fn_decl.params.as_slice(), &fn_decl.return_type) {
+            ([param], TypeExpr::Void) => match &param.ty {
+                TypeExpr::Own(inner) => inner.as_ref().clone(),
+                _ => {
+                    self.error_with_hint(
+                        ErrorCode::DropSignature,
+                        format!("'@drop' function '{}' must take an own(T)", fn_decl.name),
+                        param.span.clone(),
+                        "declare the parameter as own(T) for the type it releases",
+                    );
+                    return;
+                }
+            },
+            _ => {
+                self.error(
+                    ErrorCode::DropSignature,
+                    format!(
+                        "'@drop' function '{}' must take exactly one own(T) parameter and return void",
+                        fn_decl.name
+                    ),
+                    fn_decl.span.clone(),
+                );
+                return;
+            }
+        };
+
+        if let Some((_, existing)) = self.drop_fns.iter().find(|(ty, _)| *ty == pointee) {
+            self.error(
+                ErrorCode::DuplicateDrop,
+                format!(
+                    "'{}' is already the drop function for {:?}",
+                    existing, pointee
+                ),
+                fn_decl.span.clone(),
+            );
+            return;
+        }
+        self.drop_fns.push((pointee, fn_decl.name.clone()));
+    }
+
     fn check_block(&mut self, block: &Block) {
         self.symbols.enter_scope();
         for stmt in &block.stmts {
@@ -142,6 +282,16 @@ impl<'a> TypeChecker<'a> {
     }
 
     fn check_stmt(&mut self, stmt: &Stmt) {
+        let wrappable = matches!(
+            stmt,
+            Stmt::Expr { .. } | Stmt::Discard { .. } | Stmt::Assign { .. } | Stmt::Return { .. }
+        );
+        let outer = std::mem::replace(&mut self.unsafe_fix_span, wrappable.then(|| stmt.span()));
+        self.check_stmt_inner(stmt);
+        self.unsafe_fix_span = outer;
+    }
+
+    fn check_stmt_inner(&mut self, stmt: &Stmt) {
         match stmt {
             Stmt::Let {
                 name,
@@ -149,9 +299,10 @@ impl<'a> TypeChecker<'a> {
                 init,
                 span,
             } => {
-                let init_ty = self.infer_expr(init);
+                self.check_arr_sizes(ty, span);
+                let init_ty = self.check_expr(init, Some(ty));
                 if !self.types_compatible(ty, &init_ty) {
-                    self.error_type_mismatch(ty, &init_ty, span);
+                    self.error_value_mismatch(ty, &init_ty, span, init);
                 }
 
                 // Define variable
@@ -166,10 +317,10 @@ impl<'a> TypeChecker<'a> {
 
             Stmt::Assign { lhs, rhs, span } => {
                 let lhs_ty = self.infer_expr(lhs);
-                let rhs_ty = self.infer_expr(rhs);
+                let rhs_ty = self.check_expr(rhs, Some(&lhs_ty));
 
                 if !self.types_compatible(&lhs_ty, &rhs_ty) {
-                    self.error_type_mismatch(&lhs_ty, &rhs_ty, span);
+                    self.error_value_mismatch(&lhs_ty, &rhs_ty, span, rhs);
                 }
 
                 // Check that lhs is assignable
@@ -185,6 +336,7 @@ impl<'a> TypeChecker<'a> {
                 let cond_ty = self.infer_expr(cond);
                 if !self.is_bool(&cond_ty) {
                     self.error(
+                        ErrorCode::NonBoolCondition,
                         format!("condition must be bool, got {:?}", cond_ty),
                         span.clone(),
                     );
@@ -215,6 +367,7 @@ impl<'a> TypeChecker<'a> {
                     TypeExpr::Res(ok, _) => (**ok).clone(),
                     _ => {
                         self.error(
+                            ErrorCode::IfLetOperand,
                             format!("if-let requires opt or res type, got {:?}", expr_ty),
                             span.clone(),
                         );
@@ -246,11 +399,14 @@ impl<'a> TypeChecker<'a> {
                 let cond_ty = self.infer_expr(cond);
                 if !self.is_bool(&cond_ty) {
                     self.error(
+                        ErrorCode::NonBoolCondition,
                         format!("condition must be bool, got {:?}", cond_ty),
                         span.clone(),
                     );
                 }
+                self.enter_loop();
                 self.check_block(body);
+                self.exit_loop();
             }
 
             Stmt::For {
@@ -265,9 +421,10 @@ impl<'a> TypeChecker<'a> {
                 if let Some(init) = init {
                     match init {
                         crate::ast::ForInit::Let { name, ty, init } => {
-                            let init_ty = self.infer_expr(init);
+                            self.check_arr_sizes(ty, &init.span());
+                            let init_ty = self.check_expr(init, Some(ty));
                             if !self.types_compatible(ty, &init_ty) {
-                                self.error_type_mismatch(ty, &init_ty, &init.span());
+                                self.error_value_mismatch(ty, &init_ty, &init.span(), init);
                             }
                             let symbol = Symbol {
                                 name: name.clone(),
@@ -279,9 +436,9 @@ impl<'a> TypeChecker<'a> {
                         }
                         crate::ast::ForInit::Assign { lhs, rhs } => {
                             let lhs_ty = self.infer_expr(lhs);
-                            let rhs_ty = self.infer_expr(rhs);
+                            let rhs_ty = self.check_expr(rhs, Some(&lhs_ty));
                             if !self.types_compatible(&lhs_ty, &rhs_ty) {
-                                self.error_type_mismatch(&lhs_ty, &rhs_ty, &lhs.span());
+                                self.error_value_mismatch(&lhs_ty, &rhs_ty, &lhs.span(), rhs);
                             }
                         }
                         crate::ast::ForInit::Call(expr) => {
@@ -294,6 +451,7 @@ impl<'a> TypeChecker<'a> {
                     let cond_ty = self.infer_expr(cond);
                     if !self.is_bool(&cond_ty) {
                         self.error(
+                            ErrorCode::NonBoolCondition,
                             format!("for condition must be bool, got {:?}", cond_ty),
                             cond.span(),
                         );
@@ -304,9 +462,9 @@ impl<'a> TypeChecker<'a> {
                     match step {
                         crate::ast::ForStep::Assign { lhs, rhs } => {
                             let lhs_ty = self.infer_expr(lhs);
-                            let rhs_ty = self.infer_expr(rhs);
+                            let rhs_ty = self.check_expr(rhs, Some(&lhs_ty));
                             if !self.types_compatible(&lhs_ty, &rhs_ty) {
-                                self.error_type_mismatch(&lhs_ty, &rhs_ty, &lhs.span());
+                                self.error_value_mismatch(&lhs_ty, &rhs_ty, &lhs.span(), rhs);
                             }
                         }
                         crate::ast::ForStep::Call(expr) => {
@@ -315,9 +473,11 @@ impl<'a> TypeChecker<'a> {
                     }
                 }
 
+                self.enter_loop();
                 for stmt in &body.stmts {
                     self.check_stmt(stmt);
                 }
+                self.exit_loop();
 
                 self.symbols.exit_scope();
             }
@@ -333,6 +493,7 @@ impl<'a> TypeChecker<'a> {
                 // Switch must be on integer or enum type
                 if !self.is_integer(&expr_ty) && !matches!(expr_ty, TypeExpr::Named(_)) {
                     self.error(
+                        ErrorCode::SwitchOperand,
                         format!(
                             "switch expression must be integer or enum, got {:?}",
                             expr_ty
@@ -344,6 +505,18 @@ impl<'a> TypeChecker<'a> {
                 // Exhaustiveness check for enums
                 if let TypeExpr::Named(enum_name) = &expr_ty {
                     if let Some(enum_decl) = self.enum_decls.get(enum_name).cloned() {
+                        if enum_decl.has_payload() {
+                            self.error_with_hint(
+                                ErrorCode::SwitchOnPayloadEnum,
+                                format!(
+                                    "cannot switch on enum '{}' with payload variants",
+                                    enum_name
+                                ),
+                                expr.span(),
+                                "use match (...) to destructure the variants",
+                            );
+                        }
+
                         let expected_variants: HashSet<String> = enum_decl
                             .variants
                             .iter()
@@ -366,6 +539,7 @@ impl<'a> TypeChecker<'a> {
 
                         if !missing.is_empty() && default.is_none() {
                             self.error(
+                                ErrorCode::NonExhaustive,
                                 format!(
                                     "non-exhaustive switch on enum '{}': missing variants {:?}",
                                     enum_name, missing
@@ -376,6 +550,7 @@ impl<'a> TypeChecker<'a> {
                     }
                 }
 
+                self.breakable_depth += 1;
                 for case in cases {
                     for stmt in &case.stmts {
                         self.check_stmt(stmt);
@@ -388,31 +563,74 @@ impl<'a> TypeChecker<'a> {
                         self.check_stmt(stmt);
                     }
                 }
+                self.breakable_depth -= 1;
             }
 
+            Stmt::Match {
+                expr,
+                arms,
+                default,
+                span,
+            } => self.check_match(expr, arms, default.as_deref(), span),
+
             Stmt::Return { value, span } => {
+                if !self.defer_bases.is_empty() {
+                    self.error(
+                        ErrorCode::ExitFromDefer,
+                        "cannot return from inside a defer block".to_string(),
+                        span.clone(),
+                    );
+                }
+
                 let expected = self
                     .current_fn_return_type
                     .clone()
                     .unwrap_or(TypeExpr::Void);
 
                 if let Some(value) = value {
-                    let actual = self.infer_expr(value);
+                    let actual = self.check_expr(value, Some(&expected));
                     if !self.types_compatible(&expected, &actual) {
-                        self.error_type_mismatch(&expected, &actual, span);
+                        self.error_value_mismatch(&expected, &actual, span, value);
                     }
                 } else if !matches!(expected, TypeExpr::Void) {
                     self.error(
+                        ErrorCode::MissingReturnValue,
                         format!("expected return value of type {:?}", expected),
                         span.clone(),
                     );
                 }
             }
 
-            Stmt::Break { .. } | Stmt::Continue { .. } => {}
+            Stmt::Break { span } => {
+                if let Some(&(_, breakable_base)) = self.defer_bases.last() {
+                    if self.breakable_depth <= breakable_base {
+                        self.error(
+                            ErrorCode::ExitFromDefer,
+                            "cannot break out of a defer block".to_string(),
+                            span.clone(),
+                        );
+                    }
+                }
+            }
+
+            Stmt::Continue { span } => {
+                if let Some(&(loop_base, _)) = self.defer_bases.last() {
+                    if self.loop_depth <= loop_base {
+                        self.error(
+                            ErrorCode::ExitFromDefer,
+                            "cannot continue out of a defer block".to_string(),
+                            span.clone(),
+                        );
+                    }
+                }
+            }
 
             Stmt::Defer { body, .. } => {
+                // Control flow may not leave the deferred block
+                self.defer_bases
+                    .push((self.loop_depth, self.breakable_depth));
                 self.check_block(body);
+                self.defer_bases.pop();
             }
 
             Stmt::Expr { expr, .. } => {
@@ -432,28 +650,347 @@ impl<'a> TypeChecker<'a> {
             Stmt::Block(block) => {
                 self.check_block(block);
             }
+
+            // Reported by the parser
+            Stmt::Error { .. } => {}
+        }
+    }
+
+    /// Check a match statement: arms must name variants of the scrutinee's
+    /// enum, bind one name per payload field, and cover every variant
+    /// unless there is a default
+    fn check_match(
+        &mut self,
+        expr: &Expr,
+        arms: &[MatchArm],
+        default: Option<&[Stmt]>,
+        span: &Span,
+    ) {
+        let expr_ty = self.infer_expr(expr);
+        let enum_decl = match &expr_ty {
+            TypeExpr::Named(name) => self.enum_decls.get(name).cloned(),
+            _ => None,
+        };
+        if enum_decl.is_none() {
+            self.error(
+                ErrorCode::MatchOperand,
+                format!("match expression must be an enum, got {:?}", expr_ty),
+                expr.span(),
+            );
+        }
+
+        let mut covered = HashSet::new();
+        self.breakable_depth += 1;
+        for arm in arms {
+            let mut field_tys = Vec::new();
+            if let Some(enum_decl) = &enum_decl {
+                match enum_decl.variant(&arm.variant) {
+                    Some(variant) => {
+                        field_tys = variant.payload().to_vec();
+                        if arm.bindings.len() != field_tys.len() {
+                            self.error(
+                                ErrorCode::PatternArity,
+                                format!(
+                                    "variant '{}' has {} payload field(s), but the pattern binds {}",
+                                    arm.variant,
+                                    field_tys.len(),
+                                    arm.bindings.len()
+                                ),
+                                arm.span.clone(),
+                            );
+                        }
+                        if !covered.insert(arm.variant.clone()) {
+                            self.error(
+                                ErrorCode::DuplicateArm,
+                                format!("duplicate match arm for variant '{}'", arm.variant),
+                                arm.span.clone(),
+                            );
+                        }
+                    }
+                    None => self.error(
+                        ErrorCode::UnknownVariant,
+                        format!(
+                            "'{}' is not a variant of enum '{}'",
+                            arm.variant, enum_decl.name
+                        ),
+                        arm.span.clone(),
+                    ),
+                }
+            }
+
+            self.symbols.enter_scope();
+            for (binding, ty) in arm.bindings.iter().zip(field_tys) {
+                if binding != "_" {
+                    let _ = self.symbols.define(Symbol {
+                        name: binding.clone(),
+                        kind: SymbolKind::Variable,
+                        ty,
+                        span: arm.span.clone(),
+                    });
+                }
+            }
+            for stmt in &arm.stmts {
+                self.check_stmt(stmt);
+            }
+            self.symbols.exit_scope();
+        }
+        if let Some(default_stmts) = default {
+            for stmt in default_stmts {
+                self.check_stmt(stmt);
+            }
+        }
+        self.breakable_depth -= 1;
+
+        // Exhaustiveness check, as for switch
+        if let Some(enum_decl) = &enum_decl {
+            let missing: Vec<String> = enum_decl
+                .variants
+                .iter()
+                .map(|v| format!("{}_{}", enum_decl.name, v.name))
+                .filter(|name| !covered.contains(name))
+                .collect();
+            if !missing.is_empty() && default.is_none() {
+                self.error(
+                    ErrorCode::NonExhaustive,
+                    format!(
+                        "non-exhaustive match on enum '{}': missing variants {:?}",
+                        enum_decl.name, missing
+                    ),
+                    span.clone(),
+                );
+            }
+        }
+    }
+
+    /// Type of an integer literal: the expected integer type if there is one
+    /// (range-checked), otherwise i32, or i64 for values that do not fit
+    fn int_literal_type(
+        &mut self,
+        value: i128,
+        expected: Option<&TypeExpr>,
+        span: &Span,
+    ) -> TypeExpr {
+        if let Some(TypeExpr::Primitive(p)) = expected
+            && let Some((min, max)) = p.int_range()
+        {
+            if value < min || value > max {
+                self.error_with_hint(
+                    ErrorCode::LiteralOutOfRange,
+                    format!("integer literal {} is out of range for {}", value, p.name()),
+                    span.clone(),
+                    format!("{} holds values from {} to {}", p.name(), min, max),
+                );
+            }
+            return TypeExpr::Primitive(*p);
+        }
+
+        if i32::try_from(value).is_ok() {
+            TypeExpr::Primitive(PrimitiveType::I32)
+        } else {
+            TypeExpr::Primitive(PrimitiveType::I64)
+        }
+    }
+
+    /// Whether an expression is built only from numeric literals, so its
+    /// type comes from context
+    fn is_untyped_literal(expr: &Expr) -> bool {
+        match expr {
+            Expr::IntLit { .. } | Expr::FloatLit { .. } => true,
+            Expr::Paren { inner, .. } => Self::is_untyped_literal(inner),
+            Expr::Unary {
+                op: UnaryOp::Neg | UnaryOp::BitNot,
+                operand,
+                ..
+            } => Self::is_untyped_literal(operand),
+            Expr::Binary { op, lhs, rhs, .. } => {
+                matches!(
+                    op,
+                    BinOp::Add
+                        | BinOp::Sub
+                        | BinOp::Mul
+                        | BinOp::Div
+                        | BinOp::Rem
+                        | BinOp::BitAnd
+                        | BinOp::BitOr
+                        | BinOp::BitXor
+                        | BinOp::Shl
+                        | BinOp::Shr
+                ) && Self::is_untyped_literal(lhs)
+                    && Self::is_untyped_literal(rhs)
+            }
+            _ => false,
+        }
+    }
+
+    /// Range-check integer literals in a const initializer against its type
+    fn check_const_literal(&mut self, value: &ConstExpr, ty: &TypeExpr, span: &Span) {
+        match value {
+            ConstExpr::IntLit(n) => {
+                self.int_literal_type(*n, Some(ty), span);
+            }
+            ConstExpr::Unary {
+                op: UnaryOp::Neg,
+                operand,
+            } => {
+                if let ConstExpr::IntLit(n) = operand.as_ref() {
+                    self.int_literal_type(-n, Some(ty), span);
+                }
+            }
+            ConstExpr::Paren(inner) => self.check_const_literal(inner, ty, span),
+            _ => {}
+        }
+    }
+
+    /// Check that `arr` sizes in a type evaluate to a `usize`, range-checking
+    /// literal sizes
+    fn check_arr_sizes(&mut self, ty: &TypeExpr, span: &Span) {
+        match ty {
+            TypeExpr::Arr(inner, size) => {
+                let reported = self.errors.len();
+                self.check_const_literal(size, &TypeExpr::Primitive(PrimitiveType::Usize), span);
+                let lookup = |name: &str| self.const_value(name);
+                if self.errors.len() == reported
+                    && let Err(reason) = size.eval_size(&lookup)
+                {
+                    self.error(
+                        ErrorCode::InvalidArraySize,
+                        format!("array size {}", reason),
+                        span.clone(),
+                    );
+                }
+                self.check_arr_sizes(inner, span);
+            }
+            TypeExpr::Ref(inner)
+            | TypeExpr::Mref(inner)
+            | TypeExpr::Raw(inner)
+            | TypeExpr::Rawm(inner)
+            | TypeExpr::Own(inner)
+            | TypeExpr::Slice(inner)
+            | TypeExpr::Opt(inner) => self.check_arr_sizes(inner, span),
+            TypeExpr::Res(ok, err) => {
+                self.check_arr_sizes(ok, span);
+                self.check_arr_sizes(err, span);
+            }
+            _ => {}
+        }
+    }
+
+    /// Value of a const in scope (not shadowed by a local)
+    fn const_value(&self, name: &str) -> Option<&ConstExpr> {
+        self.symbols
+            .lookup(name)
+            .filter(|sym| sym.kind == SymbolKind::Constant)
+            .and_then(|_| self.consts.get(name))
+    }
+
+    /// Reject a constant index outside `0..size` of an `arr`
+    fn check_const_index(&mut self, index: &Expr, size: &ConstExpr, span: &Span) {
+        let lookup = |name: &str| self.const_value(name);
+        let (Some(index), Some(len)) = (index.eval_int(&lookup), size.eval_int(&lookup)) else {
+            return;
+        };
+        if index >= 0 && index < len {
+            return;
         }
+        let message = format!("index {} is out of bounds for arr of length {}", index, len);
+        if len > 0 {
+            self.error_with_hint(
+                ErrorCode::IndexOutOfBounds,
+                message,
+                span.clone(),
+                format!("valid indices are 0 to {}", len - 1),
+            );
+        } else {
+            self.error(ErrorCode::IndexOutOfBounds, message, span.clone());
+        }
+    }
+
+    /// Look up an enum variant by its qualified name (e.g. `Shape_Circle`)
+    fn enum_variant(&self, name: &str) -> Option<(String, Variant)> {
+        self.enum_decls
+            .values()
+            .find_map(|e| e.variant(name).map(|v| (e.name.clone(), v.clone())))
+    }
+
+    fn enter_loop(&mut self) {
+        self.loop_depth += 1;
+        self.breakable_depth += 1;
+    }
+
+    fn exit_loop(&mut self) {
+        self.loop_depth -= 1;
+        self.breakable_depth -= 1;
     }
 
+    /// Infer the type of an expression and record it for lowering
     fn infer_expr(&mut self, expr: &Expr) -> TypeExpr {
+        self.check_expr(expr, None)
+    }
+
+    /// Infer the type of an expression where `expected` is the type its
+    /// context requires; untyped literals adopt it
+    fn check_expr(&mut self, expr: &Expr, expected: Option<&TypeExpr>) -> TypeExpr {
+        let ty = self.infer_expr_inner(expr, expected);
+        self.record_type(expr, &ty);
+        ty
+    }
+
+    fn record_type(&mut self, expr: &Expr, ty: &TypeExpr) {
+        if let Some(fn_name) = &self.current_fn {
+            self.types.record(fn_name, expr.span(), ty.clone());
+        }
+    }
+
+    fn infer_expr_inner(&mut self, expr: &Expr, expected: Option<&TypeExpr>) -> TypeExpr {
         match expr {
-            Expr::IntLit { .. } => TypeExpr::Primitive(PrimitiveType::I32), // Default to i32
-            Expr::FloatLit { .. } => TypeExpr::Primitive(PrimitiveType::F64), // Default to f64
+            Expr::IntLit { value, span } => self.int_literal_type(*value, expected, span),
+            Expr::FloatLit { .. } => match expected {
+                Some(TypeExpr::Primitive(p @ (PrimitiveType::F32 | PrimitiveType::F64))) => {
+                    TypeExpr::Primitive(*p)
+                }
+                _ => TypeExpr::Primitive(PrimitiveType::F64),
+            },
             Expr::BoolLit { .. } => TypeExpr::Primitive(PrimitiveType::Bool),
             Expr::CStr { .. } => TypeExpr::Raw(Box::new(TypeExpr::Primitive(PrimitiveType::U8))),
             Expr::Bytes { .. } => TypeExpr::Slice(Box::new(TypeExpr::Primitive(PrimitiveType::U8))),
 
-            Expr::Ident { name, .. } => {
+            Expr::Ident { name, span } => {
                 if let Some(sym) = self.symbols.lookup(name) {
-                    sym.ty.clone()
+                    let ty = sym.ty.clone();
+                    if sym.kind == SymbolKind::Constant
+                        && let Some((_, variant)) = self.enum_variant(name)
+                        && !variant.payload().is_empty()
+                    {
+                        self.error_with_hint(
+                            ErrorCode::PayloadVariantValue,
+                            format!("variant '{}' carries a payload", name),
+                            span.clone(),
+                            format!("construct it with {}(...)", name),
+                        );
+                    }
+                    ty
                 } else {
                     TypeExpr::Void // Error already reported during resolution
                 }
             }
 
             Expr::Binary { op, lhs, rhs, span } => {
-                let lhs_ty = self.infer_expr(lhs);
-                let rhs_ty = self.infer_expr(rhs);
+                // Arithmetic passes the expected type down to its operands;
+                // an untyped literal operand adopts its peer's type
+                let operand_expected = match op {
+                    BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => None,
+                    BinOp::And | BinOp::Or => Some(TypeExpr::Primitive(PrimitiveType::Bool)),
+                    _ => expected.cloned(),
+                };
+                let (lhs_ty, rhs_ty) =
+                    if Self::is_untyped_literal(lhs) && !Self::is_untyped_literal(rhs) {
+                        let rhs_ty = self.check_expr(rhs, operand_expected.as_ref());
+                        (self.check_expr(lhs, Some(&rhs_ty)), rhs_ty)
+                    } else {
+                        let lhs_ty = self.check_expr(lhs, operand_expected.as_ref());
+                        let rhs_ty = self.check_expr(rhs, Some(&lhs_ty));
+                        (lhs_ty, rhs_ty)
+                    };
 
                 // Binary ops require same types
                 if !self.types_compatible(&lhs_ty, &rhs_ty) {
@@ -469,6 +1006,7 @@ impl<'a> TypeChecker<'a> {
                     BinOp::And | BinOp::Or => {
                         if !self.is_bool(&lhs_ty) {
                             self.error(
+                                ErrorCode::OperandType,
                                 format!("logical operator requires bool, got {:?}", lhs_ty),
                                 span.clone(),
                             );
@@ -481,12 +1019,26 @@ impl<'a> TypeChecker<'a> {
             }
 
             Expr::Unary { op, operand, span } => {
-                let operand_ty = self.infer_expr(operand);
+                // A negated literal is range-checked as one value, so that
+                // `-128` fits in an i8
+                if *op == UnaryOp::Neg
+                    && let Expr::IntLit { value, .. } = operand.as_ref()
+                {
+                    let ty = self.int_literal_type(-value, expected, span);
+                    self.record_type(operand, &ty);
+                    return ty;
+                }
+
+                let operand_ty = match op {
+                    UnaryOp::Neg | UnaryOp::BitNot => self.check_expr(operand, expected),
+                    UnaryOp::Not => self.infer_expr(operand),
+                };
 
                 match op {
                     UnaryOp::Neg => {
                         if !self.is_numeric(&operand_ty) {
                             self.error(
+                                ErrorCode::OperandType,
                                 format!("negation requires numeric type, got {:?}", operand_ty),
                                 span.clone(),
                             );
@@ -496,6 +1048,7 @@ impl<'a> TypeChecker<'a> {
                     UnaryOp::Not => {
                         if !self.is_bool(&operand_ty) {
                             self.error(
+                                ErrorCode::OperandType,
                                 format!("logical not requires bool, got {:?}", operand_ty),
                                 span.clone(),
                             );
@@ -505,6 +1058,7 @@ impl<'a> TypeChecker<'a> {
                     UnaryOp::BitNot => {
                         if !self.is_integer(&operand_ty) {
                             self.error(
+                                ErrorCode::OperandType,
                                 format!("bitwise not requires integer, got {:?}", operand_ty),
                                 span.clone(),
                             );
@@ -514,9 +1068,36 @@ impl<'a> TypeChecker<'a> {
                 }
             }
 
-            Expr::Paren { inner, .. } => self.infer_expr(inner),
+            Expr::Paren { inner, .. } => self.check_expr(inner, expected),
 
             Expr::Call { callee, args, span } => {
+                // Variant constructor: Shape_Circle(r)
+                if let Expr::Ident { name, .. } = callee.as_ref()
+                    && let Some((enum_name, variant)) = self.enum_variant(name)
+                {
+                    let payload = variant.payload();
+                    if payload.is_empty() {
+                        self.error(
+                            ErrorCode::UnitVariantCall,
+                            format!("variant '{}' has no payload", name),
+                            span.clone(),
+                        );
+                    } else if args.len() != payload.len() {
+                        self.error(
+                            ErrorCode::ArgumentCount,
+                            format!("expected {} arguments, got {}", payload.len(), args.len()),
+                            span.clone(),
+                        );
+                    }
+                    for (arg, field_ty) in args.iter().zip(payload) {
+                        let arg_ty = self.check_expr(arg, Some(field_ty));
+                        if !self.types_compatible(field_ty, &arg_ty) {
+                            self.error_value_mismatch(field_ty, &arg_ty, &arg.span(), arg);
+                        }
+                    }
+                    return TypeExpr::Named(enum_name);
+                }
+
                 let callee_ty = self.infer_expr(callee);
 
                 match callee_ty {
@@ -527,16 +1108,20 @@ impl<'a> TypeChecker<'a> {
                     } => {
                         // Check unsafe
                         if is_unsafe && !self.safety.is_unsafe() {
-                            self.error_with_hint(
-                                "call to unsafe function requires unsafe block".to_string(),
+                            let error = CompileError::type_error_with_hint(
+                                ErrorCode::UnsafeCall,
+                                "call to unsafe function requires unsafe block",
                                 span.clone(),
+                                self.source,
                                 "wrap the call in an unsafe block: unsafe { ... }",
                             );
+                            self.errors.push(error.with_fix(self.unsafe_fix()));
                         }
 
                         // Check argument count
                         if args.len() != params.len() {
                             self.error(
+                                ErrorCode::ArgumentCount,
                                 format!("expected {} arguments, got {}", params.len(), args.len()),
                                 span.clone(),
                             );
@@ -544,9 +1129,9 @@ impl<'a> TypeChecker<'a> {
 
                         // Check argument types
                         for (arg, param_ty) in args.iter().zip(params.iter()) {
-                            let arg_ty = self.infer_expr(arg);
+                            let arg_ty = self.check_expr(arg, Some(param_ty));
                             if !self.types_compatible(param_ty, &arg_ty) {
-                                self.error_type_mismatch(param_ty, &arg_ty, &arg.span());
+                                self.error_value_mismatch(param_ty, &arg_ty, &arg.span(), arg);
                             }
                         }
 
@@ -554,6 +1139,7 @@ impl<'a> TypeChecker<'a> {
                     }
                     _ => {
                         self.error(
+                            ErrorCode::NotCallable,
                             format!("cannot call non-function type {:?}", callee_ty),
                             span.clone(),
                         );
@@ -565,25 +1151,38 @@ impl<'a> TypeChecker<'a> {
             Expr::Field { base, field, span } => {
                 let base_ty = self.infer_expr(base);
 
-                // Look up field in struct
-                if let TypeExpr::Named(struct_name) = &base_ty {
-                    // TODO: Look up struct definition and find field type
-                    // For now, return void as placeholder
-                    let _ = (struct_name, field, span);
-                    TypeExpr::Void
-                } else {
-                    self.error(
-                        format!("field access on non-struct type {:?}", base_ty),
-                        span.clone(),
-                    );
-                    TypeExpr::Void
+                // Field access auto-derefs through one level of reference
+                let target_ty = match &base_ty {
+                    TypeExpr::Ref(inner) | TypeExpr::Mref(inner) | TypeExpr::Own(inner) => {
+                        inner.as_ref()
+                    }
+                    other => other,
+                };
+
+                match target_ty {
+                    TypeExpr::Named(struct_name) if self.struct_decls.contains_key(struct_name) => {
+                        self.field_type(struct_name, field, span)
+                    }
+                    TypeExpr::Error => TypeExpr::Error,
+                    _ => {
+                        self.error(
+                            ErrorCode::FieldOnNonStruct,
+                            format!("field access on non-struct type {:?}", base_ty),
+                            span.clone(),
+                        );
+                        TypeExpr::Void
+                    }
                 }
             }
 
+            // addr(x) is a mutable borrow where an mref is expected
             Expr::Addr { operand, span } => {
                 let operand_ty = self.infer_expr(operand);
                 self.check_addressable(operand, span);
-                TypeExpr::Ref(Box::new(operand_ty))
+                match expected {
+                    Some(TypeExpr::Mref(_)) => TypeExpr::Mref(Box::new(operand_ty)),
+                    _ => TypeExpr::Ref(Box::new(operand_ty)),
+                }
             }
 
             Expr::Deref { operand, span } => {
@@ -594,15 +1193,19 @@ impl<'a> TypeChecker<'a> {
                     TypeExpr::Raw(inner) | TypeExpr::Rawm(inner) => {
                         // Deref of raw pointer requires unsafe
                         if !self.safety.is_unsafe() {
-                            self.error(
-                                "dereference of raw pointer requires unsafe block".to_string(),
+                            let error = CompileError::type_error(
+                                ErrorCode::RawDeref,
+                                "dereference of raw pointer requires unsafe block",
                                 span.clone(),
+                                self.source,
                             );
+                            self.errors.push(error.with_fix(self.unsafe_fix()));
                         }
                         *inner
                     }
                     _ => {
                         self.error(
+                            ErrorCode::DerefNonPointer,
                             format!("cannot dereference non-pointer type {:?}", operand_ty),
                             span.clone(),
                         );
@@ -620,6 +1223,7 @@ impl<'a> TypeChecker<'a> {
                     // Allow any integer for now
                     if !self.is_integer(&index_ty) {
                         self.error(
+                            ErrorCode::InvalidIndex,
                             format!("index must be integer, got {:?}", index_ty),
                             span.clone(),
                         );
@@ -628,9 +1232,13 @@ impl<'a> TypeChecker<'a> {
 
                 match base_ty {
                     TypeExpr::Slice(inner) => *inner,
-                    TypeExpr::Arr(inner, _) => *inner,
+                    TypeExpr::Arr(inner, size) => {
+                        self.check_const_index(index, &size, span);
+                        *inner
+                    }
                     _ => {
                         self.error(
+                            ErrorCode::InvalidIndex,
                             format!("cannot index non-array type {:?}", base_ty),
                             span.clone(),
                         );
@@ -645,6 +1253,7 @@ impl<'a> TypeChecker<'a> {
                 // Check that cast is valid
                 if !self.can_cast(&expr_ty, ty) {
                     self.error(
+                        ErrorCode::InvalidCast,
                         format!("cannot cast {:?} to {:?}", expr_ty, ty),
                         span.clone(),
                     );
@@ -655,31 +1264,275 @@ impl<'a> TypeChecker<'a> {
 
             Expr::None { ty, .. } => TypeExpr::Opt(Box::new(ty.clone())),
 
-            Expr::Some { value, .. } => {
-                let inner_ty = self.infer_expr(value);
-                TypeExpr::Opt(Box::new(inner_ty))
+            // Wrappers take the full type their context expects, so that
+            // `return ok(5);` in a `res(i32, E)` function has type `res(i32, E)`
+            Expr::Some { value, .. } => match expected {
+                Some(TypeExpr::Opt(inner)) => {
+                    self.check_wrapped(value, inner);
+                    TypeExpr::Opt(inner.clone())
+                }
+                _ => TypeExpr::Opt(Box::new(self.infer_expr(value))),
+            },
+
+            Expr::Ok { value, span } => match expected {
+                Some(TypeExpr::Res(ok, err)) => {
+                    self.check_wrapped(value, ok);
+                    TypeExpr::Res(ok.clone(), err.clone())
+                }
+                _ => {
+                    let ok_ty = self.infer_expr(value);
+                    self.error_with_hint(
+                        ErrorCode::UninferredResult,
+                        "cannot infer the error type of ok(...)".to_string(),
+                        span.clone(),
+                        "use ok(...) where a res(T, E) is expected, such as a typed let or a return",
+                    );
+                    TypeExpr::Res(Box::new(ok_ty), Box::new(TypeExpr::Void))
+                }
+            },
+
+            Expr::Err { value, span } => match expected {
+                Some(TypeExpr::Res(ok, err)) => {
+                    self.check_wrapped(value, err);
+                    TypeExpr::Res(ok.clone(), err.clone())
+                }
+                _ => {
+                    let err_ty = self.infer_expr(value);
+                    self.error_with_hint(
+                        ErrorCode::UninferredResult,
+                        "cannot infer the value type of err(...)".to_string(),
+                        span.clone(),
+                        "use err(...) where a res(T, E) is expected, such as a typed let or a return",
+                    );
+                    TypeExpr::Res(Box::new(TypeExpr::Void), Box::new(err_ty))
+                }
+            },
+
+            Expr::Builtin { op, args, span } => self.check_builtin(*op, args, span),
+
+            // Replaced by monomorphization before type checking
+            Expr::Instance { .. } => TypeExpr::Void,
+
+            Expr::StructLit {
+                name, fields, span, ..
+            } => {
+                self.check_struct_lit(name, fields, span);
+                TypeExpr::Named(name.clone())
             }
+        }
+    }
 
-            Expr::Ok { value, .. } => {
-                let inner_ty = self.infer_expr(value);
-                // We don't know the error type, use void as placeholder
-                TypeExpr::Res(Box::new(inner_ty), Box::new(TypeExpr::Void))
+    /// Type a builtin; each is generic over the payload or element type of
+    /// its first argument
+    fn check_builtin(&mut self, op: Builtin, args: &[Expr], span: &Span) -> TypeExpr {
+        let arg_ty = self.infer_expr(&args[0]);
+        let bool_ty = TypeExpr::Primitive(PrimitiveType::Bool);
+
+        let ty = match (op, &arg_ty) {
+            (Builtin::IsSome | Builtin::IsNone, TypeExpr::Opt(_)) => Some(bool_ty),
+            (Builtin::IsOk | Builtin::IsErr, TypeExpr::Res(_, _)) => Some(bool_ty),
+            (Builtin::Unwrap | Builtin::UnwrapOr, TypeExpr::Opt(inner))
+            | (Builtin::Unwrap | Builtin::UnwrapOr, TypeExpr::Res(inner, _)) => {
+                Some(inner.as_ref().clone())
+            }
+            (Builtin::UnwrapErr, TypeExpr::Res(_, err)) => Some(err.as_ref().clone()),
+            (Builtin::ToRaw, TypeExpr::Ref(inner) | TypeExpr::Mref(inner)) => {
+                Some(TypeExpr::Raw(inner.clone()))
+            }
+            (Builtin::ToRawm, TypeExpr::Mref(inner)) => Some(TypeExpr::Rawm(inner.clone())),
+            (Builtin::FromRaw, TypeExpr::Raw(inner)) => {
+                Some(TypeExpr::Opt(Box::new(TypeExpr::Ref(inner.clone()))))
+            }
+            (Builtin::FromRawm, TypeExpr::Rawm(inner)) => {
+                Some(TypeExpr::Opt(Box::new(TypeExpr::Mref(inner.clone()))))
+            }
+            (Builtin::FromRawUnchecked, TypeExpr::Raw(inner)) => Some(TypeExpr::Ref(inner.clone())),
+            (Builtin::FromRawmUnchecked, TypeExpr::Rawm(inner)) => {
+                Some(TypeExpr::Mref(inner.clone()))
+            }
+            (Builtin::AsSlice, TypeExpr::Arr(inner, _))
+            | (Builtin::Subslice, TypeExpr::Slice(inner) | TypeExpr::Arr(inner, _))
+            | (Builtin::SliceFromRaw, TypeExpr::Raw(inner) | TypeExpr::Rawm(inner)) => {
+                Some(TypeExpr::Slice(inner.clone()))
+            }
+            (Builtin::Len, TypeExpr::Slice(_) | TypeExpr::Arr(_, _)) => {
+                Some(TypeExpr::Primitive(PrimitiveType::Usize))
+            }
+            _ => None,
+        };
+
+        let Some(ty) = ty else {
+            // Void or Error means an error was already reported for the argument
+            if !matches!(arg_ty, TypeExpr::Void | TypeExpr::Error) {
+                let wanted = match op {
+                    Builtin::IsSome | Builtin::IsNone => "opt(T)",
+                    Builtin::IsOk | Builtin::IsErr | Builtin::UnwrapErr => "res(T, E)",
+                    Builtin::Unwrap | Builtin::UnwrapOr => "opt(T) or res(T, E)",
+                    Builtin::ToRaw => "ref(T) or mref(T)",
+                    Builtin::ToRawm => "mref(T)",
+                    Builtin::FromRaw | Builtin::FromRawUnchecked => "raw(T)",
+                    Builtin::FromRawm | Builtin::FromRawmUnchecked => "rawm(T)",
+                    Builtin::AsSlice => "arr(T, N)",
+                    Builtin::Subslice | Builtin::Len => "slice(T) or arr(T, N)",
+                    Builtin::SliceFromRaw => "raw(T) or rawm(T)",
+                };
+                self.error(
+                    ErrorCode::BuiltinArgument,
+                    format!("{} expects {}, got {:?}", op.name(), wanted, arg_ty),
+                    args[0].span(),
+                );
+            }
+            for arg in &args[1..] {
+                self.infer_expr(arg);
+            }
+            return TypeExpr::Void;
+        };
+
+        if op == Builtin::UnwrapOr {
+            // The default takes the payload type
+            let default_ty = self.check_expr(&args[1], Some(&ty));
+            if !self.types_compatible(&ty, &default_ty) {
+                self.error_value_mismatch(&ty, &default_ty, &args[1].span(), &args[1]);
             }
+        }
 
-            Expr::Err { value, .. } => {
-                let inner_ty = self.infer_expr(value);
-                // We don't know the ok type, use void as placeholder
-                TypeExpr::Res(Box::new(TypeExpr::Void), Box::new(inner_ty))
+        if matches!(op, Builtin::Subslice | Builtin::SliceFromRaw) {
+            // Bounds and lengths are element counts
+            let usize_ty = TypeExpr::Primitive(PrimitiveType::Usize);
+            for arg in &args[1..] {
+                let arg_ty = self.check_expr(arg, Some(&usize_ty));
+                if !matches!(arg_ty, TypeExpr::Void | TypeExpr::Error) && !self.is_integer(&arg_ty)
+                {
+                    self.error(
+                        ErrorCode::BuiltinArgument,
+                        format!("{} expects an integer, got {:?}", op.name(), arg_ty),
+                        arg.span(),
+                    );
+                }
             }
+        }
+
+        if op == Builtin::SliceFromRaw && !self.safety.is_unsafe() {
+            let error = CompileError::type_error_with_hint(
+                ErrorCode::UnsafeBuiltin,
+                "slice_from_raw requires unsafe block",
+                span.clone(),
+                self.source,
+                "the pointer must address at least `len` valid elements; use as_slice(...) to view an arr",
+            );
+            self.errors.push(error.with_fix(self.unsafe_fix()));
+        }
 
-            Expr::StructLit { name, .. } => TypeExpr::Named(name.clone()),
+        if matches!(op, Builtin::FromRawUnchecked | Builtin::FromRawmUnchecked)
+            && !self.safety.is_unsafe()
+        {
+            let checked = if op == Builtin::FromRawUnchecked {
+                Builtin::FromRaw
+            } else {
+                Builtin::FromRawm
+            };
+            self.error_with_hint(
+                ErrorCode::UnsafeBuiltin,
+                format!("{} requires unsafe block", op.name()),
+                span.clone(),
+                format!(
+                    "use {}(...) to get an opt checked for null, or wrap it in an unsafe block",
+                    checked.name()
+                ),
+            );
         }
+
+        ty
     }
 
-    // === Type compatibility checks ===
+    /// Check the value inside `some`/`ok`/`err` against the wrapped type
+    fn check_wrapped(&mut self, value: &Expr, expected: &TypeExpr) {
+        let value_ty = self.check_expr(value, Some(expected));
+        if !self.types_compatible(expected, &value_ty) {
+            self.error_value_mismatch(expected, &value_ty, &value.span(), value);
+        }
+    }
+
+    /// Type of `field` in struct `struct_name`, reporting unknown fields
+    fn field_type(&mut self, struct_name: &str, field: &str, span: &Span) -> TypeExpr {
+        let Some(decl) = self.struct_decls.get(struct_name) else {
+            return TypeExpr::Error;
+        };
+        if let Some(f) = decl.fields.iter().find(|f| f.name == field) {
+            return f.ty.clone();
+        }
+
+        let message = format!("struct '{}' has no field '{}'", struct_name, field);
+        match find_similar(field, decl.fields.iter().map(|f| &f.name)) {
+            Some(similar) => self.error_with_hint(
+                ErrorCode::UnknownField,
+                message,
+                span.clone(),
+                format!("did you mean '{}'?", similar),
+            ),
+            None => self.error(ErrorCode::UnknownField, message, span.clone()),
+        }
+        TypeExpr::Error
+    }
+
+    /// Check a struct literal for unknown, duplicate, missing, and mistyped fields
+    fn check_struct_lit(&mut self, name: &str, fields: &[FieldInit], span: &Span) {
+        let Some(decl) = self.struct_decls.get(name).cloned() else {
+            for field in fields {
+                self.infer_expr(&field.value);
+            }
+            if self.enum_decls.contains_key(name) || self.symbols.lookup(name).is_some() {
+                self.error(
+                    ErrorCode::NotAStruct,
+                    format!("'{}' is not a struct", name),
+                    span.clone(),
+                );
+            }
+            return;
+        };
+
+        let mut seen = HashSet::new();
+        for init in fields {
+            if !seen.insert(init.name.as_str()) {
+                self.infer_expr(&init.value);
+                self.error(
+                    ErrorCode::DuplicateField,
+                    format!("field '{}' is initialized more than once", init.name),
+                    init.span.clone(),
+                );
+                continue;
+            }
+            let field_ty = self.field_type(name, &init.name, &init.span);
+            let value_ty = self.check_expr(&init.value, Some(&field_ty));
+            if !self.types_compatible(&field_ty, &value_ty) {
+                self.error_value_mismatch(&field_ty, &value_ty, &init.value.span(), &init.value);
+            }
+        }
+
+        let missing: Vec<&str> = decl
+            .fields
+            .iter()
+            .map(|f| f.name.as_str())
+            .filter(|f| !seen.contains(f))
+            .collect();
+        if !missing.is_empty() {
+            self.error(
+                ErrorCode::MissingFields,
+                format!(
+                    "missing field(s) {} in struct literal '{}'",
+                    missing.join(", "),
+                    name
+                ),
+                span.clone(),
+            );
+        }
+    }
+
+    // === Type compatibility checks ===
 
     fn types_compatible(&self, expected: &TypeExpr, actual: &TypeExpr) -> bool {
         match (expected, actual) {
+            (TypeExpr::Error, _) | (_, TypeExpr::Error) => true,
             (TypeExpr::Void, TypeExpr::Void) => true,
             (TypeExpr::Primitive(a), TypeExpr::Primitive(b)) => a == b,
             (TypeExpr::Named(a), TypeExpr::Named(b)) => a == b,
@@ -689,7 +1542,9 @@ impl<'a> TypeChecker<'a> {
             (TypeExpr::Rawm(a), TypeExpr::Rawm(b)) => self.types_compatible(a, b),
             (TypeExpr::Own(a), TypeExpr::Own(b)) => self.types_compatible(a, b),
             (TypeExpr::Slice(a), TypeExpr::Slice(b)) => self.types_compatible(a, b),
-            (TypeExpr::Arr(a, _), TypeExpr::Arr(b, _)) => self.types_compatible(a, b),
+            (TypeExpr::Arr(a, n), TypeExpr::Arr(b, m)) => {
+                self.arr_lengths_equal(n, m) && self.types_compatible(a, b)
+            }
             (TypeExpr::Opt(a), TypeExpr::Opt(b)) => self.types_compatible(a, b),
             (TypeExpr::Res(a1, a2), TypeExpr::Res(b1, b2)) => {
                 self.types_compatible(a1, b1) && self.types_compatible(a2, b2)
@@ -718,6 +1573,17 @@ impl<'a> TypeChecker<'a> {
         }
     }
 
+    /// Whether two `arr` lengths are the same. Lowering trusts the declared
+    /// length for bounds checks, so lengths that do not fold to a constant
+    /// must be written identically.
+    fn arr_lengths_equal(&self, a: &ConstExpr, b: &ConstExpr) -> bool {
+        let lookup = |name: &str| self.const_value(name);
+        match (a.eval_int(&lookup), b.eval_int(&lookup)) {
+            (Some(a), Some(b)) => a == b,
+            _ => a == b,
+        }
+    }
+
     fn is_bool(&self, ty: &TypeExpr) -> bool {
         matches!(ty, TypeExpr::Primitive(PrimitiveType::Bool))
     }
@@ -771,7 +1637,11 @@ impl<'a> TypeChecker<'a> {
             Expr::At { .. } => {}
             Expr::Field { .. } => {}
             _ => {
-                self.error("expression is not assignable".to_string(), span.clone());
+                self.error(
+                    ErrorCode::NotAssignable,
+                    "expression is not assignable".to_string(),
+                    span.clone(),
+                );
             }
         }
     }
@@ -784,6 +1654,7 @@ impl<'a> TypeChecker<'a> {
             Expr::Field { .. } => {}
             _ => {
                 self.error(
+                    ErrorCode::AddressOfValue,
                     "cannot take address of expression".to_string(),
                     span.clone(),
                 );
@@ -798,12 +1669,14 @@ impl<'a> TypeChecker<'a> {
         match ty {
             TypeExpr::Opt(_) => {
                 self.error(
+                    ErrorCode::ExternOptRes,
                     "opt(T) is not permitted in extern signatures".to_string(),
                     span.clone(),
                 );
             }
             TypeExpr::Res(_, _) => {
                 self.error(
+                    ErrorCode::ExternOptRes,
                     "res(T, E) is not permitted in extern signatures".to_string(),
                     span.clone(),
                 );
@@ -813,6 +1686,7 @@ impl<'a> TypeChecker<'a> {
                 if let Some(struct_decl) = self.struct_decls.get(name) {
                     if struct_decl.repr != Some(Repr::C) {
                         self.error(
+                            ErrorCode::ExternStructRepr,
                             format!(
                                 "struct '{}' passed by value in extern must have @repr(C)",
                                 name
@@ -830,13 +1704,20 @@ impl<'a> TypeChecker<'a> {
 
     // === Error helpers ===
 
-    fn error(&mut self, message: String, span: Span) {
+    fn error(&mut self, code: ErrorCode, message: String, span: Span) {
         self.errors
-            .push(CompileError::type_error(message, span, self.source));
+            .push(CompileError::type_error(code, message, span, self.source));
     }
 
-    fn error_with_hint(&mut self, message: String, span: Span, hint: impl Into<String>) {
+    fn error_with_hint(
+        &mut self,
+        code: ErrorCode,
+        message: String,
+        span: Span,
+        hint: impl Into<String>,
+    ) {
         self.errors.push(CompileError::type_error_with_hint(
+            code,
             message,
             span,
             self.source,
@@ -845,10 +1726,54 @@ impl<'a> TypeChecker<'a> {
     }
 
     fn error_type_mismatch(&mut self, expected: &TypeExpr, actual: &TypeExpr, span: &Span) {
-        self.error(
+        self.errors.push(self.type_mismatch(expected, actual, span));
+    }
+
+    /// Report `value` of type `actual` where `expected` is required; between
+    /// numeric types the fix is a `cast`
+    fn error_value_mismatch(
+        &mut self,
+        expected: &TypeExpr,
+        actual: &TypeExpr,
+        span: &Span,
+        value: &Expr,
+    ) {
+        let fix = match (expected, actual) {
+            (TypeExpr::Primitive(to), TypeExpr::Primitive(from))
+                if *to != PrimitiveType::Bool && *from != PrimitiveType::Bool =>
+            {
+                Some(Fix::wrap(
+                    format!("cast to `{}`", to.name()),
+                    value.span(),
+                    format!("cast({}, ", to.name()),
+                    ")",
+                ))
+            }
+            _ => None,
+        };
+        self.errors
+            .push(self.type_mismatch(expected, actual, span).with_fix(fix));
+    }
+
+    fn type_mismatch(&self, expected: &TypeExpr, actual: &TypeExpr, span: &Span) -> CompileError {
+        CompileError::type_error(
+            ErrorCode::TypeMismatch,
             format!("type mismatch: expected {:?}, got {:?}", expected, actual),
             span.clone(),
-        );
+            self.source,
+        )
+    }
+
+    /// Fix wrapping the current statement in an `unsafe` block
+    fn unsafe_fix(&self) -> Option<Fix> {
+        self.unsafe_fix_span.clone().map(|span| {
+            Fix::wrap(
+                "wrap the statement in an unsafe block",
+                span,
+                "unsafe { ",
+                " }",
+            )
+        })
     }
 }
 
@@ -860,6 +1785,7 @@ impl Default for TypeChecker<'_> {
 
 #[cfg(test)]
 mod tests {
+    use crate::diag::CompileError;
     use crate::driver::compile;
 
     fn check_error(source: &str, expected_substr: &str) {
@@ -1031,10 +1957,562 @@ mod tests {
         check_ok("fn bar(x: i32) -> i32 { return x; } fn foo() -> i32 { return bar(1); }");
     }
 
+    // === Defer tests ===
+
+    #[test]
+    fn test_return_in_defer() {
+        check_error(
+            "fn foo() -> i32 { defer { return 1; } return 0; }",
+            "cannot return from inside a defer block",
+        );
+    }
+
+    #[test]
+    fn test_break_out_of_defer() {
+        check_error(
+            "fn foo() -> void { while (true) { defer { break; } } }",
+            "cannot break out of a defer block",
+        );
+    }
+
+    #[test]
+    fn test_continue_out_of_defer() {
+        check_error(
+            "fn foo() -> void { while (true) { defer { continue; } } }",
+            "cannot continue out of a defer block",
+        );
+    }
+
+    #[test]
+    fn test_loop_inside_defer() {
+        check_ok("fn foo(b: bool) -> void { defer { while (b) { break; } } }");
+    }
+
     #[test]
     fn test_nested_calls() {
         check_ok(
             "fn a(x: i32) -> i32 { return x; } fn b(x: i32) -> i32 { return a(x); } fn foo() -> i32 { return b(1); }",
         );
     }
+
+    // === Literal inference tests ===
+
+    #[test]
+    fn test_literal_adopts_let_type() {
+        check_ok("fn f() -> void { let x: u64 = 1; let y: f32 = 0.5; let z: i64 = 3000000000; }");
+    }
+
+    #[test]
+    fn test_literal_adopts_param_and_return_type() {
+        check_ok("fn g(x: u8) -> u16 { return 65535; } fn f() -> u16 { return g(200); }");
+    }
+
+    #[test]
+    fn test_literal_adopts_peer_type() {
+        check_ok("fn f(x: u64) -> bool { return ((1 + x) > 2); }");
+        check_ok("fn f(x: f32) -> f32 { return (x * 0.5); }");
+    }
+
+    #[test]
+    fn test_literal_adopts_field_and_opt_type() {
+        check_ok(
+            "struct P { r: u8 } fn f() -> opt(usize) { let p: P = P { r: 7 }; return some(4); }",
+        );
+    }
+
+    #[test]
+    fn test_literal_out_of_range() {
+        check_error(
+            "fn f() -> void { let b: u8 = 300; }",
+            "integer literal 300 is out of range for u8",
+        );
+        check_error(
+            "fn g(x: i16) -> void { return; } fn f() -> void { g(40000); }",
+            "integer literal 40000 is out of range for i16",
+        );
+    }
+
+    #[test]
+    fn test_negative_literal_range() {
+        check_ok("fn f() -> void { let c: i8 = -128; }");
+        check_error(
+            "fn f() -> void { let c: i8 = -129; }",
+            "integer literal -129 is out of range for i8",
+        );
+        check_error(
+            "fn f() -> void { let u: u32 = -1; }",
+            "integer literal -1 is out of range for u32",
+        );
+    }
+
+    #[test]
+    fn test_const_literal_out_of_range() {
+        check_error(
+            "const MASK: u8 = 256;",
+            "integer literal 256 is out of range for u8",
+        );
+    }
+
+    #[test]
+    fn test_constant_index_out_of_bounds() {
+        check_error(
+            "fn f(a: arr(i32, 4)) -> i32 { return at(a, 4); }",
+            "index 4 is out of bounds for arr of length 4",
+        );
+        check_error(
+            "const N: usize = 2; fn f(a: arr(i32, (N * 2))) -> i32 { return at(a, -1); }",
+            "index -1 is out of bounds for arr of length 4",
+        );
+        check_ok("const N: usize = 4; fn f(a: arr(i32, N)) -> i32 { return at(a, (N - 1)); }");
+    }
+
+    #[test]
+    fn test_arr_length_mismatch() {
+        check_error(
+            "struct Small { data: arr(i32, 2) } \
+             fn g(a: arr(i32, 64)) -> i32 { return at(a, 40); } \
+             fn f(s: ref(Small)) -> i32 { return g(deref(s).data); }",
+            "type mismatch",
+        );
+        check_ok(
+            "const N: usize = 2; struct Small { data: arr(i32, (N * 1)) } \
+             fn g(a: arr(i32, 2)) -> i32 { return at(a, 1); } \
+             fn f(s: ref(Small)) -> i32 { return g(deref(s).data); }",
+        );
+    }
+
+    #[test]
+    fn test_arr_size_is_usize() {
+        check_error(
+            "struct S { a: arr(i32, -1) }",
+            "integer literal -1 is out of range for usize",
+        );
+    }
+
+    #[test]
+    fn test_invalid_arr_sizes() {
+        check_error(
+            "struct S { a: arr(i32, 2.5) }",
+            "array size is not an integer",
+        );
+        check_error(
+            "struct S { a: arr(i32, true) }",
+            "array size is not an integer",
+        );
+        check_error(
+            "struct S { a: arr(i32, (4 / 0)) }",
+            "array size divides by zero",
+        );
+        check_error(
+            "const N: usize = 4; struct S { a: arr(i32, (N - 5)) }",
+            "array size is negative",
+        );
+        check_error(
+            "struct S { a: arr(i32, (4 << 1)) }",
+            "array size uses an operator other than",
+        );
+        check_ok("const N: usize = 4; struct S { a: arr(i32, cast(usize, (N % 3))) }");
+    }
+
+    // === opt/res context tests ===
+
+    #[test]
+    fn test_ok_err_take_return_type() {
+        check_ok(
+            "enum E { Bad } fn f(x: i32) -> res(i32, E) { if (x < 0) { return err(E_Bad); } return ok(5); }",
+        );
+    }
+
+    #[test]
+    fn test_ok_err_take_let_and_param_type() {
+        check_ok(
+            "fn g(r: res(u8, bool)) -> void { return; } fn f() -> void { let r: res(u64, bool) = ok(1); g(err(true)); }",
+        );
+    }
+
+    #[test]
+    fn test_ok_value_type_mismatch() {
+        check_error(
+            "fn f() -> res(i32, bool) { return ok(true); }",
+            "type mismatch: expected Primitive(I32), got Primitive(Bool)",
+        );
+    }
+
+    #[test]
+    fn test_ok_without_context() {
+        check_error(
+            "fn f() -> void { discard(ok(1)); }",
+            "cannot infer the error type of ok(...)",
+        );
+    }
+
+    #[test]
+    fn test_some_takes_expected_type() {
+        check_ok("fn f() -> opt(opt(u8)) { return some(some(200)); }");
+    }
+
+    // === opt/res builtin tests ===
+
+    #[test]
+    fn test_unwrap_is_generic_over_payload() {
+        check_ok(
+            "fn f(o: opt(u8), r: res(bool, i32)) -> bool { return (unwrap(o) == 1) && unwrap(r); }",
+        );
+    }
+
+    #[test]
+    fn test_unwrap_or_default_takes_payload_type() {
+        check_ok("fn f(o: opt(u8)) -> u8 { return unwrap_or(o, 200); }");
+        check_error(
+            "fn f(o: opt(u8)) -> u8 { return unwrap_or(o, true); }",
+            "type mismatch",
+        );
+    }
+
+    #[test]
+    fn test_unwrap_err_requires_res() {
+        check_ok("fn f(r: res(i32, u8)) -> u8 { return unwrap_err(r); }");
+        check_error(
+            "fn f(o: opt(i32)) -> i32 { return unwrap_err(o); }",
+            "unwrap_err expects res(T, E)",
+        );
+    }
+
+    #[test]
+    fn test_is_some_on_non_opt() {
+        check_error(
+            "fn f(x: i32) -> bool { return is_some(x); }",
+            "is_some expects opt(T)",
+        );
+    }
+
+    #[test]
+    fn test_from_raw_yields_opt_ref() {
+        check_ok("fn f(p: raw(i32)) -> opt(ref(i32)) { return from_raw(p); }");
+        check_ok("fn f(p: rawm(i32)) -> opt(mref(i32)) { return from_rawm(p); }");
+    }
+
+    #[test]
+    fn test_from_raw_unchecked_requires_unsafe() {
+        check_error(
+            "fn f(p: raw(i32)) -> ref(i32) { return from_raw_unchecked(p); }",
+            "from_raw_unchecked requires unsafe block",
+        );
+        check_ok("unsafe fn f(p: rawm(i32)) -> mref(i32) { return from_rawm_unchecked(p); }");
+    }
+
+    // === Slice builtin tests ===
+
+    #[test]
+    fn test_slice_builtins_take_element_type() {
+        check_ok(
+            "fn f(a: arr(u8, 4)) -> usize { let s: slice(u8) = as_slice(a); return len(subslice(s, 1, len(s))); }",
+        );
+        check_ok("fn f(a: arr(i32, 4)) -> usize { return len(a); }");
+        check_error(
+            "fn f(a: arr(u8, 4)) -> slice(i32) { return as_slice(a); }",
+            "type mismatch",
+        );
+    }
+
+    #[test]
+    fn test_slice_builtins_check_operands() {
+        check_error(
+            "fn f(s: slice(i32)) -> slice(i32) { return as_slice(s); }",
+            "as_slice expects arr(T, N)",
+        );
+        check_error(
+            "fn f(x: i32) -> usize { return len(x); }",
+            "len expects slice(T) or arr(T, N)",
+        );
+        check_error(
+            "fn f(s: slice(i32)) -> slice(i32) { return subslice(s, 0, true); }",
+            "subslice expects an integer",
+        );
+    }
+
+    #[test]
+    fn test_slice_from_raw_requires_unsafe() {
+        check_error(
+            "fn f(p: raw(u8), n: usize) -> slice(u8) { return slice_from_raw(p, n); }",
+            "slice_from_raw requires unsafe block",
+        );
+        check_ok(
+            "unsafe fn f(p: rawm(u8), n: usize) -> slice(u8) { return slice_from_raw(p, n); }",
+        );
+    }
+
+    #[test]
+    fn test_len_field_is_not_builtin() {
+        check_ok(
+            "struct Buf { len: usize } fn f(b: Buf) -> usize { let len: usize = b.len; return len; }",
+        );
+    }
+
+    // === Struct field tests ===
+
+    const POINT: &str = "struct Point { x: i32, y: i32 } ";
+
+    #[test]
+    fn test_field_type() {
+        check_ok(&format!(
+            "{POINT}fn f(p: Point) -> i32 {{ return (p.x + 1); }}"
+        ));
+        check_error(
+            &format!("{POINT}fn f(p: Point) -> bool {{ return p.x; }}"),
+            "type mismatch",
+        );
+    }
+
+    #[test]
+    fn test_field_auto_deref() {
+        check_ok(&format!(
+            "{POINT}fn f(p: ref(Point)) -> i32 {{ return p.y; }} fn g(p: mref(Point)) -> void {{ p.x = 1; }}"
+        ));
+    }
+
+    #[test]
+    fn test_unknown_field_suggestion() {
+        check_error(
+            &format!("{POINT}fn f(p: Point) -> i32 {{ return p.z; }}"),
+            "struct 'Point' has no field 'z'",
+        );
+        check_error(
+            &format!("{POINT}fn f(p: ref(Point)) -> i32 {{ return p.xx; }}"),
+            "did you mean 'x'?",
+        );
+    }
+
+    #[test]
+    fn test_unknown_field_reported_once() {
+        // Its type is unknown, so neither the let, the chained access, nor
+        // the literal's value adds a second error
+        let source = format!(
+            "{POINT}fn f(p: Point) -> i32 {{ let v: i32 = p.z; return p.z.w; }}
+             fn g() -> Point {{ return Point {{ x: 1, y: 2, z: true }}; }}"
+        );
+        let errors = match compile(&source, "test.fc") {
+            Err(CompileError::Multiple { errors }) => errors,
+            other => panic!("Expected several errors, got: {:?}", other),
+        };
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        for error in &errors {
+            assert!(error.to_string().contains("has no field 'z'"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_field_access_on_non_struct() {
+        check_error(
+            "fn f(n: i32) -> i32 { return n.x; }",
+            "field access on non-struct type",
+        );
+    }
+
+    #[test]
+    fn test_struct_lit_ok() {
+        check_ok(&format!(
+            "{POINT}fn f() -> Point {{ return Point {{ x: 1, y: 2 }}; }}"
+        ));
+    }
+
+    #[test]
+    fn test_struct_lit_missing_field() {
+        check_error(
+            &format!("{POINT}fn f() -> Point {{ return Point {{ x: 1 }}; }}"),
+            "missing field(s) y in struct literal 'Point'",
+        );
+    }
+
+    #[test]
+    fn test_struct_lit_duplicate_field() {
+        check_error(
+            &format!("{POINT}fn f() -> Point {{ return Point {{ x: 1, x: 2, y: 3 }}; }}"),
+            "field 'x' is initialized more than once",
+        );
+    }
+
+    #[test]
+    fn test_struct_lit_field_type() {
+        check_error(
+            &format!("{POINT}fn f() -> Point {{ return Point {{ x: true, y: 2 }}; }}"),
+            "type mismatch",
+        );
+    }
+
+    #[test]
+    fn test_struct_lit_unknown_field() {
+        check_error(
+            &format!("{POINT}fn f() -> Point {{ return Point {{ x: 1, yy: 2 }}; }}"),
+            "did you mean 'y'?",
+        );
+    }
+
+    #[test]
+    fn test_module_functions_are_checked() {
+        check_error("mod m { fn f() -> i32 { return true; } }", "type mismatch");
+    }
+
+    // === Match tests ===
+
+    const SHAPE: &str = "enum Shape { Circle(i32), Rect(i32, i32), Empty } ";
+
+    #[test]
+    fn test_match_exhaustive() {
+        check_ok(&format!(
+            "{SHAPE}fn f(s: Shape) -> i32 {{ match (s) {{ case Shape_Circle(r): return r; case Shape_Rect(w, _): return w; case Shape_Empty: return 0; }} }}"
+        ));
+    }
+
+    #[test]
+    fn test_match_non_exhaustive() {
+        check_error(
+            &format!(
+                "{SHAPE}fn f(s: Shape) -> i32 {{ match (s) {{ case Shape_Circle(r): return r; }} return 0; }}"
+            ),
+            "non-exhaustive match on enum 'Shape'",
+        );
+    }
+
+    #[test]
+    fn test_match_default_covers_rest() {
+        check_ok(&format!(
+            "{SHAPE}fn f(s: Shape) -> i32 {{ match (s) {{ case Shape_Circle(r): return r; default: return 0; }} }}"
+        ));
+    }
+
+    #[test]
+    fn test_match_binding_count() {
+        check_error(
+            &format!(
+                "{SHAPE}fn f(s: Shape) -> void {{ match (s) {{ case Shape_Rect(w): break; default: break; }} }}"
+            ),
+            "variant 'Shape_Rect' has 2 payload field(s), but the pattern binds 1",
+        );
+    }
+
+    #[test]
+    fn test_match_duplicate_arm() {
+        check_error(
+            &format!(
+                "{SHAPE}fn f(s: Shape) -> void {{ match (s) {{ case Shape_Empty: break; case Shape_Empty: break; default: break; }} }}"
+            ),
+            "duplicate match arm for variant 'Shape_Empty'",
+        );
+    }
+
+    #[test]
+    fn test_match_binding_type() {
+        check_error(
+            &format!(
+                "{SHAPE}fn f(s: Shape) -> bool {{ match (s) {{ case Shape_Circle(r): return r; default: return false; }} }}"
+            ),
+            "type mismatch",
+        );
+    }
+
+    #[test]
+    fn test_switch_on_payload_enum() {
+        check_error(
+            &format!("{SHAPE}fn f(s: Shape) -> void {{ switch (s) {{ default: break; }} }}"),
+            "cannot switch on enum 'Shape' with payload variants",
+        );
+    }
+
+    #[test]
+    fn test_payload_variant_needs_args() {
+        check_error(
+            &format!("{SHAPE}fn f() -> Shape {{ return Shape_Circle; }}"),
+            "variant 'Shape_Circle' carries a payload",
+        );
+    }
+
+    #[test]
+    fn test_variant_ctor_arity() {
+        check_error(
+            &format!("{SHAPE}fn f() -> Shape {{ return Shape_Rect(1); }}"),
+            "expected 2 arguments, got 1",
+        );
+    }
+
+    #[test]
+    fn test_unit_variant_ctor() {
+        check_error(
+            &format!("{SHAPE}fn f() -> Shape {{ return Shape_Empty(1); }}"),
+            "variant 'Shape_Empty' has no payload",
+        );
+    }
+
+    // === Drop function tests ===
+
+    #[test]
+    fn test_drop_fn_ok() {
+        check_ok("struct Buf { len: i32 } @drop fn buf_drop(b: own(Buf)) -> void { return; }");
+    }
+
+    #[test]
+    fn test_drop_fn_needs_own_param() {
+        check_error(
+            "struct Buf { len: i32 } @drop fn buf_drop(b: ref(Buf)) -> void { return; }",
+            "'@drop' function 'buf_drop' must take an own(T)",
+        );
+    }
+
+    #[test]
+    fn test_drop_fn_returns_void() {
+        check_error(
+            "struct Buf { len: i32 } @drop fn buf_drop(b: own(Buf)) -> i32 { return 0; }",
+            "must take exactly one own(T) parameter and return void",
+        );
+    }
+
+    #[test]
+    fn test_duplicate_drop_fn() {
+        check_error(
+            "struct Buf { len: i32 }
+             @drop fn a(b: own(Buf)) -> void { return; }
+             @drop fn b(b: own(Buf)) -> void { return; }",
+            "'a' is already the drop function for",
+        );
+    }
+
+    #[test]
+    fn test_drop_attr_only_on_fns() {
+        check_error(
+            "@drop struct Buf { len: i32 }",
+            "'@drop' only applies to safe functions",
+        );
+    }
+
+    /// Fix of the single error `source` fails with, applied to `source`
+    fn fixed(source: &str) -> Option<String> {
+        let error = compile(source, "test.fc").unwrap_err();
+        let fix = error.fix()?;
+        Some(crate::diag::apply_fixes(source, [fix]).0)
+    }
+
+    #[test]
+    fn test_numeric_mismatch_fix_casts() {
+        assert_eq!(
+            fixed("fn f(x: i64) -> i32 { return x + 1; }").as_deref(),
+            Some("fn f(x: i64) -> i32 { return cast(i32, x + 1); }")
+        );
+        assert_eq!(fixed("fn f(x: bool) -> i32 { return x; }"), None);
+    }
+
+    #[test]
+    fn test_unsafe_call_fix_wraps_statement() {
+        let source = "unsafe fn g() -> void { return; } fn f() -> void { g(); }";
+        assert_eq!(
+            fixed(source).as_deref(),
+            Some("unsafe fn g() -> void { return; } fn f() -> void { unsafe { g(); } }")
+        );
+        // Wrapping a `let` would hide the name from the rest of the block
+        assert_eq!(
+            fixed(
+                "unsafe fn g() -> i32 { return 1; } fn f() -> i32 { let x: i32 = g(); return x; }"
+            ),
+            None
+        );
+    }
 }
@@ -1,8 +1,9 @@
 //! C interop tests
 //!
-//! These tests verify that the generated C code compiles with a C11 compiler.
+//! These tests verify that the generated C code compiles with a C11 compiler
+//! (clang, falling back to the system `cc`).
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
 use tempfile::tempdir;
 
@@ -18,36 +19,27 @@ fn workspace_root() -> PathBuf {
         .to_path_buf()
 }
 
-/// Check if clang is available
-fn clang_available() -> bool {
-    process::Command::new("clang")
-        .arg("--version")
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+/// Find a C compiler, preferring clang
+fn c_compiler() -> Option<&'static str> {
+    ["clang", "cc"].into_iter().find(|cc| {
+        process::Command::new(cc)
+            .arg("--version")
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    })
 }
 
-/// Compile a FastC file and verify the generated C compiles
-fn compile_and_verify(fc_file: &str) {
-    if !clang_available() {
-        eprintln!("Skipping test: clang not available");
-        return;
-    }
-
-    let root = workspace_root();
-    let fc_path = root.join(fc_file);
-    let runtime_path = root.join("runtime");
+/// Run fastc on a FastC file, writing the generated C to `c_file`
+fn run_fastc(fc_file: &str, c_file: &Path) {
+    let fc_path = workspace_root().join(fc_file);
 
-    let dir = tempdir().expect("Failed to create temp dir");
-    let c_file = dir.path().join("output.c");
-
-    // Run fastc to compile the .fc file
     let fastc_bin = env!("CARGO_BIN_EXE_fastc");
     let output = process::Command::new(fastc_bin)
         .arg("compile")
         .arg(&fc_path)
         .arg("-o")
-        .arg(&c_file)
+        .arg(c_file)
         .output()
         .expect("Failed to run fastc");
     assert!(
@@ -55,20 +47,146 @@ fn compile_and_verify(fc_file: &str) {
         "fastc failed: {}",
         String::from_utf8_lossy(&output.stderr)
     );
+}
+
+/// Compile a FastC file and verify the generated C compiles
+fn compile_and_verify(fc_file: &str) {
+    let Some(cc) = c_compiler() else {
+        eprintln!("Skipping test: no C compiler available");
+        return;
+    };
+
+    let dir = tempdir().expect("Failed to create temp dir");
+    let c_file = dir.path().join("output.c");
+    run_fastc(fc_file, &c_file);
 
-    // Compile with clang
-    let output = process::Command::new("clang")
+    let output = process::Command::new(cc)
         .args(["-std=c11", "-Wall", "-Werror", "-c"])
         .arg(&c_file)
+        .arg("-o")
+        .arg(dir.path().join("output.o"))
+        .arg("-I")
+        .arg(workspace_root().join("runtime"))
+        .output()
+        .expect("Failed to run C compiler");
+
+    if !output.status.success() {
+        eprintln!("{} stderr: {}", cc, String::from_utf8_lossy(&output.stderr));
+        panic!("C compilation failed for {}", fc_file);
+    }
+}
+
+/// Compile a FastC program to an executable, run it, and check its exit code
+fn compile_and_run(fc_file: &str, expected_code: i32) {
+    let Some(cc) = c_compiler() else {
+        eprintln!("Skipping test: no C compiler available");
+        return;
+    };
+
+    let dir = tempdir().expect("Failed to create temp dir");
+    let c_file = dir.path().join("output.c");
+    let exe_file = dir.path().join("output_exe");
+    run_fastc(fc_file, &c_file);
+
+    let output = process::Command::new(cc)
+        .args(["-std=c11", "-Wall", "-Werror"])
+        .arg(&c_file)
+        .arg(workspace_root().join("runtime/fastc_runtime.c"))
         .arg("-I")
-        .arg(&runtime_path)
+        .arg(workspace_root().join("runtime"))
+        .arg("-o")
+        .arg(&exe_file)
         .output()
-        .expect("Failed to run clang");
+        .expect("Failed to run C compiler");
 
     if !output.status.success() {
-        eprintln!("clang stderr: {}", String::from_utf8_lossy(&output.stderr));
+        eprintln!("{} stderr: {}", cc, String::from_utf8_lossy(&output.stderr));
         panic!("C compilation failed for {}", fc_file);
     }
+
+    let output = process::Command::new(&exe_file)
+        .output()
+        .expect("Failed to run executable");
+    assert_eq!(
+        output.status.code(),
+        Some(expected_code),
+        "Unexpected exit status for {}: {}",
+        fc_file,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Compile FastC source to an executable and run it; `None` without a C compiler
+fn run_source(source: &str) -> Option<process::Output> {
+    let Some(cc) = c_compiler() else {
+        eprintln!("Skipping test: no C compiler available");
+        return None;
+    };
+
+    let c_code = fastc::compile(source, "test.fc").expect("source should compile");
+    let dir = tempdir().expect("Failed to create temp dir");
+    let c_file = dir.path().join("output.c");
+    let exe_file = dir.path().join("output_exe");
+    std::fs::write(&c_file, c_code).expect("Failed to write C file");
+
+    let output = process::Command::new(cc)
+        .args(["-std=c11", "-Wall", "-Werror"])
+        .arg(&c_file)
+        .arg(workspace_root().join("runtime/fastc_runtime.c"))
+        .arg("-I")
+        .arg(workspace_root().join("runtime"))
+        .arg("-o")
+        .arg(&exe_file)
+        .output()
+        .expect("Failed to run C compiler");
+
+    if !output.status.success() {
+        eprintln!("{} stderr: {}", cc, String::from_utf8_lossy(&output.stderr));
+        panic!("C compilation failed for:\n{}", source);
+    }
+
+    Some(
+        process::Command::new(&exe_file)
+            .output()
+            .expect("Failed to run executable"),
+    )
+}
+
+/// Whether a process was ended by `fc_trap()` (abort) rather than exiting
+fn trapped(status: &process::ExitStatus) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        status.signal() == Some(6)
+    }
+    #[cfg(not(unix))]
+    {
+        !status.success()
+    }
+}
+
+/// Assert that a program hits a runtime check and traps
+fn assert_traps(source: &str) {
+    if let Some(output) = run_source(source) {
+        assert!(
+            trapped(&output.status),
+            "Expected a trap, got {:?} for:\n{}",
+            output.status,
+            source
+        );
+    }
+}
+
+/// Assert that a program runs to completion with `expected_code`
+fn assert_exits(source: &str, expected_code: i32) {
+    if let Some(output) = run_source(source) {
+        assert_eq!(
+            output.status.code(),
+            Some(expected_code),
+            "Unexpected exit status for:\n{}",
+            source
+        );
+    }
 }
 
 #[test]
@@ -116,12 +234,600 @@ fn test_interop_types_compiles() {
     compile_and_verify("examples/interop_types.fc");
 }
 
+#[test]
+fn test_optionals_tutorial_compile() {
+    compile_and_verify("examples/tutorials/07_optionals.fc");
+}
+
+#[test]
+fn test_optionals_tutorial_runs() {
+    compile_and_run("examples/tutorials/07_optionals.fc", 62);
+}
+
+#[test]
+fn test_results_tutorial_compile() {
+    compile_and_verify("examples/tutorials/08_results.fc");
+}
+
+#[test]
+fn test_results_tutorial_runs() {
+    compile_and_run("examples/tutorials/08_results.fc", 0);
+}
+
+#[test]
+fn test_generics_compile() {
+    compile_and_verify("examples/advanced/generics.fc");
+}
+
+#[test]
+fn test_generics_runs() {
+    compile_and_run("examples/advanced/generics.fc", 54);
+}
+
+#[test]
+fn test_ownership_runs() {
+    // A double free of a moved value would abort instead of exiting
+    compile_and_run("examples/advanced/ownership.fc", 42);
+}
+
+#[test]
+fn test_field_move_clears_field() {
+    // Each move nulls the field, so only the first `take` frees the value
+    assert_exits(
+        "struct H { p: own(i32), }
+         extern \"C\" { unsafe fn fc_alloc(size: usize, align: usize) -> own(i32); }
+         fn alloc() -> own(i32) { unsafe { return fc_alloc(cast(usize, 4), cast(usize, 4)); } }
+         fn take(p: own(i32)) -> void { }
+         fn pick(r: mref(H)) -> mref(H) { return r; }
+         fn main() -> i32 {
+             let h: H = H { p: alloc() };
+             take(h.p);
+             take(h.p);
+             h.p = alloc();
+             take(pick(addr(h)).p);
+             take(h.p);
+             return 0;
+         }",
+        0,
+    );
+}
+
+#[test]
+fn test_std_library_example_runs() {
+    compile_and_run("examples/advanced/std_library.fc", 0);
+}
+
+#[test]
+fn test_constants_compile() {
+    compile_and_verify("examples/advanced/constants.fc");
+}
+
+#[test]
+fn test_bitflags_compile() {
+    compile_and_verify("examples/advanced/bitflags.fc");
+}
+
+#[test]
+fn test_extern_declarations_compile() {
+    compile_and_verify("examples/advanced/nng_echo.fc");
+}
+
+#[test]
+fn test_libc_externs_compile() {
+    // No prototype may conflict with a C compiler's builtin memcpy or strlen
+    compile_and_verify("examples/advanced/ffi_libc.fc");
+    compile_and_verify("examples/advanced/algorithms.fc");
+}
+
+#[test]
+fn test_for_loop_compiles() {
+    compile_and_verify("examples/for_loop.fc");
+}
+
+#[test]
+fn test_for_loop_runs() {
+    compile_and_run("examples/for_loop.fc", 0);
+}
+
+#[test]
+fn test_line_directives_and_source_map() {
+    let Some(cc) = c_compiler() else {
+        eprintln!("Skipping test: no C compiler available");
+        return;
+    };
+
+    let dir = tempdir().expect("Failed to create temp dir");
+    let c_file = dir.path().join("output.c");
+    let map_file = dir.path().join("output.c.map");
+    let fc_path = workspace_root().join("examples/for_loop.fc");
+    let output = process::Command::new(env!("CARGO_BIN_EXE_fastc"))
+        .arg("compile")
+        .arg(&fc_path)
+        .arg("-o")
+        .arg(&c_file)
+        .arg("--line-directives")
+        .arg("--source-map")
+        .arg(&map_file)
+        .output()
+        .expect("Failed to run fastc");
+    assert!(
+        output.status.success(),
+        "fastc failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = process::Command::new(cc)
+        .args(["-std=c11", "-Wall", "-Werror", "-c"])
+        .arg(&c_file)
+        .arg("-o")
+        .arg(dir.path().join("output.o"))
+        .arg("-I")
+        .arg(workspace_root().join("runtime"))
+        .output()
+        .expect("Failed to run C compiler");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The preprocessor attributes function bodies to the .fc file
+    let output = process::Command::new(cc)
+        .args(["-std=c11", "-E"])
+        .arg(&c_file)
+        .arg("-I")
+        .arg(workspace_root().join("runtime"))
+        .output()
+        .expect("Failed to run C compiler");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("for_loop.fc\""));
+
+    let map: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&map_file).expect("source map written"))
+            .expect("source map is JSON");
+    assert_eq!(map["sources"][0], fc_path.display().to_string());
+    let c_lines: Vec<u64> = map["mappings"]
+        .as_array()
+        .expect("mappings array")
+        .iter()
+        .map(|m| m["c_line"].as_u64().expect("c_line"))
+        .collect();
+    assert!(!c_lines.is_empty());
+    assert!(c_lines.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+fn test_line_directives_name_module_files() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let main_file = dir.path().join("main.fc");
+    let helper_file = dir.path().join("helper.fc");
+    let c_file = dir.path().join("output.c");
+    let map_file = dir.path().join("output.c.map");
+    std::fs::write(
+        &helper_file,
+        "// Helpers\n\nfn quot(a: i32, b: i32) -> i32 {\n    return (a / b);\n}\n",
+    )
+    .expect("Failed to write module");
+    std::fs::write(
+        &main_file,
+        "mod helper;\nfn main() -> i32 { return quot(4, 2); }\n",
+    )
+    .expect("Failed to write source");
+    let output = process::Command::new(env!("CARGO_BIN_EXE_fastc"))
+        .arg("compile")
+        .arg(&main_file)
+        .arg("-o")
+        .arg(&c_file)
+        .arg("--line-directives")
+        .arg("--source-map")
+        .arg(&map_file)
+        .output()
+        .expect("Failed to run fastc");
+    assert!(
+        output.status.success(),
+        "fastc failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let c_code = std::fs::read_to_string(&c_file).expect("C written");
+    let directives: Vec<&str> = c_code.lines().filter(|l| l.starts_with("#line")).collect();
+    let helper = helper_file.display().to_string();
+    let main = main_file.display().to_string();
+    assert!(directives.contains(&format!("#line 4 \"{}\"", helper).as_str()));
+    assert!(directives.contains(&format!("#line 2 \"{}\"", main).as_str()));
+    // A directive is only written when the file or line changes
+    assert!(directives.windows(2).all(|w| w[0] != w[1]));
+
+    let map: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&map_file).expect("source map written"))
+            .expect("source map is JSON");
+    assert_eq!(map["sources"], serde_json::json!([main, helper]));
+    let mappings = map["mappings"].as_array().expect("mappings array");
+    assert!(mappings.iter().any(|m| m["source"] == 1 && m["line"] == 4));
+}
+
+#[test]
+fn test_tagged_union_compiles() {
+    compile_and_verify("examples/tagged_union.fc");
+}
+
+#[test]
+fn test_tagged_union_runs() {
+    compile_and_run("examples/tagged_union.fc", 0);
+}
+
+#[test]
+fn test_for_loop_output() {
+    let source = include_str!("../../../examples/for_loop.fc");
+    let c_code = fastc::compile(source, "for_loop.fc").expect("for_loop.fc should compile");
+    insta::assert_snapshot!("for_loop", c_code);
+}
+
+// === Runtime check tests ===
+
+const SHIFTS: &str = "
+fn shl(x: i32, n: i32) -> i32 { return (x << n); }
+fn shr(x: i32, n: i32) -> i32 { return (x >> n); }
+fn shr_u8(x: u8, n: u8) -> u8 { return (x >> n); }
+";
+
+#[test]
+fn test_shift_count_at_width_traps() {
+    assert_traps(&format!(
+        "{SHIFTS} fn main() -> i32 {{ return shl(1, 32); }}"
+    ));
+    assert_traps(&format!(
+        "{SHIFTS} fn main() -> i32 {{ return cast(i32, shr_u8(1, 8)); }}"
+    ));
+}
+
+#[test]
+fn test_negative_shift_count_traps() {
+    assert_traps(&format!(
+        "{SHIFTS} fn main() -> i32 {{ return shr(8, -1); }}"
+    ));
+}
+
+#[test]
+fn test_left_shift_of_negative_traps() {
+    assert_traps(&format!(
+        "{SHIFTS} fn main() -> i32 {{ return shl(-1, 1); }}"
+    ));
+}
+
+#[test]
+fn test_left_shift_overflow_traps() {
+    assert_traps(&format!(
+        "{SHIFTS} fn main() -> i32 {{ return shl(1, 31); }}"
+    ));
+    assert_traps(&format!(
+        "{SHIFTS} fn main() -> i32 {{ return shl(3, 30); }}"
+    ));
+    // The largest shift that keeps the sign bit clear still runs
+    assert_exits(
+        &format!("{SHIFTS} fn main() -> i32 {{ return shr(shl(1, 30), 28); }}"),
+        4,
+    );
+}
+
+#[test]
+fn test_valid_shifts_run() {
+    // Right shift of a negative value is allowed
+    assert_exits(
+        &format!(
+            "{SHIFTS} fn main() -> i32 {{
+                 let a: i32 = (shl(3, 4) + shr(64, 6));
+                 let b: i32 = (a + cast(i32, shr_u8(255, 7)));
+                 return (b + shr(-16, 31));
+             }}"
+        ),
+        49,
+    );
+}
+
+const DIVISION: &str = "
+fn quot(a: i32, b: i32) -> i32 { return (a / b); }
+fn rem(a: i64, b: i64) -> i64 { return (a % b); }
+fn neg(x: i8) -> i8 { return -x; }
+";
+
+#[test]
+fn test_min_div_minus_one_traps() {
+    assert_traps(&format!(
+        "{DIVISION} fn main() -> i32 {{ return quot(-2147483648, -1); }}"
+    ));
+}
+
+#[test]
+fn test_min_rem_minus_one_traps() {
+    assert_traps(&format!(
+        "{DIVISION} fn main() -> i32 {{ return cast(i32, rem(-9223372036854775808, -1)); }}"
+    ));
+}
+
+#[test]
+fn test_neg_of_min_traps() {
+    assert_traps(&format!(
+        "{DIVISION} fn main() -> i32 {{ return cast(i32, neg(-128)); }}"
+    ));
+}
+
+#[test]
+fn test_division_near_min_runs() {
+    assert_exits(
+        &format!(
+            "{DIVISION} fn main() -> i32 {{
+                 let a: i32 = quot(-2147483648, 1073741824);
+                 let b: i32 = cast(i32, rem(-9223372036854775807, -1));
+                 let c: i32 = cast(i32, neg(-127));
+                 return ((a + b) + c);
+             }}"
+        ),
+        125,
+    );
+}
+
+const ARRAYS: &str = "
+struct Buf { data: arr(i32, 4), }
+extern \"C\" { unsafe fn fc_alloc(size: usize, align: usize) -> own(Buf); }
+fn buf_new() -> own(Buf) {
+    unsafe {
+        let b: own(Buf) = fc_alloc(cast(usize, 16), cast(usize, 4));
+        for (let i: usize = 0; i < 4; i = (i + 1)) { at(b.data, i) = cast(i32, i); }
+        return b;
+    }
+}
+fn get(a: arr(i32, 4), i: i32) -> i32 { return at(a, i); }
+";
+
+#[test]
+fn test_array_index_past_end_traps() {
+    assert_traps(&format!(
+        "{ARRAYS} fn main() -> i32 {{ let b: own(Buf) = buf_new(); return get(b.data, 4); }}"
+    ));
+}
+
+#[test]
+fn test_negative_array_index_traps() {
+    assert_traps(&format!(
+        "{ARRAYS} fn main() -> i32 {{ let b: own(Buf) = buf_new(); return get(b.data, -1); }}"
+    ));
+}
+
+#[test]
+fn test_array_indexing_runs() {
+    assert_exits(
+        &format!(
+            "{ARRAYS} fn main() -> i32 {{
+                 let b: own(Buf) = buf_new();
+                 let s: i32 = 0;
+                 for (let i: usize = 0; i < 4; i = (i + 1)) {{ s = (s + at(b.data, i)); }}
+                 return ((s + at(b.data, 3)) + get(b.data, 2));
+             }}"
+        ),
+        11,
+    );
+}
+
+const SLICES: &str = "
+fn sum(s: slice(i32)) -> i32 {
+    let t: i32 = 0;
+    for (let i: usize = 0; i < len(s); i = (i + 1)) { t = (t + at(s, i)); }
+    return t;
+}
+";
+
+#[test]
+fn test_subslice_past_end_traps() {
+    assert_traps(&format!(
+        "{ARRAYS}{SLICES} fn main() -> i32 {{
+             let b: own(Buf) = buf_new();
+             return sum(subslice(as_slice(b.data), 2, 5));
+         }}"
+    ));
+}
+
+#[test]
+fn test_reversed_subslice_traps() {
+    assert_traps(&format!(
+        "{ARRAYS}{SLICES} fn main() -> i32 {{
+             let b: own(Buf) = buf_new();
+             return sum(subslice(b.data, 3, 2));
+         }}"
+    ));
+}
+
+#[test]
+fn test_slice_builtins_run() {
+    // 0+1+2+3, 1+2+3, and 1+2, plus the length of "hello" and its 'e'
+    assert_exits(
+        &format!(
+            "{ARRAYS}{SLICES} fn main() -> i32 {{
+                 let b: own(Buf) = buf_new();
+                 let all: slice(i32) = as_slice(b.data);
+                 let tail: slice(i32) = subslice(all, 1, len(all));
+                 let pair: slice(i32) = tail;
+                 unsafe {{ pair = slice_from_raw(to_raw(addr(at(b.data, 1))), 2); }}
+                 let msg: slice(u8) = bytes(\"hello\");
+                 let total: i32 = ((sum(all) + sum(tail)) + sum(pair));
+                 return ((total + cast(i32, len(msg))) + cast(i32, at(msg, 1)));
+             }}"
+        ),
+        121,
+    );
+}
+
+#[test]
+fn test_bytes_literals_are_writable() {
+    // 'A' written through a literal plus 'B' written through a const
+    assert_exits(
+        "const DATA: slice(u8) = bytes(\"abc\");
+         fn main() -> i32 {
+             let b: slice(u8) = bytes(\"hi\\n\");
+             at(b, 0) = cast(u8, 65);
+             let d: slice(u8) = DATA;
+             at(d, 1) = cast(u8, 66);
+             return cast(i32, (at(b, 0) + at(d, 1)));
+         }",
+        131,
+    );
+}
+
+#[test]
+fn test_c_library_names_are_free() {
+    // The runtime header must not pull in <stdio.h> or <errno.h>
+    assert_exits(
+        "fn remove(x: i32) -> i32 { return (x - 1); }
+         fn main() -> i32 {
+             let errno: i32 = remove(8);
+             return errno;
+         }",
+        7,
+    );
+}
+
+#[test]
+fn test_short_circuit_skips_right_hand_checks() {
+    // The bounds check on `at` runs only when `i < 4` holds
+    assert_exits(
+        &format!(
+            "{ARRAYS} fn main() -> i32 {{
+                 let b: own(Buf) = buf_new();
+                 let i: usize = 4;
+                 if ((i < 4) && (at(b.data, i) == 0)) {{ return 1; }}
+                 if ((i >= 4) || (at(b.data, i) == 0)) {{ return 0; }}
+                 return 2;
+             }}"
+        ),
+        0,
+    );
+}
+
+#[test]
+fn test_while_condition_reevaluated_each_iteration() {
+    assert_exits(
+        &format!(
+            "{ARRAYS} fn main() -> i32 {{
+                 let b: own(Buf) = buf_new();
+                 let i: usize = 0;
+                 while ((i < 4) && (at(b.data, i) < 3)) {{ i = (i + 1); }}
+                 return cast(i32, i);
+             }}"
+        ),
+        3,
+    );
+}
+
+#[test]
+fn test_trap_reports_kind_and_location() {
+    let source = format!("{DIVISION} fn main() -> i32 {{ return quot(1, 0); }}");
+    if let Some(output) = run_source(&source) {
+        assert!(trapped(&output.status));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "test.fc:2:42: trap: division by zero\n"
+        );
+    }
+}
+
+#[test]
+fn test_trap_in_module_reports_module_location() {
+    let Some(cc) = c_compiler() else {
+        eprintln!("Skipping test: no C compiler available");
+        return;
+    };
+
+    let dir = tempdir().expect("Failed to create temp dir");
+    let main_file = dir.path().join("main.fc");
+    let helper_file = dir.path().join("helper.fc");
+    std::fs::write(
+        &helper_file,
+        "// Helpers\n\nfn quot(a: i32, b: i32) -> i32 {\n    return (a / b);\n}\n",
+    )
+    .expect("Failed to write module");
+    let source = "mod helper;\nfn main() -> i32 { return quot(1, 0); }\n";
+    let c_code = fastc::compile(source, &main_file.to_string_lossy()).expect("should compile");
+    let c_file = dir.path().join("output.c");
+    let exe_file = dir.path().join("output_exe");
+    std::fs::write(&c_file, c_code).expect("Failed to write C file");
+
+    let output = process::Command::new(cc)
+        .args(["-std=c11", "-Wall", "-Werror"])
+        .arg(&c_file)
+        .arg(workspace_root().join("runtime/fastc_runtime.c"))
+        .arg("-I")
+        .arg(workspace_root().join("runtime"))
+        .arg("-o")
+        .arg(&exe_file)
+        .output()
+        .expect("Failed to run C compiler");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = process::Command::new(&exe_file)
+        .output()
+        .expect("Failed to run executable");
+    assert!(trapped(&output.status));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("{}:4:13: trap: division by zero\n", helper_file.display())
+    );
+}
+
+#[test]
+fn test_trap_handler_replaces_report() {
+    let Some(cc) = c_compiler() else {
+        eprintln!("Skipping test: no C compiler available");
+        return;
+    };
+
+    let source = format!("{DIVISION} fn main() -> i32 {{ return cast(i32, neg(-128)); }}");
+    let c_code = fastc::compile(&source, "test.fc").expect("source should compile");
+    let dir = tempdir().expect("Failed to create temp dir");
+    let c_file = dir.path().join("output.c");
+    let exe_file = dir.path().join("output_exe");
+    std::fs::write(&c_file, c_code).expect("Failed to write C file");
+
+    let output = process::Command::new(cc)
+        .args(["-std=c11", "-Wall", "-Werror", "-DFC_TRAP_HANDLER=on_trap"])
+        .arg(&c_file)
+        .arg(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/interop/trap_handler.c"))
+        .arg("-I")
+        .arg(workspace_root().join("runtime"))
+        .arg("-o")
+        .arg(&exe_file)
+        .output()
+        .expect("Failed to run C compiler");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = process::Command::new(&exe_file)
+        .output()
+        .expect("Failed to run executable");
+    assert_eq!(output.status.code(), Some(42));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "integer overflow test.fc:4:30\n"
+    );
+    assert!(output.stderr.is_empty());
+}
+
 /// Compile and run a C interop test that verifies ABI layout
 fn run_interop_test(c_test_file: &str) {
-    if !clang_available() {
-        eprintln!("Skipping test: clang not available");
+    let Some(cc) = c_compiler() else {
+        eprintln!("Skipping test: no C compiler available");
         return;
-    }
+    };
 
     let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     let c_test_path = manifest_dir.join("tests/interop").join(c_test_file);
@@ -130,16 +836,19 @@ fn run_interop_test(c_test_file: &str) {
     let exe_file = dir.path().join("test_exe");
 
     // Compile the C test to an executable
-    let output = process::Command::new("clang")
+    let output = process::Command::new(cc)
         .args(["-std=c11", "-Wall", "-Werror"])
         .arg(&c_test_path)
+        .arg(workspace_root().join("runtime/fastc_runtime.c"))
+        .arg("-I")
+        .arg(workspace_root().join("runtime"))
         .arg("-o")
         .arg(&exe_file)
         .output()
-        .expect("Failed to run clang");
+        .expect("Failed to run C compiler");
 
     if !output.status.success() {
-        eprintln!("clang stderr: {}", String::from_utf8_lossy(&output.stderr));
+        eprintln!("{} stderr: {}", cc, String::from_utf8_lossy(&output.stderr));
         panic!("C compilation failed for {}", c_test_file);
     }
 
@@ -172,3 +881,213 @@ fn test_enum_layout() {
 fn test_slice_layout() {
     run_interop_test("slice_test.c");
 }
+
+#[test]
+fn test_runtime_allocators() {
+    run_interop_test("allocator_test.c");
+}
+
+#[test]
+fn test_allocation_after_freeze_traps() {
+    let source = "
+extern \"C\" {
+    unsafe fn fc_alloc(size: usize, align: usize) -> rawm(u8);
+    unsafe fn fc_freeze_allocations() -> void;
+}
+fn main() -> i32 {
+    unsafe {
+        discard(fc_alloc(16, 8));
+        fc_freeze_allocations();
+        discard(fc_alloc(16, 8));
+    }
+    return 0;
+}
+";
+    if let Some(output) = run_source(source) {
+        assert!(trapped(&output.status));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "test.fc:10:17: trap: allocation after freeze\n"
+        );
+    }
+}
+
+#[test]
+fn test_allocator_state_is_shared_with_c() {
+    let Some(cc) = c_compiler() else {
+        eprintln!("Skipping test: no C compiler available");
+        return;
+    };
+
+    let source = format!(
+        "{ARRAYS} fn allocate_buf() -> i32 {{\n    let b: own(Buf) = buf_new();\n    return get(b.data, 1);\n}}"
+    );
+    let c_code = fastc::compile(&source, "test.fc").expect("source should compile");
+    let dir = tempdir().expect("Failed to create temp dir");
+    let c_file = dir.path().join("output.c");
+    let exe_file = dir.path().join("output_exe");
+    std::fs::write(&c_file, c_code).expect("Failed to write C file");
+
+    let output = process::Command::new(cc)
+        .args(["-std=c11", "-Wall", "-Werror"])
+        .arg(&c_file)
+        .arg(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/interop/shared_allocator.c"))
+        .arg(workspace_root().join("runtime/fastc_runtime.c"))
+        .arg("-I")
+        .arg(workspace_root().join("runtime"))
+        .arg("-o")
+        .arg(&exe_file)
+        .output()
+        .expect("Failed to run C compiler");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The allocator installed from C served FastC's allocation, and the
+    // freeze trapped at the FastC call site
+    let output = process::Command::new(&exe_file)
+        .output()
+        .expect("Failed to run executable");
+    assert!(trapped(&output.status));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "test.fc:6:27: trap: allocation after freeze\n"
+    );
+}
+
+#[test]
+fn test_std_file_allocation_failure_traps() {
+    let Some(cc) = c_compiler() else {
+        eprintln!("Skipping test: no C compiler available");
+        return;
+    };
+
+    let source = "mod std;\nfn open_stdout() -> i32 {\n    let out: own(File) = io_stdout();\n    return 0;\n}\n";
+    let c_code = fastc::compile(source, "test.fc").expect("source should compile");
+    let dir = tempdir().expect("Failed to create temp dir");
+    let c_file = dir.path().join("output.c");
+    let exe_file = dir.path().join("output_exe");
+    std::fs::write(&c_file, c_code).expect("Failed to write C file");
+
+    let output = process::Command::new(cc)
+        .args(["-std=c11", "-Wall", "-Werror"])
+        .arg(&c_file)
+        .arg(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/interop/exhausted_allocator.c"))
+        .arg(workspace_root().join("runtime/fastc_runtime.c"))
+        .arg("-I")
+        .arg(workspace_root().join("runtime"))
+        .arg("-o")
+        .arg(&exe_file)
+        .output()
+        .expect("Failed to run C compiler");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // file_new traps where it allocates instead of writing through NULL
+    let output = process::Command::new(&exe_file)
+        .output()
+        .expect("Failed to run executable");
+    assert!(trapped(&output.status));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "<std>/io.fc:49:28: trap: null pointer\n"
+    );
+}
+
+#[test]
+fn test_std_slices_and_strings() {
+    // 3 copied + 2 (find) + 5 chars + 1 (compare) + 2 (valid_up_to) + 8 (ö at byte 8)
+    assert_exits(
+        &format!(
+            "mod std; {ARRAYS} fn main() -> i32 {{
+                 let b: own(Buf) = buf_new();
+                 let s: Str = unwrap(str_from_utf8(bytes(\"héllo wörld\")));
+                 let bad: res(Str, Utf8Error) = str_from_utf8(subslice(bytes(\"abé\"), 0, 3));
+                 let ints: slice(i32) = as_slice(b.data);
+                 slice_fill[i32](ints, 5);
+                 let copied: usize = slice_copy[i32](ints, subslice(ints, 1, 4));
+                 let cmp: i32 = slice_compare[u8](bytes(\"abd\"), bytes(\"abc\"));
+                 let at_l: usize = unwrap(slice_find[u8](bytes(\"hello\"), 108));
+                 let tail: Str = unwrap(str_sub(s, 7, str_len(s)));
+                 let o: usize = unwrap(str_find(s, unwrap(str_from_utf8(bytes(\"ö\")))));
+                 if ((is_some(str_sub(s, 0, 2)) || !str_ends_with(s, tail)) || (at(b.data, 3) != 5)) {{
+                     return 100;
+                 }}
+                 let n: usize = ((copied + at_l) + str_char_count(tail));
+                 return ((cast(i32, n) + cmp) + cast(i32, (unwrap_err(bad).valid_up_to + o)));
+             }}"
+        ),
+        21,
+    );
+}
+
+#[test]
+fn test_std_file_io_round_trip() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let path = dir.path().join("out.txt");
+    let source = format!(
+        "mod std;
+struct Buf {{ data: arr(u8, 32), }}
+extern \"C\" {{ unsafe fn fc_alloc(size: usize, align: usize) -> own(Buf); }}
+fn main() -> i32 {{
+    let out: own(File) = unwrap(file_open(bytes(\"{path}\"), FileMode_Write));
+    discard(file_write(addr(out), bytes(\"héllo \")));
+    discard(file_write_i64(addr(out), -42));
+    discard(file_close(out));
+    unsafe {{
+        let buf: own(Buf) = fc_alloc(32, 1);
+        let f: own(File) = unwrap(file_open(bytes(\"{path}\"), FileMode_Read));
+        let n: usize = unwrap(file_read(addr(f), as_slice(buf.data)));
+        let text: Str = unwrap(str_from_utf8(subslice(buf.data, 0, n)));
+        discard(println(str_bytes(text)));
+        return cast(i32, str_char_count(text));
+    }}
+}}",
+        path = path.display()
+    );
+    if let Some(output) = run_source(&source) {
+        assert_eq!(output.status.code(), Some(9));
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "héllo -42\n");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "héllo -42");
+    }
+}
+
+#[test]
+fn test_std_open_missing_file_is_not_found() {
+    assert_exits(
+        "mod std;
+fn main() -> i32 {
+    let f: res(own(File), IoError) = file_open(bytes(\"/nonexistent/fastc/file\"), FileMode_Read);
+    if (unwrap_err(f) == IoError_NotFound) {
+        return 7;
+    }
+    return 1;
+}",
+        7,
+    );
+}
+
+#[test]
+fn test_standalone_file_loads_sibling_module() {
+    // No fastc.toml: `mod helper;` resolves next to the source file
+    let dir = tempdir().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("helper.fc"),
+        "fn answer() -> i32 { return 42; }\n",
+    )
+    .expect("Failed to write module");
+    let main = dir.path().join("main.fc");
+
+    let c_code = fastc::compile(
+        "mod helper;\nfn main() -> i32 { return answer(); }\n",
+        &main.to_string_lossy(),
+    )
+    .expect("standalone file with a sibling module should compile");
+    assert!(c_code.contains("int32_t answer(void)"));
+}
@@ -3,7 +3,7 @@
 //! These tests verify that compiling the same source code produces
 //! byte-identical output across multiple runs.
 
-use fastc::compile;
+use fastc::{EmitOptions, P10Config, compile, compile_with_emit_options};
 
 /// Verify that compiling the same source twice produces identical output
 fn verify_determinism(source: &str) {
@@ -127,3 +127,69 @@ fn test_determinism_complex_program() {
         "#,
     );
 }
+
+#[test]
+fn test_determinism_generic_instances() {
+    // Instances of generics get the same mangled names and order every run
+    verify_determinism(
+        r#"
+        struct Pair[A, B] { first: A, second: B }
+
+        fn max[T](a: T, b: T) -> T {
+            if (a > b) { return a; }
+            return b;
+        }
+
+        fn main() -> i32 {
+            let p: Pair[u8, i64] = Pair[u8, i64] { first: 1, second: 2 };
+            let q: Pair[i32, i32] = Pair[i32, i32] { first: 3, second: 4 };
+            discard(max[u8](p.first, 7));
+            discard(max[i64](p.second, 7));
+            return max[i32](q.first, q.second);
+        }
+        "#,
+    );
+}
+
+#[test]
+fn test_determinism_line_directives_and_source_map() {
+    // `#line` directives and source maps depend only on the source
+    let source = r#"
+        fn clamp(x: i32, limit: i32) -> i32 {
+            if (x > limit) {
+                return limit;
+            }
+            return x / 2;
+        }
+
+        fn main() -> i32 {
+            return clamp(9, 4);
+        }
+        "#;
+    let options = EmitOptions {
+        line_directives: true,
+        source_map: true,
+        ..EmitOptions::default()
+    };
+    let compile = || {
+        compile_with_emit_options(source, "test.fc", options, P10Config::standard())
+            .expect("Compilation failed")
+    };
+    let (first, second) = (compile(), compile());
+    assert_eq!(first.c_code, second.c_code);
+    assert_eq!(first.source_map, second.source_map);
+    assert!(first.c_code.contains("#line 2 \"test.fc\""));
+
+    // Without the options the C is unchanged by location tracking
+    let plain = fastc::compile(source, "test.fc").expect("Compilation failed");
+    assert!(!plain.contains("#line"));
+    assert_eq!(
+        plain,
+        first
+            .c_code
+            .lines()
+            .filter(|l| !l.starts_with("#line"))
+            .map(|l| format!("{}\n", l))
+            .collect::<String>()
+    );
+}
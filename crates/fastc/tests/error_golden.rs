@@ -3,35 +3,186 @@
 //! These tests verify that error messages are stable and informative.
 //! Uses insta for snapshot testing.
 
-use fastc::compile;
+use fastc::diag::{CompileError, ErrorCode, Severity};
+use fastc::lint::Level;
+use fastc::{P10Config, check, check_with_p10, compile, fix};
 
-/// Test a file that should fail to compile and snapshot the error
-fn test_error_snapshot(name: &str, source: &str) {
+/// Test a file that should fail to compile with `code` and snapshot the error
+fn test_error_snapshot(name: &str, code: ErrorCode, source: &str) {
     let result = compile(source, &format!("{}.fc", name));
     assert!(result.is_err(), "Expected compilation to fail for {}", name);
 
     let error = result.unwrap_err();
+    let codes = error_codes(&error);
+    assert!(
+        !codes.is_empty() && codes.iter().all(|c| *c == code),
+        "Expected {} for {}, got {:?}",
+        code,
+        name,
+        codes
+    );
     let error_str = format!("{:?}", error);
 
     insta::assert_snapshot!(name, error_str);
 }
 
+/// Codes of an error, or of each error it wraps
+fn error_codes(error: &CompileError) -> Vec<ErrorCode> {
+    match error {
+        CompileError::Multiple { errors } => errors.iter().flat_map(error_codes).collect(),
+        _ => error.error_code().into_iter().collect(),
+    }
+}
+
+/// Check a file that should compile with warnings and snapshot them
+fn test_warning_snapshot(name: &str, source: &str) {
+    let warnings = check_with_p10(source, &format!("{}.fc", name), P10Config::standard())
+        .unwrap_or_else(|e| panic!("Expected {} to check with warnings: {:?}", name, e));
+    assert!(!warnings.is_empty(), "Expected warnings for {}", name);
+
+    let warnings_str = warnings
+        .iter()
+        .map(|w| format!("{:?}", w))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    insta::assert_snapshot!(name, warnings_str);
+}
+
 #[test]
 fn test_error_parse() {
-    test_error_snapshot("parse_error", include_str!("errors/parse_error.fc"));
+    test_error_snapshot(
+        "parse_error",
+        ErrorCode::UnexpectedToken,
+        include_str!("errors/parse_error.fc"),
+    );
 }
 
 #[test]
 fn test_error_undefined_name() {
-    test_error_snapshot("undefined_name", include_str!("errors/undefined_name.fc"));
+    test_error_snapshot(
+        "undefined_name",
+        ErrorCode::UndefinedName,
+        include_str!("errors/undefined_name.fc"),
+    );
 }
 
 #[test]
 fn test_error_type_mismatch() {
-    test_error_snapshot("type_mismatch", include_str!("errors/type_mismatch.fc"));
+    test_error_snapshot(
+        "type_mismatch",
+        ErrorCode::TypeMismatch,
+        include_str!("errors/type_mismatch.fc"),
+    );
 }
 
 #[test]
 fn test_error_unsafe_required() {
-    test_error_snapshot("unsafe_required", include_str!("errors/unsafe_required.fc"));
+    test_error_snapshot(
+        "unsafe_required",
+        ErrorCode::UnsafeCall,
+        include_str!("errors/unsafe_required.fc"),
+    );
+}
+
+#[test]
+fn test_error_double_mref() {
+    test_error_snapshot(
+        "double_mref",
+        ErrorCode::DoubleMutableBorrow,
+        include_str!("errors/double_mref.fc"),
+    );
+}
+
+#[test]
+fn test_error_use_after_move() {
+    test_error_snapshot(
+        "use_after_move",
+        ErrorCode::UseAfterMove,
+        include_str!("errors/use_after_move.fc"),
+    );
+}
+
+#[test]
+fn test_error_file_after_close() {
+    test_error_snapshot(
+        "file_after_close",
+        ErrorCode::UseAfterMove,
+        include_str!("errors/file_after_close.fc"),
+    );
+}
+
+#[test]
+fn test_error_return_local_ref() {
+    test_error_snapshot(
+        "return_local_ref",
+        ErrorCode::ReturnLocalRef,
+        include_str!("errors/return_local_ref.fc"),
+    );
+}
+
+#[test]
+fn test_parse_recovery() {
+    let source = include_str!("errors/syntax_errors.fc");
+    let error = compile(source, "syntax_errors.fc").unwrap_err();
+    assert_eq!(
+        error_codes(&error),
+        [
+            ErrorCode::UnexpectedToken,
+            ErrorCode::UnexpectedToken,
+            ErrorCode::UnexpectedToken,
+            ErrorCode::TypeMismatch,
+        ]
+    );
+
+    let error_str = format!("{:?}", error);
+    insta::assert_snapshot!("syntax_errors", error_str);
+}
+
+#[test]
+fn test_error_code_in_report() {
+    let error = compile(include_str!("errors/type_mismatch.fc"), "type_mismatch.fc").unwrap_err();
+    let report = format!("{:?}", miette::Report::new(error));
+    assert!(report.contains("E0201"), "{}", report);
+}
+
+#[test]
+fn test_fix_golden() {
+    let source = include_str!("errors/fixable.fc");
+    let output = fix(source, "fixable.fc", P10Config::standard()).unwrap();
+    assert_eq!(output.applied, 5);
+    check(&output.source, "fixable.fc")
+        .unwrap_or_else(|e| panic!("fixed source should check: {:?}", e));
+
+    insta::assert_snapshot!("fixable", output.source);
+}
+
+#[test]
+fn test_warning_lints() {
+    test_warning_snapshot("lints", include_str!("errors/lints.fc"));
+}
+
+#[test]
+fn test_strict_promotes_warnings() {
+    let mut config = P10Config::standard();
+    config.strict_mode = true;
+    config.lints.set("shadowing", Level::Allow);
+
+    let error = check_with_p10(include_str!("errors/lints.fc"), "lints.fc", config).unwrap_err();
+    let CompileError::Multiple { errors } = error else {
+        panic!("Expected several errors, got {:?}", error);
+    };
+    assert_eq!(errors.len(), 4);
+    assert!(errors.iter().all(|e| e.severity() == Severity::Error));
+}
+
+#[test]
+fn test_allowed_lints_are_silent() {
+    let mut config = P10Config::standard();
+    config.lints.set("warnings", Level::Allow);
+    config.lints.set("unreachable_code", Level::Warn);
+
+    let warnings = check_with_p10(include_str!("errors/lints.fc"), "lints.fc", config).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity(), Severity::Warning);
 }
@@ -0,0 +1,74 @@
+/* Runtime allocator interface and bump arena test */
+#include <assert.h>
+#include <stdalign.h>
+#include <stdint.h>
+#include "fastc_runtime.h"
+
+static void test_heap_allocator(void) {
+    unsigned char* p = (unsigned char*)fc_alloc(10, 8);
+    assert(p != NULL && (uintptr_t)p % 8 == 0);
+    fc_free(p);
+
+    /* Extended alignment goes through aligned_alloc, and realloc keeps it */
+    p = (unsigned char*)fc_alloc(24, 64);
+    assert(p != NULL && (uintptr_t)p % 64 == 0);
+    for (int i = 0; i < 24; i++) {
+        p[i] = (unsigned char)i;
+    }
+    p = (unsigned char*)fc_realloc(p, 24, 200, 64);
+    assert(p != NULL && (uintptr_t)p % 64 == 0);
+    assert(p[0] == 0 && p[23] == 23);
+    fc_free(p);
+}
+
+static void test_arena(void) {
+    alignas(16) unsigned char buffer[64];
+    fc_arena arena;
+    fc_arena_init(&arena, buffer, sizeof(buffer));
+
+    unsigned char* a = (unsigned char*)fc_arena_alloc(&arena, 3, 1);
+    int64_t* b = (int64_t*)fc_arena_alloc(&arena, sizeof(int64_t), 8);
+    assert(a == buffer);
+    assert((unsigned char*)b == buffer + 8);
+    assert(fc_arena_remaining(&arena) == 48);
+    assert(fc_arena_alloc(&arena, 49, 1) == NULL);
+
+    fc_arena_reset(&arena);
+    assert(fc_arena_remaining(&arena) == 64);
+}
+
+static void test_arena_as_allocator(void) {
+    alignas(16) unsigned char buffer[128];
+    fc_arena arena;
+    fc_arena_init(&arena, buffer, sizeof(buffer));
+
+    fc_allocator allocator = fc_arena_allocator(&arena);
+    fc_set_allocator(&allocator);
+    unsigned char* first = (unsigned char*)fc_alloc(8, 8);
+    unsigned char* second = (unsigned char*)fc_alloc(8, 8);
+    assert(first == buffer && second == buffer + 8);
+
+    /* The latest block grows in place; an earlier one is copied */
+    second[0] = 7;
+    assert(fc_realloc(second, 8, 32, 8) == second);
+    first[0] = 9;
+    unsigned char* moved = (unsigned char*)fc_realloc(first, 8, 16, 8);
+    assert(moved == buffer + 40 && moved[0] == 9);
+    fc_free(moved);
+    assert(fc_arena_remaining(&arena) == 72);
+
+    fc_set_allocator(NULL);
+    unsigned char* heap = (unsigned char*)fc_alloc(8, 8);
+    assert(heap < buffer || heap >= buffer + sizeof(buffer));
+    fc_free(heap);
+}
+
+int main(void) {
+    test_heap_allocator();
+    test_arena();
+    test_arena_as_allocator();
+    assert(!fc_allocations_frozen());
+    fc_freeze_allocations();
+    assert(fc_allocations_frozen());
+    return 0;
+}
@@ -0,0 +1,19 @@
+/* Installs an allocator that has no memory left; std::io must not hand out a NULL File */
+#include "fastc_runtime.h"
+
+int32_t open_stdout(void);
+
+static void* exhausted_alloc(void* ctx, size_t size, size_t align) {
+    (void)ctx;
+    (void)size;
+    (void)align;
+    return NULL;
+}
+
+int main(void) {
+    fc_allocator exhausted = fc_heap_allocator();
+    exhausted.alloc = exhausted_alloc;
+    fc_set_allocator(&exhausted);
+
+    return open_stdout();
+}
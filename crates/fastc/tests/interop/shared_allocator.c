@@ -0,0 +1,26 @@
+/* Installs an allocator and freezes allocation from C; FastC code must see both */
+#include <stdio.h>
+#include "fastc_runtime.h"
+
+int32_t allocate_buf(void);
+
+static int allocations;
+
+static void* counting_alloc(void* ctx, size_t size, size_t align) {
+    allocations++;
+    return fc_heap_alloc(ctx, size, align);
+}
+
+int main(void) {
+    fc_allocator counting = fc_heap_allocator();
+    counting.alloc = counting_alloc;
+    fc_set_allocator(&counting);
+
+    allocate_buf();
+    printf("%d\n", allocations);
+    fflush(stdout);
+
+    fc_freeze_allocations();
+    allocate_buf();
+    return 0;
+}
@@ -0,0 +1,9 @@
+/* Trap handler installed with -DFC_TRAP_HANDLER=on_trap */
+#include <stdio.h>
+#include <stdlib.h>
+#include "fastc_runtime.h"
+
+void on_trap(fc_trap_kind kind, const char* file, unsigned line, unsigned col) {
+    printf("%s %s:%u:%u\n", fc_trap_reason(kind), file, line, col);
+    exit(42);
+}
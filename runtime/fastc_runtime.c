@@ -0,0 +1,7 @@
+/*
+ * FastC Runtime implementation unit. Compile and link this file once with
+ * every program built from FastC output; it holds the parts of the runtime
+ * that cannot live in fastc_runtime.h.
+ */
+#define FC_RUNTIME_IMPLEMENTATION
+#include "fastc_runtime.h"
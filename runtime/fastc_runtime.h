@@ -7,19 +7,61 @@
 #include <stdbool.h>
 #include <stdlib.h>
 
-/* Trap handler - abort on safety violation */
-static inline _Noreturn void fc_trap(void) {
-    abort();
+/* Why a runtime safety check failed */
+typedef enum {
+    FC_TRAP_BOUNDS,   /* index or subslice outside its slice or array */
+    FC_TRAP_OVERFLOW, /* signed arithmetic overflow */
+    FC_TRAP_DIV_ZERO, /* division or remainder by zero */
+    FC_TRAP_SHIFT,    /* shift count out of range, or signed left shift that overflows */
+    FC_TRAP_UNWRAP,   /* unwrap of none or err, unwrap_err of ok */
+    FC_TRAP_NULL,     /* null pointer */
+    FC_TRAP_BAD_TAG,  /* enum value with no matching variant */
+    FC_TRAP_ALLOC     /* allocation after fc_freeze_allocations() */
+} fc_trap_kind;
+
+static inline const char* fc_trap_reason(fc_trap_kind kind) {
+    switch (kind) {
+    case FC_TRAP_BOUNDS: return "index out of bounds";
+    case FC_TRAP_OVERFLOW: return "integer overflow";
+    case FC_TRAP_DIV_ZERO: return "division by zero";
+    case FC_TRAP_SHIFT: return "invalid shift";
+    case FC_TRAP_UNWRAP: return "unwrap failed";
+    case FC_TRAP_NULL: return "null pointer";
+    case FC_TRAP_BAD_TAG: return "invalid enum tag";
+    case FC_TRAP_ALLOC: return "allocation after freeze";
+    }
+    return "unknown trap";
 }
 
-/* Allocator stubs - users may replace */
-static inline void* fc_alloc(size_t size, size_t align) {
-    (void)align; /* C11 aligned_alloc if needed */
-    return malloc(size);
+/*
+ * Trap handler hook. Compile with -DFC_TRAP_HANDLER=my_handler and define
+ *
+ *     void my_handler(fc_trap_kind kind, const char* file, unsigned line, unsigned col);
+ *
+ * in one translation unit to replace the stderr report (e.g. to log or reset
+ * on embedded targets). Execution aborts if the handler returns. Without a
+ * handler, traps go to fc_trap_report, which fastc_runtime.c defines so that
+ * reporting to stderr does not need <stdio.h> in every translation unit.
+ */
+#ifdef FC_TRAP_HANDLER
+void FC_TRAP_HANDLER(fc_trap_kind kind, const char* file, unsigned line, unsigned col);
+#else
+void fc_trap_report(fc_trap_kind kind, const char* file, unsigned line, unsigned col);
+#endif
+
+/* Trap on a failed safety check at a FastC source location */
+static inline _Noreturn void fc_trap_at(fc_trap_kind kind, const char* file, unsigned line, unsigned col) {
+#ifdef FC_TRAP_HANDLER
+    FC_TRAP_HANDLER(kind, file, line, col);
+#else
+    fc_trap_report(kind, file, line, col);
+#endif
+    abort();
 }
 
-static inline void fc_free(void* ptr) {
-    free(ptr);
+/* Trap without a source location - abort on safety violation */
+static inline _Noreturn void fc_trap(void) {
+    abort();
 }
 
 /* Memory copy */
@@ -31,6 +73,204 @@ static inline void fc_memcpy(void* dst, const void* src, size_t n) {
     }
 }
 
+/*
+ * Allocator interface. fc_alloc, fc_realloc, and fc_free dispatch to the
+ * current allocator, which defaults to the C heap. `align` must be a power
+ * of two (0 is treated as 1); `old_size` lets allocators that cannot query
+ * a block's size copy it when moving.
+ */
+typedef struct fc_allocator {
+    void* (*alloc)(void* ctx, size_t size, size_t align);
+    void* (*realloc)(void* ctx, void* ptr, size_t old_size, size_t size, size_t align);
+    void (*free)(void* ctx, void* ptr);
+    void* ctx;
+} fc_allocator;
+
+/* Heap allocator: malloc for fundamental alignments, aligned_alloc above */
+static inline void* fc_heap_alloc(void* ctx, size_t size, size_t align) {
+    (void)ctx;
+    if (align <= _Alignof(max_align_t)) {
+        return malloc(size ? size : 1);
+    }
+    /* aligned_alloc requires a size that is a multiple of the alignment */
+    if (size > SIZE_MAX - (align - 1)) {
+        return NULL;
+    }
+    size_t rounded = (size + align - 1) / align * align;
+    return aligned_alloc(align, rounded ? rounded : align);
+}
+
+static inline void* fc_heap_realloc(void* ctx, void* ptr, size_t old_size, size_t size, size_t align) {
+    if (align <= _Alignof(max_align_t)) {
+        return realloc(ptr, size ? size : 1);
+    }
+    /* realloc does not preserve extended alignment: allocate and copy */
+    void* moved = fc_heap_alloc(ctx, size, align);
+    if (moved != NULL && ptr != NULL) {
+        fc_memcpy(moved, ptr, old_size < size ? old_size : size);
+        free(ptr);
+    }
+    return moved;
+}
+
+static inline void fc_heap_free(void* ctx, void* ptr) {
+    (void)ctx;
+    free(ptr);
+}
+
+static inline fc_allocator fc_heap_allocator(void) {
+    fc_allocator heap = { fc_heap_alloc, fc_heap_realloc, fc_heap_free, NULL };
+    return heap;
+}
+
+/*
+ * Allocator state, shared by every translation unit of a program so that an
+ * allocator installed or a freeze set from C reaches FastC code too. It is
+ * defined once, in fastc_runtime.c.
+ */
+typedef struct fc_alloc_state {
+    fc_allocator allocator;
+    bool frozen;
+} fc_alloc_state;
+
+extern fc_alloc_state fc_runtime_alloc_state;
+
+static inline fc_alloc_state* fc_allocator_state(void) {
+    return &fc_runtime_alloc_state;
+}
+
+/* Install an allocator at startup; NULL restores the heap allocator */
+static inline void fc_set_allocator(const fc_allocator* allocator) {
+    fc_allocator_state()->allocator = allocator ? *allocator : fc_heap_allocator();
+}
+
+static inline fc_allocator fc_get_allocator(void) {
+    return fc_allocator_state()->allocator;
+}
+
+/*
+ * Trap on every later fc_alloc or fc_realloc (Power of 10 Rule 3: no
+ * allocation after initialization). fc_free keeps working.
+ */
+static inline void fc_freeze_allocations(void) {
+    fc_allocator_state()->frozen = true;
+}
+
+static inline bool fc_allocations_frozen(void) {
+    return fc_allocator_state()->frozen;
+}
+
+/*
+ * Allocate through the current allocator. Once allocations are frozen these
+ * trap at the caller's location: generated code passes its FastC call site,
+ * and the fc_alloc and fc_realloc macros pass a C caller's file and line.
+ */
+static inline void* fc_alloc_at(size_t size, size_t align, const char* file, unsigned line, unsigned col) {
+    fc_alloc_state* state = fc_allocator_state();
+    if (state->frozen) {
+        fc_trap_at(FC_TRAP_ALLOC, file, line, col);
+    }
+    return state->allocator.alloc(state->allocator.ctx, size, align ? align : 1);
+}
+
+static inline void* fc_realloc_at(void* ptr, size_t old_size, size_t size, size_t align,
+                                  const char* file, unsigned line, unsigned col) {
+    fc_alloc_state* state = fc_allocator_state();
+    if (state->frozen) {
+        fc_trap_at(FC_TRAP_ALLOC, file, line, col);
+    }
+    return state->allocator.realloc(state->allocator.ctx, ptr, old_size, size, align ? align : 1);
+}
+
+#define fc_alloc(size, align) fc_alloc_at((size), (align), __FILE__, __LINE__, 0)
+#define fc_realloc(ptr, old_size, size, align) \
+    fc_realloc_at((ptr), (old_size), (size), (align), __FILE__, __LINE__, 0)
+
+/* Release a block through the allocator that is current when called */
+static inline void fc_free(void* ptr) {
+    fc_alloc_state* state = fc_allocator_state();
+    state->allocator.free(state->allocator.ctx, ptr);
+}
+
+/*
+ * Bump arena over a caller-provided buffer. Allocation bumps an offset,
+ * free is a no-op, and fc_arena_reset releases everything at once. Returns
+ * NULL when the buffer is exhausted.
+ */
+typedef struct fc_arena {
+    unsigned char* base;
+    size_t capacity;
+    size_t used;
+    void* last; /* most recent allocation, which can grow in place */
+} fc_arena;
+
+static inline void fc_arena_init(fc_arena* arena, void* buffer, size_t capacity) {
+    arena->base = (unsigned char*)buffer;
+    arena->capacity = capacity;
+    arena->used = 0;
+    arena->last = NULL;
+}
+
+static inline void* fc_arena_alloc(fc_arena* arena, size_t size, size_t align) {
+    if (align == 0) {
+        align = 1;
+    }
+    uintptr_t next = (uintptr_t)(arena->base + arena->used);
+    size_t pad = (size_t)((align - next % align) % align);
+    size_t free_bytes = arena->capacity - arena->used;
+    if (pad > free_bytes || size > free_bytes - pad) {
+        return NULL;
+    }
+    void* block = arena->base + arena->used + pad;
+    arena->used += pad + size;
+    arena->last = block;
+    return block;
+}
+
+static inline void fc_arena_reset(fc_arena* arena) {
+    arena->used = 0;
+    arena->last = NULL;
+}
+
+static inline size_t fc_arena_remaining(const fc_arena* arena) {
+    return arena->capacity - arena->used;
+}
+
+static inline void* fc_arena_alloc_fn(void* ctx, size_t size, size_t align) {
+    return fc_arena_alloc((fc_arena*)ctx, size, align);
+}
+
+static inline void* fc_arena_realloc_fn(void* ctx, void* ptr, size_t old_size, size_t size, size_t align) {
+    fc_arena* arena = (fc_arena*)ctx;
+    if (align == 0) {
+        align = 1;
+    }
+    if (ptr != NULL && ptr == arena->last && (uintptr_t)ptr % align == 0) {
+        size_t offset = (size_t)((unsigned char*)ptr - arena->base);
+        if (size > arena->capacity - offset) {
+            return NULL;
+        }
+        arena->used = offset + size;
+        return ptr;
+    }
+    void* moved = fc_arena_alloc(arena, size, align);
+    if (moved != NULL && ptr != NULL) {
+        fc_memcpy(moved, ptr, old_size < size ? old_size : size);
+    }
+    return moved;
+}
+
+static inline void fc_arena_free_fn(void* ctx, void* ptr) {
+    (void)ctx;
+    (void)ptr;
+}
+
+/* Allocator vtable over an arena, for fc_set_allocator */
+static inline fc_allocator fc_arena_allocator(fc_arena* arena) {
+    fc_allocator allocator = { fc_arena_alloc_fn, fc_arena_realloc_fn, fc_arena_free_fn, arena };
+    return allocator;
+}
+
 /* Unaligned read helpers - use memcpy for safe unaligned access */
 static inline uint16_t fc_read_u16_unaligned(const void* ptr) {
     uint16_t val;
@@ -79,4 +319,206 @@ FC_DEFINE_SLICE(int64_t, fc_slice_int64_t);
 FC_DEFINE_SLICE(float, fc_slice_float);
 FC_DEFINE_SLICE(double, fc_slice_double);
 
+/*
+ * Standard library I/O (std::io). Files are buffered stdio streams behind an
+ * opaque handle. Calls return a byte count (or 0) on success and a negated
+ * fc_io_error on failure; a NULL handle reports FC_IO_CLOSED.
+ */
+typedef struct fc_file fc_file;
+
+typedef enum {
+    FC_IO_NOT_FOUND = 1,
+    FC_IO_PERMISSION_DENIED = 2,
+    FC_IO_INVALID_INPUT = 3,
+    FC_IO_CLOSED = 4,
+    FC_IO_OTHER = 5
+} fc_io_error;
+
+/* Open modes, in the order of std::io's FileMode */
+typedef enum {
+    FC_IO_READ,
+    FC_IO_WRITE,
+    FC_IO_APPEND
+} fc_io_mode;
+
+#ifndef FC_IO_PATH_MAX
+#define FC_IO_PATH_MAX 4096
+#endif
+
+/*
+ * Storage for a std::io File, released by fc_free. Generated code calls it
+ * from std's file_new with that call site, after the lowered `File` struct
+ * is defined, so the block has File's own size and alignment. A NULL block
+ * traps with FC_TRAP_NULL there instead of reaching std as an owned File.
+ */
+static inline void* fc_io_file_nonnull(void* block, const char* file, unsigned line, unsigned col) {
+    if (block == NULL) {
+        fc_trap_at(FC_TRAP_NULL, file, line, col);
+    }
+    return block;
+}
+
+#define fc_io_file_alloc_at(file, line, col) \
+    fc_io_file_nonnull(fc_alloc_at(sizeof(File), _Alignof(File), (file), (line), (col)), \
+                       (file), (line), (col))
+
+fc_file* fc_io_stdin(void);
+fc_file* fc_io_stdout(void);
+fc_file* fc_io_stderr(void);
+
+/* Open the file named by `path` (not NUL-terminated); sets *error on failure */
+fc_file* fc_io_open(fc_slice_uint8_t path, int32_t mode, int32_t* error);
+
+/* Read up to buf.len bytes; 0 means end of file */
+int64_t fc_io_read(fc_file* file, fc_slice_uint8_t buf);
+int64_t fc_io_write(fc_file* file, fc_slice_uint8_t data);
+
+/* Write an integer in decimal */
+int64_t fc_io_write_i64(fc_file* file, int64_t value);
+int64_t fc_io_write_u64(fc_file* file, uint64_t value);
+int64_t fc_io_flush(fc_file* file);
+
+/* Close a file; the standard streams are flushed but stay open */
+int64_t fc_io_close(fc_file* file);
+
+/*
+ * Out-of-line runtime: the allocator state, the default trap reporter, and
+ * the I/O helpers. The state must exist once per program, and the rest need
+ * <stdio.h> and <errno.h>, whose names would collide with FastC identifiers
+ * (a function named `remove`, a local named `errno`), so only
+ * fastc_runtime.c defines FC_RUNTIME_IMPLEMENTATION and compiles them.
+ */
+#ifdef FC_RUNTIME_IMPLEMENTATION
+#include <errno.h>
+#include <stdio.h>
+
+fc_alloc_state fc_runtime_alloc_state = {
+    { fc_heap_alloc, fc_heap_realloc, fc_heap_free, NULL },
+    false
+};
+
+#ifndef FC_TRAP_HANDLER
+void fc_trap_report(fc_trap_kind kind, const char* file, unsigned line, unsigned col) {
+    fprintf(stderr, "%s:%u:%u: trap: %s\n", file, line, col, fc_trap_reason(kind));
+}
+#endif
+
+static int64_t fc_io_errno(void) {
+    switch (errno) {
+    case ENOENT: return -FC_IO_NOT_FOUND;
+    case EACCES: return -FC_IO_PERMISSION_DENIED;
+    case EPERM: return -FC_IO_PERMISSION_DENIED;
+    case EINVAL: return -FC_IO_INVALID_INPUT;
+    default: return -FC_IO_OTHER;
+    }
+}
+
+fc_file* fc_io_stdin(void) {
+    return (fc_file*)stdin;
+}
+
+fc_file* fc_io_stdout(void) {
+    return (fc_file*)stdout;
+}
+
+fc_file* fc_io_stderr(void) {
+    return (fc_file*)stderr;
+}
+
+fc_file* fc_io_open(fc_slice_uint8_t path, int32_t mode, int32_t* error) {
+    static const char* const modes[] = { "rb", "wb", "ab" };
+    char name[FC_IO_PATH_MAX];
+    *error = 0;
+    if (mode < FC_IO_READ || mode > FC_IO_APPEND || path.len == 0 || path.len >= sizeof name) {
+        *error = FC_IO_INVALID_INPUT;
+        return NULL;
+    }
+    for (size_t i = 0; i < path.len; i++) {
+        if (path.data[i] == 0) {
+            *error = FC_IO_INVALID_INPUT;
+            return NULL;
+        }
+        name[i] = (char)path.data[i];
+    }
+    name[path.len] = '\0';
+    errno = 0;
+    FILE* file = fopen(name, modes[mode]);
+    if (file == NULL) {
+        *error = (int32_t)-fc_io_errno();
+    }
+    return (fc_file*)file;
+}
+
+int64_t fc_io_read(fc_file* file, fc_slice_uint8_t buf) {
+    if (file == NULL) {
+        return -FC_IO_CLOSED;
+    }
+    if (buf.len == 0) {
+        return 0;
+    }
+    errno = 0;
+    size_t n = fread(buf.data, 1, buf.len, (FILE*)file);
+    if (n == 0 && ferror((FILE*)file)) {
+        clearerr((FILE*)file);
+        return fc_io_errno();
+    }
+    return (int64_t)n;
+}
+
+int64_t fc_io_write(fc_file* file, fc_slice_uint8_t data) {
+    if (file == NULL) {
+        return -FC_IO_CLOSED;
+    }
+    if (data.len == 0) {
+        return 0;
+    }
+    errno = 0;
+    size_t n = fwrite(data.data, 1, data.len, (FILE*)file);
+    if (n < data.len) {
+        clearerr((FILE*)file);
+        return fc_io_errno();
+    }
+    return (int64_t)n;
+}
+
+int64_t fc_io_write_i64(fc_file* file, int64_t value) {
+    if (file == NULL) {
+        return -FC_IO_CLOSED;
+    }
+    errno = 0;
+    int n = fprintf((FILE*)file, "%lld", (long long)value);
+    return n < 0 ? fc_io_errno() : (int64_t)n;
+}
+
+int64_t fc_io_write_u64(fc_file* file, uint64_t value) {
+    if (file == NULL) {
+        return -FC_IO_CLOSED;
+    }
+    errno = 0;
+    int n = fprintf((FILE*)file, "%llu", (unsigned long long)value);
+    return n < 0 ? fc_io_errno() : (int64_t)n;
+}
+
+int64_t fc_io_flush(fc_file* file) {
+    if (file == NULL) {
+        return -FC_IO_CLOSED;
+    }
+    errno = 0;
+    return fflush((FILE*)file) == 0 ? 0 : fc_io_errno();
+}
+
+int64_t fc_io_close(fc_file* file) {
+    if (file == NULL) {
+        return -FC_IO_CLOSED;
+    }
+    FILE* stream = (FILE*)file;
+    if (stream == stdin || stream == stdout || stream == stderr) {
+        return fc_io_flush(file);
+    }
+    errno = 0;
+    return fclose(stream) == 0 ? 0 : fc_io_errno();
+}
+
+#endif /* FC_RUNTIME_IMPLEMENTATION */
+
 #endif /* FASTC_RUNTIME_H */